@@ -0,0 +1,37 @@
+extern crate criterion;
+extern crate tmcl;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use tmcl::Command;
+use tmcl::Operand;
+use tmcl::FromOperand;
+use tmcl::Status;
+use tmcl::modules::tmcm::instructions::*;
+use tmcl::modules::tmcm::axis_parameters::*;
+
+fn serialize(c: &mut Criterion) {
+    let command = Command::new(1, MVP::new(0, MoveOperation::Absolute(-123456)));
+
+    c.bench_function("serialize", |b| b.iter(|| black_box(&command).serialize()));
+    c.bench_function("serialize_i2c", |b| b.iter(|| black_box(&command).serialize_i2c()));
+    c.bench_function("serialize_can", |b| b.iter(|| black_box(&command).serialize_can()));
+
+    let mut buffer = [0u8; 64];
+    c.bench_function("serialize_into", |b| b.iter(|| black_box(&command).serialize_into(&mut buffer)));
+    c.bench_function("serialize_can_into", |b| b.iter(|| black_box(&command).serialize_can_into(&mut buffer)));
+
+    let command = Command::new(1, SAP::new(0, MaximumPositioningSpeed::new(1000)));
+    c.bench_function("serialize_sap", |b| b.iter(|| black_box(&command).serialize()));
+}
+
+fn deserialize(c: &mut Criterion) {
+    let operand = Operand::from_le([0x40, 0xe2, 0x01, 0x00]);
+
+    c.bench_function("deserialize_i32", |b| b.iter(|| i32::from_operand(black_box(operand))));
+    c.bench_function("deserialize_actual_position", |b| b.iter(|| ActualPosition::from_operand(black_box(operand))));
+    c.bench_function("deserialize_status", |b| b.iter(|| Status::try_from_u8(black_box(100))));
+}
+
+criterion_group!(benches, serialize, deserialize);
+criterion_main!(benches);
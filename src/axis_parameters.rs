@@ -3,6 +3,27 @@
 //! ## `axis_param` macros
 //! These macros works for most cases, if the register is represented by an enum or
 //! a type not implemented `Return` for these macros wont work.
+//!
+//! `axis_param_rw!` optionally takes the valid range of an integer register as a last argument,
+//! e.g. `MaximumPositioningSpeed, u16, 4, 0..=2047`, and then generates the `MIN` and `MAX`
+//! constants, a `const fn new` panicking outside of the range, `try_new` returning `OutOfRange`
+//! and `saturating` clamping to the range. The global parameters with a range use the same
+//! `param_define_range!`.
+//!
+//! ## `axis_param_enum` macro
+//! Registers selecting one of a set of values are defined as an enum with the value of every
+//! variant, `axis_param_enum!` generates `TryFrom<u8>`, `Return` and both the readable and the
+//! writeable trait.
+//!
+//! ## `bitfield` macro
+//! Registers packing several fields into one value are defined with an unsigned type and
+//! `bitfield!` adds an accessor per field, either a single bit (`name: 3`) returning `bool`
+//! or a range of bits (`name: 4..8 as u8`) returning the given type. It is exported from the
+//! crate, see its documentation.
+//!
+//! ## `tmcm_axis_parameter` macro
+//! Exported from the crate, for axis parameters of a single module family that are not part of
+//! `modules::tmcm::axis_parameters`, see its documentation.
 
 macro_rules! axis_param_r {
     ($(#[$doc:meta])* $name:ident, $ty:ty, $number:expr) => {
@@ -24,12 +45,17 @@ macro_rules! axis_param_rw {
         axis_param_define_read!($name, $ty);
         axis_param_define_write!($name, $ty);
     };
+    ($(#[$doc:meta])* $name:ident, $ty:tt, $number:expr, $min:literal..=$max:literal) => {
+        axis_param_rw!($(#[$doc])* $name, $ty, $number);
+        param_define_range!($name, $ty, $min, $max);
+    };
 }
 
 macro_rules! axis_param_define{
     ($(#[$doc:meta])* $name:ident, $ty:ty, $number:expr) => {
         $(#[$doc])*
         #[derive(Debug, PartialEq)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
         pub struct $name($ty);
 
         impl From<$name> for $ty {
@@ -46,63 +72,340 @@ macro_rules! axis_param_define{
 
 macro_rules! axis_param_define_read {
     ($name:ident, $ty:ty) => {
-        impl Return for $name {
-            fn from_operand(operand: [u8; 4]) -> Self {
-                $name(<$ty as Return>::from_operand(operand))
+        impl FromOperand for $name {
+            fn from_operand(operand: Operand) -> Self {
+                $name(<$ty as FromOperand>::from_operand(operand))
             }
         }
         impl ReadableAxisParameter for $name {}
     };
 }
 
+macro_rules! param_define_range {
+    ($name:ident, $ty:ty, $min:literal, $max:literal) => {
+        impl $name {
+            /// The smallest valid value
+            pub const MIN: $ty = $min;
+
+            /// The largest valid value
+            pub const MAX: $ty = $max;
+
+            /// Panics if `value` is outside of `MIN..=MAX`
+            pub const fn new(value: $ty) -> Self {
+                match $name::try_new(value) {
+                    Ok(parameter) => parameter,
+                    Err(_) => panic!(concat!("the value of ", stringify!($name), " must be in ", stringify!($min..=$max))),
+                }
+            }
+
+            /// Returns `OutOfRange` if `value` is outside of `MIN..=MAX`
+            pub const fn try_new(value: $ty) -> Result<Self, ::OutOfRange> {
+                match value {
+                    $min..=$max => Ok($name(value)),
+                    _ => Err(::OutOfRange),
+                }
+            }
+
+            /// Clamps `value` to `MIN..=MAX`
+            pub fn saturating(value: $ty) -> Self {
+                $name(value.clamp($name::MIN, $name::MAX))
+            }
+        }
+    };
+}
+
 macro_rules! axis_param_define_write {
     ($name:ident, u32) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [(self.0 >> 0) as u8, (self.0 >> 8) as u8, (self.0 >> 16) as u8 , (self.0 >> 24) as u8]
+            fn operand(&self) -> Operand {
+                Operand::from_u32(self.0)
             }
         }
     };
     ($name:ident, u16) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [(self.0 >> 0) as u8, (self.0 >> 8) as u8, 0u8 , 0u8]
+            fn operand(&self) -> Operand {
+                Operand::from_u32(u32::from(self.0))
             }
         }
     };
     ($name:ident, u8) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [(self.0 >> 0) as u8, 0u8, 0u8 , 0u8]
+            fn operand(&self) -> Operand {
+                Operand::from_u32(u32::from(self.0))
             }
         }
     };
     ($name:ident, i32) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [(self.0 >> 0) as u8, (self.0 >> 8) as u8, (self.0 >> 16) as u8 , (self.0 >> 24) as u8]
+            fn operand(&self) -> Operand {
+                Operand::from_i32(self.0)
             }
         }
     };
     ($name:ident, i16) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [(self.0 >> 0) as u8, (self.0 >> 8) as u8, 0u8, 0u8]
+            fn operand(&self) -> Operand {
+                Operand::from_i32(i32::from(self.0))
             }
         }
     };
     ($name:ident, i8) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [(self.0 >> 0) as u8, 0u8, 0u8, 0u8]
+            fn operand(&self) -> Operand {
+                Operand::from_i32(i32::from(self.0))
+            }
+        }
+    };
+    ($name:ident, I12) => {
+        impl WriteableAxisParameter for $name {
+            fn operand(&self) -> Operand {
+                self.0.operand()
+            }
+        }
+    };
+    ($name:ident, I24) => {
+        impl WriteableAxisParameter for $name {
+            fn operand(&self) -> Operand {
+                self.0.operand()
             }
         }
     };
     ($name:ident, bool) => {
         impl WriteableAxisParameter for $name {
-            fn operand(&self) -> [u8; 4] {
-                [self.0 as u8, 0, 0, 0]
+            fn operand(&self) -> Operand {
+                Operand::from_u32(u32::from(self.0))
+            }
+        }
+    };
+}
+macro_rules! axis_param_enum {
+    ($(#[$doc:meta])* $name:ident, $number:literal { $($(#[$variant_doc:meta])* $variant:ident = $value:literal,)* }) => {
+        $(#[$doc])*
+        #[derive(Debug, PartialEq, Clone, Copy)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub enum $name {
+            $($(#[$variant_doc])* $variant = $value,)*
+        }
+
+        impl ::lib::convert::TryFrom<u8> for $name {
+            type Error = InvalidOperand;
+
+            fn try_from(v: u8) -> Result<Self, InvalidOperand> {
+                match v {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(InvalidOperand),
+                }
+            }
+        }
+
+        impl AxisParameter for $name {
+            const NUMBER: u8 = $number;
+        }
+
+        impl Return for $name {
+            fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> {
+                <$name as ::lib::convert::TryFrom<u8>>::try_from(operand[0])
+            }
+        }
+        impl ReadableAxisParameter for $name {}
+
+        impl WriteableAxisParameter for $name {
+            fn operand(&self) -> Operand {
+                Operand::from_u32(u32::from(*self as u8))
             }
         }
     };
-}
\ No newline at end of file
+}
+
+/// Add accessors for the fields of a register packing several fields into one value.
+///
+/// The register is a newtype around an unsigned integer of up to 32 bits. A field is either a
+/// single bit (`name: 3`) returning `bool`, or the bits from `low` up to but not including `high`
+/// (`name: 4..8 as u8`) returning them shifted down as the given type.
+///
+/// ```
+/// #[macro_use]
+/// extern crate tmcl;
+///
+/// struct Status(u32);
+/// bitfield!(Status {
+///     /// The motor is enabled
+///     enabled: 0,
+///
+///     /// The selected profile
+///     profile: 4..8 as u8,
+///
+///     /// All bits
+///     raw: 0..32 as u32,
+/// });
+///
+/// fn main() {
+///     let status = Status(0xffff_ff51);
+///     assert!(status.enabled());
+///     assert_eq!(status.profile(), 5);
+///     assert_eq!(status.raw(), 0xffff_ff51);
+/// }
+/// ```
+#[macro_export]
+macro_rules! bitfield {
+    ($name:ident {}) => {};
+    ($name:ident { $(#[$doc:meta])* $field:ident: $bit:literal, $($rest:tt)* }) => {
+        impl $name {
+            $(#[$doc])*
+            pub fn $field(&self) -> bool {
+                (u32::from(self.0) >> $bit) & 1 != 0
+            }
+        }
+        $crate::bitfield!($name { $($rest)* });
+    };
+    ($name:ident { $(#[$doc:meta])* $field:ident: $low:literal..$high:literal as $ty:ident, $($rest:tt)* }) => {
+        impl $name {
+            $(#[$doc])*
+            pub fn $field(&self) -> $ty {
+                ((u32::from(self.0) >> $low) & (u32::MAX >> (32 - ($high - $low)))) as $ty
+            }
+        }
+        $crate::bitfield!($name { $($rest)* });
+    };
+}
+
+/// Define an axis parameter useable with `TmcmModule` outside of this crate.
+///
+/// Parameters only available on some module families, e.g. the stallGuard or coolStep settings,
+/// can be defined by the application or by a crate for that family without changing this crate.
+/// The parameter is a newtype around a primitive integer or `bool` with a `new` constructor, and
+/// is readable, writable or both depending on the access given after the number: `read`, `write`
+/// or `read_write`. Further operations can be added to `Axis` or `TmcmModule` with an extension
+/// trait.
+///
+/// ```no_run
+/// #[macro_use]
+/// extern crate tmcl;
+/// extern crate interior_mut;
+///
+/// use std::cell::RefCell;
+/// use interior_mut::InteriorMut;
+/// use tmcl::Error;
+/// use tmcl::Interface;
+/// use tmcl::modules::tmcm::TmcmModule as Module;
+/// use tmcl::modules::tmcm::axis::Axis;
+/// use std::ops::Deref;
+///
+/// # use tmcl::Encode;
+/// # use tmcl::Command;
+/// # use tmcl::Reply;
+/// #
+/// # struct MyInterface();
+/// # #[derive(Debug)]
+/// # struct MyInterfaceError();
+/// #
+/// # impl MyInterface { fn new() -> Self {unimplemented!()} }
+/// #
+/// # impl Interface for MyInterface {
+///    # type Error = MyInterfaceError;
+///    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+///        # unimplemented!()
+///    # }
+///    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+///        # unimplemented!()
+///    # }
+/// # }
+/// #
+/// tmcm_axis_parameter!(
+///     /// The stallGuard threshold of modules with a TMC2130 driver
+///     StallGuardThreshold: i8 = 174, read_write
+/// );
+///
+/// trait StallGuard<E> {
+///     fn set_stall_guard_threshold(&self, threshold: i8) -> Result<(), Error<E>>;
+/// }
+///
+/// impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> StallGuard<IF::Error> for Axis<'a, IF, Cell, T> {
+///     fn set_stall_guard_threshold(&self, threshold: i8) -> Result<(), Error<IF::Error>> {
+///         self.set(StallGuardThreshold::new(threshold))
+///     }
+/// }
+///
+/// fn main() {
+///     let interface = RefCell::new(MyInterface::new());
+///     let module = Module::new(&interface, 1);
+///
+///     module.axis(0).set_stall_guard_threshold(-10).unwrap();
+///     println!("{:?}", module.axis(0).get::<StallGuardThreshold>().unwrap());
+/// }
+/// ```
+///
+/// Signed values are sign extended to 32 bits, like those of the parameters of this crate.
+///
+/// ```
+/// #[macro_use]
+/// extern crate tmcl;
+///
+/// use tmcl::{Operand, WriteableAxisParameter};
+///
+/// tmcm_axis_parameter!(
+///     /// The stallGuard threshold of modules with a TMC2130 driver
+///     StallGuardThreshold: i8 = 174, read_write
+/// );
+///
+/// fn main() {
+///     assert_eq!(StallGuardThreshold::new(-10).operand(), Operand::from_i32(-10));
+/// }
+/// ```
+#[macro_export]
+macro_rules! tmcm_axis_parameter {
+    ($(#[$doc:meta])* $name:ident: $ty:ty = $number:expr, read) => {
+        tmcm_axis_parameter!(@define $(#[$doc])* $name, $ty, $number);
+        tmcm_axis_parameter!(@read $name, $ty);
+    };
+    ($(#[$doc:meta])* $name:ident: $ty:ty = $number:expr, write) => {
+        tmcm_axis_parameter!(@define $(#[$doc])* $name, $ty, $number);
+        tmcm_axis_parameter!(@write $name, $ty);
+    };
+    ($(#[$doc:meta])* $name:ident: $ty:ty = $number:expr, read_write) => {
+        tmcm_axis_parameter!(@define $(#[$doc])* $name, $ty, $number);
+        tmcm_axis_parameter!(@read $name, $ty);
+        tmcm_axis_parameter!(@write $name, $ty);
+    };
+    (@define $(#[$doc:meta])* $name:ident, $ty:ty, $number:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, PartialEq, Clone, Copy)]
+        pub struct $name($ty);
+
+        impl $name {
+            pub fn new(value: $ty) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $ty {
+            fn from(v: $name) -> $ty {
+                v.0
+            }
+        }
+
+        impl $crate::AxisParameter for $name {
+            const NUMBER: u8 = $number;
+        }
+
+        impl $crate::modules::tmcm::TmcmAxisParameter for $name {}
+    };
+    (@read $name:ident, $ty:ty) => {
+        impl $crate::FromOperand for $name {
+            fn from_operand(operand: $crate::Operand) -> Self {
+                $name(<$ty as $crate::FromOperand>::from_operand(operand))
+            }
+        }
+        impl $crate::ReadableAxisParameter for $name {}
+        impl $crate::modules::tmcm::ReadableTmcmAxisParameter for $name {}
+    };
+    (@write $name:ident, $ty:ty) => {
+        impl $crate::WriteableAxisParameter for $name {
+            fn operand(&self) -> $crate::Operand {
+                $crate::Operand::from_i32(self.0 as i32)
+            }
+        }
+        impl $crate::modules::tmcm::WriteableTmcmAxisParameter for $name {}
+    };
+}
@@ -0,0 +1,198 @@
+//! Interactive TMCL control of modules connected over a serial line or CAN.
+//!
+//! ```text
+//! tmcl serial /dev/ttyACM0 [address]
+//! tmcl can can0 [address]
+//! ```
+//!
+//! The serial line must already be configured (e.g. with `stty`) to match the module.
+
+extern crate tmcl;
+#[cfg(feature = "socketcan")]
+extern crate socketcan;
+
+use std::cell::RefCell;
+use std::env;
+use std::fmt::Debug;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::{BufRead, Write};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use tmcl::Interface;
+use tmcl::FromOperand;
+use tmcl::opcodes::{OPCODE_GAP, OPCODE_SAP};
+use tmcl::demux::Demux;
+use tmcl::serial::Serial;
+use tmcl::program::{self, TmclProgram};
+use tmcl::modules::tmcm::TmcmModule;
+use tmcl::modules::tmcm::instructions::{GAP, RawInstruction};
+use tmcl::modules::tmcm::axis_parameters::ActualPosition;
+use tmcl::modules::tmcm::parameter_info::AXIS_PARAMETERS;
+
+const USAGE: &str = "usage: tmcl serial <tty> [address]\n       tmcl can <interface> [address]";
+
+const HELP: &str = "\
+<MNEMONIC> <type> <motor> <value>   send an instruction, e.g. `MVP 0 0 1000`
+get <parameter> <motor>             read an axis parameter by name, mnemonic or number
+set <parameter> <motor> <value>     write an axis parameter by name, mnemonic or number
+download <file>                     download a program, one instruction per line
+monitor <motor> [samples]           print the actual position every 100 ms
+module <address>                    talk to another module
+parameters                          list the known axis parameters
+help                                show this text
+quit                                leave";
+
+const MONITOR_INTERVAL: Duration = Duration::from_millis(100);
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let address = match args.get(3) {
+        Some(address) => address.parse().unwrap_or_else(|_| exit(USAGE)),
+        None => 1,
+    };
+
+    match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("serial"), Some(path)) => {
+            let tty = OpenOptions::new().read(true).write(true).open(path)
+                .unwrap_or_else(|e| exit(&format!("could not open {}: {}", path, e)));
+            repl(Demux::new(Serial::new(tty)), address);
+        },
+        #[cfg(feature = "socketcan")]
+        (Some("can"), Some(name)) => {
+            let socket = socketcan::CANSocket::open(name)
+                .unwrap_or_else(|e| exit(&format!("could not open {}: {}", name, e)));
+            repl(socket, address);
+        },
+        #[cfg(not(feature = "socketcan"))]
+        (Some("can"), Some(_)) => exit("CAN support requires the socketcan feature"),
+        _ => exit(USAGE),
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+fn repl<IF: Interface>(interface: IF, mut address: u8) where IF::Error: Debug {
+    let interface = RefCell::new(interface);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("tmcl[{}]> ", address);
+        let _ = io::stdout().flush();
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return,
+        };
+        match execute(&interface, &mut address, &line) {
+            Ok(true) => (),
+            Ok(false) => return,
+            Err(message) => println!("error: {}", message),
+        }
+    }
+}
+
+/// Execute one line of input, returns `Ok(false)` when the user wants to leave.
+fn execute<IF: Interface>(interface: &RefCell<IF>, address: &mut u8, line: &str) -> Result<bool, String> where IF::Error: Debug {
+    let module = TmcmModule::new(interface, *address);
+    let words: Vec<&str> = tokenize(line);
+
+    match words.as_slice() {
+        [] => (),
+        ["quit"] | ["exit"] => return Ok(false),
+        ["help"] => println!("{}", HELP),
+        ["parameters"] => {
+            for info in AXIS_PARAMETERS {
+                println!("{:>3} {:<5} {}", info.number(), info.mnemonic(), info.name());
+            }
+        },
+        ["module", new_address] => *address = parse(new_address)?,
+        ["get", parameter, motor] => {
+            let operand = module.write_raw_command(OPCODE_GAP, parameter_number(parameter)?, parse(motor)?, 0)
+                .map_err(|e| format!("{:?}", e))?;
+            println!("{}", i32::from_operand(operand));
+        },
+        ["set", parameter, motor, value] => {
+            module.write_raw_command(OPCODE_SAP, parameter_number(parameter)?, parse(motor)?, parse(value)?)
+                .map_err(|e| format!("{:?}", e))?;
+        },
+        ["download", path] => {
+            let source = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+            let program = parse_program(&source)?;
+            module.download_program(&program).map_err(|e| format!("{:?}", e))?;
+            println!("downloaded {} instructions", program.len());
+        },
+        ["monitor", motor] => monitor(&module, parse(motor)?, None)?,
+        ["monitor", motor, samples] => monitor(&module, parse(motor)?, Some(parse(samples)?))?,
+        [mnemonic, type_number, motor, value] => {
+            let instruction_number = program::instruction_number(mnemonic)
+                .ok_or_else(|| format!("unknown instruction {}", mnemonic))?;
+            let operand = module.write_raw_command(instruction_number, parse(type_number)?, parse(motor)?, parse(value)?)
+                .map_err(|e| format!("{:?}", e))?;
+            println!("{}", i32::from_operand(operand));
+        },
+        _ => return Err(String::from("unknown command, try `help`")),
+    }
+
+    Ok(true)
+}
+
+/// Print the actual position of `motor` until `samples` positions have been printed (forever if `None`).
+fn monitor<IF: Interface>(module: &TmcmModule<IF, RefCell<IF>, &RefCell<IF>>, motor: u8, samples: Option<u32>) -> Result<(), String> where IF::Error: Debug {
+    let mut printed = 0;
+    while samples.is_none_or(|samples| printed < samples) {
+        let position = module.write_command(GAP::<ActualPosition>::new(motor)).map_err(|e| format!("{:?}", e))?;
+        println!("{}", i32::from(position));
+        printed += 1;
+        thread::sleep(MONITOR_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Parse a program in the same format as a disassembled listing: `[address:] MNEMONIC type, motor, value`
+///
+/// Empty lines and everything following `//` are ignored.
+fn parse_program(source: &str) -> Result<TmclProgram, String> {
+    let mut program = TmclProgram::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or("");
+        let mut words = tokenize(line);
+        if words.first().is_some_and(|word| word.ends_with(':')) {
+            words.remove(0);
+        }
+        match words.as_slice() {
+            [] => (),
+            [mnemonic, type_number, motor, value] => {
+                let instruction_number = program::instruction_number(mnemonic)
+                    .ok_or_else(|| format!("line {}: unknown instruction {}", line_number + 1, mnemonic))?;
+                program.push(RawInstruction::new(instruction_number, parse(type_number)?, parse(motor)?, parse(value)?));
+            },
+            _ => return Err(format!("line {}: expected `MNEMONIC type, motor, value`", line_number + 1)),
+        }
+    }
+    Ok(program)
+}
+
+fn tokenize(line: &str) -> Vec<&str> {
+    line.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn parameter_number(parameter: &str) -> Result<u8, String> {
+    AXIS_PARAMETERS.iter()
+        .find(|info| info.name().eq_ignore_ascii_case(parameter) || info.mnemonic().eq_ignore_ascii_case(parameter))
+        .map(|info| Ok(info.number()))
+        .unwrap_or_else(|| parse(parameter))
+}
+
+fn parse<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("invalid number {}", word))
+}
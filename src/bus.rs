@@ -0,0 +1,593 @@
+//! Operations spanning several modules connected to the same interface.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::bus::Bus;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let bus = Bus::new(&interface);
+//!
+//!     // Move axis 0 of module 1 and axis 2 of module 3 so they arrive at the same time.
+//!     bus.move_all(&[(1, 0, 10000), (3, 2, -2500)], MaximumPositioningSpeed::new(1000), MaximumAcceleration::new(500)).unwrap();
+//!     bus.wait_for_all(&[(1, 0), (3, 2)], || std::thread::sleep(std::time::Duration::from_millis(10))).unwrap();
+//! }
+//! ```
+
+use lib::convert::TryFrom;
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use cancel::Cancel;
+use Interface;
+use instructions::DirectInstruction;
+use opcodes::OPCODE_GGP;
+use kinematics::LinearKinematics;
+
+use modules::tmcm::{
+    TmcmModule,
+    TmcmInstruction,
+    ReadableTmcmGlobalParameter,
+    WriteableTmcmGlobalParameter,
+};
+use modules::tmcm::instructions::{
+    GAP,
+    SAP,
+    MVP,
+    MST,
+    SGP,
+    GGP,
+    GetFirmwareVersion,
+    FirmwareVersion,
+    MoveOperation,
+};
+use modules::tmcm::axis_parameters::{
+    ActualPosition,
+    MaximumPositioningSpeed,
+    MaximumAcceleration,
+    PulseDivisor,
+    RampDivisor,
+    TargetPositionReached,
+};
+use modules::tmcm::ramp::{acceleration_limits, validate_acceleration};
+use modules::tmcm::global_parameters::{
+    SerialAddress,
+    CanId,
+    CanSecondaryId,
+    CanBitrate,
+};
+
+/// The aggregated result of `Bus::emergency_stop_all`.
+#[derive(Debug, PartialEq)]
+pub struct EmergencyStopReport<E> {
+    stopped: usize,
+    failed: usize,
+    first_error: Option<Error<E>>,
+}
+
+impl<E> EmergencyStopReport<E> {
+    /// Number of axes that acknowledged the stop
+    pub fn stopped(&self) -> usize {
+        self.stopped
+    }
+
+    /// Number of axes where the stop could not be confirmed
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// The first error that occurred, if any
+    pub fn first_error(&self) -> Option<&Error<E>> {
+        self.first_error.as_ref()
+    }
+
+    /// Returns true if every axis acknowledged the stop
+    pub fn is_ok(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// A module found by `Bus::scan`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DiscoveredModule {
+    address: u8,
+    firmware_version: Option<FirmwareVersion>,
+}
+
+impl DiscoveredModule {
+    /// The address the module replied on
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// The module type and firmware version, `None` if the module did not report it
+    pub fn firmware_version(&self) -> Option<FirmwareVersion> {
+        self.firmware_version
+    }
+}
+
+/// All modules connected to a single interface.
+///
+/// Commands are written through a `TmcmModule` with the default settings, which is pointed at the
+/// address of every command, so they get the same protocol handling as commands written to a
+/// module directly.
+#[derive(Debug)]
+pub struct Bus<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell> + 'a> {
+    module: TmcmModule<'a, IF, Cell, T>,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> Bus<'a, IF, Cell, T> {
+    /// Create a new bus
+    pub fn new(interface: T) -> Self {
+        Bus{
+            module: TmcmModule::new(interface, 0),
+        }
+    }
+
+    /// Create a bus writing through `module`, its address is ignored
+    ///
+    /// The settings of the module apply to every command written through the bus, e.g. a
+    /// persistence guard, restricted capabilities or an audit trail.
+    pub fn from_module(module: TmcmModule<'a, IF, Cell, T>) -> Self {
+        Bus{module}
+    }
+
+    /// Create a handle to the module with the given address
+    pub fn module(&self, address: u8) -> TmcmModule<'a, IF, Cell, T> where T: Clone {
+        TmcmModule::new(self.module.interface().clone(), address)
+    }
+
+    /// Synchronously write a command to the module with the given address and wait for the Reply
+    pub fn write_command<Instruction: TmcmInstruction + DirectInstruction>(&'a self, address: u8, instruction: Instruction) -> Result<Instruction::Return, Error<IF::Error>> {
+        self.module.write_command_to(address, None, instruction)
+    }
+
+    /// Probe every address with `GetFirmwareVersion` and call `found` for every module that replies.
+    ///
+    /// An address is considered empty if no reply is received within `timeout`, which requires
+    /// the interface to implement `Interface::receive_reply_timeout`.
+    /// Modules replying with an error (e.g. because they don't support `GetFirmwareVersion`) are
+    /// reported without identity. Returns the number of modules found.
+    pub fn scan<I, F>(&'a self, addresses: I, timeout: Duration, mut found: F) -> Result<usize, Error<IF::Error>>
+    where
+        I: IntoIterator<Item=u8>,
+        F: FnMut(DiscoveredModule),
+    {
+        let mut count = 0;
+        for address in addresses {
+            let firmware_version = match self.write_command_timeout(address, GetFirmwareVersion, timeout) {
+                Ok(firmware_version) => Some(firmware_version),
+                Err(Error::Timeout) => continue,
+                Err(Error::ProtocolError(..)) | Err(Error::InvalidReply) => None,
+                Err(e) => return Err(e),
+            };
+            found(DiscoveredModule{address, firmware_version});
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Change the serial address of the module at `old_address` to `new_address`.
+    ///
+    /// The module must reply on `old_address` and no module may reply on `new_address` within
+    /// `timeout`, otherwise `Error::Timeout` respectively `Error::AddressInUse` is returned.
+    /// Any reply on `new_address`, including an error status, means the address is in use, other
+    /// errors of probing it are returned unchanged.
+    /// After the address has been written it is read back on `new_address`.
+    /// `Error::InvalidAddress` is returned without contacting the module if `new_address` is 0.
+    /// The address is stored in the EEPROM of the module, the commands of a `Bus` are only guarded
+    /// if it was created `from_module` a module created `with_persistence_guard`.
+    /// Useful when commissioning several identical modules that all come with the same default address.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use std::time::Duration;
+    /// use tmcl::Error;
+    /// use tmcl::bus::Bus;
+    /// use tmcl::testing::{Ordering, Script};
+    ///
+    /// let interface = RefCell::new(Script::new(Ordering::Strict));
+    /// let bus = Bus::new(&interface);
+    ///
+    /// let result = bus.assign_serial_address(1, 0, Duration::from_millis(100));
+    /// assert!(matches!(result, Err(Error::InvalidAddress(0))));
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn assign_serial_address(&'a self, old_address: u8, new_address: u8, timeout: Duration) -> Result<(), Error<IF::Error>> {
+        let parameter = SerialAddress::try_new(new_address).or(Err(Error::InvalidAddress(u16::from(new_address))))?;
+        self.assign_address(old_address, new_address, parameter, timeout)
+    }
+
+    /// Change the CAN ID of the module at `old_address` to `new_address`.
+    ///
+    /// The same checks as in `assign_serial_address` are done.
+    pub fn assign_can_id(&'a self, old_address: u8, new_address: u8, timeout: Duration) -> Result<(), Error<IF::Error>> {
+        let parameter = CanId::try_new(u16::from(new_address)).or(Err(Error::InvalidAddress(u16::from(new_address))))?;
+        self.assign_address(old_address, new_address, parameter, timeout)
+    }
+
+    /// Make the modules with the given addresses members of the group `group_id` by setting their CAN secondary ID
+    ///
+    /// Every module must reply on its address. The ID is read back from every module and
+    /// `Error::VerificationFailed` is returned if it was not taken over. The ID is stored in EEPROM.
+    /// `Error::InvalidAddress` is returned without contacting a module if `group_id` is 0, which
+    /// means no group, or above 0x7ff.
+    pub fn join_can_group(&'a self, addresses: &[u8], group_id: u16) -> Result<(), Error<IF::Error>> {
+        if group_id == 0 {
+            return Err(Error::InvalidAddress(group_id));
+        }
+        let parameter = CanSecondaryId::try_new(group_id).or(Err(Error::InvalidAddress(group_id)))?;
+        for &address in addresses {
+            self.write_command(address, SGP::new(parameter))?;
+            let read = self.write_command(address, GGP::<CanSecondaryId>::new())?;
+            if read != parameter {
+                return Err(Error::VerificationFailed{written: i32::from(group_id), read: i32::from(u16::from(read))});
+            }
+        }
+        Ok(())
+    }
+
+    /// Transmit a command to all modules in the group `group_id`, e.g. `ROR` to start several drives at once
+    ///
+    /// The modules execute the command without replying, so only the transmission is confirmed.
+    /// The group is configured with `join_can_group` or `TmcmModule::set_can_group_id`.
+    /// The command passes the persistence guard, the capability check and the audit trail of the
+    /// bus like every other command, see `from_module`.
+    ///
+    /// The address of a `Command` is a single byte, `Error::InvalidAddress` is returned without
+    /// transmitting anything if `group_id` is 0, which means no group, or above 255.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use tmcl::Error;
+    /// use tmcl::bus::Bus;
+    /// use tmcl::modules::tmcm::TmcmModule;
+    /// use tmcl::modules::tmcm::axis_parameters::MaximumPositioningSpeed;
+    /// use tmcl::modules::tmcm::instructions::{ROR, STAP};
+    /// use tmcl::testing::{Ordering, Script};
+    ///
+    /// let interface = RefCell::new(Script::new(Ordering::Strict));
+    /// let bus = Bus::from_module(TmcmModule::new(&interface, 0).with_persistence_guard());
+    ///
+    /// assert!(matches!(bus.write_group_command(0, ROR::new(0, 100)), Err(Error::InvalidAddress(0))));
+    /// assert!(matches!(bus.write_group_command(0x100, ROR::new(0, 100)), Err(Error::InvalidAddress(0x100))));
+    /// assert!(matches!(
+    ///     bus.write_group_command(5, STAP::<MaximumPositioningSpeed>::new(0)),
+    ///     Err(Error::PersistenceNotAllowed)
+    /// ));
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn write_group_command<Instruction: TmcmInstruction + DirectInstruction>(&'a self, group_id: u16, instruction: Instruction) -> Result<(), Error<IF::Error>> {
+        let address = match u8::try_from(group_id) {
+            Ok(0) | Err(_) => return Err(Error::InvalidAddress(group_id)),
+            Ok(address) => address,
+        };
+        self.module.send_command_to(address, instruction)
+    }
+
+    /// Change the CAN bit rate of the module at `address` to `bitrate`, rolling back if it is lost.
+    ///
+    /// The current bit rate is read and the new one written. Then `reopen` is called with the
+    /// interface and the new bit rate, it must make the module apply it (the module only does
+    /// after a reset, e.g. by asking the operator to power cycle it) and reopen the interface at the
+    /// new rate, e.g. by replacing it with a new socket. Communication is verified by reading the bit
+    /// rate back within `timeout`. The bit rate is stored in EEPROM.
+    ///
+    /// If verification fails for any reason, including the interface errors a bit rate mismatch
+    /// typically causes, `rollback` is called with the old bit rate. It must restore it on the
+    /// module through another way, e.g. its serial interface, and reset the module. An error of
+    /// `rollback` is returned as `Error::InterfaceError`. The interface is reopened at the old rate
+    /// with `reopen`, and the error of the verification is returned once the module replies on the
+    /// old rate again. If it does not, the error of that check is returned.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::{Cell, RefCell};
+    /// use std::time::Duration;
+    /// use tmcl::{Error, ErrorKind};
+    /// use tmcl::bus::Bus;
+    /// use tmcl::opcodes::{OPCODE_GGP, OPCODE_SGP};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::global_parameters::CanBitrate;
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     .expect(expect_command(OPCODE_GGP).type_number(69..=69).reply_with_value(6))
+    ///     .expect(expect_command(OPCODE_SGP).type_number(69..=69).value(8..=8));
+    /// let interface = RefCell::new(script);
+    /// let bus = Bus::new(&interface);
+    /// let rolled_back = Cell::new(None);
+    ///
+    /// // The module is lost at the new rate, the script fails the verification with an interface
+    /// // error, and replies again once it is reopened at the old rate.
+    /// let result = bus.change_can_bitrate(1, CanBitrate::Kbps1000, Duration::from_millis(100),
+    ///     |script, bitrate| {
+    ///         if bitrate == CanBitrate::Kbps250 {
+    ///             script.push(expect_command(OPCODE_GGP).type_number(69..=69).reply_with_value(6));
+    ///         }
+    ///         Ok(())
+    ///     },
+    ///     |bitrate| {
+    ///         rolled_back.set(Some(bitrate));
+    ///         Ok(())
+    ///     },
+    /// );
+    /// assert_eq!(result.unwrap_err().kind(), ErrorKind::Io);
+    /// assert_eq!(rolled_back.get(), Some(CanBitrate::Kbps250));
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn change_can_bitrate<R, F>(&'a self, address: u8, bitrate: CanBitrate, timeout: Duration, mut reopen: R, rollback: F) -> Result<(), Error<IF::Error>>
+    where
+        R: FnMut(&mut IF, CanBitrate) -> Result<(), IF::Error>,
+        F: FnOnce(CanBitrate) -> Result<(), IF::Error>,
+    {
+        let old_bitrate = self.write_command_timeout(address, GGP::<CanBitrate>::new(), timeout)?;
+        if old_bitrate == bitrate {
+            return Ok(());
+        }
+        self.write_command_timeout(address, SGP::new(bitrate), timeout)?;
+        self.reopen(address, bitrate, &mut reopen)?;
+        let error = match self.verify_can_bitrate(address, bitrate, timeout) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+
+        rollback(old_bitrate).map_err(Error::InterfaceError)?;
+        self.reopen(address, old_bitrate, &mut reopen)?;
+        self.verify_can_bitrate(address, old_bitrate, timeout)?;
+        Err(error)
+    }
+
+    fn reopen<R: FnMut(&mut IF, CanBitrate) -> Result<(), IF::Error>>(&'a self, address: u8, bitrate: CanBitrate, reopen: &mut R) -> Result<(), Error<IF::Error>> {
+        let mut interface = self.module.interface().borrow_int_mut()
+            .or(Err(Error::InterfaceUnavailable{address, instruction_number: OPCODE_GGP}))?;
+        reopen(&mut interface, bitrate).map_err(Error::InterfaceError)
+    }
+
+    fn verify_can_bitrate(&'a self, address: u8, bitrate: CanBitrate, timeout: Duration) -> Result<(), Error<IF::Error>> {
+        let read = self.write_command_timeout(address, GGP::<CanBitrate>::new(), timeout)?;
+        if read != bitrate {
+            return Err(Error::VerificationFailed{written: bitrate as i32, read: read as i32});
+        }
+        Ok(())
+    }
+
+    fn assign_address<P>(&'a self, old_address: u8, new_address: u8, parameter: P, timeout: Duration) -> Result<(), Error<IF::Error>>
+    where
+        P: ReadableTmcmGlobalParameter + WriteableTmcmGlobalParameter + PartialEq + Copy,
+    {
+        self.write_command_timeout(old_address, GetFirmwareVersion, timeout)?;
+        if old_address == new_address {
+            return Ok(());
+        }
+        match self.write_command_timeout(new_address, GetFirmwareVersion, timeout) {
+            Err(Error::Timeout) => (),
+            Ok(_) | Err(Error::ProtocolError(..)) => return Err(Error::AddressInUse),
+            Err(e) => return Err(e),
+        }
+
+        self.write_command_timeout(old_address, SGP::new(parameter), timeout)?;
+
+        let read = self.write_command_timeout(new_address, GGP::<P>::new(), timeout)?;
+        if read != parameter {
+            return Err(Error::VerificationFailed{written: parameter.operand().to_i32(), read: read.operand().to_i32()});
+        }
+        Ok(())
+    }
+
+    fn write_command_timeout<Instruction: TmcmInstruction + DirectInstruction>(&'a self, address: u8, instruction: Instruction, timeout: Duration) -> Result<Instruction::Return, Error<IF::Error>> {
+        self.module.write_command_to(address, Some(timeout), instruction)
+    }
+
+    /// Move several axes, given as `(module address, motor number, target position)`, so that they
+    /// all arrive at their targets at the same time.
+    ///
+    /// The axis with the longest distance to travel will move with `speed` and `acceleration`, the
+    /// other axes will have both their `MaximumPositioningSpeed` and their `MaximumAcceleration`
+    /// scaled down proportionally to their distance. Scaling both by the same ratio scales the
+    /// whole trapezoidal ramp, so every axis spends the same time accelerating, cruising and
+    /// decelerating. The scaled accelerations are validated against the divisors of every axis,
+    /// see the `ramp` module, and `Error::InvalidRamp` is returned before anything is written if
+    /// one is outside the allowed range. All moves are issued back-to-back after the speeds and
+    /// accelerations have been written.
+    ///
+    /// Axes that are already at their target are skipped. A scaled acceleration below the lowest
+    /// one allowed by the divisors of its axis is raised to it, so a short move may arrive slightly
+    /// early. The actual positions are read once and returned.
+    ///
+    /// As with `MoveOperation::Coordinate` the ramp and pulse divisors of all axes must be set to
+    /// the same values, as otherwise the axes will not arrive at the same time.
+    ///
+    /// The scaled speeds and accelerations are not restored after the move, they stay in effect
+    /// until they are written again, e.g. with `TmcmModule::set_ramp`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use tmcl::bus::Bus;
+    /// use tmcl::opcodes::{OPCODE_GAP, OPCODE_MVP, OPCODE_SAP};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::axis_parameters::{MaximumAcceleration, MaximumPositioningSpeed};
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     // The first axis moves 1000 microsteps, the second stays put and the third moves 10.
+    ///     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(0))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(250))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(0))
+    ///     // Pulse divisor 3 and ramp divisor 7, which allow accelerations from 8.
+    ///     .expect(expect_command(OPCODE_GAP).type_number(154..=154).reply_with_value(3))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(153..=153).reply_with_value(7))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(154..=154).reply_with_value(3))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(153..=153).reply_with_value(7))
+    ///     // The third axis moves with a hundredth of the speed, its acceleration is raised to 8.
+    ///     .expect(expect_command(OPCODE_SAP).type_number(4..=4).value(1000..=1000))
+    ///     .expect(expect_command(OPCODE_SAP).type_number(5..=5).value(200..=200))
+    ///     .expect(expect_command(OPCODE_SAP).type_number(4..=4).value(10..=10))
+    ///     .expect(expect_command(OPCODE_SAP).type_number(5..=5).value(8..=8))
+    ///     .expect(expect_command(OPCODE_MVP).value(1000..=1000))
+    ///     .expect(expect_command(OPCODE_MVP).value(10..=10));
+    /// let interface = RefCell::new(script);
+    /// let bus = Bus::new(&interface);
+    ///
+    /// let moves = [(1, 0, 1000), (2, 0, 250), (3, 0, 10)];
+    /// let positions = bus.move_all(&moves, MaximumPositioningSpeed::new(1000), MaximumAcceleration::new(200)).unwrap();
+    /// assert_eq!(positions, [0, 250, 0]);
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn move_all<const N: usize>(&'a self, moves: &[(u8, u8, i32); N], speed: MaximumPositioningSpeed, acceleration: MaximumAcceleration) -> Result<[i32; N], Error<IF::Error>> {
+        let speed = u64::from(u16::from(speed));
+        let acceleration = u64::from(u16::from(acceleration));
+
+        let mut positions = [0; N];
+        let mut longest_distance = 0u64;
+        for (position, &(address, motor_number, target)) in positions.iter_mut().zip(moves) {
+            *position = i32::from(self.write_command(address, GAP::<ActualPosition>::new(motor_number))?);
+            longest_distance = longest_distance.max(distance(*position, target));
+        }
+
+        if longest_distance == 0 {
+            return Ok(positions);
+        }
+
+        let scale = |value: u64, distance: u64| ((value * distance + longest_distance / 2) / longest_distance).max(1) as u16;
+        let mut accelerations = [0u16; N];
+        for ((scaled, &position), &(address, motor_number, target)) in accelerations.iter_mut().zip(&positions).zip(moves) {
+            let distance = distance(position, target);
+            if distance == 0 {
+                continue;
+            }
+            let pulse_divisor = u8::from(self.write_command(address, GAP::<PulseDivisor>::new(motor_number))?);
+            let ramp_divisor = u8::from(self.write_command(address, GAP::<RampDivisor>::new(motor_number))?);
+            let min = acceleration_limits(pulse_divisor, ramp_divisor).map_or(1, |(min, _)| min);
+            *scaled = scale(acceleration, distance).max(min);
+            validate_acceleration(*scaled, pulse_divisor, ramp_divisor).map_err(Error::InvalidRamp)?;
+        }
+
+        for ((&scaled, &position), &(address, motor_number, target)) in accelerations.iter().zip(&positions).zip(moves) {
+            let distance = distance(position, target);
+            if distance == 0 {
+                continue;
+            }
+            self.write_command(address, SAP::new(motor_number, MaximumPositioningSpeed::new(scale(speed, distance))))?;
+            self.write_command(address, SAP::new(motor_number, MaximumAcceleration::new(scaled)))?;
+        }
+
+        for (&position, &(address, motor_number, target)) in positions.iter().zip(moves) {
+            if position != target {
+                self.write_command(address, MVP::new(motor_number, MoveOperation::Absolute(target)))?;
+            }
+        }
+
+        Ok(positions)
+    }
+
+    /// Move the axes of a machine on a straight line to `position`, in machine units.
+    ///
+    /// The targets are issued with `move_all`, the axis with the longest distance in microsteps
+    /// moves with `speed` and `acceleration`.
+    pub fn move_linear<const N: usize>(&'a self, kinematics: &LinearKinematics<N>, position: [f32; N], speed: MaximumPositioningSpeed, acceleration: MaximumAcceleration) -> Result<(), Error<IF::Error>> {
+        self.move_all(&kinematics.targets(position), speed, acceleration).map(|_| ())
+    }
+
+    /// Stop all axes, given as `(module address, motor number)`, as fast as the interface allows.
+    ///
+    /// `MST` is sent to every axis even if stopping one of the previous axes failed.
+    /// The outcome for all axes is aggregated in the returned report.
+    pub fn emergency_stop_all(&'a self, axes: &[(u8, u8)]) -> EmergencyStopReport<IF::Error> {
+        let mut report = EmergencyStopReport{
+            stopped: 0,
+            failed: 0,
+            first_error: None,
+        };
+        for &(address, motor_number) in axes {
+            match self.write_command(address, MST::new(motor_number)) {
+                Ok(()) => report.stopped += 1,
+                Err(e) => {
+                    report.failed += 1;
+                    if report.first_error.is_none() {
+                        report.first_error = Some(e);
+                    }
+                },
+            }
+        }
+        report
+    }
+
+    /// Block until all axes, given as `(module address, motor number)`, have reached their target position.
+    ///
+    /// `wait` is called between every round of polling, without the interface being borrowed.
+    pub fn wait_for_all<F: FnMut()>(&'a self, axes: &[(u8, u8)], wait: F) -> Result<(), Error<IF::Error>> {
+        self.wait_for_all_cancellable(axes, wait, || false)
+    }
+
+    /// Like `wait_for_all`, but `cancel` is checked before every round of polling
+    ///
+    /// Cancelling only stops waiting, the motors keep moving towards their targets.
+    pub fn wait_for_all_cancellable<F: FnMut(), C: Cancel>(&'a self, axes: &[(u8, u8)], mut wait: F, mut cancel: C) -> Result<(), Error<IF::Error>> {
+        loop {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            let mut all_reached = true;
+            for &(address, motor_number) in axes {
+                if !bool::from(self.write_command(address, GAP::<TargetPositionReached>::new(motor_number))?) {
+                    all_reached = false;
+                    break;
+                }
+            }
+            if all_reached {
+                return Ok(());
+            }
+            wait();
+        }
+    }
+}
+
+/// The number of microsteps between `position` and `target`
+fn distance(position: i32, target: i32) -> u64 {
+    (i64::from(target) - i64::from(position)).unsigned_abs()
+}
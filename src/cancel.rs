@@ -0,0 +1,70 @@
+//! Cancelling long running operations.
+//!
+//! Operations spanning many bus transactions, like homing, waiting for a target position or
+//! downloading a program, have `_cancellable` variants taking a `Cancel`. It is checked between
+//! bus transactions, never in the middle of one, and the operation returns `Error::Cancelled`.
+//! What is done to leave the module in a defined state is documented on every operation.
+//!
+//! `Cancel` is implemented for closures returning `bool`, and with `std` for `CancellationToken`
+//! which can be cancelled from another thread.
+//!
+//! ```
+//! use tmcl::cancel::Cancel;
+//!
+//! let mut polls = 0;
+//! let mut cancel = || { polls += 1; polls > 3 };
+//! assert!(!cancel.is_cancelled());
+//! ```
+
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Decides whether an operation should be cancelled.
+pub trait Cancel {
+    /// Returns true if the operation should be cancelled
+    fn is_cancelled(&mut self) -> bool;
+}
+
+impl<F: FnMut() -> bool> Cancel for F {
+    fn is_cancelled(&mut self) -> bool {
+        self()
+    }
+}
+
+/// A cancellation flag shared between threads.
+///
+/// Clones share the flag, cancelling one cancels all of them.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "std")]
+impl CancellationToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        CancellationToken{cancelled: Arc::new(AtomicBool::new(false))}
+    }
+
+    /// Cancel the operations using this token (or a clone of it)
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "std")]
+impl Cancel for CancellationToken {
+    fn is_cancelled(&mut self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Cancel for &CancellationToken {
+    fn is_cancelled(&mut self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,226 @@
+//! Converting captures to and from the formats of CAN logging tools.
+//!
+//! On CAN, a command is a frame with the module address as identifier and the 7 bytes of the
+//! binary command format after the address as data, a reply is a frame with the reply address
+//! as identifier and the 7 bytes after the reply address as data. The functions in this module
+//! write a `Capture` in this layout, so it can be compared with captures from the vendor tooling
+//! or a bus analyzer:
+//!
+//! - `write_candump` writes the log format of `candump -l` from can-utils.
+//! - `write_pcan_trace` writes a PCAN trace file, version 1.1.
+//! - `write_blf` writes a minimal, uncompressed binary logging file as read by the Vector tools.
+//!
+//! `read_candump` reads a candump log back into a capture. The replies carry the timestamp of
+//! their command, since a `Capture` only keeps the time the command was transmitted.
+//!
+//! ```
+//! use tmcl::Reply;
+//! use tmcl::Status;
+//! use tmcl::OkStatus;
+//! use tmcl::Operand;
+//! use tmcl::capture::{Capture, Exchange};
+//! use tmcl::canlog::{read_candump, write_candump};
+//! use std::time::Duration;
+//!
+//! let mut capture = Capture::new();
+//! let reply = Reply::new(2, 1, Status::Ok(OkStatus::Ok), 6, Operand::from_i32(1000));
+//! capture.push(Exchange::new(Duration::from_millis(10), [1, 6, 1, 0, 0, 0, 0, 0, 8], Some(reply)));
+//!
+//! let mut log = Vec::new();
+//! write_candump(&capture, &mut log, "can0").unwrap();
+//! assert_eq!(
+//!     String::from_utf8(log.clone()).unwrap(),
+//!     "(0.010000) can0 001#06010000000000\n(0.010000) can0 002#016406000003E8\n",
+//! );
+//! assert_eq!(read_candump(&log[..]).unwrap(), capture);
+//! ```
+
+use std::io;
+use std::io::{BufRead, Write};
+use std::time::Duration;
+use std::vec::Vec;
+
+use capture::{Capture, Exchange};
+use demux::Frame;
+
+/// The data length of a TMCL frame on CAN.
+pub const CAN_DATA_LENGTH: usize = 7;
+
+/// The object type of CAN messages in binary logging files.
+const BLF_CAN_MESSAGE: u32 = 1;
+
+/// The object type of containers in binary logging files.
+const BLF_LOG_CONTAINER: u32 = 10;
+
+/// The size of the file header of binary logging files.
+const BLF_FILE_HEADER_SIZE: usize = 144;
+
+/// The size of a CAN message object in binary logging files, including its header.
+const BLF_CAN_MESSAGE_SIZE: usize = 48;
+
+/// The identifier and data of a frame in binary command format when sent on CAN
+///
+/// ```
+/// use tmcl::canlog::can_frame;
+///
+/// assert_eq!(can_frame(&[1, 6, 1, 0, 0, 0, 0, 0, 8]), (1, [6, 1, 0, 0, 0, 0, 0]));
+/// ```
+pub fn can_frame(frame: &[u8; 9]) -> (u16, [u8; CAN_DATA_LENGTH]) {
+    let mut data = [0; CAN_DATA_LENGTH];
+    data.copy_from_slice(&frame[1..8]);
+    (u16::from(frame[0]), data)
+}
+
+/// The frames of a capture in the order they were transmitted, as `(timestamp, transmitted, frame)`
+fn frames(capture: &Capture) -> Vec<(Duration, bool, [u8; 9])> {
+    let mut frames = Vec::new();
+    for exchange in capture.exchanges() {
+        frames.push((exchange.timestamp(), true, exchange.command()));
+        if let Some(reply) = exchange.reply() {
+            frames.push((exchange.timestamp(), false, reply.serialize()));
+        }
+    }
+    frames
+}
+
+/// Write `capture` in the log format of `candump -l`, as received on `interface`
+pub fn write_candump<W: Write>(capture: &Capture, mut writer: W, interface: &str) -> io::Result<()> {
+    for (timestamp, _, frame) in frames(capture) {
+        let (id, data) = can_frame(&frame);
+        write!(writer, "({}.{:06}) {} {:03X}#", timestamp.as_secs(), timestamp.subsec_micros(), interface, id)?;
+        for byte in &data {
+            write!(writer, "{:02X}", byte)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Read a log in the format of `candump -l`
+///
+/// A frame is taken as the reply to the preceding command if it comes from the address of that
+/// module and contains its instruction number, every other frame as a command. Frames that are
+/// not 7 bytes long are skipped.
+pub fn read_candump<R: BufRead>(reader: R) -> io::Result<Capture> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid candump line");
+    let mut capture = Capture::new();
+    let mut pending: Option<(Duration, [u8; 9])> = None;
+    for line in reader.lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let timestamp = match fields.next() {
+            Some(timestamp) => timestamp.trim_start_matches('(').trim_end_matches(')'),
+            None => continue,
+        };
+        let (seconds, micros) = timestamp.split_once('.').ok_or_else(invalid)?;
+        let timestamp = Duration::from_secs(seconds.parse().or(Err(invalid()))?)
+            + Duration::from_micros(micros.parse().or(Err(invalid()))?);
+        let (id, data) = fields.nth(1).and_then(|frame| frame.split_once('#')).ok_or_else(invalid)?;
+        if data.len() != 2 * CAN_DATA_LENGTH {
+            continue;
+        }
+        let mut frame = [0; 9];
+        frame[0] = u8::from_str_radix(id, 16).or(Err(invalid()))?;
+        for (byte, index) in frame[1..8].iter_mut().zip((0..data.len()).step_by(2)) {
+            *byte = u8::from_str_radix(&data[index..index + 2], 16).or(Err(invalid()))?;
+        }
+        frame[8] = frame[..8].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        match pending {
+            Some((command_timestamp, command)) if frame[1] == command[0] && frame[3] == command[1] => {
+                if let Frame::Reply(reply) = Frame::classify(frame) {
+                    capture.push(Exchange::new(command_timestamp, command, Some(reply)));
+                    pending = None;
+                    continue;
+                }
+            },
+            _ => (),
+        }
+        if let Some((command_timestamp, command)) = pending.take() {
+            capture.push(Exchange::new(command_timestamp, command, None));
+        }
+        pending = Some((timestamp, frame));
+    }
+    if let Some((timestamp, command)) = pending {
+        capture.push(Exchange::new(timestamp, command, None));
+    }
+    Ok(capture)
+}
+
+/// Write `capture` as a PCAN trace file, version 1.1
+pub fn write_pcan_trace<W: Write>(capture: &Capture, mut writer: W) -> io::Result<()> {
+    writeln!(writer, ";$FILEVERSION=1.1")?;
+    writeln!(writer, ";")?;
+    writeln!(writer, ";   Message Number")?;
+    writeln!(writer, ";   |         Time Offset (ms)")?;
+    writeln!(writer, ";   |         |        Type")?;
+    writeln!(writer, ";   |         |        |        ID (hex)")?;
+    writeln!(writer, ";   |         |        |        |     Data Length Code")?;
+    writeln!(writer, ";   |         |        |        |     |   Data Bytes (hex) ...")?;
+    writeln!(writer, ";   |         |        |        |     |   |")?;
+    writeln!(writer, ";---+--   ----+----  --+--  ----+---  +  -+ -- -- -- -- -- --")?;
+    for (number, (timestamp, transmitted, frame)) in frames(capture).into_iter().enumerate() {
+        let (id, data) = can_frame(&frame);
+        let millis = timestamp.as_secs_f64() * 1000.0;
+        let direction = if transmitted { "Tx" } else { "Rx" };
+        write!(writer, "{:>6}){:>12.1}  {:<4}{:>12}  {} ", number + 1, millis, direction, format!("{:04X}", id), CAN_DATA_LENGTH)?;
+        for byte in &data {
+            write!(writer, " {:02X}", byte)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Write `capture` as a binary logging file, with all frames on the 1-based `channel`
+///
+/// The frames are written uncompressed in a single container. The measurement start time in the
+/// file header is left empty, the timestamps are relative to the start of the capture.
+pub fn write_blf<W: Write>(capture: &Capture, mut writer: W, channel: u16) -> io::Result<()> {
+    let frames = frames(capture);
+    let mut objects = Vec::with_capacity(frames.len() * BLF_CAN_MESSAGE_SIZE);
+    for &(timestamp, transmitted, frame) in &frames {
+        let (id, data) = can_frame(&frame);
+        objects.extend_from_slice(b"LOBJ");
+        objects.extend_from_slice(&32u16.to_le_bytes());
+        objects.extend_from_slice(&1u16.to_le_bytes());
+        objects.extend_from_slice(&(BLF_CAN_MESSAGE_SIZE as u32).to_le_bytes());
+        objects.extend_from_slice(&BLF_CAN_MESSAGE.to_le_bytes());
+        // Timestamps in nanoseconds, client index and object version.
+        objects.extend_from_slice(&2u32.to_le_bytes());
+        objects.extend_from_slice(&0u16.to_le_bytes());
+        objects.extend_from_slice(&0u16.to_le_bytes());
+        objects.extend_from_slice(&(timestamp.as_nanos() as u64).to_le_bytes());
+        objects.extend_from_slice(&channel.to_le_bytes());
+        objects.push(u8::from(transmitted));
+        objects.push(CAN_DATA_LENGTH as u8);
+        objects.extend_from_slice(&u32::from(id).to_le_bytes());
+        objects.extend_from_slice(&data);
+        objects.push(0);
+    }
+
+    let container_size = 32 + objects.len();
+    let file_size = BLF_FILE_HEADER_SIZE + container_size;
+    let mut header = Vec::with_capacity(BLF_FILE_HEADER_SIZE);
+    header.extend_from_slice(b"LOGG");
+    header.extend_from_slice(&(BLF_FILE_HEADER_SIZE as u32).to_le_bytes());
+    // Application and library versions.
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&(file_size as u64).to_le_bytes());
+    header.extend_from_slice(&(file_size as u64).to_le_bytes());
+    header.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.resize(BLF_FILE_HEADER_SIZE, 0);
+    writer.write_all(&header)?;
+
+    writer.write_all(b"LOBJ")?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&(container_size as u32).to_le_bytes())?;
+    writer.write_all(&BLF_LOG_CONTAINER.to_le_bytes())?;
+    // No compression.
+    writer.write_all(&0u16.to_le_bytes())?;
+    writer.write_all(&[0; 6])?;
+    writer.write_all(&(objects.len() as u32).to_le_bytes())?;
+    writer.write_all(&[0; 4])?;
+    writer.write_all(&objects)
+}
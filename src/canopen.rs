@@ -0,0 +1,317 @@
+//! Bridging TMCL to modules running CANopen firmware.
+//!
+//! Some Trinamic modules are available with CANopen firmware (CiA 402) instead of TMCL. `SdoBridge`
+//! implements `Interface` by translating the motion instructions and the axis parameters of this
+//! crate to SDO transfers on the object dictionary of such a module, so the same `TmcmModule` code
+//! can drive both kinds of modules in a mixed network. The module address is used as the node id.
+//!
+//! Only a minimal subset is bridged, with expedited SDO transfers:
+//!
+//! - `GAP` and `SAP` of the axis parameters listed by `axis_parameter_object`.
+//! - `MVP` with absolute and relative targets, in profile position mode.
+//! - `ROR` and `ROL`, in profile velocity mode.
+//! - `MST`, by setting the halt bit of the controlword.
+//!
+//! Other instructions are answered with `ErrStatus::InvalidCommand` without reaching the bus, and
+//! SDO aborts are translated to the closest TMCL status, see `SdoBridge::last_abort`. The drive
+//! must already be switched on and enabled, e.g. by the application or an NMT master.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use tmcl::canopen::{CanTransport, SdoBridge};
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::axis_parameters::ActualPosition;
+//!
+//! // A node answering every upload with 1000 and acknowledging every download.
+//! struct Node(Option<[u8; 8]>);
+//!
+//! impl CanTransport for Node {
+//!     type Error = ();
+//!
+//!     fn transmit(&mut self, _id: u16, data: &[u8; 8]) -> Result<(), ()> {
+//!         let command = if data[0] == 0x40 { 0x43 } else { 0x60 };
+//!         let value = if data[0] == 0x40 { 1000i32.to_le_bytes() } else { [0; 4] };
+//!         self.0 = Some([command, data[1], data[2], data[3], value[0], value[1], value[2], value[3]]);
+//!         Ok(())
+//!     }
+//!
+//!     fn receive(&mut self) -> Result<(u16, [u8; 8]), ()> {
+//!         Ok((0x585, self.0.take().unwrap()))
+//!     }
+//! }
+//!
+//! let interface = RefCell::new(SdoBridge::new(Node(None)));
+//! let module = Module::new(&interface, 5);
+//! assert_eq!(module.axis(0).get::<ActualPosition>().unwrap(), ActualPosition::new(1000));
+//! ```
+
+use lib::time::Duration;
+
+use Interface;
+use Encode;
+use Command;
+use Reply;
+use Status;
+use OkStatus;
+use ErrStatus;
+use Operand;
+use opcodes::{
+    OPCODE_ROR,
+    OPCODE_ROL,
+    OPCODE_MST,
+    OPCODE_MVP,
+    OPCODE_SAP,
+    OPCODE_GAP,
+};
+
+/// The reply address of the replies made up by `SdoBridge`, the default host address of TMCL.
+pub const REPLY_ADDRESS: u8 = 2;
+
+/// The offset of the object dictionary of each further axis of a multi-axis drive (CiA 402).
+pub const AXIS_OFFSET: u16 = 0x800;
+
+/// Controlword, to command motion.
+pub const CONTROLWORD: u16 = 0x6040;
+
+/// Statusword, to read the state of the drive.
+pub const STATUSWORD: u16 = 0x6041;
+
+/// Modes of operation.
+pub const MODES_OF_OPERATION: u16 = 0x6060;
+
+/// Profile position mode, used for `MVP`.
+pub const PROFILE_POSITION_MODE: i8 = 1;
+
+/// Profile velocity mode, used for `ROR` and `ROL`.
+pub const PROFILE_VELOCITY_MODE: i8 = 3;
+
+/// Sends and receives CAN frames with standard identifiers for `SdoBridge`.
+pub trait CanTransport {
+    type Error;
+
+    /// Transmit a frame with 8 data bytes
+    fn transmit(&mut self, id: u16, data: &[u8; 8]) -> Result<(), Self::Error>;
+
+    /// Receive the next frame, shorter frames are padded with zeros
+    fn receive(&mut self) -> Result<(u16, [u8; 8]), Self::Error>;
+}
+
+/// An entry of the object dictionary of the first axis.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Object {
+    index: u16,
+    subindex: u8,
+    size: u8,
+}
+
+impl Object {
+    /// An entry of `size` bytes, at most 4
+    pub const fn new(index: u16, subindex: u8, size: u8) -> Self {
+        Object{index, subindex, size}
+    }
+
+    /// The index
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// The subindex
+    pub fn subindex(&self) -> u8 {
+        self.subindex
+    }
+
+    /// The size in bytes
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// The same entry of another axis of a multi-axis drive
+    pub fn for_axis(&self, motor_number: u8) -> Self {
+        Object{index: self.index + AXIS_OFFSET * u16::from(motor_number), ..*self}
+    }
+}
+
+/// The object an axis parameter is mapped to, `None` if it is not bridged
+///
+/// `TargetPositionReached` (8) is read from bit 10 of the statusword.
+pub fn axis_parameter_object(number: u8) -> Option<Object> {
+    match number {
+        0 => Some(Object::new(0x607A, 0, 4)),
+        1 => Some(Object::new(0x6064, 0, 4)),
+        2 => Some(Object::new(0x60FF, 0, 4)),
+        3 => Some(Object::new(0x606C, 0, 4)),
+        4 => Some(Object::new(0x6081, 0, 4)),
+        5 => Some(Object::new(0x6083, 0, 4)),
+        8 => Some(Object::new(STATUSWORD, 0, 2)),
+        _ => None,
+    }
+}
+
+/// An SDO transfer that was aborted by the node, contains the abort code.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SdoAbort(pub u32);
+
+impl SdoAbort {
+    /// The TMCL status closest to the abort code
+    pub fn status(&self) -> ErrStatus {
+        match self.0 {
+            0x0601_0000..=0x0601_0002 | 0x0602_0000 | 0x0609_0011 => ErrStatus::WrongType,
+            0x0609_0030..=0x0609_0036 | 0x0800_0020..=0x0800_0022 => ErrStatus::InvalidValue,
+            _ => ErrStatus::CommandNotAvailable,
+        }
+    }
+}
+
+/// The outcome of an SDO transfer, `Err` if it was aborted by the node.
+type Transfer<E> = Result<Result<u32, SdoAbort>, E>;
+
+/// An `Interface` translating TMCL instructions to SDO transfers on CANopen nodes.
+#[derive(Debug)]
+pub struct SdoBridge<CT: CanTransport> {
+    transport: CT,
+    reply: Option<Reply>,
+    last_abort: Option<SdoAbort>,
+}
+
+impl<CT: CanTransport> SdoBridge<CT> {
+    /// Bridge to the nodes reachable through `transport`
+    pub fn new(transport: CT) -> Self {
+        SdoBridge{transport, reply: None, last_abort: None}
+    }
+
+    /// The abort of the last aborted SDO transfer, if any
+    pub fn last_abort(&self) -> Option<SdoAbort> {
+        self.last_abort
+    }
+
+    /// Stop bridging, returning the transport
+    pub fn into_inner(self) -> CT {
+        self.transport
+    }
+
+    /// Read an object with an expedited SDO upload
+    pub fn upload(&mut self, node: u8, object: Object) -> Transfer<CT::Error> {
+        let [index_low, index_high] = object.index.to_le_bytes();
+        self.exchange(node, object, [0x40, index_low, index_high, object.subindex, 0, 0, 0, 0])
+    }
+
+    /// Write an object with an expedited SDO download
+    pub fn download(&mut self, node: u8, object: Object, value: u32) -> Transfer<CT::Error> {
+        let [index_low, index_high] = object.index.to_le_bytes();
+        let [value0, value1, value2, value3] = value.to_le_bytes();
+        let command = 0x23 | ((4 - object.size.clamp(1, 4)) << 2);
+        self.exchange(node, object, [command, index_low, index_high, object.subindex, value0, value1, value2, value3])
+    }
+
+    /// Send a request and wait for the response of the node to it, ignoring other frames
+    fn exchange(&mut self, node: u8, object: Object, request: [u8; 8]) -> Transfer<CT::Error> {
+        self.transport.transmit(0x600 + u16::from(node), &request)?;
+        loop {
+            let (id, data) = self.transport.receive()?;
+            if id != 0x580 + u16::from(node) || data[1..4] != request[1..4] {
+                continue;
+            }
+            let value = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+            if data[0] == 0x80 {
+                let abort = SdoAbort(value);
+                self.last_abort = Some(abort);
+                return Ok(Err(abort));
+            }
+            let mask = match object.size {
+                1 => 0xff,
+                2 => 0xffff,
+                3 => 0xff_ffff,
+                _ => 0xffff_ffff,
+            };
+            return Ok(Ok(value & mask));
+        }
+    }
+
+    /// Write `value` to the objects in order, stopping at the first abort
+    fn download_all(&mut self, node: u8, motor_number: u8, downloads: &[(Object, u32)]) -> Transfer<CT::Error> {
+        for &(object, value) in downloads {
+            if let Err(abort) = self.download(node, object.for_axis(motor_number), value)? {
+                return Ok(Err(abort));
+            }
+        }
+        Ok(Ok(0))
+    }
+
+    /// Carry out an instruction, `None` if it is not bridged
+    fn execute(&mut self, node: u8, instruction: [u8; 7]) -> Result<Option<Result<u32, SdoAbort>>, CT::Error> {
+        let [number, type_number, motor_number, ..] = instruction;
+        let value = Operand::from_wire([instruction[3], instruction[4], instruction[5], instruction[6]]).to_i32() as u32;
+        let controlword = Object::new(CONTROLWORD, 0, 2);
+        let mode = Object::new(MODES_OF_OPERATION, 0, 1);
+        let outcome = match (number, type_number) {
+            (OPCODE_GAP, _) => match axis_parameter_object(type_number) {
+                Some(object) => self.upload(node, object.for_axis(motor_number))?
+                    .map(|value| if type_number == 8 { (value >> 10) & 1 } else { value }),
+                None => return Ok(None),
+            },
+            (OPCODE_SAP, _) => match axis_parameter_object(type_number) {
+                Some(object) if object.index != STATUSWORD => self.download(node, object.for_axis(motor_number), value)?,
+                _ => return Ok(None),
+            },
+            (OPCODE_MVP, 0) | (OPCODE_MVP, 1) => {
+                let start = if type_number == 0 { 0x003F } else { 0x007F };
+                self.download_all(node, motor_number, &[
+                    (mode, PROFILE_POSITION_MODE as u32),
+                    (Object::new(0x607A, 0, 4), value),
+                    (controlword, 0x000F),
+                    (controlword, start),
+                ])?
+            },
+            (OPCODE_ROR, _) | (OPCODE_ROL, _) => {
+                let velocity = if number == OPCODE_ROR { value } else { (value as i32).wrapping_neg() as u32 };
+                self.download_all(node, motor_number, &[
+                    (mode, PROFILE_VELOCITY_MODE as u32),
+                    (Object::new(0x60FF, 0, 4), velocity),
+                    (controlword, 0x000F),
+                ])?
+            },
+            (OPCODE_MST, _) => self.download_all(node, motor_number, &[(controlword, 0x010F)])?,
+            _ => return Ok(None),
+        };
+        Ok(Some(outcome))
+    }
+}
+
+impl<CT: CanTransport> Interface for SdoBridge<CT> {
+    type Error = CT::Error;
+
+    /// Carry out the instruction with SDO transfers, the reply is made up from their outcome
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        let frame = command.serialize();
+        let node = frame[0];
+        let instruction = [frame[1], frame[2], frame[3], frame[4], frame[5], frame[6], frame[7]];
+        let (status, value) = match self.execute(node, instruction)? {
+            Some(Ok(value)) => (Status::Ok(OkStatus::Ok), value),
+            Some(Err(abort)) => (Status::Err(abort.status()), 0),
+            None => (Status::Err(ErrStatus::InvalidCommand), 0),
+        };
+        self.reply = Some(Reply::new(REPLY_ADDRESS, node, status, instruction[0], Operand::from_u32(value)));
+        Ok(())
+    }
+
+    /// The reply to the last command, blocks receiving frames if there is none
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        loop {
+            if let Some(reply) = self.reply.take() {
+                return Ok(reply);
+            }
+            self.transport.receive()?;
+        }
+    }
+
+    fn receive_reply_timeout(&mut self, _timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        Ok(self.reply.take())
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        self.reply = None;
+        Ok(())
+    }
+}
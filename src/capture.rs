@@ -0,0 +1,405 @@
+//! Recording and replaying the traffic between host and modules.
+//!
+//! A `Recorder` wraps an interface and records every command together with its reply and a
+//! timestamp. The resulting `Capture` can be stored in a compact binary format, attached to a
+//! support ticket and later replayed: either against a real interface to compare the replies,
+//! or with `Replay` acting as a mock interface that answers with the recorded replies.
+//!
+//! `Replay::open` also reads candump logs, see the `canlog` module, so traffic recorded with
+//! can-utils at a customer site can be reproduced offline. With `Replay::with_timing` the replies
+//! are delayed to keep the pace of the recording.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use tmcl::capture::{Capture, Replay};
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//!
+//! // A capture with one GAP ActualPosition exchange, e.g. recorded at a customer site.
+//! let stored: Vec<u8> = vec![
+//!     b'T', b'M', b'C', b'L', b'C', b'A', b'P', 1,
+//!     0x10, 0x27, 0, 0, 0, 0, 0, 0,
+//!     1, 6, 1, 0, 0, 0, 0, 0, 8,
+//!     1,
+//!     2, 1, 100, 6, 0, 0, 0x03, 0xe8, 0x58,
+//! ];
+//! let capture = Capture::read_from(&stored[..]).unwrap();
+//!
+//! let interface = RefCell::new(Replay::new(capture));
+//! let module = Module::new(&interface, 1);
+//! assert_eq!(module.write_command(GAP::<ActualPosition>::new(0)).unwrap(), ActualPosition::new(1000));
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::fs::File;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+use Interface;
+use Encode;
+use Command;
+use Reply;
+use demux::Frame;
+use instructions::RawInstruction;
+use canlog::read_candump;
+
+/// The first bytes of a capture stored in the binary format, the last byte is the format version.
+pub const CAPTURE_MAGIC: [u8; 8] = [b'T', b'M', b'C', b'L', b'C', b'A', b'P', 1];
+
+/// A command and the reply it caused.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Exchange {
+    timestamp: Duration,
+    command: [u8; 9],
+    reply: Option<Reply>,
+}
+
+impl Exchange {
+    /// Create an exchange from a command in binary command format and its reply
+    pub fn new(timestamp: Duration, command: [u8; 9], reply: Option<Reply>) -> Self {
+        Exchange{timestamp, command, reply}
+    }
+
+    /// The time the command was transmitted, relative to the start of the capture
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// The command in binary command format, `[MODULE_ADR, CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0, CHECKSUM]`
+    pub fn command(&self) -> [u8; 9] {
+        self.command
+    }
+
+    /// The reply to the command, `None` if no reply was received
+    pub fn reply(&self) -> Option<&Reply> {
+        self.reply.as_ref()
+    }
+}
+
+/// A reply that differs from the recorded one when replaying a capture against an interface.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mismatch {
+    index: usize,
+    expected: Option<Reply>,
+    actual: Reply,
+}
+
+impl Mismatch {
+    /// The index of the exchange in the capture
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The recorded reply
+    pub fn expected(&self) -> Option<&Reply> {
+        self.expected.as_ref()
+    }
+
+    /// The reply received during the replay
+    pub fn actual(&self) -> &Reply {
+        &self.actual
+    }
+}
+
+/// A sequence of recorded exchanges.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Capture {
+    exchanges: Vec<Exchange>,
+}
+
+impl Capture {
+    /// Create an empty capture
+    pub fn new() -> Self {
+        Capture{exchanges: Vec::new()}
+    }
+
+    /// Append an exchange to the capture
+    pub fn push(&mut self, exchange: Exchange) {
+        self.exchanges.push(exchange);
+    }
+
+    /// The recorded exchanges in the order they happened
+    pub fn exchanges(&self) -> &[Exchange] {
+        &self.exchanges
+    }
+
+    /// The number of recorded exchanges
+    pub fn len(&self) -> usize {
+        self.exchanges.len()
+    }
+
+    /// Returns true if nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.exchanges.is_empty()
+    }
+
+    /// Write the capture in the binary format
+    ///
+    /// The capture starts with `CAPTURE_MAGIC`, followed by 27 bytes per exchange: the timestamp
+    /// in microseconds (u64, little endian), the command frame, 1 if a reply was received (else 0)
+    /// and the reply frame in the serial reply format (all zero if no reply was received).
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&CAPTURE_MAGIC)?;
+        for exchange in &self.exchanges {
+            let micros = exchange.timestamp.as_secs() * 1_000_000 + u64::from(exchange.timestamp.subsec_micros());
+            writer.write_all(&micros.to_le_bytes())?;
+            writer.write_all(&exchange.command)?;
+            match exchange.reply {
+                Some(ref reply) => {
+                    writer.write_all(&[1])?;
+                    writer.write_all(&reply.serialize())?;
+                },
+                None => writer.write_all(&[0; 10])?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a capture written by `write_to`
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Capture> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != CAPTURE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a capture"));
+        }
+
+        let mut capture = Capture::new();
+        let mut record = [0u8; 27];
+        loop {
+            match reader.read_exact(&mut record) {
+                Ok(()) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(capture),
+                Err(e) => return Err(e),
+            }
+
+            let mut micros = [0u8; 8];
+            micros.copy_from_slice(&record[0..8]);
+            let mut command = [0u8; 9];
+            command.copy_from_slice(&record[8..17]);
+            let reply = match record[17] {
+                0 => None,
+                _ => {
+                    let mut frame = [0u8; 9];
+                    frame.copy_from_slice(&record[18..27]);
+                    match Frame::classify(frame) {
+                        Frame::Reply(reply) => Some(reply),
+                        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid reply frame")),
+                    }
+                },
+            };
+            capture.push(Exchange::new(Duration::from_micros(u64::from_le_bytes(micros)), command, reply));
+        }
+    }
+
+    /// Transmit every recorded command to `interface` and compare the replies with the recorded ones
+    ///
+    /// Returns all exchanges where the reply differed.
+    pub fn replay_against<IF: Interface>(&self, interface: &mut IF) -> Result<Vec<Mismatch>, IF::Error> {
+        let mut mismatches = Vec::new();
+        for (index, exchange) in self.exchanges.iter().enumerate() {
+            let frame = exchange.command;
+            let value = i32::from_be_bytes([frame[4], frame[5], frame[6], frame[7]]);
+            interface.transmit_command(&Command::new(frame[0], RawInstruction::new(frame[1], frame[2], frame[3], value)))?;
+            let actual = interface.receive_reply()?;
+            if exchange.reply.as_ref() != Some(&actual) {
+                mismatches.push(Mismatch{index, expected: exchange.reply.clone(), actual});
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+/// An interface recording all traffic passing through it.
+#[derive(Debug)]
+pub struct Recorder<IF: Interface> {
+    interface: IF,
+    start: Instant,
+    capture: Capture,
+}
+
+impl<IF: Interface> Recorder<IF> {
+    /// Start recording the traffic on `interface`
+    pub fn new(interface: IF) -> Self {
+        Recorder{
+            interface,
+            start: Instant::now(),
+            capture: Capture::new(),
+        }
+    }
+
+    /// The traffic recorded so far
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    /// Stop recording, returning the interface and the capture
+    pub fn into_inner(self) -> (IF, Capture) {
+        (self.interface, self.capture)
+    }
+
+    fn record_reply(&mut self, reply: &Reply) {
+        if let Some(exchange) = self.capture.exchanges.last_mut() {
+            if exchange.reply.is_none() {
+                exchange.reply = Some(reply.clone());
+            }
+        }
+    }
+}
+
+impl<IF: Interface> Interface for Recorder<IF> {
+    type Error = IF::Error;
+
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        self.capture.push(Exchange::new(self.start.elapsed(), command.serialize(), None));
+        self.interface.transmit_command(command)
+    }
+
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        let reply = self.interface.receive_reply()?;
+        self.record_reply(&reply);
+        Ok(reply)
+    }
+
+    fn receive_reply_timeout(&mut self, timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        let reply = self.interface.receive_reply_timeout(timeout)?;
+        if let Some(ref reply) = reply {
+            self.record_reply(reply);
+        }
+        Ok(reply)
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        self.interface.discard_input()
+    }
+}
+
+/// The errors of a `Replay` interface.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReplayError {
+    /// The command differs from the recorded command.
+    UnexpectedCommand {
+        index: usize,
+        expected: [u8; 9],
+        actual: [u8; 9],
+    },
+
+    /// All recorded exchanges have been replayed.
+    EndOfCapture,
+
+    /// No reply was recorded for the exchange with the given index.
+    ///
+    /// Only returned by `receive_reply`, `receive_reply_timeout` replays the missing reply as a timeout.
+    MissingReply(usize),
+}
+
+/// A mock interface answering with the replies of a capture.
+///
+/// Every transmitted command is checked against the recorded command. An exchange recorded
+/// without a reply times out when received with a timeout.
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::time::Duration;
+/// use tmcl::{Command, Error};
+/// use tmcl::capture::{Capture, Exchange, Replay};
+/// use tmcl::modules::tmcm::TmcmModule as Module;
+/// use tmcl::modules::tmcm::instructions::*;
+/// use tmcl::modules::tmcm::axis_parameters::*;
+///
+/// // The module did not reply to the recorded command.
+/// let mut capture = Capture::new();
+/// let command = Command::new(1, GAP::<ActualPosition>::new(0)).serialize();
+/// capture.push(Exchange::new(Duration::from_secs(0), command, None));
+///
+/// let interface = RefCell::new(Replay::new(capture));
+/// let module = Module::new(&interface, 1).with_timeout(Duration::from_millis(100));
+/// assert!(matches!(module.write_command(GAP::<ActualPosition>::new(0)), Err(Error::Timeout)));
+/// ```
+#[derive(Debug)]
+pub struct Replay {
+    capture: Capture,
+    position: usize,
+    timing: bool,
+    start: Option<Instant>,
+}
+
+impl Replay {
+    /// Replay `capture` from the start
+    pub fn new(capture: Capture) -> Self {
+        Replay{capture, position: 0, timing: false, start: None}
+    }
+
+    /// Replay the capture stored in the file at `path`, in the binary format or as a candump log
+    ///
+    /// Files starting with `CAPTURE_MAGIC` are read with `Capture::read_from`, all other files with
+    /// `canlog::read_candump`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let binary = reader.fill_buf()?.starts_with(&CAPTURE_MAGIC);
+        let capture = if binary { Capture::read_from(reader)? } else { read_candump(reader)? };
+        Ok(Replay::new(capture))
+    }
+
+    /// Delay every reply until the time it was recorded at, relative to the first command
+    ///
+    /// The time of an exchange is the time its command was transmitted, so the replay keeps the
+    /// pace of the recording but replies as fast as possible within an exchange.
+    pub fn with_timing(mut self) -> Self {
+        self.timing = true;
+        self
+    }
+
+    /// The number of exchanges replayed so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns true if all exchanges have been replayed
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.capture.len()
+    }
+
+    /// Advance to the next exchange and return its recorded reply
+    fn next_reply(&mut self) -> Result<Option<Reply>, ReplayError> {
+        let index = self.position;
+        let exchange = self.capture.exchanges.get(index).ok_or(ReplayError::EndOfCapture)?;
+        self.position += 1;
+        if self.timing {
+            let first = self.capture.exchanges[0].timestamp;
+            let start = *self.start.get_or_insert_with(Instant::now);
+            let due = exchange.timestamp.checked_sub(first).unwrap_or_default();
+            if let Some(remaining) = due.checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+        Ok(exchange.reply.clone())
+    }
+}
+
+impl Interface for Replay {
+    type Error = ReplayError;
+
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        let exchange = self.capture.exchanges.get(self.position).ok_or(ReplayError::EndOfCapture)?;
+        let actual = command.serialize();
+        if actual != exchange.command {
+            return Err(ReplayError::UnexpectedCommand{index: self.position, expected: exchange.command, actual});
+        }
+        Ok(())
+    }
+
+    /// Reply with the recorded reply, `ReplayError::MissingReply` if none was recorded
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        let index = self.position;
+        self.next_reply()?.ok_or(ReplayError::MissingReply(index))
+    }
+
+    /// Reply with the recorded reply, `Ok(None)` as if timed out if none was recorded
+    fn receive_reply_timeout(&mut self, _timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        self.next_reply()
+    }
+}
@@ -0,0 +1,235 @@
+//! Clocks and delays for polling helpers.
+//!
+//! Helpers that poll a module until something has happened, like waiting for a target position or
+//! a reference search, have variants taking a `Poller`. The poller waits between polls with a
+//! `Delay` and gives up with `Error::Timeout` when a `Clock` says the timeout has passed.
+//!
+//! Nothing here depends on `std`. With `std`, `StdClock` and `StdDelay` use `Instant` and
+//! `thread::sleep`. With the `embedded-hal` feature, `HalDelay` waits using a `DelayNs`. `MockClock`
+//! is both a clock and a delay where time only advances when delayed, which makes timeouts
+//! deterministic in tests.
+//!
+//! ```
+//! use std::time::Duration;
+//! use tmcl::clock::{MockClock, Poller};
+//!
+//! let clock = MockClock::new();
+//! let mut poller = Poller::new(&clock, &clock, Duration::from_millis(10))
+//!     .with_timeout(Duration::from_millis(25));
+//!
+//! poller.start();
+//! poller.pause();
+//! poller.pause();
+//! assert!(!poller.expired());
+//! poller.pause();
+//! assert!(poller.expired());
+//! assert_eq!(clock.elapsed(), Duration::from_millis(30));
+//! ```
+
+use lib::cell::Cell;
+use lib::time::Duration;
+
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::delay::DelayNs;
+
+/// A monotonic clock.
+pub trait Clock {
+    /// The time passed since an arbitrary, fixed point in time
+    fn now(&mut self) -> Duration;
+}
+
+/// Waits for a given time.
+///
+/// Implemented for closures taking a `Duration`, e.g. `std::thread::sleep`.
+pub trait Delay {
+    /// Wait for `duration`
+    fn delay(&mut self, duration: Duration);
+}
+
+impl<F: FnMut(Duration)> Delay for F {
+    fn delay(&mut self, duration: Duration) {
+        self(duration)
+    }
+}
+
+/// A `Clock` measuring the time since it was created with `Instant`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct StdClock {
+    epoch: Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Create a clock starting at zero
+    pub fn new() -> Self {
+        StdClock{epoch: Instant::now()}
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&mut self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+/// A `Delay` putting the current thread to sleep.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdDelay;
+
+#[cfg(feature = "std")]
+impl Delay for StdDelay {
+    fn delay(&mut self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// A `Delay` backed by an embedded-hal `DelayNs`.
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug)]
+pub struct HalDelay<D: DelayNs> {
+    delay: D,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<D: DelayNs> HalDelay<D> {
+    /// Wrap a `DelayNs`
+    pub fn new(delay: D) -> Self {
+        HalDelay{delay}
+    }
+
+    /// Release the wrapped `DelayNs`
+    pub fn into_inner(self) -> D {
+        self.delay
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<D: DelayNs> Delay for HalDelay<D> {
+    /// Wait in steps of at most `u32::MAX` microseconds, with microsecond resolution
+    fn delay(&mut self, duration: Duration) {
+        let mut remaining = duration.as_micros();
+        while remaining > 0 {
+            let step = if remaining > u128::from(u32::MAX) { u32::MAX } else { remaining as u32 };
+            self.delay.delay_us(step);
+            remaining -= u128::from(step);
+        }
+    }
+}
+
+/// A clock that only advances when delayed, or explicitly with `advance`.
+///
+/// A shared reference is both a `Clock` and a `Delay`, so the same mock can be passed as both to a
+/// `Poller`.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    now: Cell<Duration>,
+}
+
+impl MockClock {
+    /// Create a clock starting at zero
+    pub fn new() -> Self {
+        MockClock{now: Cell::new(Duration::from_secs(0))}
+    }
+
+    /// Advance the clock by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    /// The time passed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+impl Clock for &MockClock {
+    fn now(&mut self) -> Duration {
+        self.elapsed()
+    }
+}
+
+impl Delay for &MockClock {
+    fn delay(&mut self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+/// Paces a polling loop and decides when it has timed out.
+#[derive(Debug)]
+pub struct Poller<C: Clock, D: Delay> {
+    clock: C,
+    delay: D,
+    interval: Duration,
+    timeout: Option<Duration>,
+    deadline: Option<Duration>,
+}
+
+impl<C: Clock, D: Delay> Poller<C, D> {
+    /// Create a poller waiting `interval` between polls, without a timeout
+    pub fn new(clock: C, delay: D, interval: Duration) -> Self {
+        Poller{
+            clock,
+            delay,
+            interval,
+            timeout: None,
+            deadline: None,
+        }
+    }
+
+    /// Give up after `timeout` has passed since `start`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The time waited between polls
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The timeout, if any
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Start measuring the timeout from now
+    ///
+    /// Called by the polling helpers before the first poll.
+    pub fn start(&mut self) {
+        self.deadline = match self.timeout {
+            Some(timeout) => Some(self.clock.now() + timeout),
+            None => None,
+        };
+    }
+
+    /// Returns true if the timeout has passed since `start`
+    pub fn expired(&mut self) -> bool {
+        match self.deadline {
+            Some(deadline) => self.clock.now() >= deadline,
+            None => false,
+        }
+    }
+
+    /// Wait for the poll interval
+    pub fn pause(&mut self) {
+        self.delay.delay(self.interval)
+    }
+
+    /// Release the clock and delay
+    pub fn into_inner(self) -> (C, D) {
+        (self.clock, self.delay)
+    }
+}
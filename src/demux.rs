@@ -0,0 +1,295 @@
+//! Demultiplexing of serial reply streams containing both replies and events.
+//!
+//! A module can be configured to send telegrams on its own (e.g. when a target position has been
+//! reached) on the same line as the replies to direct commands. This applies to serial lines as
+//! well as CAN, where `CANSocket` implements `FrameInterface` when the socketcan feature is enabled. `Demux` sits between the raw
+//! serial line and the modules, hands replies to `receive_reply` and buffers the events until
+//! they are picked up with `pop_event`. Frames that can not be interpreted are dropped.
+//!
+//! ```
+//! use tmcl::demux::Frame;
+//!
+//! // A target position reached event (command 138) from module 1
+//! let frame = [2, 1, 128, 138, 0, 0, 0, 0, 13];
+//! match Frame::classify(frame) {
+//!     Frame::Event(event) => assert_eq!(event.command_number(), 138),
+//!     _ => panic!("not classified as an event"),
+//! }
+//! ```
+
+use lib::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use Interface;
+use Encode;
+use Command;
+use Reply;
+use Timestamp;
+use Status;
+use Operand;
+use opcodes::OPCODE_REQUEST_TARGET_POSITION_REACHED_EVENT;
+
+/// The status code the module uses in telegrams that are not replies to a command.
+pub const EVENT_STATUS: u8 = 128;
+
+/// The command number of target position reached events, see `RequestTargetPositionReachedEvent`.
+pub const TARGET_POSITION_REACHED: u8 = OPCODE_REQUEST_TARGET_POSITION_REACHED_EVENT;
+
+/// The number of events `Demux` can hold before the oldest event is dropped.
+pub const EVENT_CAPACITY: usize = 8;
+
+/// A received frame together with the time it was received, if known.
+pub type ReceivedFrame = ([u8; 9], Option<Timestamp>);
+
+/// A raw serial line transmitting and receiving frames in the binary command format.
+pub trait FrameInterface {
+    type Error;
+
+    /// Transmit a frame, `[MODULE_ADR, CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0, CHECKSUM]`
+    fn transmit_frame(&mut self, frame: &[u8; 9]) -> Result<(), Self::Error>;
+
+    /// Receive a frame, `[REPLY_ADR, MODULE_ADR, STATUS, CMD_N, VALUE3, VALUE2, VALUE1, VALUE0, CHECKSUM]`
+    fn receive_frame(&mut self) -> Result<[u8; 9], Self::Error>;
+
+    /// Receive a frame together with the time it was received, if known
+    ///
+    /// The default implementation does not record a timestamp.
+    fn receive_frame_timestamped(&mut self) -> Result<([u8; 9], Option<Timestamp>), Self::Error> {
+        Ok((self.receive_frame()?, None))
+    }
+
+    /// Receive a frame together with the time it was received, giving up when `timeout` has elapsed
+    ///
+    /// Returns `Ok(None)` if no complete frame was received in time.
+    /// The default implementation ignores the timeout and blocks in `receive_frame_timestamped`,
+    /// lines that are able to time out should override it.
+    fn receive_frame_timeout(&mut self, timeout: Duration) -> Result<Option<ReceivedFrame>, Self::Error> {
+        let _ = timeout;
+        self.receive_frame_timestamped().map(Some)
+    }
+
+    /// Discard received data that has not been read yet, resynchronizing to the frame boundaries.
+    ///
+    /// The default implementation does nothing.
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A telegram sent by a module without being a reply to a command.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Event {
+    reply_address: u8,
+    module_address: u8,
+    command_number: u8,
+    operand: Operand,
+}
+
+impl Event {
+    /// The address of the host the event was sent to
+    pub fn reply_address(&self) -> u8 {
+        self.reply_address
+    }
+
+    /// The address of the module that sent the event
+    pub fn module_address(&self) -> u8 {
+        self.module_address
+    }
+
+    /// The command number that caused the event, e.g. 138 for target position reached events
+    pub fn command_number(&self) -> u8 {
+        self.command_number
+    }
+
+    /// The operand
+    pub fn operand(&self) -> Operand {
+        self.operand
+    }
+
+    /// Returns true if this is a target position reached event
+    pub fn is_target_position_reached(&self) -> bool {
+        self.command_number == TARGET_POSITION_REACHED
+    }
+
+    /// The motors (bit 0 for motor 0 etc.) of a target position reached event
+    pub fn motor_mask(&self) -> u32 {
+        self.operand.to_u32()
+    }
+}
+
+/// The classification of a received frame.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Frame {
+    /// A reply to a command.
+    Reply(Reply),
+
+    /// A telegram sent by the module on its own.
+    Event(Event),
+
+    /// A frame with wrong checksum.
+    Garbage([u8; 9]),
+}
+
+impl Frame {
+    /// Classify a frame received in the binary command format
+    pub fn classify(frame: [u8; 9]) -> Frame {
+        let checksum = frame[..8].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if checksum != frame[8] {
+            return Frame::Garbage(frame);
+        }
+
+        let operand = Operand::from_wire([frame[4], frame[5], frame[6], frame[7]]);
+        if frame[2] == EVENT_STATUS {
+            return Frame::Event(Event{
+                reply_address: frame[0],
+                module_address: frame[1],
+                command_number: frame[3],
+                operand,
+            });
+        }
+
+        Frame::Reply(Reply::new(frame[0], frame[1], Status::from_u8(frame[2]), frame[3], operand))
+    }
+}
+
+/// Splits the frames received on a serial line into replies and events.
+///
+/// `Demux` implements `Interface` and can be used with the modules like any other interface.
+#[derive(Debug)]
+pub struct Demux<FI: FrameInterface> {
+    interface: FI,
+    events: [Option<Event>; EVENT_CAPACITY],
+    first_event: usize,
+    event_count: usize,
+    dropped_events: usize,
+    garbage_frames: usize,
+}
+
+impl<FI: FrameInterface> Demux<FI> {
+    /// Create a new demultiplexer on top of a serial line
+    pub fn new(interface: FI) -> Self {
+        Demux{
+            interface,
+            events: [None; EVENT_CAPACITY],
+            first_event: 0,
+            event_count: 0,
+            dropped_events: 0,
+            garbage_frames: 0,
+        }
+    }
+
+    /// Take the oldest buffered event
+    pub fn pop_event(&mut self) -> Option<Event> {
+        if self.event_count == 0 {
+            return None;
+        }
+        let event = self.events[self.first_event].take();
+        self.first_event = (self.first_event + 1) % EVENT_CAPACITY;
+        self.event_count -= 1;
+        event
+    }
+
+    /// Receive frames until an event is available and return it
+    ///
+    /// Replies received while waiting are dropped as there is no command waiting for them.
+    pub fn receive_event(&mut self) -> Result<Event, FI::Error> {
+        loop {
+            if let Some(event) = self.pop_event() {
+                return Ok(event);
+            }
+            self.receive_classified()?;
+        }
+    }
+
+    /// Number of events dropped because the buffer was full
+    pub fn dropped_events(&self) -> usize {
+        self.dropped_events
+    }
+
+    /// Number of frames dropped because they could not be interpreted
+    pub fn garbage_frames(&self) -> usize {
+        self.garbage_frames
+    }
+
+    /// Release the underlying serial line
+    pub fn into_inner(self) -> FI {
+        self.interface
+    }
+
+    fn push_event(&mut self, event: Event) {
+        if self.event_count == EVENT_CAPACITY {
+            self.pop_event();
+            self.dropped_events += 1;
+        }
+        let index = (self.first_event + self.event_count) % EVENT_CAPACITY;
+        self.events[index] = Some(event);
+        self.event_count += 1;
+    }
+
+    /// Receive one frame, buffering it if it is an event. Returns the reply if it was one.
+    fn receive_classified(&mut self) -> Result<Option<Reply>, FI::Error> {
+        let (frame, timestamp) = self.interface.receive_frame_timestamped()?;
+        Ok(self.classify(frame, timestamp))
+    }
+
+    fn classify(&mut self, frame: [u8; 9], timestamp: Option<Timestamp>) -> Option<Reply> {
+        match Frame::classify(frame) {
+            Frame::Reply(reply) => Some(match timestamp {
+                Some(timestamp) => reply.with_timestamp(timestamp),
+                None => reply,
+            }),
+            Frame::Event(event) => {
+                self.push_event(event);
+                None
+            },
+            Frame::Garbage(_) => {
+                self.garbage_frames += 1;
+                None
+            },
+        }
+    }
+}
+
+impl<FI: FrameInterface> Interface for Demux<FI> {
+    type Error = FI::Error;
+
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        self.interface.transmit_frame(&command.serialize())
+    }
+
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        loop {
+            if let Some(reply) = self.receive_classified()? {
+                return Ok(reply);
+            }
+        }
+    }
+
+    /// Receive frames with `FrameInterface::receive_frame_timeout` until a reply arrives
+    ///
+    /// Events and garbage received while waiting do not restart the timeout. Without the `std`
+    /// feature the elapsed time is not known, and every frame is waited for up to `timeout`.
+    fn receive_reply_timeout(&mut self, timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        loop {
+            #[cfg(feature = "std")]
+            let timeout = match timeout.checked_sub(start.elapsed()) {
+                Some(timeout) => timeout,
+                None => return Ok(None),
+            };
+            let (frame, timestamp) = match self.interface.receive_frame_timeout(timeout)? {
+                Some(received) => received,
+                None => return Ok(None),
+            };
+            if let Some(reply) = self.classify(frame, timestamp) {
+                return Ok(Some(reply));
+            }
+        }
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        self.interface.discard_input()
+    }
+}
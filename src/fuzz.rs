@@ -0,0 +1,232 @@
+//! Support for fuzzing interfaces and parsers (requires the `arbitrary` or `fuzz` feature).
+//!
+//! `Command<RawInstruction>`, `Reply`, `Status`, `Event` and `Frame` implement `Arbitrary`, and
+//! `ReceivedFrame` generates frames as seen on a serial line: mostly well formed replies and
+//! events, some of them corrupted. The `check_*` functions panic when an invariant of the crate's
+//! serialization or parsing is violated, and are meant to be called from fuzz targets.
+//!
+//! ```ignore
+//! #![no_main]
+//! #[macro_use] extern crate libfuzzer_sys;
+//! extern crate tmcl;
+//!
+//! use tmcl::fuzz::{self, ReceivedFrame};
+//!
+//! fuzz_target!(|frame: ReceivedFrame| {
+//!     fuzz::check_frame(frame.0);
+//! });
+//! ```
+//!
+//! Custom interfaces can be fuzzed by feeding arbitrary `ReceivedFrame`s to them in place of the
+//! data received from the module.
+//!
+//! ```
+//! extern crate arbitrary;
+//! extern crate tmcl;
+//!
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use tmcl::Command;
+//! use tmcl::fuzz;
+//! use tmcl::modules::tmcm::instructions::RawInstruction;
+//!
+//! fn main() {
+//!     let data = [7u8; 64];
+//!     let mut u = Unstructured::new(&data);
+//!     fuzz::check_command(&Command::<RawInstruction>::arbitrary(&mut u).unwrap());
+//!     fuzz::check_frame(fuzz::ReceivedFrame::arbitrary(&mut u).unwrap().0);
+//! }
+//! ```
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use Command;
+use Encode;
+use Operand;
+use Reply;
+use Status;
+use OkStatus;
+use ErrStatus;
+use instructions::RawInstruction;
+use demux::{Event, Frame, EVENT_STATUS};
+
+const STATUS_CODES: [u8; 8] = [100, 101, 1, 2, 3, 4, 5, 6];
+
+/// A frame in the serial reply format as received from a module.
+///
+/// Most generated frames are valid replies or events, the rest have a wrong checksum or an
+/// unknown status code.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ReceivedFrame(pub [u8; 9]);
+
+impl<'a> Arbitrary<'a> for RawInstruction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(RawInstruction::new(u8::arbitrary(u)?, u8::arbitrary(u)?, u8::arbitrary(u)?, i32::arbitrary(u)?))
+    }
+}
+
+impl<'a, T: Encode + Arbitrary<'a>> Arbitrary<'a> for Command<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Command::new(u8::arbitrary(u)?, T::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Status {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        match u.int_in_range(0u8..=9)? {
+            0 => Ok(Status::Err(ErrStatus::Unknown(unknown_status_code(u)?))),
+            _ => Ok(Status::from_u8(*u.choose(&STATUS_CODES)?)),
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for OkStatus {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[OkStatus::Ok, OkStatus::LoadedIntoEEPROM])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ErrStatus {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let unknown = ErrStatus::Unknown(unknown_status_code(u)?);
+        Ok(*u.choose(&[
+            ErrStatus::WrongChecksum,
+            ErrStatus::InvalidCommand,
+            ErrStatus::WrongType,
+            ErrStatus::InvalidValue,
+            ErrStatus::EEPROMLocked,
+            ErrStatus::CommandNotAvailable,
+            unknown,
+        ])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Operand {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Operand::from_le(<[u8; 4]>::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Reply {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Reply::new(
+            u8::arbitrary(u)?,
+            u8::arbitrary(u)?,
+            Status::arbitrary(u)?,
+            u8::arbitrary(u)?,
+            Operand::arbitrary(u)?,
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Event {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let operand = Operand::arbitrary(u)?.to_wire();
+        let frame = with_checksum([
+            u8::arbitrary(u)?,
+            u8::arbitrary(u)?,
+            EVENT_STATUS,
+            u8::arbitrary(u)?,
+            operand[0], operand[1], operand[2], operand[3],
+            0,
+        ]);
+        match Frame::classify(frame) {
+            Frame::Event(event) => Ok(event),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for Frame {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Frame::classify(ReceivedFrame::arbitrary(u)?.0))
+    }
+}
+
+impl<'a> Arbitrary<'a> for ReceivedFrame {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut frame = <[u8; 9]>::arbitrary(u)?;
+        frame[2] = match u.int_in_range(0u8..=9)? {
+            0 => frame[2],
+            1 => EVENT_STATUS,
+            2 => unknown_status_code(u)?,
+            _ => *u.choose(&STATUS_CODES)?,
+        };
+        if !u.ratio(1u8, 10)? {
+            frame = with_checksum(frame);
+        }
+        Ok(ReceivedFrame(frame))
+    }
+}
+
+/// A status code outside of the documented set that is not taken for an event either
+fn unknown_status_code(u: &mut Unstructured) -> Result<u8> {
+    let code = u8::arbitrary(u)?;
+    if code == EVENT_STATUS || Status::try_from_u8(code).is_ok() {
+        return Ok(0);
+    }
+    Ok(code)
+}
+
+fn with_checksum(mut frame: [u8; 9]) -> [u8; 9] {
+    frame[8] = frame[..8].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    frame
+}
+
+/// Check that the serialization formats of a command agree with each other
+pub fn check_command<T: Encode>(command: &Command<T>) {
+    let frame = command.serialize();
+    assert_eq!(frame, with_checksum(frame), "wrong checksum");
+    assert_eq!(frame[0], command.module_address());
+    assert_eq!(&frame[1..8], &command.serialize_can()[..]);
+
+    let i2c = command.serialize_i2c();
+    assert_eq!(&i2c[..7], &frame[1..8]);
+    assert_eq!(i2c[7], frame[8], "I2C checksum differs from serial checksum");
+
+    let mut buffer = [0u8; 9];
+    assert_eq!(command.serialize_into(&mut buffer), Ok(9));
+    assert_eq!(buffer, frame);
+    assert_eq!(command.serialize_i2c_into(&mut buffer), Ok(8));
+    assert_eq!(&buffer[..8], &i2c[..]);
+    assert_eq!(command.serialize_can_into(&mut buffer), Ok(7));
+    assert_eq!(&buffer[..7], &frame[1..8]);
+}
+
+/// Check that a reply survives serialization and classification unchanged
+pub fn check_reply(reply: &Reply) {
+    let frame = reply.serialize();
+    assert_eq!(frame, with_checksum(frame), "wrong checksum");
+    assert_eq!(Frame::classify(frame), Frame::Reply(reply.clone()));
+}
+
+/// Check that classifying a received frame does not lose information
+pub fn check_frame(frame: [u8; 9]) {
+    match Frame::classify(frame) {
+        Frame::Reply(reply) => assert_eq!(reply.serialize(), frame),
+        Frame::Event(event) => {
+            assert_eq!(frame, with_checksum(frame), "event with wrong checksum");
+            assert_eq!(frame[2], EVENT_STATUS);
+            assert_eq!(
+                [event.reply_address(), event.module_address(), event.command_number()],
+                [frame[0], frame[1], frame[3]]
+            );
+            assert_eq!(event.operand().to_wire(), [frame[4], frame[5], frame[6], frame[7]]);
+        },
+        Frame::Garbage(garbage) => {
+            assert_eq!(garbage, frame);
+            assert!(frame != with_checksum(frame), "garbage with correct checksum");
+        },
+    }
+}
+
+/// Check that parsing arbitrary data as a capture does not panic, and that parsed captures are written back unchanged
+#[cfg(feature = "std")]
+pub fn check_capture(data: &[u8]) {
+    use capture::Capture;
+
+    if let Ok(capture) = Capture::read_from(data) {
+        let mut written = Vec::new();
+        capture.write_to(&mut written).unwrap();
+        assert_eq!(Capture::read_from(&written[..]).unwrap(), capture);
+    }
+}
@@ -6,11 +6,39 @@ use std::marker::PhantomData;
 use core::marker::PhantomData;
 
 use {
+    Operand,
     WriteableAxisParameter,
     ReadableAxisParameter,
+    WriteableGlobalParameter,
+    ReadableGlobalParameter,
 };
 
 /// A `TMCL` `Instruction`
+///
+/// This trait describes what an instruction means, while the wire format is handled by `Encode`.
+///
+/// # Vendor specific instructions
+/// Instructions not provided by this crate (e.g. vendor specific or undocumented instructions) can
+/// be used by implementing this trait. The reordering of the operand into wire order is done by
+/// `Encode`.
+///
+/// ```
+/// use tmcl::{Instruction, Operand};
+///
+/// struct VendorInstruction {
+///     value: i32,
+/// }
+///
+/// impl Instruction for VendorInstruction {
+///     const INSTRUCTION_NUMBER: u8 = 200;
+///
+///     fn type_number(&self) -> u8 { 0 }
+///
+///     fn motor_bank_number(&self) -> u8 { 0 }
+///
+///     fn operand(&self) -> Operand { Operand::from_i32(self.value) }
+/// }
+/// ```
 pub trait Instruction {
     /// The command number (sometimes referred to as the instruction number).
     const INSTRUCTION_NUMBER: u8;
@@ -20,13 +48,74 @@ pub trait Instruction {
     /// The motor/bank number
     fn motor_bank_number(&self) -> u8;
 
-    /// Return the operand serialized.
+    /// The operand
+    fn operand(&self) -> Operand;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// The wire encoding of an `Instruction`.
+///
+/// This trait is sealed and implemented for every `Instruction`, it can not be implemented outside this crate.
+/// It guarantees that all instructions are laid out the same way in serialized commands.
+pub trait Encode: private::Sealed {
+    /// Encode the instruction into the part of the frame common for all interfaces.
     ///
-    /// Even though the whole frame (in serialized form) is represented as:
-    /// `[..., operand[3], operand[2], operand[1], operand[0], ...]`.
-    /// This function instead return the operand:
-    /// `[operand[0], operand[1], operand[2], operand[3]]`.
-    fn operand(&self) -> [u8; 4];
+    /// The array will look like the following:
+    /// `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0]`
+    fn encode(&self) -> [u8; 7];
+}
+
+impl<T: Instruction> private::Sealed for T {}
+
+impl<T: Instruction> Encode for T {
+    fn encode(&self) -> [u8; 7] {
+        let operand = self.operand().to_wire();
+        [
+            T::INSTRUCTION_NUMBER,
+            self.type_number(),
+            self.motor_bank_number(),
+            operand[0],
+            operand[1],
+            operand[2],
+            operand[3],
+        ]
+    }
+}
+
+/// An instruction where the instruction number is only known at runtime.
+///
+/// This is an escape hatch for sending vendor specific or undocumented instructions,
+/// it is neither an `Instruction` nor a `DirectInstruction` and the reply is returned as the raw operand.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RawInstruction {
+    instruction_number: u8,
+    type_number: u8,
+    motor_bank_number: u8,
+    value: i32,
+}
+impl RawInstruction {
+    pub fn new(instruction_number: u8, type_number: u8, motor_bank_number: u8, value: i32) -> Self {
+        RawInstruction{instruction_number, type_number, motor_bank_number, value}
+    }
+}
+impl private::Sealed for RawInstruction {}
+impl Encode for RawInstruction {
+    fn encode(&self) -> [u8; 7] {
+        let operand = Operand::from_i32(self.value).to_wire();
+        [
+            self.instruction_number,
+            self.type_number,
+            self.motor_bank_number,
+            operand[0],
+            operand[1],
+            operand[2],
+            operand[3],
+        ]
+    }
 }
 
 /// An `Instruction` useable in direct mode
@@ -40,17 +129,38 @@ pub trait Return {
 
     /// The deserialization function.
     ///
-    /// The argument of the deserialize function is the operand bytes array:
-    /// `[operand[0], operand[1], operand[2], operand[3]]`.
-    /// This is a different to how it is represented in the serialized frame:
-    /// `[..., operand[3], operand[2], operand[1], operand[0], ...]`
-    fn from_operand(operand: [u8; 4]) -> Self;
+    /// Returns `InvalidOperand` for operands that are not a valid value of the type, it is what
+    /// modules use when deserializing replies so that malformed device data results in an error
+    /// instead of a panic.
+    fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> where Self: Sized;
 }
 
+/// A `Return` type where every operand is a valid value.
+///
+/// Types implementing this trait are deserialized infallibly and implement `Return` through it.
+/// Enum-valued registers and other types where not every operand is valid only implement `Return`.
+pub trait FromOperand {
+
+    /// The infallible deserialization function.
+    fn from_operand(operand: Operand) -> Self;
+}
+
+impl<T: FromOperand> Return for T {
+    fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> {
+        Ok(T::from_operand(operand))
+    }
+}
+
+/// The result of attempting to deserialize an operand that is not a valid value for the type.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidOperand;
+
 /// ROR - Rotate Right
 ///
 /// This instruction starts rotation in "right" direction, i.e. increasing the position counter.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ROR {
     motor_number: u8,
     velocity: u32,
@@ -61,13 +171,8 @@ impl ROR {
 impl Instruction for ROR {
     const INSTRUCTION_NUMBER: u8 = 1;
 
-    fn operand(&self) -> [u8; 4] {
-        return [
-            (self.velocity & 0xff) as u8,
-            ((self.velocity >> 8) & 0xff) as u8,
-            ((self.velocity >> 16) & 0xff) as u8,
-            ((self.velocity >> 24) & 0xff) as u8
-        ]
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.velocity)
     }
 
     fn type_number(&self) -> u8 {
@@ -86,6 +191,7 @@ impl DirectInstruction for ROR {
 ///
 /// This instruction starts rotation in "left" direction, i.e. decreasing the position counter.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ROL {
     motor_number: u8,
     velocity: u32,
@@ -96,13 +202,8 @@ impl ROL {
 impl Instruction for ROL {
     const INSTRUCTION_NUMBER: u8 = 2;
 
-    fn operand(&self) -> [u8; 4] {
-        return [
-            (self.velocity & 0xff) as u8,
-            ((self.velocity >> 8) & 0xff) as u8,
-            ((self.velocity >> 16) & 0xff) as u8,
-            ((self.velocity >> 24) & 0xff) as u8
-        ]
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.velocity)
     }
 
     fn type_number(&self) -> u8 {
@@ -122,6 +223,7 @@ impl DirectInstruction for ROL {
 ///
 /// This instruction stops the motor.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MST {
     motor_number: u8,
 }
@@ -131,8 +233,8 @@ impl MST {
 impl Instruction for MST {
     const INSTRUCTION_NUMBER: u8 = 3;
 
-    fn operand(&self) -> [u8; 4] {
-        return [0, 0, 0, 0]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
@@ -149,6 +251,7 @@ impl DirectInstruction for MST {
 
 /// The type and value of a `MVP` instruction
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MoveOperation {
     /// Moving to an absolute position in the range from -8388608 to +8388608 (-2^23 to +2^23).
     Absolute(i32),
@@ -172,6 +275,7 @@ pub enum MoveOperation {
 /// A movement towards the specified position is started, with automatic generation of acceleration
 /// and deceleration ramps. The maximum velocity and acceleration are defined by axis parameters #4 and #5.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MVP {
     motor_number: u8,
     value: MoveOperation,
@@ -182,37 +286,26 @@ impl MVP {
 impl Instruction for MVP {
     const INSTRUCTION_NUMBER: u8 = 4;
 
-    fn operand(&self) -> [u8; 4] {
+    fn operand(&self) -> Operand {
         match self.value {
             MoveOperation::Absolute(x) => {
-                [
-                    (x & 0xff) as u8,
-                    ((x >> 8) & 0xff) as u8,
-                    ((x >> 16) & 0xff) as u8,
-                    ((x >> 24) & 0xff) as u8
-                ]
+                Operand::from_i32(x)
             },
             MoveOperation::Relative(x) => {
-                [
-                    (x & 0xff) as u8,
-                    ((x >> 8) & 0xff) as u8,
-                    ((x >> 16) & 0xff) as u8,
-                    ((x >> 24) & 0xff) as u8
-                ]
+                Operand::from_i32(x)
             },
             MoveOperation::Coordinate(x) => {
-                [
-                    (x & 0xff) as u8,
-                    ((x >> 8) & 0xff) as u8,
-                    ((x >> 16) & 0xff) as u8,
-                    ((x >> 24) & 0xff) as u8
-                ]
+                Operand::from_u32(x)
             },
         }
     }
 
     fn type_number(&self) -> u8 {
-        0
+        match self.value {
+            MoveOperation::Absolute(_) => 0,
+            MoveOperation::Relative(_) => 1,
+            MoveOperation::Coordinate(_) => 2,
+        }
     }
 
     fn motor_bank_number(&self) -> u8 {
@@ -231,6 +324,7 @@ impl DirectInstruction for MVP {
 /// and physical locations (TMC428, TMC453, controller RAM, controller EEPROM),
 /// they all can be set by this function.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SAP<T: WriteableAxisParameter> {
     motor_number: u8,
     axis_parameter: T,
@@ -246,7 +340,7 @@ impl<T: WriteableAxisParameter> SAP<T> {
 impl<T: WriteableAxisParameter> Instruction for SAP<T> {
     const INSTRUCTION_NUMBER: u8 = 5;
 
-    fn operand(&self) -> [u8; 4] {
+    fn operand(&self) -> Operand {
         self.axis_parameter.operand()
     }
 
@@ -269,6 +363,7 @@ impl<T: WriteableAxisParameter> DirectInstruction for SAP<T> {
 /// and physical locations (TMC428, TMC453, controller RAM, controller EEPROM),
 /// they all can be read by this function.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GAP<T: ReadableAxisParameter> {
     motor_number: u8,
     phantom: PhantomData<T>,
@@ -284,8 +379,8 @@ impl<T: ReadableAxisParameter> GAP<T> {
 impl<T: ReadableAxisParameter> Instruction for GAP<T> {
     const INSTRUCTION_NUMBER: u8 = 6;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
@@ -305,6 +400,7 @@ impl<T: ReadableAxisParameter> DirectInstruction for GAP<T> {
 /// Axis parameters are located in RAM memory, so modifications are lost at power down.
 /// This instruction enables permanent storing.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct STAP<T: WriteableAxisParameter> {
     motor_number: u8,
     phantom: PhantomData<T>,
@@ -320,8 +416,8 @@ impl<T: WriteableAxisParameter> STAP<T> {
 impl<T: WriteableAxisParameter> Instruction for STAP<T> {
     const INSTRUCTION_NUMBER: u8 = 7;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
@@ -342,6 +438,7 @@ impl<T: WriteableAxisParameter> DirectInstruction for STAP<T> {
 /// By default, most parameters are automatically restored after power up (see axis parameter list in
 /// chapter 4). A single parameter that has been changed before can be reset by this instruction.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RSAP<T: WriteableAxisParameter> {
     motor_number: u8,
     phantom: PhantomData<T>,
@@ -357,8 +454,8 @@ impl<T: WriteableAxisParameter> RSAP<T> {
 impl<T: WriteableAxisParameter> Instruction for RSAP<T> {
     const INSTRUCTION_NUMBER: u8 = 8;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
@@ -373,63 +470,283 @@ impl<T: WriteableAxisParameter> DirectInstruction for RSAP<T> {
     type Return = ();
 }
 
-/// Choses what action to execute with the `RFS` instruction
+/// SGP - Set Global Parameter
+///
+/// Global parameters are related to the host interface, peripherals or application specific variables.
+/// They are organized in banks, and this instruction sets a parameter in the given bank.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SGP<T: WriteableGlobalParameter> {
+    global_parameter: T,
+}
+impl<T: WriteableGlobalParameter> SGP<T> {
+    pub fn new(global_parameter: T) -> SGP<T> {
+        SGP{global_parameter}
+    }
+}
+impl<T: WriteableGlobalParameter> Instruction for SGP<T> {
+    const INSTRUCTION_NUMBER: u8 = 9;
+
+    fn operand(&self) -> Operand {
+        self.global_parameter.operand()
+    }
+
+    fn type_number(&self) -> u8 {
+        T::NUMBER
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        T::BANK
+    }
+}
+impl<T: WriteableGlobalParameter> DirectInstruction for SGP<T> {
+    type Return = ();
+}
+
+/// GGP - Get Global Parameter
+///
+/// Global parameters are related to the host interface, peripherals or application specific variables.
+/// They are organized in banks, and this instruction reads a parameter from the given bank.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GGP<T: ReadableGlobalParameter> {
+    phantom: PhantomData<T>,
+}
+impl<T: ReadableGlobalParameter> GGP<T> {
+    pub fn new() -> GGP<T> {
+        GGP{phantom: PhantomData}
+    }
+}
+impl<T: ReadableGlobalParameter> Default for GGP<T> {
+    fn default() -> Self {
+        GGP::new()
+    }
+}
+impl<T: ReadableGlobalParameter> Instruction for GGP<T> {
+    const INSTRUCTION_NUMBER: u8 = 10;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        T::NUMBER
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        T::BANK
+    }
+}
+impl<T: ReadableGlobalParameter> DirectInstruction for GGP<T> {
+    type Return = T;
+}
+
+/// STGP - Store Global Parameter
+///
+/// Global parameters are located in RAM memory, so modifications are lost at power down.
+/// This instruction stores a global parameter in EEPROM, from where it is restored at power up.
+/// The EEPROM endures a limited number of writes, so parameters should not be stored periodically.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct STGP<T: WriteableGlobalParameter> {
+    phantom: PhantomData<T>,
+}
+impl<T: WriteableGlobalParameter> STGP<T> {
+    pub fn new() -> STGP<T> {
+        STGP{phantom: PhantomData}
+    }
+}
+impl<T: WriteableGlobalParameter> Default for STGP<T> {
+    fn default() -> Self {
+        STGP::new()
+    }
+}
+impl<T: WriteableGlobalParameter> Instruction for STGP<T> {
+    const INSTRUCTION_NUMBER: u8 = 11;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        T::NUMBER
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        T::BANK
+    }
+}
+impl<T: WriteableGlobalParameter> DirectInstruction for STGP<T> {
+    type Return = ();
+}
+
+/// RSGP - Restore Global Parameter
+///
+/// Sets a global parameter back to the value stored in EEPROM with `STGP`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RSGP<T: WriteableGlobalParameter> {
+    phantom: PhantomData<T>,
+}
+impl<T: WriteableGlobalParameter> RSGP<T> {
+    pub fn new() -> RSGP<T> {
+        RSGP{phantom: PhantomData}
+    }
+}
+impl<T: WriteableGlobalParameter> Default for RSGP<T> {
+    fn default() -> Self {
+        RSGP::new()
+    }
+}
+impl<T: WriteableGlobalParameter> Instruction for RSGP<T> {
+    const INSTRUCTION_NUMBER: u8 = 12;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        T::NUMBER
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        T::BANK
+    }
+}
+impl<T: WriteableGlobalParameter> DirectInstruction for RSGP<T> {
+    type Return = ();
+}
+
+/// The status of a reference search, returned by `RfsStatus`.
 #[derive(Debug, PartialEq, Clone, Copy)]
-pub enum ReferenceSearchAction {
-    /// Start reference search
-    Start = 0,
-    /// Stop reference search
-    Stop = 1,
-    /// Get status
-    Status = 2,
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReferenceSearchStatus {
+    /// The reference search is not active (it has finished or was never started).
+    Finished,
+    /// The reference search is still running.
+    Searching,
+}
+impl ReferenceSearchStatus {
+    /// Returns true if the reference search is still running
+    pub fn is_searching(&self) -> bool {
+        *self == ReferenceSearchStatus::Searching
+    }
+}
+impl FromOperand for ReferenceSearchStatus {
+    fn from_operand(operand: Operand) -> Self {
+        if operand == Operand::ZERO {
+            ReferenceSearchStatus::Finished
+        } else {
+            ReferenceSearchStatus::Searching
+        }
+    }
 }
 
-/// RFS - Reference Search
+/// RFS START - Start Reference Search
 ///
 /// A build-in reference point search algorithm can be started (and stopped). The reference search
-/// algorithm provides switching point calibration and three switch modes. The status of the
-/// reference search can also be queried to see if it has already finished. (In a TMCL program
-/// it is better to use the WAIT command to wait for the end of a reference search.)
+/// algorithm provides switching point calibration and three switch modes.
 /// Please see the appropriate parameters in the axis parameter table to configure the
-/// reference search algorithm to meet your needs. The reference search can be started or stop
-/// ped, or the actual status of the reference search can be checked.
+/// reference search algorithm to meet your needs.
 #[derive(Debug, PartialEq)]
-pub struct RFS {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RfsStart {
     motor_number: u8,
-    action: ReferenceSearchAction,
 }
-impl RFS {
-    pub fn new(motor_number: u8, action: ReferenceSearchAction) -> RFS {
-        RFS {
-            motor_number,
-            action
-        }
+impl RfsStart {
+    pub fn new(motor_number: u8) -> RfsStart {
+        RfsStart{motor_number}
+    }
+}
+impl Instruction for RfsStart {
+    const INSTRUCTION_NUMBER: u8 = 13;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        0
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        self.motor_number
+    }
+}
+impl DirectInstruction for RfsStart {
+    type Return = ();
+}
+
+/// RFS STOP - Stop Reference Search
+///
+/// Stops a running reference search.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RfsStop {
+    motor_number: u8,
+}
+impl RfsStop {
+    pub fn new(motor_number: u8) -> RfsStop {
+        RfsStop{motor_number}
+    }
+}
+impl Instruction for RfsStop {
+    const INSTRUCTION_NUMBER: u8 = 13;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        1
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        self.motor_number
+    }
+}
+impl DirectInstruction for RfsStop {
+    type Return = ();
+}
+
+/// RFS STATUS - Reference Search Status
+///
+/// The status of the reference search can be queried to see if it has already finished.
+/// (In a TMCL program it is better to use the WAIT command to wait for the end of a reference search.)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RfsStatus {
+    motor_number: u8,
+}
+impl RfsStatus {
+    pub fn new(motor_number: u8) -> RfsStatus {
+        RfsStatus{motor_number}
     }
 }
-impl Instruction for RFS {
+impl Instruction for RfsStatus {
     const INSTRUCTION_NUMBER: u8 = 13;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
-        self.action as u8
+        2
     }
 
     fn motor_bank_number(&self) -> u8 {
         self.motor_number
     }
 }
-impl DirectInstruction for RFS {
-    // TODO: use const generics (when it lands) to distinguish return between RFS<Status> and RFS<_>
-    type Return = bool;
+impl DirectInstruction for RfsStatus {
+    type Return = ReferenceSearchStatus;
 }
 
 /// SIO - Set Output
 ///
 /// This command sets the status of a digital output either to low (0) or to high (1).
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SIO {
     bank_number: u8,
     port_number: u8,
@@ -443,7 +760,7 @@ impl SIO {
 impl Instruction for SIO {
     const INSTRUCTION_NUMBER: u8 = 14;
 
-    fn operand(&self) -> [u8; 4] {[self.state as u8, 0u8, 0u8, 0u8]}
+    fn operand(&self) -> Operand {Operand::from_u32(u32::from(self.state))}
 
     fn type_number(&self) -> u8 { self.port_number }
 
@@ -461,6 +778,7 @@ impl DirectInstruction for SIO {
 /// as conditioned jumps. In  direct  mode the value is only output in the “value” field of the reply,
 /// without affecting the accumulator. The actual status of a digital output line can also be read.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GIO {
     bank_number: u8,
     port_number: u8,
@@ -473,7 +791,7 @@ impl GIO {
 impl Instruction for GIO {
     const INSTRUCTION_NUMBER: u8 = 15;
 
-    fn operand(&self) -> [u8; 4] {[0u8, 0u8, 0u8, 0u8]}
+    fn operand(&self) -> Operand {Operand::ZERO}
 
     fn type_number(&self) -> u8 { self.port_number }
 
@@ -483,8 +801,374 @@ impl DirectInstruction for GIO {
     type Return = u32;
 }
 
+/// The motor number used with `SCO` and `GCO` to copy coordinates between RAM and EEPROM.
+pub const COORDINATE_EEPROM_MOTOR_NUMBER: u8 = 0xff;
+
+/// SCO - Set Coordinate
+///
+/// Up to 20 position values (coordinates) can be stored for every axis for use with the `MVP` `COORD` command.
+/// This instruction sets a coordinate to a specified value.
+///
+/// Depending on global parameter 84 (`CoordinateStorage`) the coordinates are either kept in RAM or stored
+/// directly in EEPROM. Coordinates kept in RAM can be persisted with `SCO::store_to_eeprom`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SCO {
+    motor_number: u8,
+    coordinate_number: u8,
+    position: i32,
+}
+impl SCO {
+    pub fn new(motor_number: u8, coordinate_number: u8, position: i32) -> SCO {
+        SCO{motor_number, coordinate_number, position}
+    }
+
+    /// Copy a coordinate from RAM to EEPROM, so that it survives a power cycle
+    pub fn store_to_eeprom(coordinate_number: u8) -> SCO {
+        SCO{motor_number: COORDINATE_EEPROM_MOTOR_NUMBER, coordinate_number, position: 0}
+    }
+}
+impl Instruction for SCO {
+    const INSTRUCTION_NUMBER: u8 = 30;
+
+    fn operand(&self) -> Operand {
+        Operand::from_i32(self.position)
+    }
+
+    fn type_number(&self) -> u8 {
+        self.coordinate_number
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        self.motor_number
+    }
+}
+impl DirectInstruction for SCO {
+    type Return = ();
+}
+
+/// GCO - Get Coordinate
+///
+/// Using this command previously stored coordinates can be read back.
+///
+/// Coordinates stored in EEPROM can be copied back to RAM with `GCO::load_from_eeprom`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GCO {
+    motor_number: u8,
+    coordinate_number: u8,
+}
+impl GCO {
+    pub fn new(motor_number: u8, coordinate_number: u8) -> GCO {
+        GCO{motor_number, coordinate_number}
+    }
+
+    /// Copy a coordinate from EEPROM to RAM
+    pub fn load_from_eeprom(coordinate_number: u8) -> GCO {
+        GCO{motor_number: COORDINATE_EEPROM_MOTOR_NUMBER, coordinate_number}
+    }
+}
+impl Instruction for GCO {
+    const INSTRUCTION_NUMBER: u8 = 31;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        self.coordinate_number
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        self.motor_number
+    }
+}
+impl DirectInstruction for GCO {
+    type Return = i32;
+}
+
+/// CCO - Capture Coordinate
+///
+/// The actual position of the axis is copied to the selected coordinate variable.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CCO {
+    motor_number: u8,
+    coordinate_number: u8,
+}
+impl CCO {
+    pub fn new(motor_number: u8, coordinate_number: u8) -> CCO {
+        CCO{motor_number, coordinate_number}
+    }
+}
+impl Instruction for CCO {
+    const INSTRUCTION_NUMBER: u8 = 32;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        self.coordinate_number
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        self.motor_number
+    }
+}
+impl DirectInstruction for CCO {
+    type Return = ();
+}
+
+/// The condition for a `JC` (conditional jump) instruction.
+///
+/// The comparison conditions refer to the result of the last `COMP` instruction,
+/// the error conditions refer to the error flags of the module.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Condition {
+    /// Zero
+    ZE = 0,
+    /// Not zero
+    NZ = 1,
+    /// Equal
+    EQ = 2,
+    /// Not equal
+    NE = 3,
+    /// Greater
+    GT = 4,
+    /// Greater or equal
+    GE = 5,
+    /// Lower
+    LT = 6,
+    /// Lower or equal
+    LE = 7,
+    /// Time out error (set by `WAIT` with timeout)
+    ETO = 8,
+    /// External alarm
+    EAL = 9,
+    /// Deviation error
+    EDV = 10,
+    /// Position error
+    EPO = 11,
+    /// Shutdown error
+    ESD = 12,
+}
+
+/// JC - Jump Conditional
+///
+/// The `JC` instruction enables a conditional jump to a fixed address in the TMCL program memory,
+/// if the specified condition is met. It is only useable in stand-alone mode.
+///
+/// When building a `TmclProgram`, use `TmclProgram::push_jc` to jump to a label instead of an address.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JC {
+    condition: Condition,
+    address: u32,
+}
+impl JC {
+    pub fn new(condition: Condition, address: u32) -> JC {
+        JC{condition, address}
+    }
+}
+impl Instruction for JC {
+    const INSTRUCTION_NUMBER: u8 = 21;
+
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.address)
+    }
+
+    fn type_number(&self) -> u8 {
+        self.condition as u8
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+
+/// JA - Jump Always
+///
+/// Jump to a fixed address in the TMCL program memory. It is only useable in stand-alone mode.
+///
+/// When building a `TmclProgram`, use `TmclProgram::push_ja` to jump to a label instead of an address.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JA {
+    address: u32,
+}
+impl JA {
+    pub fn new(address: u32) -> JA {
+        JA{address}
+    }
+}
+impl Instruction for JA {
+    const INSTRUCTION_NUMBER: u8 = 22;
+
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.address)
+    }
+
+    fn type_number(&self) -> u8 {
+        0
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+
+/// CSUB - Call Subroutine
+///
+/// Calls a subroutine in the TMCL program memory. It is only useable in stand-alone mode.
+///
+/// When building a `TmclProgram`, use `TmclProgram::push_csub` to call a label instead of an address.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CSUB {
+    address: u32,
+}
+impl CSUB {
+    pub fn new(address: u32) -> CSUB {
+        CSUB{address}
+    }
+}
+impl Instruction for CSUB {
+    const INSTRUCTION_NUMBER: u8 = 23;
+
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.address)
+    }
+
+    fn type_number(&self) -> u8 {
+        0
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+
+/// RSUB - Return from Subroutine
+///
+/// Continue the program at the address following the `CSUB` that called the subroutine.
+/// It is only useable in stand-alone mode.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RSUB;
+impl Instruction for RSUB {
+    const INSTRUCTION_NUMBER: u8 = 24;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        0
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+
+/// STOP - Stop TMCL program execution
+///
+/// This instruction stops the execution of the TMCL program. It is only useable in stand-alone mode.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct STOP;
+impl Instruction for STOP {
+    const INSTRUCTION_NUMBER: u8 = 28;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        0
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+
+/// The condition a `WAIT` instruction waits for.
+///
+/// The timeout is given in ticks of 10 milliseconds, a timeout of zero means waiting forever.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WaitCondition {
+    /// Wait for the given number of ticks (10 milliseconds each).
+    Ticks(u32),
+
+    /// Wait until the target position of the motor is reached.
+    Position{motor_number: u8, timeout: u32},
+
+    /// Wait until the reference switch of the motor is triggered.
+    ReferenceSwitch{motor_number: u8, timeout: u32},
+
+    /// Wait until one of the limit switches of the motor is triggered.
+    LimitSwitch{motor_number: u8, timeout: u32},
+
+    /// Wait until the reference search of the motor has finished.
+    ReferenceSearch{motor_number: u8, timeout: u32},
+}
+
+/// WAIT - Wait for an event
+///
+/// This instruction interrupts the execution of the TMCL program until the specified condition is met.
+/// It is only useable in stand-alone mode, i.e. as part of a `TmclProgram`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WAIT {
+    condition: WaitCondition,
+}
+impl WAIT {
+    pub fn new(condition: WaitCondition) -> WAIT {
+        WAIT{condition}
+    }
+}
+impl Instruction for WAIT {
+    const INSTRUCTION_NUMBER: u8 = 27;
+
+    fn operand(&self) -> Operand {
+        let value = match self.condition {
+            WaitCondition::Ticks(ticks) => ticks,
+            WaitCondition::Position{timeout, ..} => timeout,
+            WaitCondition::ReferenceSwitch{timeout, ..} => timeout,
+            WaitCondition::LimitSwitch{timeout, ..} => timeout,
+            WaitCondition::ReferenceSearch{timeout, ..} => timeout,
+        };
+        Operand::from_u32(value)
+    }
+
+    fn type_number(&self) -> u8 {
+        match self.condition {
+            WaitCondition::Ticks(_) => 0,
+            WaitCondition::Position{..} => 1,
+            WaitCondition::ReferenceSwitch{..} => 2,
+            WaitCondition::LimitSwitch{..} => 3,
+            WaitCondition::ReferenceSearch{..} => 4,
+        }
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        match self.condition {
+            WaitCondition::Ticks(_) => 0,
+            WaitCondition::Position{motor_number, ..} => motor_number,
+            WaitCondition::ReferenceSwitch{motor_number, ..} => motor_number,
+            WaitCondition::LimitSwitch{motor_number, ..} => motor_number,
+            WaitCondition::ReferenceSearch{motor_number, ..} => motor_number,
+        }
+    }
+}
+
 /// CALC - Calculate
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CALC {
     /// Add the operand to the accumulator
     Add(i32),
@@ -520,18 +1204,18 @@ pub enum CALC {
 impl Instruction for CALC {
     const INSTRUCTION_NUMBER: u8 = 19;
 
-    fn operand(&self) -> [u8; 4] {
-        match self {
-            CALC::Add(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Sub(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Mul(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Div(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Mod(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::And(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Or(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Xor(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
-            CALC::Not => [0u8, 0u8, 0u8, 0u8],
-            CALC::Load(x) => [(x >> 0) as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8],
+    fn operand(&self) -> Operand {
+        match *self {
+            CALC::Add(x) => Operand::from_i32(x),
+            CALC::Sub(x) => Operand::from_i32(x),
+            CALC::Mul(x) => Operand::from_i32(x),
+            CALC::Div(x) => Operand::from_i32(x),
+            CALC::Mod(x) => Operand::from_i32(x),
+            CALC::And(x) => Operand::from_i32(x),
+            CALC::Or(x) => Operand::from_i32(x),
+            CALC::Xor(x) => Operand::from_i32(x),
+            CALC::Not => Operand::ZERO,
+            CALC::Load(x) => Operand::from_i32(x),
         }
     }
 
@@ -554,4 +1238,216 @@ impl Instruction for CALC {
 }
 impl DirectInstruction for CALC {
     type Return = ();
-}
\ No newline at end of file
+}
+
+/// AAP - Accumulator to Axis Parameter
+///
+/// Copies the value of the accumulator to an axis parameter, e.g. after it has been computed with `CALC`.
+/// It is only useable in stand-alone mode, i.e. as part of a `TmclProgram`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AAP<T: WriteableAxisParameter> {
+    motor_number: u8,
+    phantom: PhantomData<T>,
+}
+impl<T: WriteableAxisParameter> AAP<T> {
+    pub fn new(motor_number: u8) -> AAP<T> {
+        AAP{
+            motor_number,
+            phantom: PhantomData,
+        }
+    }
+}
+impl<T: WriteableAxisParameter> Instruction for AAP<T> {
+    const INSTRUCTION_NUMBER: u8 = 34;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        T::NUMBER
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        self.motor_number
+    }
+}
+
+/// AGP - Accumulator to Global Parameter
+///
+/// Copies the value of the accumulator to a global parameter, e.g. after it has been computed with `CALC`.
+/// It is only useable in stand-alone mode, i.e. as part of a `TmclProgram`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AGP<T: WriteableGlobalParameter> {
+    phantom: PhantomData<T>,
+}
+impl<T: WriteableGlobalParameter> AGP<T> {
+    pub fn new() -> AGP<T> {
+        AGP{phantom: PhantomData}
+    }
+}
+impl<T: WriteableGlobalParameter> Default for AGP<T> {
+    fn default() -> Self {
+        AGP::new()
+    }
+}
+impl<T: WriteableGlobalParameter> Instruction for AGP<T> {
+    const INSTRUCTION_NUMBER: u8 = 35;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        T::NUMBER
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        T::BANK
+    }
+}
+
+/// The identity of a module as reported by `GetFirmwareVersion`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FirmwareVersion {
+    module_type: u16,
+    major: u8,
+    minor: u8,
+}
+impl FirmwareVersion {
+    /// The module type number, e.g. 6110 for a TMCM-6110
+    pub fn module_type(&self) -> u16 {
+        self.module_type
+    }
+
+    /// The major firmware version
+    pub fn major(&self) -> u8 {
+        self.major
+    }
+
+    /// The minor firmware version
+    pub fn minor(&self) -> u8 {
+        self.minor
+    }
+}
+impl FromOperand for FirmwareVersion {
+    fn from_operand(operand: Operand) -> Self {
+        FirmwareVersion{
+            module_type: u16::from(operand[2]) | (u16::from(operand[3]) << 8),
+            major: operand[1],
+            minor: operand[0],
+        }
+    }
+}
+
+/// GetFirmwareVersion - Get the module type and firmware version
+///
+/// The firmware version is requested in binary format (type 1).
+/// The instruction has no side effects and can be used to probe for modules.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetFirmwareVersion;
+impl Instruction for GetFirmwareVersion {
+    const INSTRUCTION_NUMBER: u8 = 136;
+
+    fn operand(&self) -> Operand {
+        Operand::ZERO
+    }
+
+    fn type_number(&self) -> u8 {
+        1
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+impl DirectInstruction for GetFirmwareVersion {
+    type Return = FirmwareVersion;
+}
+
+/// Request Target Position Reached Event
+///
+/// Requests the module to send an event (a telegram with status 128 and command number 138) as
+/// soon as the given motors have reached their target position. The request has to be repeated for
+/// every positioning, the events can be received with `demux::Demux`.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RequestTargetPositionReachedEvent {
+    motor_mask: u32,
+}
+impl RequestTargetPositionReachedEvent {
+    /// Request an event when all motors in the bit mask (bit 0 for motor 0 etc.) have reached their target
+    pub fn new(motor_mask: u32) -> Self {
+        RequestTargetPositionReachedEvent{motor_mask}
+    }
+
+    /// Request an event when the given motor has reached its target
+    pub fn for_motor(motor_number: u8) -> Self {
+        assert!(motor_number < 32);
+        RequestTargetPositionReachedEvent{motor_mask: 1 << motor_number}
+    }
+}
+impl Instruction for RequestTargetPositionReachedEvent {
+    const INSTRUCTION_NUMBER: u8 = 138;
+
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.motor_mask)
+    }
+
+    fn type_number(&self) -> u8 {
+        0
+    }
+
+    fn motor_bank_number(&self) -> u8 {
+        0
+    }
+}
+impl DirectInstruction for RequestTargetPositionReachedEvent {
+    type Return = ();
+}
+
+/// The mnemonic of an instruction number, `None` if the instruction number is unknown
+pub(crate) fn mnemonic(instruction_number: u8) -> Option<&'static str> {
+    match instruction_number {
+        1 => Some("ROR"),
+        2 => Some("ROL"),
+        3 => Some("MST"),
+        4 => Some("MVP"),
+        5 => Some("SAP"),
+        6 => Some("GAP"),
+        7 => Some("STAP"),
+        8 => Some("RSAP"),
+        9 => Some("SGP"),
+        10 => Some("GGP"),
+        11 => Some("STGP"),
+        12 => Some("RSGP"),
+        13 => Some("RFS"),
+        14 => Some("SIO"),
+        15 => Some("GIO"),
+        19 => Some("CALC"),
+        20 => Some("COMP"),
+        21 => Some("JC"),
+        22 => Some("JA"),
+        23 => Some("CSUB"),
+        24 => Some("RSUB"),
+        25 => Some("EI"),
+        26 => Some("DI"),
+        27 => Some("WAIT"),
+        28 => Some("STOP"),
+        30 => Some("SCO"),
+        31 => Some("GCO"),
+        32 => Some("CCO"),
+        33 => Some("CALCX"),
+        34 => Some("AAP"),
+        35 => Some("AGP"),
+        36 => Some("CLE"),
+        37 => Some("VECT"),
+        38 => Some("RETI"),
+        39 => Some("ACO"),
+        _ => None,
+    }
+}
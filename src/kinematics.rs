@@ -0,0 +1,93 @@
+//! Straight-line moves in machine coordinates.
+//!
+//! A `LinearKinematics` maps the N coordinates of a cartesian machine, e.g. an XY stage built from
+//! two axes on one or more modules, to the axes driving them through their `Calibration`. Moving
+//! to a position with `Bus::move_linear` converts it to microstep targets and issues them with
+//! `Bus::move_all`, which scales the speeds and accelerations of the axes so they arrive at the
+//! same time and the tool travels on a straight line.
+//!
+//! The line is only straight if the ramp and pulse divisors of all axes are the same, see
+//! `Bus::move_all`.
+//!
+//! ```
+//! use tmcl::kinematics::{LinearKinematics, MachineAxis};
+//! use tmcl::units::Calibration;
+//!
+//! // X on motor 0 of module 1, Y on motor 0 of module 2, both with 1600 microsteps per mm.
+//! let stage = LinearKinematics::new([
+//!     MachineAxis::new(1, 0, Calibration::new(1600.0)),
+//!     MachineAxis::new(2, 0, Calibration::new(1600.0)),
+//! ]);
+//! assert_eq!(stage.targets([10.0, -2.5]), [(1, 0, 16000), (2, 0, -4000)]);
+//! assert_eq!(stage.position([16000, -4000]), [10.0, -2.5]);
+//! ```
+
+use units::Calibration;
+
+/// An axis driving one coordinate of a machine.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MachineAxis {
+    address: u8,
+    motor_number: u8,
+    calibration: Calibration,
+}
+
+impl MachineAxis {
+    /// The axis `motor_number` of the module with `address`, converting to microsteps with `calibration`
+    pub fn new(address: u8, motor_number: u8, calibration: Calibration) -> Self {
+        MachineAxis{address, motor_number, calibration}
+    }
+
+    /// The address of the module
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// The motor number of the axis
+    pub fn motor_number(&self) -> u8 {
+        self.motor_number
+    }
+
+    /// The conversion between microsteps and machine units
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+}
+
+/// The axes of a cartesian machine with `N` coordinates.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinearKinematics<const N: usize> {
+    axes: [MachineAxis; N],
+}
+
+impl<const N: usize> LinearKinematics<N> {
+    /// A machine where coordinate `i` is driven by `axes[i]`
+    pub fn new(axes: [MachineAxis; N]) -> Self {
+        LinearKinematics{axes}
+    }
+
+    /// The axes driving the coordinates
+    pub fn axes(&self) -> &[MachineAxis; N] {
+        &self.axes
+    }
+
+    /// The targets of a move to `position`, as `(module address, motor number, target position)`
+    pub fn targets(&self, position: [f32; N]) -> [(u8, u8, i32); N] {
+        let mut targets = [(0, 0, 0); N];
+        for ((target, axis), &coordinate) in targets.iter_mut().zip(self.axes.iter()).zip(position.iter()) {
+            *target = (axis.address, axis.motor_number, axis.calibration.to_steps(coordinate));
+        }
+        targets
+    }
+
+    /// The position in machine units of the axes at the microstep positions `steps`
+    pub fn position(&self, steps: [i32; N]) -> [f32; N] {
+        let mut position = [0.0; N];
+        for ((coordinate, axis), &steps) in position.iter_mut().zip(self.axes.iter()).zip(steps.iter()) {
+            *coordinate = axis.calibration.to_units(steps);
+        }
+        position
+    }
+}
@@ -16,7 +16,7 @@
 //! use tmcl::modules::tmcm::TmcmModule as Module;
 //!
 //! # use tmcl::Interface;
-//! # use tmcl::Instruction;
+//! # use tmcl::Encode;
 //! # use tmcl::Command;
 //! # use tmcl::Reply;
 //! #
@@ -28,7 +28,7 @@
 //! #
 //! # impl Interface for MyInterface {
 //!    # type Error = MyInterfaceError;
-//!    # fn transmit_command<T: Instruction>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
 //!        # unimplemented!()
 //!    # }
 //!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
@@ -60,21 +60,24 @@
 //! use tmcl::modules::generic::instructions::*;
 //! // We use the Generic module instead
 //! use tmcl::modules::generic::GenericModule;
+//! use tmcl::Error;
+//! use tmcl::ErrStatus;
+//! use tmcl::Operand;
 //!
 //! # use tmcl::Interface;
-//! # use tmcl::Instruction;
+//! # use tmcl::Encode;
 //! # use tmcl::Command;
 //! # use tmcl::Reply;
 //! #
 //! # struct MyInterface();
-//! # #[derive(Debug)]
+//! # #[derive(Debug, PartialEq)]
 //! # struct MyInterfaceError();
 //! #
 //! # impl MyInterface { fn new() -> Self {unimplemented!()} }
 //! #
 //! # impl Interface for MyInterface {
 //!    # type Error = MyInterfaceError;
-//!    # fn transmit_command<T: Instruction>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
 //!        # unimplemented!()
 //!    # }
 //!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
@@ -87,9 +90,47 @@
 //!
 //!     let module = GenericModule::new(&interface, 1);
 //!
+//!     match module.write_command(SAP::new(0, 3, Operand::ZERO)) {
+//!         Err(Error::ProtocolError(ErrStatus::WrongType, command)) => println!("{} failed: WrongType", command),
+//!         _ => panic!("expected WrongType"),
+//!     }
+//! }
+//! ```
+//!
+//! ## No panics on malformed device data
+//! Replies are never trusted. A reply containing a value that is not valid for the expected type
+//! results in `Error::InvalidReply` instead of a panic.
+//! Only constructors for axis parameters may panic, when given a value outside the documented range.
+//!
+//! ```
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::{Interface, Encode, Command, Operand, Reply, Status, OkStatus, Error};
+//!
+//! struct MalformedInterface();
+//!
+//! impl Interface for MalformedInterface {
+//!     type Error = ();
+//!     fn transmit_command<T: Encode>(&mut self, _command: &Command<T>) -> Result<(), ()> {
+//!         Ok(())
+//!     }
+//!     fn receive_reply(&mut self) -> Result<Reply, ()> {
+//!         // 200 is not a valid microstep resolution
+//!         Ok(Reply::new(2, 1, Status::Ok(OkStatus::Ok), 6, Operand::from_u32(200)))
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let interface = RefCell::new(MalformedInterface());
+//!     let module = Module::new(&interface, 1);
+//!
 //!     assert_eq!(
-//!         module.write_command(SAP::new(0, 3, [0u8, 0u8, 0u8, 0u8])),
-//!         Error::ProtocolError(ErrStatus::WrongType)
+//!         module.write_command(GAP::<MicrostepResolution>::new(0)),
+//!         Err(Error::InvalidReply)
 //!     );
 //! }
 //! ```
@@ -178,7 +219,7 @@
 //! use tmcl::Interface;
 //! use tmcl::Reply;
 //! use tmcl::Command;
-//! use tmcl::Instruction;
+//! use tmcl::Encode;
 //! use tmcl::modules::tmcm::instructions::*;
 //! use tmcl::modules::tmcm::axis_parameters::*;
 //! use tmcl::modules::tmcm::TmcmModule as Module;
@@ -191,7 +232,7 @@
 //! impl Interface for MyInterface {
 //!    type Error = MyInterfaceError;
 //!
-//!    fn transmit_command<T: Instruction>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
 //!        // Implement transmit_command for your interface
 //!        # unimplemented!()
 //!    }
@@ -228,6 +269,21 @@ extern crate interior_mut;
 #[cfg(feature = "socketcan")]
 extern crate socketcan;
 
+#[cfg(feature = "embedded-hal")]
+extern crate embedded_hal;
+
+#[cfg(feature = "defmt")]
+extern crate defmt;
+
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+
+#[cfg(feature = "heapless")]
+extern crate heapless;
+
+#[cfg(feature = "serde")]
+extern crate serde;
+
 #[cfg(feature = "socketcan")]
 mod socketcan_impl;
 
@@ -235,10 +291,47 @@ mod instructions;
 #[macro_use]
 mod axis_parameters;
 
+pub mod bus;
+#[cfg(feature = "canopen")]
+pub mod canopen;
+pub mod cancel;
+pub mod clock;
+pub mod demux;
+pub mod kinematics;
+pub mod listing;
 pub mod modules;
+pub mod opcodes;
+pub mod prelude;
+pub mod profiles;
+pub mod remote;
+pub mod rs485;
+pub mod units;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(any(feature = "std", feature = "heapless"))]
+pub mod program;
+#[cfg(feature = "std")]
+pub mod canlog;
+#[cfg(feature = "std")]
+pub mod capture;
+#[cfg(feature = "std")]
+pub mod modbus;
+#[cfg(feature = "std")]
+pub mod reconnect;
+#[cfg(feature = "std")]
+pub mod serial;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod testing;
 
 pub use instructions::Instruction;
-use instructions::Return;
+pub use instructions::Encode;
+pub use instructions::Return;
+pub use instructions::FromOperand;
+pub use instructions::InvalidOperand;
+
+use modules::tmcm::ramp::RampError;
 
 /// A interface for a TMCM module
 ///
@@ -246,12 +339,30 @@ use instructions::Return;
 pub trait Interface {
     type Error;
 
-    fn transmit_command<T: Instruction>(&mut self, command: &Command<T>) -> Result<(), Self::Error>;
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error>;
     fn receive_reply(&mut self) -> Result<Reply, Self::Error>;
+
+    /// Receive a reply, giving up when `timeout` has elapsed.
+    ///
+    /// Returns `Ok(None)` if no reply was received in time.
+    /// The default implementation ignores the timeout and blocks in `receive_reply`,
+    /// interfaces that are able to time out should override it.
+    fn receive_reply_timeout(&mut self, timeout: lib::time::Duration) -> Result<Option<Reply>, Self::Error> {
+        let _ = timeout;
+        self.receive_reply().map(Some)
+    }
+
+    /// Discard received data that has not been read yet, e.g. late replies to earlier commands.
+    ///
+    /// The default implementation does nothing, interfaces that buffer input should override it.
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 /// All possible errors when communicating with
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<T> {
 
     /// This means that the library was not able to get the mutable reference to the interface.
@@ -259,26 +370,209 @@ pub enum Error<T> {
     /// This can be cause by many different things
     ///  - If `RefCell` is used then the interface might be used by a different stepper motor.
     ///  - If `Mutex` is used a thread may have panicked and the mutex is poisoned.
-    InterfaceUnavailable,
+    ///
+    /// Contains the address of the module and the number of the instruction that was about to be sent.
+    InterfaceUnavailable {
+        address: u8,
+        instruction_number: u8,
+    },
 
     /// The interface had an error.
     InterfaceError(T),
 
-    /// The `TMCL` module reported an error.
-    ProtocolError(ErrStatus),
+    /// The `TMCL` module reported an error, contains the command that failed.
+    ProtocolError(ErrStatus, CommandContext),
+
+    /// The module did not reply before the deadline.
+    Timeout,
+
+    /// The reply from the module contained an operand that is not valid for the expected type.
+    InvalidReply,
+
+    /// Reading back a value after writing it returned a different value.
+    VerificationFailed {
+        written: i32,
+        read: i32,
+    },
+
+    /// The program could not be assembled, e.g. because a jump targets a label that was never bound
+    /// or the capacity of the program was exceeded.
+    InvalidProgram,
+
+    /// A module already replies on the address that was about to be assigned.
+    AddressInUse,
+
+    /// The address can not be assigned to a module, e.g. 0. Nothing was transmitted.
+    InvalidAddress(u16),
+
+    /// Writing to EEPROM is guarded and no (or an exhausted) `PersistenceToken` was given.
+    PersistenceNotAllowed,
+
+    /// The reply had another reply address than expected, contains the received reply address.
+    UnexpectedReplyAddress(u8),
+
+    /// The instruction is not supported by the module, it was not transmitted.
+    Unsupported,
+
+    /// The operation was cancelled through a `Cancel`.
+    Cancelled,
+
+    /// The acceleration is not allowed with the pulse and ramp divisors of the axis.
+    InvalidRamp(RampError),
+
+    /// The velocity is above the `MaximumPositioningSpeed` of the axis, it was not commanded.
+    SpeedLimitExceeded {
+        velocity: u32,
+        maximum: u16,
+    },
+
+    /// The motion would take the axis outside of its soft limits, it was not commanded.
+    ///
+    /// Contains the target position, or the actual position for rotations.
+    SoftLimit {
+        position: i32,
+        min: i32,
+        max: i32,
+    },
+}
+
+/// The category of an `Error`, for handling errors without matching every variant.
+///
+/// ```
+/// use tmcl::{Error, ErrorKind};
+///
+/// let error: Error<std::io::Error> = Error::Timeout;
+/// assert_eq!(error.kind(), ErrorKind::Timeout);
+///
+/// let error: Error<std::io::Error> = Error::InterfaceUnavailable{address: 1, instruction_number: 5};
+/// assert_eq!(error.kind(), ErrorKind::Busy);
+/// assert_eq!(format!("{}", error), "interface unavailable when writing instruction 5 to module 1");
+/// assert_eq!(format!("{}", error.kind()), "interface busy");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorKind {
+    /// The instruction is not available on the module, see `Error::Unsupported` and `ErrStatus::CommandNotAvailable`.
+    Unavailable,
+
+    /// The interface is in use and could not be borrowed, see `Error::InterfaceUnavailable`.
+    Busy,
+
+    /// The interface reported an error, see `Error::InterfaceError`.
+    Io,
+
+    /// No reply was received before the deadline.
+    Timeout,
+
+    /// The module rejected the command or replied with something unexpected.
+    Protocol,
+
+    /// The command was refused on the host and not transmitted, e.g. by a soft limit.
+    Refused,
+
+    /// The operation was cancelled.
+    Cancelled,
+}
+
+impl ErrorKind {
+    /// The category of an interface that could not be borrowed, the old name of `ErrorKind::Busy`
+    #[deprecated(note = "renamed to `ErrorKind::Busy`")]
+    #[allow(non_upper_case_globals)]
+    pub const InterfaceBusy: ErrorKind = ErrorKind::Busy;
+}
+
+impl lib::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match *self {
+            ErrorKind::Unavailable => write!(f, "not available"),
+            ErrorKind::Busy => write!(f, "interface busy"),
+            ErrorKind::Io => write!(f, "interface error"),
+            ErrorKind::Timeout => write!(f, "timeout"),
+            ErrorKind::Protocol => write!(f, "protocol error"),
+            ErrorKind::Refused => write!(f, "refused"),
+            ErrorKind::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl<T> Error<T> {
+    /// The interface could not be borrowed, the old name of `Error::InterfaceUnavailable`
+    #[deprecated(note = "renamed to `Error::InterfaceUnavailable`")]
+    #[allow(non_snake_case)]
+    pub fn InterfaceBusy(address: u8, instruction_number: u8) -> Self {
+        Error::InterfaceUnavailable{address, instruction_number}
+    }
+
+    /// The category of the error
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::InterfaceUnavailable{..} => ErrorKind::Busy,
+            Error::InterfaceError(_) => ErrorKind::Io,
+            Error::Timeout => ErrorKind::Timeout,
+            Error::ProtocolError(ErrStatus::CommandNotAvailable, _)
+            | Error::Unsupported => ErrorKind::Unavailable,
+            Error::ProtocolError(..)
+            | Error::InvalidReply
+            | Error::VerificationFailed{..}
+            | Error::AddressInUse
+            | Error::UnexpectedReplyAddress(_) => ErrorKind::Protocol,
+            Error::InvalidProgram
+            | Error::InvalidAddress(_)
+            | Error::PersistenceNotAllowed
+            | Error::InvalidRamp(_)
+            | Error::SpeedLimitExceeded{..}
+            | Error::SoftLimit{..} => ErrorKind::Refused,
+            Error::Cancelled => ErrorKind::Cancelled,
+        }
+    }
+}
+
+impl<T: lib::fmt::Display> lib::fmt::Display for Error<T> {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match *self {
+            Error::InterfaceUnavailable{address, instruction_number} =>
+                write!(f, "interface unavailable when writing instruction {} to module {}", instruction_number, address),
+            Error::InterfaceError(ref e) => write!(f, "interface error: {}", e),
+            Error::ProtocolError(status, context) => write!(f, "{} ({})", status, context),
+            Error::Timeout => write!(f, "no reply before the deadline"),
+            Error::InvalidReply => write!(f, "invalid operand in reply"),
+            Error::VerificationFailed{written, read} => write!(f, "wrote {} but read back {}", written, read),
+            Error::InvalidProgram => write!(f, "program could not be assembled"),
+            Error::AddressInUse => write!(f, "address already in use"),
+            Error::InvalidAddress(address) => write!(f, "address {} can not be assigned", address),
+            Error::PersistenceNotAllowed => write!(f, "writing to EEPROM not allowed"),
+            Error::UnexpectedReplyAddress(address) => write!(f, "unexpected reply address {}", address),
+            Error::Unsupported => write!(f, "instruction not supported by the module"),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::InvalidRamp(e) => write!(f, "{}", e),
+            Error::SpeedLimitExceeded{velocity, maximum} => write!(f, "velocity {} above the maximum {}", velocity, maximum),
+            Error::SoftLimit{position, min, max} => write!(f, "position {} outside of the soft limits {} to {}", position, min, max),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
+impl<T: lib::fmt::Debug + lib::fmt::Display> std::error::Error for Error<T> {}
+
 /// A `Comamnd` is an `Instruction` with a module address.
 ///
 /// It contains everything required to serialize itself into Binary command format.
 #[derive(Debug, PartialEq)]
-pub struct Command<T: Instruction> {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Command<T: Encode> {
     module_address: u8,
     instruction: T,
 }
 
 /// A TMCM module will respond with a `Reply` after receiving a `Command`.
-#[derive(Debug, PartialEq, Clone)]
+///
+/// The status of the reply must be checked before the operand can be trusted, e.g. with `into_result`.
+///
+/// Interfaces may record when the reply was received, see `timestamp`. The timestamp is not
+/// compared by `==`, a reply received twice is the same reply.
+#[must_use]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Reply {
     reply_address: u8,
 
@@ -288,7 +582,93 @@ pub struct Reply {
 
     command_number: u8,
 
-    operand: [u8; 4],
+    operand: Operand,
+
+    timestamp: Option<Timestamp>,
+}
+
+impl PartialEq for Reply {
+    fn eq(&self, other: &Reply) -> bool {
+        self.reply_address == other.reply_address
+            && self.module_address == other.module_address
+            && self.status == other.status
+            && self.command_number == other.command_number
+            && self.operand == other.operand
+    }
+}
+
+/// Where the time of a `Timestamp` was taken.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimestampSource {
+    /// A monotonic host clock, read when the frame was handed to the interface
+    Monotonic,
+
+    /// The receiving hardware, e.g. a CAN controller, read when the frame arrived
+    Hardware,
+}
+
+/// The time a frame was received, since an arbitrary epoch of its source.
+///
+/// Timestamps are only comparable if they have the same source. With `std`, `Timestamp::now`
+/// reads a monotonic clock shared by the whole process, which the interfaces of this crate use.
+///
+/// ```
+/// use std::time::Duration;
+/// use tmcl::Timestamp;
+///
+/// let sent = Timestamp::monotonic(Duration::from_millis(100));
+/// let received = Timestamp::monotonic(Duration::from_millis(103));
+/// assert_eq!(received.duration_since(sent), Some(Duration::from_millis(3)));
+/// assert_eq!(received.duration_since(Timestamp::hardware(Duration::from_millis(1))), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timestamp {
+    time: lib::time::Duration,
+    source: TimestampSource,
+}
+
+impl Timestamp {
+    /// A timestamp read from a monotonic host clock
+    pub fn monotonic(time: lib::time::Duration) -> Self {
+        Timestamp{time, source: TimestampSource::Monotonic}
+    }
+
+    /// A timestamp read from the receiving hardware
+    pub fn hardware(time: lib::time::Duration) -> Self {
+        Timestamp{time, source: TimestampSource::Hardware}
+    }
+
+    /// The current time of the process wide monotonic clock
+    ///
+    /// The epoch is the first call in the process.
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        let epoch = *EPOCH.get_or_init(std::time::Instant::now);
+        Timestamp::monotonic(epoch.elapsed())
+    }
+
+    /// The time since the epoch of the source
+    pub fn time(&self) -> lib::time::Duration {
+        self.time
+    }
+
+    /// Where the time was taken
+    pub fn source(&self) -> TimestampSource {
+        self.source
+    }
+
+    /// The time elapsed from `earlier` to `self`
+    ///
+    /// `None` if the sources differ or `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<lib::time::Duration> {
+        if self.source != earlier.source {
+            return None;
+        }
+        self.time.checked_sub(earlier.time)
+    }
 }
 
 /// Axis parameter - useable with SAP, GAP, AAP, STAP and/or RSAP instructions.
@@ -302,11 +682,43 @@ pub trait ReadableAxisParameter: AxisParameter + Return {}
 
 /// An axis parameter useable with the SAP instruction.
 pub trait WriteableAxisParameter: AxisParameter {
-    fn operand(&self) -> [u8; 4];
+    fn operand(&self) -> Operand;
+}
+
+/// The result of attempting to create a parameter from a value outside of its valid range.
+///
+/// ```
+/// use tmcl::OutOfRange;
+/// use tmcl::modules::tmcm::axis_parameters::MaximumPositioningSpeed;
+///
+/// assert_eq!(MaximumPositioningSpeed::try_new(4000), Err(OutOfRange));
+/// assert_eq!(MaximumPositioningSpeed::saturating(4000), MaximumPositioningSpeed::new(2047));
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutOfRange;
+
+/// Global parameter - useable with SGP, GGP, STGP and/or RSGP instructions.
+pub trait GlobalParameter {
+    /// The Bank Number.
+    const BANK: u8;
+
+    /// The Parameter Number.
+    const NUMBER: u8;
+}
+
+/// A global parameter useable with the GGP instruction.
+pub trait ReadableGlobalParameter: GlobalParameter + Return {}
+
+/// A global parameter useable with the SGP instruction.
+pub trait WriteableGlobalParameter: GlobalParameter {
+    fn operand(&self) -> Operand;
 }
 
 /// A `Status` that indicates that everything went well.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum OkStatus {
     /// Successfully executed, no error
     Ok = 100,
@@ -317,35 +729,121 @@ pub enum OkStatus {
 
 /// A `Status` that indicate an `Error` has occured.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum ErrStatus {
     /// Wrong checksum
-    WrongChecksum = 1,
+    WrongChecksum,
 
     /// Invalid command
-    InvalidCommand = 2,
+    InvalidCommand,
 
     /// Wrong type
-    WrongType = 3,
+    WrongType,
 
     /// Invalid value
-    InvalidValue = 4,
+    InvalidValue,
 
     /// Configuration EEPROM locked
-    EEPROMLocked = 5,
+    EEPROMLocked,
 
     /// Command not available
-    CommandNotAvailable = 6,
+    CommandNotAvailable,
+
+    /// A status code outside of the documented set, e.g. from newer firmware
+    Unknown(u8),
+}
+
+impl OkStatus {
+    /// The status code
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl lib::fmt::Display for ErrStatus {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match *self {
+            ErrStatus::WrongChecksum => write!(f, "wrong checksum"),
+            ErrStatus::InvalidCommand => write!(f, "invalid command"),
+            ErrStatus::WrongType => write!(f, "wrong type"),
+            ErrStatus::InvalidValue => write!(f, "invalid value"),
+            ErrStatus::EEPROMLocked => write!(f, "configuration EEPROM locked"),
+            ErrStatus::CommandNotAvailable => write!(f, "command not available"),
+            ErrStatus::Unknown(code) => write!(f, "unknown status {}", code),
+        }
+    }
+}
+
+impl ErrStatus {
+    /// The status code
+    pub fn code(&self) -> u8 {
+        match *self {
+            ErrStatus::WrongChecksum => 1,
+            ErrStatus::InvalidCommand => 2,
+            ErrStatus::WrongType => 3,
+            ErrStatus::InvalidValue => 4,
+            ErrStatus::EEPROMLocked => 5,
+            ErrStatus::CommandNotAvailable => 6,
+            ErrStatus::Unknown(code) => code,
+        }
+    }
 }
 
 /// Every reply from a `Module` contains a `Status`
 #[must_use]
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Status {
     Ok(OkStatus),
     Err(ErrStatus),
 }
 
-impl<T: Instruction> Command<T> {
+/// Conversion into a `Result`, for values that tell whether something went well.
+///
+/// ```
+/// use tmcl::{ErrStatus, IntoResult, OkStatus, Status};
+///
+/// assert_eq!(Status::Ok(OkStatus::LoadedIntoEEPROM).into_result(), Ok(OkStatus::LoadedIntoEEPROM));
+/// assert_eq!(Status::try_from_u8(4).unwrap().into_result(), Err(ErrStatus::InvalidValue));
+/// ```
+pub trait IntoResult {
+    /// The value when everything went well
+    type Ok;
+
+    /// The value when an error occured
+    type Err;
+
+    /// Convert into a `Result`
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+}
+
+impl IntoResult for Status {
+    type Ok = OkStatus;
+    type Err = ErrStatus;
+
+    fn into_result(self) -> Result<OkStatus, ErrStatus> {
+        match self {
+            Status::Ok(status) => Ok(status),
+            Status::Err(status) => Err(status),
+        }
+    }
+}
+
+impl IntoResult for Reply {
+    type Ok = Reply;
+    type Err = ErrStatus;
+
+    /// The reply if the module reported success, otherwise the error status
+    fn into_result(self) -> Result<Reply, ErrStatus> {
+        match self.status {
+            Status::Ok(_) => Ok(self),
+            Status::Err(status) => Err(status),
+        }
+    }
+}
+
+impl<T: Encode> Command<T> {
     pub fn new(module_address: u8, instruction: T) -> Command<T> {
         Command{module_address, instruction}
     }
@@ -360,15 +858,27 @@ impl<T: Instruction> Command<T> {
     /// The array will look like the following:
     /// `[MODULE_ADR, CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0, CHECKSUM]`
     pub fn serialize(&self) -> [u8; 9] {
-        unimplemented!()
+        let body = self.instruction.encode();
+        let mut frame = [
+            self.module_address,
+            body[0], body[1], body[2], body[3], body[4], body[5], body[6],
+            0,
+        ];
+        frame[8] = checksum(&frame[..8]);
+        frame
     }
 
     /// Serialize into binary command format suited for I2C
     ///
+    /// The module address is used as the I2C address and is included in the checksum.
     /// The array will look like the following:
     /// `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0, CHECKSUM]`
     pub fn serialize_i2c(&self) -> [u8; 8] {
-        unimplemented!()
+        let body = self.instruction.encode();
+        [
+            body[0], body[1], body[2], body[3], body[4], body[5], body[6],
+            self.module_address.wrapping_add(checksum(&body)),
+        ]
     }
 
     /// Serialize into binary command format suited for CAN (controller area network)
@@ -377,26 +887,53 @@ impl<T: Instruction> Command<T> {
     /// The array will look like the following:
     /// `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0]`
     pub fn serialize_can(&self) -> [u8; 7] {
-        [
-            T::INSTRUCTION_NUMBER,
-            self.instruction.type_number(),
-            self.instruction.motor_bank_number(),
-            self.instruction.operand()[3],
-            self.instruction.operand()[2],
-            self.instruction.operand()[1],
-            self.instruction.operand()[0],
-        ]
+        self.instruction.encode()
+    }
+
+    /// Serialize into binary command format suited for RS232, RS485 etc directly into `buffer`
+    ///
+    /// Uses the same format as `serialize` and returns the number of bytes written (9).
+    pub fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let frame = buffer.get_mut(..9).ok_or(BufferTooSmall)?;
+        frame[0] = self.module_address;
+        frame[1..8].copy_from_slice(&self.instruction.encode());
+        frame[8] = checksum(&frame[..8]);
+        Ok(9)
+    }
+
+    /// Serialize into binary command format suited for I2C directly into `buffer`
+    ///
+    /// Uses the same format as `serialize_i2c` and returns the number of bytes written (8).
+    pub fn serialize_i2c_into(&self, buffer: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let frame = buffer.get_mut(..8).ok_or(BufferTooSmall)?;
+        frame[..7].copy_from_slice(&self.instruction.encode());
+        frame[7] = self.module_address.wrapping_add(checksum(&frame[..7]));
+        Ok(8)
+    }
+
+    /// Serialize into binary command format suited for CAN directly into `buffer`
+    ///
+    /// Uses the same format as `serialize_can` and returns the number of bytes written (7).
+    pub fn serialize_can_into(&self, buffer: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        let frame = buffer.get_mut(..7).ok_or(BufferTooSmall)?;
+        frame.copy_from_slice(&self.instruction.encode());
+        Ok(7)
     }
 
 }
 
+/// The checksum used in the binary command format, the sum of all bytes truncated to 8 bits.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
 impl Reply {
     pub fn new(
         reply_address: u8,
         module_address: u8,
         status: Status,
         command_number: u8,
-        operand: [u8; 4],
+        operand: Operand,
     ) -> Self {
         Reply {
             reply_address,
@@ -404,20 +941,120 @@ impl Reply {
             status,
             command_number,
             operand,
+            timestamp: None,
         }
     }
 
-    fn operand(&self) -> [u8; 4] {
-        self.operand
+    /// Attach the time the reply was received
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// The time the reply was received, if recorded by the interface
+    ///
+    /// The interfaces of this crate record `Timestamp::now` when a complete frame has been read.
+    /// Timestamps of consecutive replies make it possible to analyze the jitter of a control loop.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+
+    /// The address of the host the reply was sent to
+    pub fn reply_address(&self) -> u8 {
+        self.reply_address
+    }
+
+    /// The address of the module that sent the reply
+    pub fn module_address(&self) -> u8 {
+        self.module_address
     }
 
-    fn status(&self) -> Status {
+    /// The status reported by the module
+    pub fn status(&self) -> Status {
         self.status
     }
+
+    /// The number of the instruction this is a reply to
+    pub fn command_number(&self) -> u8 {
+        self.command_number
+    }
+
+    /// The operand
+    pub fn operand(&self) -> Operand {
+        self.operand
+    }
+
+    /// The operand interpreted as a signed integer
+    pub fn value(&self) -> i32 {
+        self.operand.to_i32()
+    }
+
+    /// Decode the operand as `T`
+    ///
+    /// The status is not taken into account, check `status` before trusting the value or use
+    /// `decode_checked`.
+    ///
+    /// ```
+    /// use tmcl::{Operand, Reply, Status, OkStatus};
+    /// use tmcl::modules::tmcm::axis_parameters::ActualPosition;
+    ///
+    /// let reply = Reply::new(2, 1, Status::Ok(OkStatus::Ok), 6, Operand::from_i32(10000));
+    /// assert_eq!(reply.value(), 10000);
+    /// assert_eq!(reply.decode::<ActualPosition>(), Ok(ActualPosition::new(10000)));
+    /// ```
+    pub fn decode<T: Return>(&self) -> Result<T, InvalidOperand> {
+        T::try_from_operand(self.operand)
+    }
+
+    /// Decode the operand as `T` if the module reported success
+    ///
+    /// ```
+    /// use tmcl::{ErrStatus, Operand, Reply, ReplyError, Status};
+    /// use tmcl::modules::tmcm::axis_parameters::ActualPosition;
+    ///
+    /// let reply = Reply::new(2, 1, Status::Err(ErrStatus::WrongType), 6, Operand::ZERO);
+    /// assert_eq!(reply.decode_checked::<ActualPosition>(), Err(ReplyError::Status(ErrStatus::WrongType)));
+    /// ```
+    pub fn decode_checked<T: Return>(&self) -> Result<T, ReplyError> {
+        self.status.into_result().map_err(ReplyError::Status)?;
+        T::try_from_operand(self.operand).map_err(ReplyError::InvalidOperand)
+    }
+
+    /// Serialize into the binary reply format used on serial lines
+    ///
+    /// `[REPLY_ADR, MODULE_ADR, STATUS, CMD_N, VALUE3, VALUE2, VALUE1, VALUE0, CHECKSUM]`
+    pub fn serialize(&self) -> [u8; 9] {
+        let operand = self.operand.to_wire();
+        let mut frame = [
+            self.reply_address,
+            self.module_address,
+            u8::from(self.status),
+            self.command_number,
+            operand[0], operand[1], operand[2], operand[3],
+            0,
+        ];
+        frame[8] = checksum(&frame[..8]);
+        frame
+    }
 }
 
 impl Status {
-    /// Fallible conversion from `u8`
+    /// Conversion from `u8`, tolerating unknown status codes
+    ///
+    /// Codes outside of the documented set become `ErrStatus::Unknown`.
+    ///
+    /// ```
+    /// use tmcl::{ErrStatus, OkStatus, Status};
+    ///
+    /// assert_eq!(Status::from_u8(100), Status::Ok(OkStatus::Ok));
+    /// assert_eq!(Status::from_u8(42), Status::Err(ErrStatus::Unknown(42)));
+    /// assert!(Status::try_from_u8(42).is_err());
+    /// ```
+    pub fn from_u8(id: u8) -> Status {
+        Status::try_from_u8(id).unwrap_or(Status::Err(ErrStatus::Unknown(id)))
+    }
+
+    /// Fallible conversion from `u8`, only accepting the documented status codes
     pub fn try_from_u8(id: u8) -> Result<Status, NonValidErrorCode> {
         match id {
             100 => Ok(Status::Ok(OkStatus::Ok)),
@@ -433,62 +1070,399 @@ impl Status {
     }
 }
 
+impl From<Status> for u8 {
+    fn from(status: Status) -> u8 {
+        match status {
+            Status::Ok(status) => status.code(),
+            Status::Err(status) => status.code(),
+        }
+    }
+}
+
+impl From<u8> for Status {
+    fn from(id: u8) -> Status {
+        Status::from_u8(id)
+    }
+}
+
 /// The result of attempting to converted a number that is not a valid status code into `Status`.
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NonValidErrorCode;
 
-impl Return for () {
-    fn from_operand(_operand: [u8; 4]) -> () {()}
+/// The reasons a `Reply` can not be decoded by `Reply::decode_checked`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReplyError {
+    /// The module reported an error.
+    Status(ErrStatus),
+
+    /// The operand is not a valid value for the type.
+    InvalidOperand(InvalidOperand),
+}
+
+/// The result of attempting to serialize a `Command` into a buffer that can not hold the whole frame.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BufferTooSmall;
+
+/// The 32 bit operand of a command or reply.
+///
+/// The operand is indexed least significant byte first: `operand[0]` is the least significant
+/// byte. In the binary command format it is transmitted most significant byte first, as
+/// `[..., VALUE3, VALUE2, VALUE1, VALUE0, ...]`. The constructors and accessors name the order
+/// they use, so the two can not be mixed up.
+///
+/// ```
+/// use tmcl::Operand;
+///
+/// let operand = Operand::from_i32(1000);
+/// assert_eq!(operand.to_le(), [0xe8, 0x03, 0, 0]);
+/// assert_eq!(operand.to_wire(), [0, 0, 0x03, 0xe8]);
+/// assert_eq!(operand[0], 0xe8);
+/// assert_eq!(Operand::from_wire([0, 0, 0x03, 0xe8]).to_i32(), 1000);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Operand([u8; 4]);
+
+impl Operand {
+    /// The operand with all bits cleared
+    pub const ZERO: Operand = Operand([0; 4]);
+
+    /// Create an operand from bytes ordered least significant first
+    pub const fn from_le(bytes: [u8; 4]) -> Self {
+        Operand(bytes)
+    }
+
+    /// Create an operand from bytes in wire order, most significant first
+    pub const fn from_wire(bytes: [u8; 4]) -> Self {
+        Operand([bytes[3], bytes[2], bytes[1], bytes[0]])
+    }
+
+    /// Create an operand from a signed value
+    pub const fn from_i32(value: i32) -> Self {
+        Operand(value.to_le_bytes())
+    }
+
+    /// Create an operand from an unsigned value
+    pub const fn from_u32(value: u32) -> Self {
+        Operand(value.to_le_bytes())
+    }
+
+    /// The bytes ordered least significant first
+    pub const fn to_le(self) -> [u8; 4] {
+        self.0
+    }
+
+    /// The bytes in wire order, most significant first
+    pub const fn to_wire(self) -> [u8; 4] {
+        [self.0[3], self.0[2], self.0[1], self.0[0]]
+    }
+
+    /// The operand interpreted as a signed value
+    pub const fn to_i32(self) -> i32 {
+        i32::from_le_bytes(self.0)
+    }
+
+    /// The operand interpreted as an unsigned value
+    pub const fn to_u32(self) -> u32 {
+        u32::from_le_bytes(self.0)
+    }
+}
+
+impl lib::ops::Index<usize> for Operand {
+    type Output = u8;
+
+    /// The byte of significance `index`, `operand[0]` is the least significant byte
+    fn index(&self, index: usize) -> &u8 {
+        &self.0[index]
+    }
+}
+
+impl FromOperand for () {
+    fn from_operand(_operand: Operand) {}
 }
 
-impl Return for [u8; 4] {
-    fn from_operand(array: [u8; 4]) -> [u8; 4] {
-        array
+impl FromOperand for Operand {
+    fn from_operand(operand: Operand) -> Operand {
+        operand
     }
 }
 
-impl Return for bool {
-    fn from_operand(array: [u8; 4]) -> bool {(array[0] & 1) != 0}
+impl FromOperand for bool {
+    fn from_operand(operand: Operand) -> bool {(operand[0] & 1) != 0}
 }
 
-impl Return for i32 {
-    fn from_operand(array: [u8; 4]) -> i32 {
-        (array[0] as u32 | ((array[1] as u32) << 8) |  ((array[2] as u32) << 16) |((array[3] as u32) << 24)) as i32
+impl FromOperand for i32 {
+    fn from_operand(operand: Operand) -> i32 {
+        operand.to_i32()
     }
 }
 
-impl Return for i16 {
-    fn from_operand(array: [u8; 4]) -> i16 {
-        (array[0] as u16 | ((array[1] as u16) << 8)) as i16
+impl FromOperand for i16 {
+    fn from_operand(operand: Operand) -> i16 {
+        (operand[0] as u16 | ((operand[1] as u16) << 8)) as i16
     }
 }
 
-impl Return for i8 {
-    fn from_operand(array: [u8; 4]) -> i8 {
-        array[0] as i8
+impl FromOperand for i8 {
+    fn from_operand(operand: Operand) -> i8 {
+        operand[0] as i8
     }
 }
 
-impl Return for u32 {
-    fn from_operand(array: [u8; 4]) -> u32 {
-        (array[0] as u32 | ((array[1] as u32) << 8) |  ((array[2] as u32) << 16) |((array[3] as u32) << 24))
+impl FromOperand for u32 {
+    fn from_operand(operand: Operand) -> u32 {
+        operand.to_u32()
     }
 }
 
-impl Return for u16 {
-    fn from_operand(array: [u8; 4]) -> u16 {
-        array[0] as u16 | ((array[1] as u16) << 8)
+impl FromOperand for u16 {
+    fn from_operand(operand: Operand) -> u16 {
+        operand[0] as u16 | ((operand[1] as u16) << 8)
     }
 }
 
-impl Return for u8 {
-    fn from_operand(array: [u8; 4]) -> u8 {
-        array[0]
+impl FromOperand for u8 {
+    fn from_operand(operand: Operand) -> u8 {
+        operand[0]
     }
 }
 
-impl<T> From<ErrStatus> for Error<T> {
-    fn from(es: ErrStatus) -> Self {
-        Error::ProtocolError(es)
+/// A signed 12 bit integer, e.g. velocities of TMC428 based modules.
+///
+/// When decoded, bit 11 is sign extended, the upper bits of the operand are ignored. When
+/// encoded, the value is sign extended to 32 bits.
+///
+/// ```
+/// use tmcl::{FromOperand, I12, Operand};
+///
+/// assert_eq!(I12::from_operand(Operand::from_le([0xff, 0x0f, 0, 0])).value(), -1);
+/// assert_eq!(I12::from_operand(Operand::from_le([0xff, 0x07, 0, 0])).value(), 2047);
+/// assert_eq!(I12::new(-1).operand(), Operand::from_i32(-1));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct I12(i16);
+
+impl I12 {
+    /// The smallest value
+    pub const MIN: i16 = -2048;
+
+    /// The largest value
+    pub const MAX: i16 = 2047;
+
+    /// Create a 12 bit integer, panics if `value` is out of range
+    pub fn new(value: i16) -> Self {
+        assert!((I12::MIN..=I12::MAX).contains(&value));
+        I12(value)
+    }
+
+    /// The value
+    pub fn value(&self) -> i16 {
+        self.0
+    }
+
+    /// The operand, sign extended to 32 bits
+    pub fn operand(&self) -> Operand {
+        Operand::from_i32(i32::from(self.0))
+    }
+}
+
+impl From<I12> for i16 {
+    fn from(v: I12) -> i16 {
+        v.0
+    }
+}
+
+impl FromOperand for I12 {
+    fn from_operand(operand: Operand) -> I12 {
+        I12(((u16::from(operand[0]) | (u16::from(operand[1]) << 8)) << 4) as i16 >> 4)
+    }
+}
+
+/// A signed 24 bit integer, e.g. positions of TMC428 based modules.
+///
+/// When decoded, bit 23 is sign extended, the upper byte of the operand is ignored. When
+/// encoded, the value is sign extended to 32 bits.
+///
+/// ```
+/// use tmcl::{FromOperand, I24, Operand};
+///
+/// assert_eq!(I24::from_operand(Operand::from_le([0xff, 0xff, 0xff, 0])).value(), -1);
+/// assert_eq!(I24::from_operand(Operand::from_le([0xff, 0xff, 0x7f, 0])).value(), 8388607);
+/// assert_eq!(I24::new(-2).operand(), Operand::from_i32(-2));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct I24(i32);
+
+impl I24 {
+    /// The smallest value
+    pub const MIN: i32 = -8_388_608;
+
+    /// The largest value
+    pub const MAX: i32 = 8_388_607;
+
+    /// Create a 24 bit integer, panics if `value` is out of range
+    pub fn new(value: i32) -> Self {
+        assert!((I24::MIN..=I24::MAX).contains(&value));
+        I24(value)
+    }
+
+    /// The value
+    pub fn value(&self) -> i32 {
+        self.0
+    }
+
+    /// The operand, sign extended to 32 bits
+    pub fn operand(&self) -> Operand {
+        Operand::from_i32(self.0)
+    }
+}
+
+impl From<I24> for i32 {
+    fn from(v: I24) -> i32 {
+        v.0
+    }
+}
+
+impl FromOperand for I24 {
+    fn from_operand(operand: Operand) -> I24 {
+        I24(i32::from_le_bytes([0, operand[0], operand[1], operand[2]]) >> 8)
+    }
+}
+
+/// A signed fixed-point number with `FRAC` fractional bits, the Q(32-`FRAC`).`FRAC` format.
+///
+/// The operand holds the raw bits, i.e. the value multiplied by `2^FRAC`. `FRAC` must be below 32,
+/// using a `Fixed` with more fractional bits fails to compile.
+///
+/// ```
+/// use tmcl::{Fixed, FromOperand, Operand};
+///
+/// // Q24.8
+/// let value = Fixed::<8>::from_f32(-1.5);
+/// assert_eq!(value.to_bits(), -384);
+/// assert_eq!(value.operand(), Operand::from_i32(-384));
+/// assert_eq!(Fixed::<8>::from_operand(Operand::from_i32(640)).to_f32(), 2.5);
+/// ```
+///
+/// ```compile_fail
+/// use tmcl::Fixed;
+///
+/// let value = Fixed::<32>::from_bits(1);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Fixed<const FRAC: u32>(i32);
+
+impl<const FRAC: u32> Fixed<FRAC> {
+    /// The scale of the raw bits, evaluating it rejects `FRAC` of 32 and above at compile time
+    pub const SCALE: units::LinearScale = {
+        assert!(FRAC < 32, "a Fixed must have less than 32 fractional bits");
+        units::LinearScale::fixed_point::<FRAC>()
+    };
+
+    /// Create a fixed-point number from its raw bits
+    pub const fn from_bits(bits: i32) -> Self {
+        let _ = Self::SCALE;
+        Fixed(bits)
+    }
+
+    /// Create a fixed-point number from an integer, panics if it does not fit
+    pub fn from_int(value: i32) -> Self {
+        let _ = Self::SCALE;
+        let bits = i64::from(value) << FRAC;
+        assert!(i64::from(i32::MIN) <= bits && bits <= i64::from(i32::MAX));
+        Fixed(bits as i32)
+    }
+
+    /// Create a fixed-point number, rounded to the nearest representable value and saturated
+    pub fn from_f32(value: f32) -> Self {
+        Fixed(Self::SCALE.to_raw(value))
+    }
+
+    /// The raw bits, the value multiplied by `2^FRAC`
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// The value as a float
+    pub fn to_f32(self) -> f32 {
+        Self::SCALE.to_physical(self.0)
+    }
+
+    /// The operand, the raw bits
+    pub fn operand(&self) -> Operand {
+        Operand::from_i32(self.0)
+    }
+}
+
+impl<const FRAC: u32> FromOperand for Fixed<FRAC> {
+    fn from_operand(operand: Operand) -> Self {
+        Fixed::from_bits(operand.to_i32())
+    }
+}
+
+/// The command a module reported an error for.
+///
+/// Formats as e.g. `SAP 140 on motor 0 @ module 3`, suitable for logging together with the status.
+///
+/// ```
+/// use tmcl::CommandContext;
+///
+/// let context = CommandContext::new(3, [5, 140, 0, 0, 0, 0, 8]);
+/// assert_eq!(format!("{} failed", context), "SAP 140 on motor 0 @ module 3 failed");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandContext {
+    module_address: u8,
+    instruction_number: u8,
+    type_number: u8,
+    motor_bank_number: u8,
+}
+
+impl CommandContext {
+    /// Create the context of a command given its module address and encoded instruction
+    pub fn new(module_address: u8, encoded: [u8; 7]) -> Self {
+        CommandContext{
+            module_address,
+            instruction_number: encoded[0],
+            type_number: encoded[1],
+            motor_bank_number: encoded[2],
+        }
+    }
+
+    /// The address of the module the command was sent to
+    pub fn module_address(&self) -> u8 {
+        self.module_address
+    }
+
+    /// The instruction number of the command
+    pub fn instruction_number(&self) -> u8 {
+        self.instruction_number
+    }
+
+    /// The type number of the command
+    pub fn type_number(&self) -> u8 {
+        self.type_number
+    }
+
+    /// The motor or bank number of the command
+    pub fn motor_bank_number(&self) -> u8 {
+        self.motor_bank_number
+    }
+}
+
+impl lib::fmt::Display for CommandContext {
+    fn fmt(&self, f: &mut lib::fmt::Formatter) -> lib::fmt::Result {
+        match instructions::mnemonic(self.instruction_number) {
+            Some(mnemonic) => write!(f, "{}", mnemonic)?,
+            None => write!(f, "#{}", self.instruction_number)?,
+        }
+        write!(f, " {} on motor {} @ module {}", self.type_number, self.motor_bank_number, self.module_address)
     }
 }
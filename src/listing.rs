@@ -0,0 +1,165 @@
+//! Mnemonic listings of the instructions sent during a session.
+//!
+//! `Listing` wraps an interface and writes one line per transmitted instruction, using the same
+//! syntax as the direct mode log of the TMCL-IDE (e.g. `MVP ABS, 0, 10000` or `GAP 1, 0`). A
+//! session driven from Rust can then be reviewed, or repeated instruction by instruction, with the
+//! vendor tooling. The module address is not part of the syntax and is left out of the listing.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use tmcl::listing::Listing;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! # use tmcl::{Command, Encode, Interface, OkStatus, Operand, Reply, Status};
+//! #
+//! # struct MyInterface(u8);
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = ();
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), ()> {
+//!        # self.0 = command.serialize()[1];
+//!        # Ok(())
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, ()> {
+//!        # Ok(Reply::new(2, 1, Status::Ok(OkStatus::Ok), self.0, Operand::ZERO))
+//!    # }
+//! # }
+//!
+//! let interface = RefCell::new(Listing::new(MyInterface(0), String::new()));
+//! let module = Module::new(&interface, 1);
+//! module.write_command(MVP::new(0, MoveOperation::Absolute(10000))).unwrap();
+//! module.write_command(GAP::<ActualPosition>::new(0)).unwrap();
+//!
+//! let (_, listing) = interface.into_inner().into_inner();
+//! assert_eq!(listing, "MVP ABS, 0, 10000\nGAP 1, 0\n");
+//! ```
+
+use lib::fmt;
+use lib::time::Duration;
+
+use Interface;
+use Encode;
+use Command;
+use Operand;
+use Reply;
+use instructions::mnemonic;
+
+/// An encoded instruction formatted in TMCL-IDE syntax.
+///
+/// Instruction numbers without a mnemonic are formatted as `#<number> <type>, <motor>, <value>`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ListedInstruction {
+    encoded: [u8; 7],
+}
+
+impl ListedInstruction {
+    /// Wrap an instruction in the form `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0]`
+    pub fn new(encoded: [u8; 7]) -> Self {
+        ListedInstruction{encoded}
+    }
+
+    /// The instruction in the form `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0]`
+    pub fn encoded(&self) -> [u8; 7] {
+        self.encoded
+    }
+}
+
+impl fmt::Display for ListedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [number, type_number, motor, ..] = self.encoded;
+        let value = Operand::from_wire([self.encoded[3], self.encoded[4], self.encoded[5], self.encoded[6]]).to_i32();
+        let name = match mnemonic(number) {
+            Some(name) => name,
+            None => return write!(f, "#{} {}, {}, {}", number, type_number, motor, value),
+        };
+        let keyword = keyword(number, type_number);
+        match (name, keyword) {
+            ("ROR", _) | ("ROL", _) => write!(f, "{} {}, {}", name, motor, value),
+            ("MST", _) => write!(f, "{} {}", name, motor),
+            ("MVP", Some(keyword)) | ("WAIT", Some(keyword)) => write!(f, "{} {}, {}, {}", name, keyword, motor, value),
+            ("RFS", Some(keyword)) => write!(f, "{} {}, {}", name, keyword, motor),
+            ("JC", Some(keyword)) | ("CALC", Some(keyword)) => write!(f, "{} {}, {}", name, keyword, value),
+            ("GAP", _) | ("STAP", _) | ("RSAP", _) | ("GGP", _) | ("STGP", _) | ("RSGP", _) | ("GIO", _) =>
+                write!(f, "{} {}, {}", name, type_number, motor),
+            ("JA", _) | ("CSUB", _) | ("COMP", _) => write!(f, "{} {}", name, value),
+            ("EI", _) | ("DI", _) => write!(f, "{} {}", name, type_number),
+            ("RSUB", _) | ("STOP", _) | ("RETI", _) => write!(f, "{}", name),
+            _ => write!(f, "{} {}, {}, {}", name, type_number, motor, value),
+        }
+    }
+}
+
+/// The keyword TMCL-IDE uses instead of the type number, if any
+fn keyword(instruction_number: u8, type_number: u8) -> Option<&'static str> {
+    const MVP: [&str; 3] = ["ABS", "REL", "COORD"];
+    const RFS: [&str; 3] = ["START", "STOP", "STATUS"];
+    const WAIT: [&str; 5] = ["TICKS", "POS", "REFSW", "LIMSW", "RFS"];
+    const JC: [&str; 13] = ["ZE", "NZ", "EQ", "NE", "GT", "GE", "LT", "LE", "ETO", "EAL", "EDV", "EPO", "ESD"];
+    const CALC: [&str; 10] = ["ADD", "SUB", "MUL", "DIV", "MOD", "AND", "OR", "XOR", "NOT", "LOAD"];
+    let keywords: &[&'static str] = match instruction_number {
+        4 => &MVP,
+        13 => &RFS,
+        27 => &WAIT,
+        21 => &JC,
+        19 => &CALC,
+        _ => &[],
+    };
+    keywords.get(usize::from(type_number)).cloned()
+}
+
+/// An interface writing a TMCL-IDE compatible listing of every instruction transmitted through it.
+///
+/// Failing to write the listing does not fail the command, but is remembered, see `is_complete`.
+#[derive(Debug)]
+pub struct Listing<IF: Interface, W: fmt::Write> {
+    interface: IF,
+    writer: W,
+    complete: bool,
+}
+
+impl<IF: Interface, W: fmt::Write> Listing<IF, W> {
+    /// Start writing the instructions transmitted on `interface` to `writer`
+    pub fn new(interface: IF, writer: W) -> Self {
+        Listing{interface, writer, complete: true}
+    }
+
+    /// The listing written so far
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns false if a line could not be written to the listing
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Stop writing the listing, returning the interface and the writer
+    pub fn into_inner(self) -> (IF, W) {
+        (self.interface, self.writer)
+    }
+}
+
+impl<IF: Interface, W: fmt::Write> Interface for Listing<IF, W> {
+    type Error = IF::Error;
+
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        let line = ListedInstruction::new(command.serialize_can());
+        if writeln!(self.writer, "{}", line).is_err() {
+            self.complete = false;
+        }
+        self.interface.transmit_command(command)
+    }
+
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        self.interface.receive_reply()
+    }
+
+    fn receive_reply_timeout(&mut self, timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        self.interface.receive_reply_timeout(timeout)
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        self.interface.discard_input()
+    }
+}
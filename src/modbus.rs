@@ -0,0 +1,291 @@
+//! Tunneling TMCL through Modbus RTU gateways.
+//!
+//! Gateways connecting TMCM modules to a Modbus RTU network (e.g. next to a PLC) expose the binary
+//! command format in holding registers: a command frame is written to a block of 5 registers, and
+//! the reply frame is read back from another block of 5 registers once the gateway received it.
+//! The 9 bytes of a frame are packed big-endian into the registers, and the last byte is zero.
+//! `ModbusRtu` implements `Interface` on top of anything implementing `Read` and `Write`, e.g. an
+//! opened and configured tty, for a gateway with a given unit id and `RegisterMap`.
+//!
+//! Before a command is written, the reply registers are cleared, so a reply left over from an
+//! earlier command is never mistaken for the reply to the current one. The reply registers are
+//! then polled until they contain a valid reply.
+//!
+//! ```no_run
+//! use std::cell::RefCell;
+//! use std::fs::OpenOptions;
+//! use tmcl::modbus::{ModbusRtu, RegisterMap};
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! let tty = OpenOptions::new().read(true).write(true).open("/dev/ttyUSB0").unwrap();
+//! let interface = RefCell::new(ModbusRtu::new(tty, 17, RegisterMap::new(0x0100, 0x0200)));
+//! let module = Module::new(&interface, 1);
+//!
+//! module.write_command(ROR::new(0, 250)).unwrap();
+//! ```
+
+use std::io;
+use std::io::{Read, Write};
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use Interface;
+use Encode;
+use Command;
+use Reply;
+use demux::Frame;
+
+/// The function code for reading holding registers.
+pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+
+/// The function code for writing multiple holding registers.
+pub const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+
+/// The number of registers a frame is packed into.
+pub const FRAME_REGISTERS: u16 = 5;
+
+/// The CRC of Modbus RTU frames
+///
+/// ```
+/// use tmcl::modbus::crc16;
+///
+/// // Read one holding register at address 0 of unit 1.
+/// assert_eq!(crc16(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]).to_le_bytes(), [0x84, 0x0A]);
+/// ```
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xffff, |crc, byte| {
+        (0..8).fold(crc ^ u16::from(*byte), |crc, _| {
+            if crc & 1 != 0 { (crc >> 1) ^ 0xa001 } else { crc >> 1 }
+        })
+    })
+}
+
+/// Where the gateway expects the command frames and places the reply frames.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RegisterMap {
+    command: u16,
+    reply: u16,
+}
+
+impl RegisterMap {
+    /// Command frames are written to the 5 registers starting at `command` and reply frames are
+    /// read from the 5 registers starting at `reply`
+    pub fn new(command: u16, reply: u16) -> Self {
+        RegisterMap{command, reply}
+    }
+
+    /// The address of the first command register
+    pub fn command(&self) -> u16 {
+        self.command
+    }
+
+    /// The address of the first reply register
+    pub fn reply(&self) -> u16 {
+        self.reply
+    }
+}
+
+/// An error when tunneling through a Modbus RTU gateway.
+#[derive(Debug)]
+pub enum ModbusError {
+    /// Reading from or writing to the port failed.
+    Io(io::Error),
+
+    /// The gateway answered with an exception, contains the exception code.
+    Exception(u8),
+
+    /// A response had a wrong CRC, or did not match the request.
+    InvalidResponse,
+}
+
+impl fmt::Display for ModbusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModbusError::Io(ref e) => write!(f, "modbus transfer failed: {}", e),
+            ModbusError::Exception(code) => write!(f, "gateway answered with exception {}", code),
+            ModbusError::InvalidResponse => write!(f, "invalid response from gateway"),
+        }
+    }
+}
+
+impl From<io::Error> for ModbusError {
+    fn from(e: io::Error) -> ModbusError {
+        ModbusError::Io(e)
+    }
+}
+
+/// An interface tunneling TMCL frames through the holding registers of a Modbus RTU gateway.
+#[derive(Debug)]
+pub struct ModbusRtu<T: Read + Write> {
+    port: T,
+    unit_id: u8,
+    map: RegisterMap,
+    poll_interval: Duration,
+}
+
+impl<T: Read + Write> ModbusRtu<T> {
+    /// Tunnel through the gateway with `unit_id`, reachable on `port`
+    pub fn new(port: T, unit_id: u8, map: RegisterMap) -> Self {
+        ModbusRtu{port, unit_id, map, poll_interval: Duration::from_millis(5)}
+    }
+
+    /// Wait `interval` between polls of the reply registers, the default is 5 ms
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// The unit id of the gateway
+    pub fn unit_id(&self) -> u8 {
+        self.unit_id
+    }
+
+    /// The register map of the gateway
+    pub fn register_map(&self) -> RegisterMap {
+        self.map
+    }
+
+    /// Mutably borrow the underlying port, e.g. to change its timeouts
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.port
+    }
+
+    /// Release the underlying port
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    /// Write `registers` starting at `address` with function code 0x10
+    pub fn write_registers(&mut self, address: u16, registers: &[u16]) -> Result<(), ModbusError> {
+        assert!(registers.len() <= 123);
+        let mut request = Vec::with_capacity(9 + 2 * registers.len());
+        request.push(self.unit_id);
+        request.push(WRITE_MULTIPLE_REGISTERS);
+        request.extend_from_slice(&address.to_be_bytes());
+        request.extend_from_slice(&(registers.len() as u16).to_be_bytes());
+        request.push(2 * registers.len() as u8);
+        for register in registers {
+            request.extend_from_slice(&register.to_be_bytes());
+        }
+        self.send(&mut request)?;
+        let response = self.receive(WRITE_MULTIPLE_REGISTERS)?;
+        if response[2..6] != request[2..6] {
+            return Err(ModbusError::InvalidResponse);
+        }
+        Ok(())
+    }
+
+    /// Read `registers.len()` registers starting at `address` with function code 0x03
+    pub fn read_registers(&mut self, address: u16, registers: &mut [u16]) -> Result<(), ModbusError> {
+        assert!(registers.len() <= 125);
+        let mut request = vec![self.unit_id, READ_HOLDING_REGISTERS];
+        request.extend_from_slice(&address.to_be_bytes());
+        request.extend_from_slice(&(registers.len() as u16).to_be_bytes());
+        self.send(&mut request)?;
+        let response = self.receive(READ_HOLDING_REGISTERS)?;
+        if usize::from(response[2]) != 2 * registers.len() {
+            return Err(ModbusError::InvalidResponse);
+        }
+        for (register, bytes) in registers.iter_mut().zip(response[3..].chunks(2)) {
+            *register = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, request: &mut Vec<u8>) -> Result<(), ModbusError> {
+        let crc = crc16(request);
+        request.extend_from_slice(&crc.to_le_bytes());
+        self.port.write_all(request)?;
+        self.port.flush()?;
+        request.truncate(request.len() - 2);
+        Ok(())
+    }
+
+    /// Receive the response to a request with `function`, including the CRC
+    fn receive(&mut self, function: u8) -> Result<Vec<u8>, ModbusError> {
+        let mut response = vec![0; 3];
+        self.port.read_exact(&mut response)?;
+        let remaining = match response[1] {
+            code if code == function | 0x80 => 2,
+            code if code == function && function == READ_HOLDING_REGISTERS => usize::from(response[2]) + 2,
+            code if code == function => 5,
+            _ => return Err(ModbusError::InvalidResponse),
+        };
+        let start = response.len();
+        response.resize(start + remaining, 0);
+        self.port.read_exact(&mut response[start..])?;
+        let (payload, crc) = response.split_at(response.len() - 2);
+        if response[0] != self.unit_id || crc16(payload).to_le_bytes() != crc {
+            return Err(ModbusError::InvalidResponse);
+        }
+        if response[1] & 0x80 != 0 {
+            return Err(ModbusError::Exception(response[2]));
+        }
+        Ok(response)
+    }
+
+    /// Read the reply registers once, `None` if they do not contain a reply
+    fn poll_reply(&mut self) -> Result<Option<Reply>, ModbusError> {
+        let mut registers = [0; FRAME_REGISTERS as usize];
+        let address = self.map.reply;
+        self.read_registers(address, &mut registers)?;
+        let mut bytes = [0; 2 * FRAME_REGISTERS as usize];
+        for (bytes, register) in bytes.chunks_mut(2).zip(registers.iter()) {
+            bytes.copy_from_slice(&register.to_be_bytes());
+        }
+        let mut frame = [0; 9];
+        frame.copy_from_slice(&bytes[..9]);
+        if frame == [0; 9] {
+            return Ok(None);
+        }
+        match Frame::classify(frame) {
+            Frame::Reply(reply) => Ok(Some(reply)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Pack a frame into registers, big-endian with a zero pad byte
+fn registers(frame: &[u8; 9]) -> [u16; FRAME_REGISTERS as usize] {
+    let mut registers = [0; FRAME_REGISTERS as usize];
+    for (register, bytes) in registers.iter_mut().zip(frame.chunks(2)) {
+        *register = u16::from_be_bytes([bytes[0], *bytes.get(1).unwrap_or(&0)]);
+    }
+    registers
+}
+
+impl<T: Read + Write> Interface for ModbusRtu<T> {
+    type Error = ModbusError;
+
+    /// Clear the reply registers and write the command to the command registers
+    fn transmit_command<E: Encode>(&mut self, command: &Command<E>) -> Result<(), Self::Error> {
+        let (command_address, reply_address) = (self.map.command, self.map.reply);
+        self.write_registers(reply_address, &[0; FRAME_REGISTERS as usize])?;
+        self.write_registers(command_address, &registers(&command.serialize()))
+    }
+
+    /// Poll the reply registers until they contain a reply
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        loop {
+            if let Some(reply) = self.poll_reply()? {
+                return Ok(reply);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+
+    fn receive_reply_timeout(&mut self, timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(reply) = self.poll_reply()? {
+                return Ok(Some(reply));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
@@ -1,19 +1,40 @@
 //! `Instruction`s available for the generic TMCM module.
 
+use lib::marker::PhantomData;
+
 use instructions::Instruction;
 use instructions::DirectInstruction;
+use instructions::Return;
+use Operand;
 
 pub use instructions::{
     ROR,
     ROL,
     MST,
     MVP,
-    RFS,
+    RfsStart,
+    RfsStop,
+    RfsStatus,
     SIO,
     GIO,
+    SCO,
+    GCO,
+    CCO,
+    JC,
+    JA,
+    CSUB,
+    RSUB,
+    WAIT,
+    STOP,
     CALC,
+    GetFirmwareVersion,
+    RequestTargetPositionReachedEvent,
     MoveOperation,
-    ReferenceSearchAction,
+    ReferenceSearchStatus,
+    FirmwareVersion,
+    WaitCondition,
+    Condition,
+    COORDINATE_EEPROM_MOTOR_NUMBER,
 };
 
 /// SAP - Set Axis Parameter
@@ -23,13 +44,14 @@ pub use instructions::{
 /// and physical locations (TMC428, TMC453, controller RAM, controller EEPROM),
 /// they all can be set by this function.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SAP {
     motor_number: u8,
     parameter_number: u8,
-    operand: [u8; 4],
+    operand: Operand,
 }
 impl SAP {
-    pub fn new(motor_number: u8, parameter_number: u8, operand: [u8; 4]) -> SAP {
+    pub fn new(motor_number: u8, parameter_number: u8, operand: Operand) -> SAP {
         SAP{
             motor_number,
             parameter_number,
@@ -40,7 +62,7 @@ impl SAP {
 impl Instruction for SAP {
     const INSTRUCTION_NUMBER: u8 = 5;
 
-    fn operand(&self) -> [u8; 4] {
+    fn operand(&self) -> Operand {
         self.operand
     }
 
@@ -62,24 +84,39 @@ impl DirectInstruction for SAP {
 /// Although  these parameters vary widely in their formats (1 to 24 bits, signed or unsigned)
 /// and physical locations (TMC428, TMC453, controller RAM, controller EEPROM),
 /// they all can be read by this function.
+///
+/// By default the raw operand is returned. Use `GAP::new_i32` to have it decoded as a signed integer.
 #[derive(Debug, PartialEq)]
-pub struct GAP {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GAP<R: Return = Operand> {
     motor_number: u8,
     parameter_number: u8,
+    phantom: PhantomData<R>,
 }
 impl GAP {
     pub fn new(motor_number: u8, parameter_number: u8) -> GAP {
         GAP{
             motor_number,
             parameter_number,
+            phantom: PhantomData,
+        }
+    }
+}
+impl GAP<i32> {
+    /// Create a GAP instruction where the value is decoded as a signed integer
+    pub fn new_i32(motor_number: u8, parameter_number: u8) -> GAP<i32> {
+        GAP{
+            motor_number,
+            parameter_number,
+            phantom: PhantomData,
         }
     }
 }
-impl Instruction for GAP {
+impl<R: Return> Instruction for GAP<R> {
     const INSTRUCTION_NUMBER: u8 = 6;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
@@ -90,8 +127,8 @@ impl Instruction for GAP {
         self.motor_number
     }
 }
-impl DirectInstruction for GAP {
-    type Return = [u8; 4];
+impl<R: Return> DirectInstruction for GAP<R> {
+    type Return = R;
 }
 
 /// STAP - Store Axis Parameter
@@ -99,6 +136,7 @@ impl DirectInstruction for GAP {
 /// Axis parameters are located in RAM memory, so modifications are lost at power down.
 /// This instruction enables permanent storing.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct STAP {
     motor_number: u8,
     parameter_number: u8,
@@ -114,8 +152,8 @@ impl STAP {
 impl Instruction for STAP {
     const INSTRUCTION_NUMBER: u8 = 7;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
@@ -136,13 +174,14 @@ impl DirectInstruction for STAP {
 /// By default, most parameters are automatically restored after power up (see axis parameter list in
 /// chapter 4). A single parameter that has been changed before can be reset by this instruction.
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RSAP {
     motor_number: u8,
     parameter_number: u8,
 }
 impl RSAP {
-    pub fn new(motor_number: u8, parameter_number: u8) -> STAP {
-        STAP{
+    pub fn new(motor_number: u8, parameter_number: u8) -> RSAP {
+        RSAP{
             motor_number,
             parameter_number,
         }
@@ -151,8 +190,8 @@ impl RSAP {
 impl Instruction for RSAP {
     const INSTRUCTION_NUMBER: u8 = 8;
 
-    fn operand(&self) -> [u8; 4] {
-        [0u8, 0u8, 0u8, 0u8]
+    fn operand(&self) -> Operand {
+        Operand::ZERO
     }
 
     fn type_number(&self) -> u8 {
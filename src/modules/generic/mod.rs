@@ -17,8 +17,10 @@ use Instruction;
 use instructions::DirectInstruction;
 use Interface;
 use Return;
-use Status;
+use IntoResult;
 use Command;
+use CommandContext;
+use Encode;
 
 /// This type represents a generic TMCM module.
 #[derive(Debug)]
@@ -43,12 +45,20 @@ impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> Generi
 
     /// Synchronously write a command and wait for the Reply
     pub fn write_command<Inst: Instruction + DirectInstruction>(&'a self, instruction: Inst) -> Result<Inst::Return, Error<IF::Error>> {
-        let mut interface = self.interface.borrow_int_mut().or(Err(Error::InterfaceUnavailable))?;
-        interface.transmit_command(&Command::new(self.address, instruction)).map_err(|e| Error::InterfaceError(e))?;
-        let reply = interface.receive_reply().map_err(|e| Error::InterfaceError(e))?;
-        match reply.status() {
-            Status::Ok(_) => Ok(<Inst::Return as Return>::from_operand(reply.operand())),
-            Status::Err(e) => Err(e.into()),
-        }
+        self.write_command_decoded(instruction)
+    }
+
+    /// Synchronously write a command and wait for the Reply, decoding the reply operand as `R`
+    ///
+    /// This is useful when the type of a value is known even if the generic instruction
+    /// returns the raw operand, e.g. `write_command_decoded::<_, i32>(GAP::new(0, 1))`.
+    pub fn write_command_decoded<Inst: Instruction + DirectInstruction, R: Return>(&'a self, instruction: Inst) -> Result<R, Error<IF::Error>> {
+        let context = CommandContext::new(self.address, instruction.encode());
+        let mut interface = self.interface.borrow_int_mut()
+            .or(Err(Error::InterfaceUnavailable{address: self.address, instruction_number: Inst::INSTRUCTION_NUMBER}))?;
+        interface.transmit_command(&Command::new(self.address, instruction)).map_err(Error::InterfaceError)?;
+        let reply = interface.receive_reply().map_err(Error::InterfaceError)?;
+        let reply = reply.into_result().map_err(|e| Error::ProtocolError(e, context))?;
+        R::try_from_operand(reply.operand()).or(Err(Error::InvalidReply))
     }
 }
\ No newline at end of file
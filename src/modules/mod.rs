@@ -1,5 +1,6 @@
 //! Implementation of functionality special for different hardware modules
 
 pub mod generic;
+pub mod tmc5xxx;
 pub mod tmcm;
 pub mod tmcm100;
\ No newline at end of file
@@ -0,0 +1,93 @@
+//! TMC5130/TMC5160 register names for TMCM axis parameters.
+//!
+//! Code written against the register map of a TMC5xxx motion controller (e.g. on an evaluation
+//! board) can be moved to a TMCM module by using these aliases with `SAP`/`GAP` and `TmcmModule`.
+//! Every alias is the TMCM axis parameter with the same meaning, so values are in TMCL units and
+//! ranges, not in the units of the register. Where a TMCL parameter only corresponds to a field of
+//! a register, the alias is named after the field.
+//!
+//! The limit switch parameters have no alias, `SW_MODE.stop_l_enable` and `SW_MODE.stop_r_enable`
+//! have the inverted meaning of `LeftLimitSwitchDisable` and `RightLimitSwitchDisable`.
+//!
+//! ```
+//! use tmcl::modules::tmc5xxx::{self, XACTUAL, VMAX};
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::opcodes::ParameterNumber;
+//! use tmcl::Instruction;
+//!
+//! let read_position = GAP::<XACTUAL>::new(0);
+//! let set_speed = SAP::new(0, VMAX::new(1000));
+//! assert_eq!(set_speed.type_number(), 4);
+//!
+//! assert_eq!(tmc5xxx::register_name(ParameterNumber::ActualPosition), Some("XACTUAL"));
+//! assert_eq!(tmc5xxx::parameter("vmax"), Some(ParameterNumber::MaximumPositioningSpeed));
+//! # let _ = read_position;
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use opcodes::ParameterNumber;
+use modules::tmcm::axis_parameters::{
+    ActualPosition,
+    ActualSpeed,
+    MaximumPositioningSpeed,
+    AbsoluteMaxCurrent,
+    StandbyCurrent,
+    TargetPositionReached,
+    MicrostepResolution,
+    SoftStopFlag,
+    DriverErrorFlags,
+};
+
+/// Actual motor position (`XACTUAL`, 0x21), the TMCL parameter `ActualPosition`.
+pub type XACTUAL = ActualPosition;
+
+/// Actual motor velocity (`VACTUAL`, 0x22), the TMCL parameter `ActualSpeed`.
+pub type VACTUAL = ActualSpeed;
+
+/// Maximum velocity of the ramp (`VMAX`, 0x27), the TMCL parameter `MaximumPositioningSpeed`.
+pub type VMAX = MaximumPositioningSpeed;
+
+/// Motor run current (`IHOLD_IRUN.IRUN`), the TMCL parameter `AbsoluteMaxCurrent`.
+pub type IRUN = AbsoluteMaxCurrent;
+
+/// Standstill current (`IHOLD_IRUN.IHOLD`), the TMCL parameter `StandbyCurrent`.
+pub type IHOLD = StandbyCurrent;
+
+/// Target position reached (`RAMP_STAT.position_reached`), the TMCL parameter `TargetPositionReached`.
+pub type POSITION_REACHED = TargetPositionReached;
+
+/// Microstep resolution (`CHOPCONF.MRES`), the TMCL parameter `MicrostepResolution`.
+///
+/// Note that TMCL numbers the resolutions from full step upwards, `MRES` from 256 microsteps downwards.
+pub type MRES = MicrostepResolution;
+
+/// Soft stop on limit switches (`SW_MODE.en_softstop`), the TMCL parameter `SoftStopFlag`.
+pub type EN_SOFTSTOP = SoftStopFlag;
+
+/// Driver status flags (`DRV_STATUS`, 0x6F), the TMCL parameter `DriverErrorFlags`.
+///
+/// The TMCM module packs the flags in its own bit order, use the methods of `DriverErrorFlags`.
+pub type DRV_STATUS = DriverErrorFlags;
+
+const REGISTER_NAMES: [(&str, ParameterNumber); 9] = [
+    ("XACTUAL", ParameterNumber::ActualPosition),
+    ("VACTUAL", ParameterNumber::ActualSpeed),
+    ("VMAX", ParameterNumber::MaximumPositioningSpeed),
+    ("IRUN", ParameterNumber::AbsoluteMaxCurrent),
+    ("IHOLD", ParameterNumber::StandbyCurrent),
+    ("POSITION_REACHED", ParameterNumber::TargetPositionReached),
+    ("MRES", ParameterNumber::MicrostepResolution),
+    ("EN_SOFTSTOP", ParameterNumber::SoftStopFlag),
+    ("DRV_STATUS", ParameterNumber::DriverErrorFlags),
+];
+
+/// The TMC5xxx register name of a parameter, `None` if it has no TMC5xxx counterpart
+pub fn register_name(parameter: ParameterNumber) -> Option<&'static str> {
+    REGISTER_NAMES.iter().find(|(_, p)| *p == parameter).map(|(name, _)| *name)
+}
+
+/// The parameter with the given TMC5xxx register name, ignoring case
+pub fn parameter(register_name: &str) -> Option<ParameterNumber> {
+    REGISTER_NAMES.iter().find(|(name, _)| name.eq_ignore_ascii_case(register_name)).map(|(_, p)| *p)
+}
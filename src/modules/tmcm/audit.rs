@@ -0,0 +1,205 @@
+//! An audit trail of parameter changes.
+//!
+//! An `AuditTrail` given to `TmcmModule::with_audit_trail` filters the commands written to the
+//! module for the ones changing parameters, `SAP`, `SGP`, `STAP` and `STGP`, and calls its sink
+//! with an `AuditRecord` for every one of them once it completed, after any retries. The record
+//! contains the written value, whether the module accepted it, a timestamp (with the `std`
+//! feature), the tag of the trail and optionally the value before the change, read with `GAP` or `GGP` before
+//! `SAP` and `SGP` are written. This gives the audit trail required in regulated environments.
+//!
+//! Like the observer, the sink is called while the interface is not borrowed, but it must not
+//! write to the module itself. Clones of a module can be given trails with different tags, to tell
+//! which part of the application changed a parameter.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::audit::{AuditRecord, AuditTrail};
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn log(record: &AuditRecord) {
+//!     println!(
+//!         "{:?} {:?}: parameter {} of motor {} changed from {:?} to {}",
+//!         record.timestamp(),
+//!         record.tag(),
+//!         record.type_number(),
+//!         record.motor_bank_number(),
+//!         record.old_value(),
+//!         record.value(),
+//!     );
+//! }
+//!
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let trail = AuditTrail::new(log).with_read_before_write().with_tag("calibration");
+//!     let module = Module::new(&interface, 1).with_audit_trail(trail);
+//!
+//!     module.axis(0).set(AbsoluteMaxCurrent::new(128)).unwrap();
+//! }
+//! ```
+
+use Timestamp;
+use opcodes::{
+    OPCODE_SAP,
+    OPCODE_GAP,
+    OPCODE_STAP,
+    OPCODE_SGP,
+    OPCODE_GGP,
+    OPCODE_STGP,
+};
+
+/// A change of a parameter, reported to the sink of an `AuditTrail`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AuditRecord {
+    timestamp: Option<Timestamp>,
+    tag: Option<&'static str>,
+    address: u8,
+    instruction_number: u8,
+    type_number: u8,
+    motor_bank_number: u8,
+    value: i32,
+    old_value: Option<i32>,
+    accepted: bool,
+}
+
+impl AuditRecord {
+    /// When the command completed, `None` without the `std` feature
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+
+    /// The tag of the audit trail
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// The address of the module
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// The instruction number, `OPCODE_SAP`, `OPCODE_SGP`, `OPCODE_STAP` or `OPCODE_STGP`
+    pub fn instruction_number(&self) -> u8 {
+        self.instruction_number
+    }
+
+    /// The parameter number
+    pub fn type_number(&self) -> u8 {
+        self.type_number
+    }
+
+    /// The motor number of axis parameters, the bank number of global parameters
+    pub fn motor_bank_number(&self) -> u8 {
+        self.motor_bank_number
+    }
+
+    /// The written value, 0 for `STAP` and `STGP`
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// The value before the change, if it was read
+    pub fn old_value(&self) -> Option<i32> {
+        self.old_value
+    }
+
+    /// Returns true if the command succeeded, false if it failed or timed out
+    pub fn accepted(&self) -> bool {
+        self.accepted
+    }
+}
+
+/// Recording of the parameter changes of a module, see `TmcmModule::with_audit_trail`.
+#[derive(Debug, Clone, Copy)]
+pub struct AuditTrail {
+    sink: fn(&AuditRecord),
+    read_before_write: bool,
+    tag: Option<&'static str>,
+}
+
+impl AuditTrail {
+    /// Call `sink` for every parameter change
+    pub fn new(sink: fn(&AuditRecord)) -> Self {
+        AuditTrail{sink, read_before_write: false, tag: None}
+    }
+
+    /// Read the value before every `SAP` and `SGP`, costing one extra command per change
+    ///
+    /// If the value can not be read, the change is still written and recorded without an old value.
+    pub fn with_read_before_write(mut self) -> Self {
+        self.read_before_write = true;
+        self
+    }
+
+    /// Tag every record with `tag`, e.g. the name of the part of the application using the module
+    pub fn with_tag(mut self, tag: &'static str) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+
+    /// The tag of the records
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+
+    /// Returns true if the value is read before every `SAP` and `SGP`
+    pub fn reads_before_write(&self) -> bool {
+        self.read_before_write
+    }
+
+    /// Returns true if commands with `instruction_number` are recorded
+    pub fn records(&self, instruction_number: u8) -> bool {
+        matches!(instruction_number, OPCODE_SAP | OPCODE_SGP | OPCODE_STAP | OPCODE_STGP)
+    }
+
+    /// The instruction reading the old value before a command with `instruction_number`
+    pub(crate) fn read_instruction(&self, instruction_number: u8) -> Option<u8> {
+        match instruction_number {
+            OPCODE_SAP if self.read_before_write => Some(OPCODE_GAP),
+            OPCODE_SGP if self.read_before_write => Some(OPCODE_GGP),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn record(&self, address: u8, encoded: [u8; 7], old_value: Option<i32>, accepted: bool) {
+        #[cfg(feature = "std")]
+        let timestamp = Some(Timestamp::now());
+        #[cfg(not(feature = "std"))]
+        let timestamp = None;
+        (self.sink)(&AuditRecord{
+            timestamp,
+            tag: self.tag,
+            address,
+            instruction_number: encoded[0],
+            type_number: encoded[1],
+            motor_bank_number: encoded[2],
+            value: i32::from_be_bytes([encoded[3], encoded[4], encoded[5], encoded[6]]),
+            old_value,
+            accepted,
+        });
+    }
+}
@@ -0,0 +1,583 @@
+//! Handles to the individual axes of multi-axis modules.
+//!
+//! Modules like the TMCM-310 (3 axes) or TMCM-610 (6 axes) have one set of axis parameters per
+//! motor. An `Axis` is bound to one motor number, so the motor number only has to be given once.
+//!
+//! `Axis::rotate_checked` refuses or clamps velocities above the `MaximumPositioningSpeed` of the
+//! axis, which protects the mechanics from a mistyped velocity during development.
+//!
+//! An axis with a holding brake releases and engages it around motion, see the `brake` module.
+//! Backlash can be compensated in `move_to`, see the `backlash` module.
+//!
+//! `Axis::with_soft_limits` restricts the travel of the axis on the host, independent of the limit
+//! switches. Moves with a target outside of the limits, and rotations towards a limit that has
+//! already been reached, return `Error::SoftLimit` without being commanded. A rotation is not
+//! stopped at the limit by the module, poll `enforce_soft_limits` while the axis rotates.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::instructions::MoveOperation;
+//! use tmcl::modules::tmcm::axis::SpeedGuard;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1).with_axis_count(3);
+//!
+//!     for axis in module.axes() {
+//!         axis.set(MaximumPositioningSpeed::new(1000)).unwrap();
+//!         axis.move_to(MoveOperation::Absolute(0)).unwrap();
+//!     }
+//!
+//!     // Rotates left with 1000 instead of 5000.
+//!     module.axis(0).rotate_checked(-5000, SpeedGuard::Clamp).unwrap();
+//! }
+//! ```
+
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use cancel::Cancel;
+use clock::{Clock, Delay, Poller};
+use Interface;
+
+use modules::tmcm::axis_parameters::{
+    ActualPosition,
+    MaximumPositioningSpeed,
+    TargetPositionReached,
+};
+use modules::tmcm::backlash::{
+    APPROACH_POLL_INTERVAL,
+    ApproachDirection,
+    Backlash,
+};
+use modules::tmcm::brake::BrakeConfig;
+use modules::tmcm::io::OUTPUT_BANK;
+use modules::tmcm::{
+    TmcmModule,
+    ReadableTmcmAxisParameter,
+    WriteableTmcmAxisParameter,
+};
+use modules::tmcm::instructions::{
+    ROR,
+    ROL,
+    MST,
+    MVP,
+    SAP,
+    GAP,
+    SIO,
+    GIO,
+    GCO,
+    MoveOperation,
+};
+
+/// What `Axis::rotate_checked` does with velocities above the `MaximumPositioningSpeed` of the axis.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SpeedGuard {
+    /// Return `Error::SpeedLimitExceeded` without commanding the velocity.
+    Refuse,
+
+    /// Rotate with the maximum speed instead.
+    Clamp,
+
+    /// Rotate with the velocity anyway, without reading the maximum speed.
+    Override,
+}
+
+impl SpeedGuard {
+    /// The velocity to command instead of `velocity`, given the maximum speed of the axis
+    pub(crate) fn limit<E>(self, velocity: i32, maximum: MaximumPositioningSpeed) -> Result<i32, Error<E>> {
+        let maximum = u16::from(maximum);
+        if velocity.unsigned_abs() <= u32::from(maximum) {
+            return Ok(velocity);
+        }
+        match self {
+            SpeedGuard::Refuse => Err(Error::SpeedLimitExceeded{velocity: velocity.unsigned_abs(), maximum}),
+            SpeedGuard::Clamp => Ok(velocity.signum() * i32::from(maximum)),
+            SpeedGuard::Override => Ok(velocity),
+        }
+    }
+}
+
+/// Travel limits of an axis enforced on the host, see `Axis::with_soft_limits`.
+///
+/// ```
+/// use tmcl::modules::tmcm::axis::SoftLimits;
+///
+/// let limits = SoftLimits::new(-1000, 50000);
+/// assert!(limits.contains(0));
+/// assert!(!limits.contains(50001));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SoftLimits {
+    min: i32,
+    max: i32,
+}
+
+impl SoftLimits {
+    /// Allow positions from `min` to `max`, inclusive
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn new(min: i32, max: i32) -> Self {
+        assert!(min <= max);
+        SoftLimits{min, max}
+    }
+
+    /// The lowest allowed position
+    pub fn min(&self) -> i32 {
+        self.min
+    }
+
+    /// The highest allowed position
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+
+    /// Returns true if `position` is within the limits
+    pub fn contains(&self, position: i32) -> bool {
+        self.min <= position && position <= self.max
+    }
+
+    /// `Error::SoftLimit` for `position`
+    pub(crate) fn violation<E>(&self, position: i32) -> Error<E> {
+        Error::SoftLimit{position, min: self.min, max: self.max}
+    }
+}
+
+/// One axis (motor) of a module.
+#[derive(Debug)]
+pub struct Axis<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a> {
+    module: &'a TmcmModule<'a, IF, Cell, T>,
+    motor_number: u8,
+    brake: Option<BrakeConfig>,
+    soft_limits: Option<SoftLimits>,
+    backlash: Option<Backlash>,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> Axis<'a, IF, Cell, T> {
+    /// Create a handle to the axis with the given motor number
+    pub fn new(module: &'a TmcmModule<'a, IF, Cell, T>, motor_number: u8) -> Self {
+        Axis{module, motor_number, brake: None, soft_limits: None, backlash: None}
+    }
+
+    /// Release `brake` before every motion command and engage it after the motor stopped
+    pub fn with_brake(mut self, brake: BrakeConfig) -> Self {
+        self.brake = Some(brake);
+        self
+    }
+
+    /// The holding brake of the axis, if any
+    pub fn brake(&self) -> Option<BrakeConfig> {
+        self.brake
+    }
+
+    /// Refuse motion commands that would take the axis outside of `limits`
+    pub fn with_soft_limits(mut self, limits: SoftLimits) -> Self {
+        self.soft_limits = Some(limits);
+        self
+    }
+
+    /// The travel limits enforced on the host, if any
+    pub fn soft_limits(&self) -> Option<SoftLimits> {
+        self.soft_limits
+    }
+
+    /// Compensate for backlash in `move_to`
+    pub fn with_backlash(mut self, backlash: Backlash) -> Self {
+        self.backlash = Some(backlash);
+        self
+    }
+
+    /// The backlash compensation of the axis, if any
+    pub fn backlash(&self) -> Option<Backlash> {
+        self.backlash
+    }
+
+    /// The motor number of the axis
+    pub fn motor_number(&self) -> u8 {
+        self.motor_number
+    }
+
+    /// The module the axis belongs to
+    pub fn module(&self) -> &'a TmcmModule<'a, IF, Cell, T> {
+        self.module
+    }
+
+    /// Read an axis parameter with `GAP`
+    pub fn get<P: ReadableTmcmAxisParameter>(&self) -> Result<P, Error<IF::Error>> {
+        self.module.write_command(GAP::<P>::new(self.motor_number))
+    }
+
+    /// Write an axis parameter with `SAP`
+    pub fn set<P: WriteableTmcmAxisParameter>(&self, parameter: P) -> Result<(), Error<IF::Error>> {
+        self.module.write_command(SAP::new(self.motor_number, parameter))
+    }
+
+    /// Move to a position with `MVP`, releasing the brake first
+    ///
+    /// With soft limits, the target is checked first. This reads the position for relative moves
+    /// and the coordinate with `GCO` for coordinate moves. With backlash compensation, the move is
+    /// compensated as described in the `backlash` module, which turns it into an absolute move.
+    /// A checked move is sent as an absolute move to the checked target.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use tmcl::opcodes::{OPCODE_GAP, OPCODE_MVP};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::TmcmModule;
+    /// use tmcl::modules::tmcm::axis::SoftLimits;
+    /// use tmcl::modules::tmcm::instructions::MoveOperation;
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(400))
+    ///     // Moving 100 steps from 400 is an absolute move to 500.
+    ///     .expect(expect_command(OPCODE_MVP).type_number(0..=0).value(500..=500));
+    /// let interface = RefCell::new(script);
+    /// let module = TmcmModule::new(&interface, 1);
+    /// let axis = module.axis(0).with_soft_limits(SoftLimits::new(0, 1000));
+    ///
+    /// axis.move_to(MoveOperation::Relative(100)).unwrap();
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn move_to(&self, operation: MoveOperation) -> Result<(), Error<IF::Error>> {
+        self.move_to_cancellable(operation, || false)
+    }
+
+    /// Like `move_to`, but `cancel` is checked between the polls while moving to the overshoot of `Backlash::Approach`
+    ///
+    /// Cancelling only stops waiting, the motor keeps moving towards the overshoot and the target
+    /// is not commanded.
+    pub fn move_to_cancellable<C: Cancel>(&self, operation: MoveOperation, mut cancel: C) -> Result<(), Error<IF::Error>> {
+        self.move_to_pausing(operation, |delay| {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            delay(APPROACH_POLL_INTERVAL);
+            Ok(())
+        })
+    }
+
+    /// Like `move_to`, but the overshoot of `Backlash::Approach` is polled at the pace of a `Poller` instead of its `delay`
+    ///
+    /// Returns `Error::Timeout` if the poller's timeout passes before the overshoot is reached. The
+    /// motor keeps moving towards the overshoot and the target is not commanded.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use std::time::Duration;
+    /// use tmcl::Error;
+    /// use tmcl::clock::{MockClock, Poller};
+    /// use tmcl::opcodes::{OPCODE_GAP, OPCODE_MVP};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::TmcmModule;
+    /// use tmcl::modules::tmcm::backlash::{ApproachDirection, Backlash};
+    /// use tmcl::modules::tmcm::instructions::MoveOperation;
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(0))
+    ///     .expect(expect_command(OPCODE_MVP).value(-300..=-300))
+    ///     // The motor stalls before reaching the overshoot.
+    ///     .expect(expect_command(OPCODE_GAP).type_number(8..=8).reply_with_value(0))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(8..=8).reply_with_value(0))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(8..=8).reply_with_value(0));
+    /// let interface = RefCell::new(script);
+    /// let module = TmcmModule::new(&interface, 1);
+    /// let axis = module.axis(0).with_backlash(Backlash::Approach{
+    ///     direction: ApproachDirection::FromBelow,
+    ///     overshoot: 200,
+    ///     delay: std::thread::sleep,
+    /// });
+    ///
+    /// let clock = MockClock::new();
+    /// let mut poller = Poller::new(&clock, &clock, Duration::from_millis(10))
+    ///     .with_timeout(Duration::from_millis(15));
+    /// assert_eq!(axis.move_to_with(MoveOperation::Absolute(-100), &mut poller), Err(Error::Timeout));
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn move_to_with<C: Clock, D: Delay>(&self, operation: MoveOperation, poller: &mut Poller<C, D>) -> Result<(), Error<IF::Error>> {
+        poller.start();
+        self.move_to_pausing(operation, |_| {
+            if poller.expired() {
+                return Err(Error::Timeout);
+            }
+            poller.pause();
+            Ok(())
+        })
+    }
+
+    /// `pause` is called with the `delay` of `Backlash::Approach` between the polls of the overshoot
+    fn move_to_pausing<P: FnMut(fn(Duration)) -> Result<(), Error<IF::Error>>>(&self, operation: MoveOperation, mut pause: P) -> Result<(), Error<IF::Error>> {
+        if self.soft_limits.is_none() && self.backlash.is_none() {
+            self.release_brake()?;
+            return self.module.write_command(MVP::new(self.motor_number, operation));
+        }
+        let target = self.target(operation)?;
+        self.check_soft_limits(target)?;
+        match self.backlash {
+            None => {
+                self.release_brake()?;
+                self.module.write_command(MVP::new(self.motor_number, MoveOperation::Absolute(target)))
+            },
+            Some(Backlash::Approach{direction, overshoot, delay}) => {
+                let position = i32::from(self.get::<ActualPosition>()?);
+                let overshoot = match direction {
+                    ApproachDirection::FromBelow if target < position => Some(target.saturating_sub_unsigned(overshoot)),
+                    ApproachDirection::FromAbove if target > position => Some(target.saturating_add_unsigned(overshoot)),
+                    _ => None,
+                };
+                self.release_brake()?;
+                if let Some(overshoot) = overshoot {
+                    self.check_soft_limits(overshoot)?;
+                    self.module.write_command(MVP::new(self.motor_number, MoveOperation::Absolute(overshoot)))?;
+                    while !bool::from(self.get::<TargetPositionReached>()?) {
+                        pause(delay)?;
+                    }
+                }
+                self.module.write_command(MVP::new(self.motor_number, MoveOperation::Absolute(target)))
+            },
+            Some(Backlash::ReversalOffset(distance)) => {
+                let negative = target < self.compensated_position()?;
+                let motor_target = if negative { target.saturating_sub_unsigned(distance) } else { target };
+                self.release_brake()?;
+                self.module.write_command(MVP::new(self.motor_number, MoveOperation::Absolute(motor_target)))?;
+                self.module.set_moved_negative(self.motor_number, negative);
+                Ok(())
+            },
+        }
+    }
+
+    /// The actual position of the load
+    ///
+    /// This is the `ActualPosition` of the motor, corrected by the distance of
+    /// `Backlash::ReversalOffset` if the last move was in the negative direction.
+    pub fn compensated_position(&self) -> Result<i32, Error<IF::Error>> {
+        let position = i32::from(self.get::<ActualPosition>()?);
+        match self.backlash {
+            Some(Backlash::ReversalOffset(distance)) if self.module.moved_negative(self.motor_number) =>
+                Ok(position.saturating_add_unsigned(distance)),
+            _ => Ok(position),
+        }
+    }
+
+    /// Rotate right with the given velocity using `ROR`, releasing the brake first
+    ///
+    /// With soft limits, the rotation is refused if the axis is at or beyond the upper limit.
+    pub fn rotate_right(&self, velocity: u32) -> Result<(), Error<IF::Error>> {
+        if let Some(limits) = self.soft_limits {
+            let position = self.compensated_position()?;
+            if velocity != 0 && position >= limits.max {
+                return Err(limits.violation(position));
+            }
+        }
+        self.release_brake()?;
+        self.module.write_command(ROR::new(self.motor_number, velocity))?;
+        self.record_rotation(velocity, false);
+        Ok(())
+    }
+
+    /// Rotate left with the given velocity using `ROL`, releasing the brake first
+    ///
+    /// With soft limits, the rotation is refused if the axis is at or beyond the lower limit.
+    pub fn rotate_left(&self, velocity: u32) -> Result<(), Error<IF::Error>> {
+        if let Some(limits) = self.soft_limits {
+            let position = self.compensated_position()?;
+            if velocity != 0 && position <= limits.min {
+                return Err(limits.violation(position));
+            }
+        }
+        self.release_brake()?;
+        self.module.write_command(ROL::new(self.motor_number, velocity))?;
+        self.record_rotation(velocity, true);
+        Ok(())
+    }
+
+    /// Rotate with a signed velocity, right if positive and left if negative, unless it is above the
+    /// `MaximumPositioningSpeed` of the axis
+    ///
+    /// The maximum speed is read from the axis first, except with `SpeedGuard::Override`. Use
+    /// `CachedAxis::rotate_checked` to avoid reading it every time.
+    pub fn rotate_checked(&self, velocity: i32, guard: SpeedGuard) -> Result<(), Error<IF::Error>> {
+        let velocity = match guard {
+            SpeedGuard::Override => velocity,
+            _ => guard.limit(velocity, self.get::<MaximumPositioningSpeed>()?)?,
+        };
+        self.rotate(velocity)
+    }
+
+    /// Rotate right with `ROR` if `velocity` is positive, otherwise left with `ROL`
+    pub(crate) fn rotate(&self, velocity: i32) -> Result<(), Error<IF::Error>> {
+        if velocity < 0 {
+            self.rotate_left(velocity.unsigned_abs())
+        } else {
+            self.rotate_right(velocity.unsigned_abs())
+        }
+    }
+
+    /// Stop the motor with `MST`, engaging the brake afterwards
+    pub fn stop(&self) -> Result<(), Error<IF::Error>> {
+        self.module.write_command(MST::new(self.motor_number))?;
+        self.engage_brake()
+    }
+
+    /// Stop the motor if it has left the soft limits, if any
+    ///
+    /// Reads the `ActualPosition` and returns `Error::SoftLimit` after stopping the motor if it is
+    /// outside of the limits. Should be polled while the axis rotates.
+    pub fn enforce_soft_limits(&self) -> Result<(), Error<IF::Error>> {
+        let limits = match self.soft_limits {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+        let position = self.compensated_position()?;
+        if limits.contains(position) {
+            return Ok(());
+        }
+        self.stop()?;
+        Err(limits.violation(position))
+    }
+
+    /// The absolute target of a move, as a position of the load
+    fn target(&self, operation: MoveOperation) -> Result<i32, Error<IF::Error>> {
+        match operation {
+            MoveOperation::Absolute(position) => Ok(position),
+            MoveOperation::Relative(offset) => Ok(self.compensated_position()?.saturating_add(offset)),
+            MoveOperation::Coordinate(number) => {
+                if number > u32::from(u8::MAX) {
+                    return Err(Error::Unsupported);
+                }
+                self.module.write_command(GCO::new(self.motor_number, number as u8))
+            },
+        }
+    }
+
+    fn check_soft_limits(&self, position: i32) -> Result<(), Error<IF::Error>> {
+        match self.soft_limits {
+            Some(limits) if !limits.contains(position) => Err(limits.violation(position)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remember the direction of a rotation for `Backlash::ReversalOffset`
+    fn record_rotation(&self, velocity: u32, left: bool) {
+        if let Some(Backlash::ReversalOffset(_)) = self.backlash {
+            if velocity != 0 {
+                self.module.set_moved_negative(self.motor_number, left);
+            }
+        }
+    }
+
+    /// Release the brake, if any, and wait for the release delay
+    ///
+    /// Nothing is written or waited for if the output already releases the brake.
+    pub fn release_brake(&self) -> Result<(), Error<IF::Error>> {
+        let brake = match self.brake {
+            Some(brake) => brake,
+            None => return Ok(()),
+        };
+        let released = brake.released_state();
+        if (self.module.write_command(GIO::new(OUTPUT_BANK, brake.port_number()))? != 0) == released {
+            return Ok(());
+        }
+        self.module.write_command(SIO::new(OUTPUT_BANK, brake.port_number(), released))?;
+        brake.wait_released();
+        Ok(())
+    }
+
+    /// Wait for the engage delay and engage the brake, if any
+    pub fn engage_brake(&self) -> Result<(), Error<IF::Error>> {
+        let brake = match self.brake {
+            Some(brake) => brake,
+            None => return Ok(()),
+        };
+        brake.wait_engage();
+        self.module.write_command(SIO::new(OUTPUT_BANK, brake.port_number(), !brake.released_state()))
+    }
+
+    /// Block until the motor has reached its target position, then engage the brake
+    ///
+    /// `wait` is called between polls. The interface is not borrowed while `wait` runs, so it may
+    /// use the interface itself, e.g. to poll another module.
+    pub fn wait_for_target_reached<F: FnMut()>(&self, wait: F) -> Result<(), Error<IF::Error>> {
+        self.wait_for_target_reached_cancellable(wait, || false)
+    }
+
+    /// Like `wait_for_target_reached`, but `cancel` is checked before every poll
+    ///
+    /// Cancelling only stops waiting, the motor keeps moving towards its target and the brake stays
+    /// released.
+    pub fn wait_for_target_reached_cancellable<F: FnMut(), C: Cancel>(&self, mut wait: F, mut cancel: C) -> Result<(), Error<IF::Error>> {
+        loop {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            if bool::from(self.get::<TargetPositionReached>()?) {
+                return self.engage_brake();
+            }
+            wait();
+        }
+    }
+
+    /// Like `wait_for_target_reached`, but paced by a `Poller`
+    ///
+    /// Returns `Error::Timeout` if the poller's timeout passes before the target is reached. The
+    /// motor keeps moving towards its target.
+    pub fn wait_for_target_reached_with<C: Clock, D: Delay>(&self, poller: &mut Poller<C, D>) -> Result<(), Error<IF::Error>> {
+        poller.start();
+        loop {
+            if bool::from(self.get::<TargetPositionReached>()?) {
+                return self.engage_brake();
+            }
+            if poller.expired() {
+                return Err(Error::Timeout);
+            }
+            poller.pause();
+        }
+    }
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> Clone for Axis<'a, IF, Cell, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> Copy for Axis<'a, IF, Cell, T> {}
@@ -4,16 +4,35 @@
 //! - AP - ActualPosition (1)
 //! - AS - ActualSpeed (3)
 //! - MPS - MaximumPositioningSpeed (4)
+//! - MA - MaximumAcceleration (5)
 //! - AMC - AbolsuteMaxCurrent (6)
 //! - SBC - StandbyCurrent (7)
+//! - TPR - TargetPositionReached (8)
+//! - RLSS - RightLimitSwitchState (10)
+//! - LLSS - LeftLimitSwitchState (11)
 //! - RLSD - RightLimitSwitchDisable (12)
 //! - LLSD - LeftLimitSwitchDisable (13)
 //! - MSR - MicrostepResolution (140)
+//! - SSF - SoftStopFlag (149)
+//! - RD - RampDivisor (153)
+//! - PD - PulseDivisor (154)
+//! - RSM - ReferenceSearchMode (193)
+//! - RSS - ReferenceSearchSpeed (194)
+//! - RSWS - ReferenceSwitchSpeed (195)
+//! - ESD - EndSwitchDistance (196)
+//! - EEF - ExtendedErrorFlags (207)
+//! - DEF - DriverErrorFlags (208)
+//! - PCM - PositionCaptureMode (220)
+//! - CP - CapturedPosition (221)
 
 use AxisParameter;
 use ReadableAxisParameter;
 use WriteableAxisParameter;
 use Return;
+use FromOperand;
+use InvalidOperand;
+use Operand;
+use I12;
 
 use modules::tmcm::{
     TmcmAxisParameter,
@@ -28,14 +47,19 @@ axis_param_rw!(
 /// Should only be overwritten for reference point setting.
 ActualPosition, i32, 1
 );
+impl ActualPosition {
+    pub fn new(position: i32) -> Self {
+        ActualPosition(position)
+    }
+}
 impl ReadableTmcmAxisParameter for ActualPosition {}
 impl WriteableTmcmAxisParameter for ActualPosition {}
 
 axis_param_r!(
 /// The current rotation speed.
 ///
-/// Should never be overwritten.
-ActualSpeed, i16, 3
+/// Should never be overwritten. The speed is in the range -2047 to 2047.
+ActualSpeed, I12, 3
 );
 impl ReadableTmcmAxisParameter for ActualSpeed {}
 
@@ -45,18 +69,23 @@ axis_param_rw!(
 /// Should not exceed the physically highest possible value. Adjust the pulse divisor (no. 154),
 /// if the speed value is very  low  (<50)  or  above  the  upper  limit.
 /// See TMC 428 datasheet (p.24) for calculation of physical units.
-MaximumPositioningSpeed, u16, 4
+MaximumPositioningSpeed, u16, 4, 0..=2047
 );
-impl MaximumPositioningSpeed {
-    pub fn new(speed: u16) -> Self {
-        assert!(speed <= 2047);
-        MaximumPositioningSpeed(speed)
-    }
-}
 impl TmcmAxisParameter for MaximumPositioningSpeed {}
 impl ReadableTmcmAxisParameter for MaximumPositioningSpeed {}
 impl WriteableTmcmAxisParameter for MaximumPositioningSpeed {}
 
+axis_param_rw!(
+/// The maximum acceleration used by the ramp generator.
+///
+/// The valid range depends on the pulse divisor (no. 154) and ramp divisor (no. 153),
+/// see `TmcmModule::set_ramp` and the TMC 428 datasheet (p.24).
+MaximumAcceleration, u16, 5, 0..=2047
+);
+impl TmcmAxisParameter for MaximumAcceleration {}
+impl ReadableTmcmAxisParameter for MaximumAcceleration {}
+impl WriteableTmcmAxisParameter for MaximumAcceleration {}
+
 axis_param_rw!(
 /// The absolute maximum current
 ///
@@ -98,6 +127,27 @@ impl TmcmAxisParameter for StandbyCurrent {}
 impl ReadableTmcmAxisParameter for StandbyCurrent {}
 impl WriteableTmcmAxisParameter for StandbyCurrent {}
 
+axis_param_r!(
+/// Indicates that the actual position equals the target position.
+TargetPositionReached, bool, 8
+);
+impl TmcmAxisParameter for TargetPositionReached {}
+impl ReadableTmcmAxisParameter for TargetPositionReached {}
+
+axis_param_r!(
+/// The logical state of the right limit switch input, set while the switch is active.
+RightLimitSwitchState, bool, 10
+);
+impl TmcmAxisParameter for RightLimitSwitchState {}
+impl ReadableTmcmAxisParameter for RightLimitSwitchState {}
+
+axis_param_r!(
+/// The logical state of the left limit switch input, set while the switch is active.
+LeftLimitSwitchState, bool, 11
+);
+impl TmcmAxisParameter for LeftLimitSwitchState {}
+impl ReadableTmcmAxisParameter for LeftLimitSwitchState {}
+
 axis_param_rw!(
 /// If set, deactivates the stop function of the right switch
 RightLimitSwitchDisable, bool, 12
@@ -130,6 +180,7 @@ impl TmcmAxisParameter for LeftLimitSwitchDisable {}
 impl ReadableTmcmAxisParameter for LeftLimitSwitchDisable {}
 impl WriteableTmcmAxisParameter for LeftLimitSwitchDisable {}
 
+axis_param_enum!(
 /// Microstep Resolution
 ///
 /// Note that modifying this parameter will affect the rotation speed in the same relation.
@@ -142,8 +193,7 @@ impl WriteableTmcmAxisParameter for LeftLimitSwitchDisable {}
 /// use without an adapted microstepping table. These settings just step through the microstep table
 /// in steps of 64 respectively 32. To get real full stepping use axis parameter 211 or load an
 /// adapted microstepping table.
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum MicrostepResolution {
+MicrostepResolution, 140 {
     /// Fullstep
     Full = 0,
     /// Halfstep
@@ -158,33 +208,257 @@ pub enum MicrostepResolution {
     Micro32 = 5,
     /// 64 microsteps
     Micro64 = 6,
-}
+});
 impl MicrostepResolution {
+    /// The number of microsteps per full step
+    pub fn microsteps(&self) -> u32 {
+        1 << (*self as u8)
+    }
+}
+impl TmcmAxisParameter for MicrostepResolution {}
+impl ReadableTmcmAxisParameter for MicrostepResolution {}
+impl WriteableTmcmAxisParameter for MicrostepResolution {}
+
+axis_param_rw!(
+/// If set, the motor will decelerate using the ramp instead of stopping immediately.
+///
+/// Applies to stops caused by `MST` as well as by the limit switches.
+SoftStopFlag, bool, 149
+);
+impl SoftStopFlag {
+    pub fn soft() -> Self {
+        SoftStopFlag(true)
+    }
+    pub fn hard() -> Self {
+        SoftStopFlag(false)
+    }
+}
+impl TmcmAxisParameter for SoftStopFlag {}
+impl ReadableTmcmAxisParameter for SoftStopFlag {}
+impl WriteableTmcmAxisParameter for SoftStopFlag {}
+
+axis_param_rw!(
+/// The exponent of the scaling factor for the acceleration.
+///
+/// The value range is 0 to 13, every increment halves the acceleration.
+RampDivisor, u8, 153, 0..=13
+);
+impl TmcmAxisParameter for RampDivisor {}
+impl ReadableTmcmAxisParameter for RampDivisor {}
+impl WriteableTmcmAxisParameter for RampDivisor {}
+
+axis_param_rw!(
+/// The exponent of the scaling factor for the pulse (step) generator.
+///
+/// The value range is 0 to 13, every increment halves the velocity.
+PulseDivisor, u8, 154, 0..=13
+);
+impl TmcmAxisParameter for PulseDivisor {}
+impl ReadableTmcmAxisParameter for PulseDivisor {}
+impl WriteableTmcmAxisParameter for PulseDivisor {}
+
+/// The kind of reference search performed by the `RFS` instruction.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReferenceSearchType {
+    /// Search left stop switch only
+    LeftSwitch = 1,
+    /// Search right stop switch, then search left stop switch
+    RightThenLeftSwitch = 2,
+    /// Search right stop switch, then search left stop switch from both sides
+    RightThenLeftSwitchBothSides = 3,
+    /// Search left stop switch from both sides
+    LeftSwitchBothSides = 4,
+    /// Search home switch in negative direction, reverse the direction when left stop switch reached
+    HomeSwitchNegativeReverseAtLeft = 5,
+    /// Search home switch in positive direction, reverse the direction when right stop switch reached
+    HomeSwitchPositiveReverseAtRight = 6,
+    /// Search home switch in positive direction, ignore end switches
+    HomeSwitchPositive = 7,
+    /// Search home switch in negative direction, ignore end switches
+    HomeSwitchNegative = 8,
+}
+impl ReferenceSearchType {
     fn try_from_u8(v: u8) -> Result<Self, ()> {
         match v {
-            0 => Ok(MicrostepResolution::Full),
-            1 => Ok(MicrostepResolution::Half),
-            2 => Ok(MicrostepResolution::Micro4),
-            3 => Ok(MicrostepResolution::Micro8),
-            4 => Ok(MicrostepResolution::Micro16),
-            5 => Ok(MicrostepResolution::Micro32),
-            6 => Ok(MicrostepResolution::Micro64),
+            1 => Ok(ReferenceSearchType::LeftSwitch),
+            2 => Ok(ReferenceSearchType::RightThenLeftSwitch),
+            3 => Ok(ReferenceSearchType::RightThenLeftSwitchBothSides),
+            4 => Ok(ReferenceSearchType::LeftSwitchBothSides),
+            5 => Ok(ReferenceSearchType::HomeSwitchNegativeReverseAtLeft),
+            6 => Ok(ReferenceSearchType::HomeSwitchPositiveReverseAtRight),
+            7 => Ok(ReferenceSearchType::HomeSwitchPositive),
+            8 => Ok(ReferenceSearchType::HomeSwitchNegative),
             _ => Err(()),
         }
     }
 }
-impl AxisParameter for MicrostepResolution {
-    const NUMBER: u8 = 140;
+
+/// Reference Search Mode
+///
+/// Selects how the reference search algorithm started by `RFS` looks for the reference point.
+/// The polarity of the home switch can be inverted, this is only meaningful for the home switch
+/// search types (5 to 8).
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReferenceSearchMode {
+    search_type: ReferenceSearchType,
+    invert_home_switch: bool,
 }
-impl Return for MicrostepResolution {
-    fn from_operand(array: [u8; 4]) -> Self {MicrostepResolution::try_from_u8(array[0]).unwrap()}
+impl ReferenceSearchMode {
+    pub fn new(search_type: ReferenceSearchType, invert_home_switch: bool) -> Self {
+        ReferenceSearchMode{search_type, invert_home_switch}
+    }
+
+    /// The kind of reference search
+    pub fn search_type(&self) -> ReferenceSearchType {
+        self.search_type
+    }
+
+    /// Whether the polarity of the home switch is inverted
+    pub fn invert_home_switch(&self) -> bool {
+        self.invert_home_switch
+    }
 }
-impl TmcmAxisParameter for MicrostepResolution {}
-impl ReadableAxisParameter for MicrostepResolution {}
-impl ReadableTmcmAxisParameter for MicrostepResolution {}
-impl WriteableAxisParameter for MicrostepResolution {
-    fn operand(&self) -> [u8; 4] {
-        [*self as u8, 0u8, 0u8, 0u8]
+impl AxisParameter for ReferenceSearchMode {
+    const NUMBER: u8 = 193;
+}
+impl Return for ReferenceSearchMode {
+    fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> {
+        Ok(ReferenceSearchMode{
+            search_type: ReferenceSearchType::try_from_u8(operand[0] & 0x7f).or(Err(InvalidOperand))?,
+            invert_home_switch: (operand[0] & 0x80) != 0,
+        })
     }
 }
-impl WriteableTmcmAxisParameter for MicrostepResolution {}
+impl TmcmAxisParameter for ReferenceSearchMode {}
+impl ReadableAxisParameter for ReferenceSearchMode {}
+impl ReadableTmcmAxisParameter for ReferenceSearchMode {}
+impl WriteableAxisParameter for ReferenceSearchMode {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.search_type as u8 | ((self.invert_home_switch as u8) << 7)))
+    }
+}
+impl WriteableTmcmAxisParameter for ReferenceSearchMode {}
+
+axis_param_rw!(
+/// The speed used for searching the reference switch.
+///
+/// The value range is the same as for `MaximumPositioningSpeed` (0 to 2047).
+ReferenceSearchSpeed, u16, 194, 0..=2047
+);
+impl TmcmAxisParameter for ReferenceSearchSpeed {}
+impl ReadableTmcmAxisParameter for ReferenceSearchSpeed {}
+impl WriteableTmcmAxisParameter for ReferenceSearchSpeed {}
+
+axis_param_rw!(
+/// The speed used for positioning at the switching point after the reference switch is found.
+///
+/// A lower value than `ReferenceSearchSpeed` gives a more accurate switching point.
+/// The value range is the same as for `MaximumPositioningSpeed` (0 to 2047).
+ReferenceSwitchSpeed, u16, 195, 0..=2047
+);
+impl TmcmAxisParameter for ReferenceSwitchSpeed {}
+impl ReadableTmcmAxisParameter for ReferenceSwitchSpeed {}
+impl WriteableTmcmAxisParameter for ReferenceSwitchSpeed {}
+
+axis_param_r!(
+/// The distance between the two end switches.
+///
+/// Measured by the reference search modes that search both the left and the right stop switch.
+EndSwitchDistance, i32, 196
+);
+impl TmcmAxisParameter for EndSwitchDistance {}
+impl ReadableTmcmAxisParameter for EndSwitchDistance {}
+
+axis_param_r!(
+/// Error flags set by the motion controller.
+///
+/// The flags are cleared when the parameter is read.
+ExtendedErrorFlags, u8, 207
+);
+bitfield!(ExtendedErrorFlags {
+    /// The motor was stopped because the stallGuard load value exceeded the stall detection threshold
+    stallguard_error: 0,
+
+    /// The deviation between the encoder and the actual position exceeded the maximum deviation
+    deviation_error: 1,
+});
+impl TmcmAxisParameter for ExtendedErrorFlags {}
+impl ReadableTmcmAxisParameter for ExtendedErrorFlags {}
+
+axis_param_r!(
+/// The status flags of the motor driver chip.
+///
+/// ```
+/// use tmcl::{FromOperand, Operand};
+/// use tmcl::modules::tmcm::axis_parameters::DriverErrorFlags;
+///
+/// // A short to ground on coil A and an open load on coil B.
+/// let flags = DriverErrorFlags::from_operand(Operand::from_u32(0x48));
+/// assert!(flags.short_to_ground_a());
+/// assert_eq!(flags.short_to_ground_coils(), 0b01);
+/// assert_eq!(flags.open_load_coils(), 0b10);
+/// ```
+DriverErrorFlags, u8, 208
+);
+bitfield!(DriverErrorFlags {
+    /// The stallGuard status bit of the driver
+    stallguard: 0,
+
+    /// The driver has shut down because of overtemperature
+    overtemperature: 1,
+
+    /// The driver temperature exceeds the overtemperature pre-warning threshold
+    overtemperature_prewarning: 2,
+
+    /// A short to ground was detected on coil A
+    short_to_ground_a: 3,
+
+    /// A short to ground was detected on coil B
+    short_to_ground_b: 4,
+
+    /// An open load was detected on coil A
+    open_load_a: 5,
+
+    /// An open load was detected on coil B
+    open_load_b: 6,
+
+    /// The coils a short to ground was detected on, bit 0 for coil A and bit 1 for coil B
+    short_to_ground_coils: 3..5 as u8,
+
+    /// The coils an open load was detected on, bit 0 for coil A and bit 1 for coil B
+    open_load_coils: 5..7 as u8,
+
+    /// The motor is at standstill
+    standstill: 7,
+});
+impl TmcmAxisParameter for DriverErrorFlags {}
+impl ReadableTmcmAxisParameter for DriverErrorFlags {}
+
+axis_param_enum!(
+/// Position Capture Mode
+///
+/// On modules with a latch input, `ActualPosition` is captured to `CapturedPosition` on the
+/// selected edge of the input. Capturing is one-shot, the mode reads back as `Disabled` once a
+/// position has been captured.
+PositionCaptureMode, 220 {
+    /// Capturing is disabled, or a position has been captured
+    Disabled = 0,
+    /// Capture the position on a rising edge of the latch input
+    RisingEdge = 1,
+    /// Capture the position on a falling edge of the latch input
+    FallingEdge = 2,
+});
+impl TmcmAxisParameter for PositionCaptureMode {}
+impl ReadableTmcmAxisParameter for PositionCaptureMode {}
+impl WriteableTmcmAxisParameter for PositionCaptureMode {}
+
+axis_param_r!(
+/// The `ActualPosition` captured on the last trigger of the latch input.
+///
+/// See `PositionCaptureMode`.
+CapturedPosition, i32, 221
+);
+impl TmcmAxisParameter for CapturedPosition {}
+impl ReadableTmcmAxisParameter for CapturedPosition {}
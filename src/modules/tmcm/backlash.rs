@@ -0,0 +1,92 @@
+//! Backlash compensation for positioning through lead screws and gears.
+//!
+//! Lead screws and gears have play, so the load ends up at a slightly different position
+//! depending on the direction it was moved in. The module firmware does not compensate for it, an
+//! `Axis` with a `Backlash` does it on the host in `move_to`:
+//!
+//! - `Backlash::Approach` always approaches the target from the same side. A target on the other
+//!   side is first overshot by the given distance, and approached from there after the overshoot
+//!   was reached. This is the most repeatable, but costs an extra move and blocks until the
+//!   overshoot has been reached. `Axis::move_to_cancellable` and `Axis::move_to_with` stop
+//!   waiting when cancelled respectively timed out, e.g. when the motor stalls.
+//! - `Backlash::ReversalOffset` moves the motor the given distance further when moving in the
+//!   negative direction, taking up the play on every reversal. The motor position then differs
+//!   from the position of the load by the distance while the last move was negative. The module
+//!   remembers the direction of the last move of each of its first 32 axes,
+//!   `Axis::compensated_position` returns the position of the load.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::backlash::{ApproachDirection, Backlash};
+//! use tmcl::modules::tmcm::instructions::MoveOperation;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!
+//!     // Targets below the actual position are overshot by 200 steps and approached from below.
+//!     let axis = module.axis(0).with_backlash(Backlash::Approach{
+//!         direction: ApproachDirection::FromBelow,
+//!         overshoot: 200,
+//!         delay: std::thread::sleep,
+//!     });
+//!     axis.move_to(MoveOperation::Absolute(-5000)).unwrap();
+//! }
+//! ```
+
+use lib::time::Duration;
+
+/// The time waited between polls while moving to the overshoot of `Backlash::Approach`.
+pub const APPROACH_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The side targets are approached from with `Backlash::Approach`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ApproachDirection {
+    /// Approach targets moving in the positive direction.
+    FromBelow,
+
+    /// Approach targets moving in the negative direction.
+    FromAbove,
+}
+
+/// How `Axis::move_to` compensates for backlash.
+#[derive(Debug, Clone, Copy)]
+pub enum Backlash {
+    /// Approach every target from `direction`, overshooting targets on the other side by `overshoot`
+    ///
+    /// `delay` is called with `APPROACH_POLL_INTERVAL` between polls while moving to the overshoot,
+    /// except with `Axis::move_to_with` which waits with its `Poller`.
+    Approach {
+        direction: ApproachDirection,
+        overshoot: u32,
+        delay: fn(Duration),
+    },
+
+    /// Move the motor the given distance further when moving in the negative direction.
+    ReversalOffset(u32),
+}
@@ -0,0 +1,146 @@
+//! Holding brakes on digital outputs.
+//!
+//! Axes carrying a load, e.g. vertical axes, often have a holding brake switched by a digital
+//! output of the module. An `Axis` with a `BrakeConfig` releases the brake before every motion
+//! command and engages it again after `stop` and when `wait_for_target_reached` finds the target
+//! reached. Releasing first checks the output with `GIO`, so consecutive motion commands only wait
+//! for the release delay once.
+//!
+//! The delays give the brake time to open before the motor moves, and the motor time to come to
+//! rest before the brake closes. They are waited with the `fn` given to the config, e.g.
+//! `std::thread::sleep`.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::brake::{BrakeConfig, BrakePolarity};
+//! use tmcl::modules::tmcm::instructions::MoveOperation;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!
+//!     // The brake on output 0 is released while the output is high.
+//!     let brake = BrakeConfig::new(0, std::thread::sleep)
+//!         .with_polarity(BrakePolarity::HighReleases)
+//!         .with_release_delay(Duration::from_millis(80))
+//!         .with_engage_delay(Duration::from_millis(50));
+//!     let axis = module.axis(0).with_brake(brake);
+//!
+//!     axis.move_to(MoveOperation::Absolute(10000)).unwrap();
+//!     axis.wait_for_target_reached(|| ()).unwrap();
+//! }
+//! ```
+
+use lib::time::Duration;
+
+/// Which output state releases the brake.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BrakePolarity {
+    /// The brake is released while the output is high, the usual fail-safe wiring.
+    HighReleases,
+
+    /// The brake is released while the output is low.
+    LowReleases,
+}
+
+/// A holding brake switched by a digital output of the module.
+#[derive(Debug, Clone, Copy)]
+pub struct BrakeConfig {
+    port_number: u8,
+    polarity: BrakePolarity,
+    release_delay: Duration,
+    engage_delay: Duration,
+    delay: fn(Duration),
+}
+
+impl BrakeConfig {
+    /// A brake on output `port_number`, waiting the delays with `delay`
+    ///
+    /// By default the output is high to release the brake and both delays are 100 ms.
+    pub fn new(port_number: u8, delay: fn(Duration)) -> Self {
+        BrakeConfig{
+            port_number,
+            polarity: BrakePolarity::HighReleases,
+            release_delay: Duration::from_millis(100),
+            engage_delay: Duration::from_millis(100),
+            delay,
+        }
+    }
+
+    /// Set which output state releases the brake
+    pub fn with_polarity(mut self, polarity: BrakePolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Wait `delay` after releasing the brake before the motor moves
+    pub fn with_release_delay(mut self, delay: Duration) -> Self {
+        self.release_delay = delay;
+        self
+    }
+
+    /// Wait `delay` after the motor stopped before engaging the brake
+    pub fn with_engage_delay(mut self, delay: Duration) -> Self {
+        self.engage_delay = delay;
+        self
+    }
+
+    /// The output the brake is connected to
+    pub fn port_number(&self) -> u8 {
+        self.port_number
+    }
+
+    /// Which output state releases the brake
+    pub fn polarity(&self) -> BrakePolarity {
+        self.polarity
+    }
+
+    /// The time waited after releasing the brake
+    pub fn release_delay(&self) -> Duration {
+        self.release_delay
+    }
+
+    /// The time waited before engaging the brake
+    pub fn engage_delay(&self) -> Duration {
+        self.engage_delay
+    }
+
+    /// The output state releasing the brake
+    pub fn released_state(&self) -> bool {
+        self.polarity == BrakePolarity::HighReleases
+    }
+
+    pub(crate) fn wait_released(&self) {
+        (self.delay)(self.release_delay)
+    }
+
+    pub(crate) fn wait_engage(&self) {
+        (self.delay)(self.engage_delay)
+    }
+}
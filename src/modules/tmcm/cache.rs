@@ -0,0 +1,157 @@
+//! Caching axis parameters to reduce bus traffic.
+//!
+//! Configuration heavy applications often write the same parameters again and again. A
+//! `CachedAxis` remembers the last value written to or read from every axis parameter. Writes of
+//! the value already known to be in the module are skipped (unless disabled) and reads of known
+//! values are answered from the cache.
+//!
+//! The cache only knows about the traffic going through it. Parameters changed by the module
+//! itself (e.g. `ActualPosition`, `TargetPositionReached`), by a TMCL program or by another host
+//! must be read with `refresh`, and the cache must be invalidated after e.g. `RSAP` or a reset.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::cache::CachedAxis;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!     let mut axis = CachedAxis::new(module.axis(0));
+//!
+//!     axis.set(MaximumPositioningSpeed::new(1000)).unwrap();
+//!     // Skipped, the value is already in the module.
+//!     axis.set(MaximumPositioningSpeed::new(1000)).unwrap();
+//!
+//!     // Always read from the module.
+//!     let position = axis.refresh::<ActualPosition>().unwrap();
+//! }
+//! ```
+
+use lib::ops::Deref;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use opcodes::OPCODE_GAP;
+use AxisParameter;
+use Operand;
+
+use modules::tmcm::{
+    ReadableTmcmAxisParameter,
+    WriteableTmcmAxisParameter,
+};
+use modules::tmcm::axis::{
+    Axis,
+    SpeedGuard,
+};
+use modules::tmcm::axis_parameters::MaximumPositioningSpeed;
+
+/// An `Axis` remembering the values of its parameters.
+#[derive(Debug)]
+pub struct CachedAxis<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a> {
+    axis: Axis<'a, IF, Cell, T>,
+    values: [Option<Operand>; 256],
+    skip_redundant_writes: bool,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> CachedAxis<'a, IF, Cell, T> {
+    /// Create an empty cache for `axis`, redundant writes are skipped
+    pub fn new(axis: Axis<'a, IF, Cell, T>) -> Self {
+        CachedAxis{
+            axis,
+            values: [None; 256],
+            skip_redundant_writes: true,
+        }
+    }
+
+    /// Choose whether writes of the cached value are skipped
+    ///
+    /// When not skipped, writes still update the cache for later reads.
+    pub fn with_skip_redundant_writes(mut self, skip: bool) -> Self {
+        self.skip_redundant_writes = skip;
+        self
+    }
+
+    /// The uncached axis
+    pub fn axis(&self) -> Axis<'a, IF, Cell, T> {
+        self.axis
+    }
+
+    /// Read an axis parameter, with `GAP` only if the value is not cached
+    pub fn get<P: ReadableTmcmAxisParameter>(&mut self) -> Result<P, Error<IF::Error>> {
+        match self.values[usize::from(P::NUMBER)] {
+            Some(operand) => P::try_from_operand(operand).or(Err(Error::InvalidReply)),
+            None => self.refresh(),
+        }
+    }
+
+    /// Read an axis parameter with `GAP` and update the cache
+    pub fn refresh<P: ReadableTmcmAxisParameter>(&mut self) -> Result<P, Error<IF::Error>> {
+        let operand = self.axis.module().write_raw_command(OPCODE_GAP, P::NUMBER, self.axis.motor_number(), 0)?;
+        let value = P::try_from_operand(operand).or(Err(Error::InvalidReply))?;
+        self.values[usize::from(P::NUMBER)] = Some(operand);
+        Ok(value)
+    }
+
+    /// Write an axis parameter with `SAP`, unless it is known to have this value already
+    pub fn set<P: WriteableTmcmAxisParameter>(&mut self, parameter: P) -> Result<(), Error<IF::Error>> {
+        let operand = parameter.operand();
+        if self.skip_redundant_writes && self.values[usize::from(P::NUMBER)] == Some(operand) {
+            return Ok(());
+        }
+        self.values[usize::from(P::NUMBER)] = None;
+        self.axis.set(parameter)?;
+        self.values[usize::from(P::NUMBER)] = Some(operand);
+        Ok(())
+    }
+
+    /// Like `Axis::rotate_checked`, but the `MaximumPositioningSpeed` is only read if it is not cached
+    pub fn rotate_checked(&mut self, velocity: i32, guard: SpeedGuard) -> Result<(), Error<IF::Error>> {
+        let velocity = match guard {
+            SpeedGuard::Override => velocity,
+            _ => guard.limit(velocity, self.get::<MaximumPositioningSpeed>()?)?,
+        };
+        self.axis.rotate(velocity)
+    }
+
+    /// Returns true if the value of the axis parameter is cached
+    pub fn is_cached<P: AxisParameter>(&self) -> bool {
+        self.values[usize::from(P::NUMBER)].is_some()
+    }
+
+    /// Forget the cached value of an axis parameter
+    pub fn invalidate<P: AxisParameter>(&mut self) {
+        self.values[usize::from(P::NUMBER)] = None;
+    }
+
+    /// Forget all cached values
+    pub fn invalidate_all(&mut self) {
+        self.values = [None; 256];
+    }
+}
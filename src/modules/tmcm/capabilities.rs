@@ -0,0 +1,64 @@
+//! The instructions supported by a module.
+//!
+//! Different module families and firmware revisions support different subsets of the instruction
+//! set. When a module is given its `Capabilities` with `TmcmModule::with_capabilities`, instructions
+//! it does not support are rejected with `Error::Unsupported` without being transmitted.
+//!
+//! ```
+//! use tmcl::modules::tmcm::capabilities::Capabilities;
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! // A module where CALCX (33) is not available.
+//! let capabilities = Capabilities::all().without(33);
+//! assert!(capabilities.supports(MVP::new(0, MoveOperation::Absolute(0))));
+//! assert!(!capabilities.supports_number(33));
+//! ```
+
+use Encode;
+
+/// A set of supported instruction numbers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capabilities {
+    instructions: [u64; 4],
+}
+
+impl Capabilities {
+    /// All instructions are supported
+    pub fn all() -> Self {
+        Capabilities{instructions: [!0; 4]}
+    }
+
+    /// No instructions are supported
+    pub fn none() -> Self {
+        Capabilities{instructions: [0; 4]}
+    }
+
+    /// Add support for the instruction with the given number
+    pub fn with(mut self, instruction_number: u8) -> Self {
+        self.instructions[usize::from(instruction_number / 64)] |= 1 << (instruction_number % 64);
+        self
+    }
+
+    /// Remove support for the instruction with the given number
+    pub fn without(mut self, instruction_number: u8) -> Self {
+        self.instructions[usize::from(instruction_number / 64)] &= !(1 << (instruction_number % 64));
+        self
+    }
+
+    /// Returns true if the instruction with the given number is supported
+    pub fn supports_number(&self, instruction_number: u8) -> bool {
+        self.instructions[usize::from(instruction_number / 64)] & (1 << (instruction_number % 64)) != 0
+    }
+
+    /// Returns true if the instruction is supported
+    pub fn supports<E: Encode>(&self, instruction: E) -> bool {
+        self.supports_number(instruction.encode()[0])
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::all()
+    }
+}
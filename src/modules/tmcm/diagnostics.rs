@@ -0,0 +1,129 @@
+//! Dumping and comparing axis parameters for commissioning and field diagnosis.
+//!
+//! A dump taken with `TmcmModule::dump_axis_parameters` can be compared against a dump taken from
+//! another module (or the same module at another time), or against the `AxisConfig` the axis is
+//! expected to have.
+//!
+//! ```
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::diagnostics::*;
+//!
+//! let mut dump = AxisParameterDump::new();
+//! dump.insert(4, 1000);
+//! dump.insert(7, 10);
+//!
+//! let config = AxisConfig::new()
+//!     .set(MaximumPositioningSpeed::new(1000))
+//!     .set(StandbyCurrent::new(8));
+//!
+//! let differences = diff_config(&dump, &config);
+//! assert_eq!(differences, vec![ParameterDifference::new(7, Some(10), Some(8))]);
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::vec::Vec;
+
+use FromOperand;
+use modules::tmcm::WriteableTmcmAxisParameter;
+use modules::tmcm::parameter_info::{ParameterInfo, AXIS_PARAMETERS};
+
+/// The axis parameters read by `TmcmModule::dump_axis_parameters`
+///
+/// These are all parameters in `AXIS_PARAMETERS`, including the read-only ones describing the
+/// state of the axis, except the ones cleared by reading them, e.g. `ExtendedErrorFlags`.
+///
+/// ```
+/// use tmcl::modules::tmcm::diagnostics::dumped_axis_parameters;
+///
+/// assert!(dumped_axis_parameters().any(|info| info.number() == 153));
+/// assert!(!dumped_axis_parameters().any(|info| info.number() == 207));
+/// ```
+pub fn dumped_axis_parameters() -> impl Iterator<Item = &'static ParameterInfo> {
+    AXIS_PARAMETERS.iter().filter(|info| !info.is_cleared_by_read())
+}
+
+/// The raw values of the axis parameters of one axis, indexed by parameter number.
+pub type AxisParameterDump = BTreeMap<u8, i32>;
+
+/// The expected values of a set of axis parameters of one axis.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AxisConfig {
+    values: BTreeMap<u8, i32>,
+}
+
+impl AxisConfig {
+    /// Create an empty configuration
+    pub fn new() -> Self {
+        AxisConfig{values: BTreeMap::new()}
+    }
+
+    /// Expect `parameter` to have the given value
+    pub fn set<P: WriteableTmcmAxisParameter>(mut self, parameter: P) -> Self {
+        self.values.insert(P::NUMBER, i32::from_operand(parameter.operand()));
+        self
+    }
+
+    /// The expected raw value of the parameter with the given number
+    pub fn get(&self, number: u8) -> Option<i32> {
+        self.values.get(&number).cloned()
+    }
+
+    /// All expected values as `(parameter number, raw value)`, ordered by parameter number
+    pub fn iter(&self) -> btree_map::Iter<'_, u8, i32> {
+        self.values.iter()
+    }
+}
+
+/// A parameter that differs between two dumps, or between a dump and an `AxisConfig`.
+///
+/// A value of `None` means the parameter is missing on that side.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParameterDifference {
+    number: u8,
+    left: Option<i32>,
+    right: Option<i32>,
+}
+
+impl ParameterDifference {
+    /// Create a new difference for the parameter with the given number
+    pub fn new(number: u8, left: Option<i32>, right: Option<i32>) -> Self {
+        ParameterDifference{number, left, right}
+    }
+
+    /// The parameter number
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// The value in the left (first) dump
+    pub fn left(&self) -> Option<i32> {
+        self.left
+    }
+
+    /// The value in the right (second) dump or configuration
+    pub fn right(&self) -> Option<i32> {
+        self.right
+    }
+}
+
+/// Compare two dumps, returning every parameter that differs ordered by parameter number
+pub fn diff(left: &AxisParameterDump, right: &AxisParameterDump) -> Vec<ParameterDifference> {
+    let mut numbers: Vec<u8> = left.keys().chain(right.keys()).cloned().collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers.into_iter()
+        .map(|number| ParameterDifference::new(number, left.get(&number).cloned(), right.get(&number).cloned()))
+        .filter(|difference| difference.left != difference.right)
+        .collect()
+}
+
+/// Compare a dump against a configuration, returning every configured parameter that differs
+///
+/// Parameters that are in the dump but not in the configuration are not compared.
+pub fn diff_config(dump: &AxisParameterDump, config: &AxisConfig) -> Vec<ParameterDifference> {
+    config.iter()
+        .map(|(&number, &value)| ParameterDifference::new(number, dump.get(&number).cloned(), Some(value)))
+        .filter(|difference| difference.left != difference.right)
+        .collect()
+}
@@ -0,0 +1,453 @@
+//! All global parameters useable with TMCM modules other than TMCM-100 and Monopack 2.
+
+use GlobalParameter;
+use ReadableGlobalParameter;
+use WriteableGlobalParameter;
+use Return;
+use FromOperand;
+use InvalidOperand;
+use Operand;
+
+use modules::tmcm::{
+    TmcmGlobalParameter,
+    ReadableTmcmGlobalParameter,
+    WriteableTmcmGlobalParameter,
+};
+
+/// Coordinate Storage
+///
+/// Selects where the coordinates used by `SCO`, `GCO`, `CCO` and `MVP` `COORD` are stored.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CoordinateStorage {
+    /// Coordinates are stored in RAM only, but can be copied explicitly between RAM and EEPROM
+    /// with `SCO::store_to_eeprom` and `GCO::load_from_eeprom`.
+    Ram = 0,
+    /// Coordinates are always stored in EEPROM only.
+    Eeprom = 1,
+}
+impl GlobalParameter for CoordinateStorage {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 84;
+}
+impl Return for CoordinateStorage {
+    fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> {
+        match operand[0] {
+            0 => Ok(CoordinateStorage::Ram),
+            1 => Ok(CoordinateStorage::Eeprom),
+            _ => Err(InvalidOperand),
+        }
+    }
+}
+impl TmcmGlobalParameter for CoordinateStorage {}
+impl ReadableGlobalParameter for CoordinateStorage {}
+impl ReadableTmcmGlobalParameter for CoordinateStorage {}
+impl WriteableGlobalParameter for CoordinateStorage {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(*self as u8))
+    }
+}
+impl WriteableTmcmGlobalParameter for CoordinateStorage {}
+
+/// Serial Address
+///
+/// The address of the module on the RS232/RS485 line (1-255).
+/// The address is stored in EEPROM and takes effect after the reply to `SGP` has been sent.
+///
+/// ```
+/// use tmcl::OutOfRange;
+/// use tmcl::modules::tmcm::global_parameters::{CanId, SerialAddress};
+///
+/// assert_eq!(SerialAddress::try_new(0), Err(OutOfRange));
+/// assert_eq!(u8::from(SerialAddress::saturating(0)), 1);
+/// assert_eq!(CanId::try_new(0x800), Err(OutOfRange));
+/// assert_eq!(CanId::MAX, 0x7ff);
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SerialAddress(u8);
+param_define_range!(SerialAddress, u8, 1, 255);
+impl From<SerialAddress> for u8 {
+    fn from(address: SerialAddress) -> u8 {
+        address.0
+    }
+}
+impl GlobalParameter for SerialAddress {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 66;
+}
+impl FromOperand for SerialAddress {
+    fn from_operand(operand: Operand) -> Self {SerialAddress(operand[0])}
+}
+impl TmcmGlobalParameter for SerialAddress {}
+impl ReadableGlobalParameter for SerialAddress {}
+impl ReadableTmcmGlobalParameter for SerialAddress {}
+impl WriteableGlobalParameter for SerialAddress {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for SerialAddress {}
+
+/// Serial Baud Rate
+///
+/// The baud rate of the RS232/RS485 interface. The rate is stored in EEPROM and takes effect
+/// after the module has been reset. Not all modules support the rates above 115200.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SerialBaudRate {
+    Baud9600 = 0,
+    Baud14400 = 1,
+    Baud19200 = 2,
+    Baud28800 = 3,
+    Baud38400 = 4,
+    Baud57600 = 5,
+    Baud76800 = 6,
+    Baud115200 = 7,
+    Baud230400 = 8,
+    Baud250000 = 9,
+    Baud500000 = 10,
+    Baud1000000 = 11,
+}
+impl SerialBaudRate {
+    /// The baud rate in bits per second
+    pub fn bits_per_second(&self) -> u32 {
+        match *self {
+            SerialBaudRate::Baud9600 => 9600,
+            SerialBaudRate::Baud14400 => 14400,
+            SerialBaudRate::Baud19200 => 19200,
+            SerialBaudRate::Baud28800 => 28800,
+            SerialBaudRate::Baud38400 => 38400,
+            SerialBaudRate::Baud57600 => 57600,
+            SerialBaudRate::Baud76800 => 76800,
+            SerialBaudRate::Baud115200 => 115200,
+            SerialBaudRate::Baud230400 => 230400,
+            SerialBaudRate::Baud250000 => 250000,
+            SerialBaudRate::Baud500000 => 500000,
+            SerialBaudRate::Baud1000000 => 1000000,
+        }
+    }
+}
+impl GlobalParameter for SerialBaudRate {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 65;
+}
+impl Return for SerialBaudRate {
+    fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> {
+        match operand.to_u32() {
+            0 => Ok(SerialBaudRate::Baud9600),
+            1 => Ok(SerialBaudRate::Baud14400),
+            2 => Ok(SerialBaudRate::Baud19200),
+            3 => Ok(SerialBaudRate::Baud28800),
+            4 => Ok(SerialBaudRate::Baud38400),
+            5 => Ok(SerialBaudRate::Baud57600),
+            6 => Ok(SerialBaudRate::Baud76800),
+            7 => Ok(SerialBaudRate::Baud115200),
+            8 => Ok(SerialBaudRate::Baud230400),
+            9 => Ok(SerialBaudRate::Baud250000),
+            10 => Ok(SerialBaudRate::Baud500000),
+            11 => Ok(SerialBaudRate::Baud1000000),
+            _ => Err(InvalidOperand),
+        }
+    }
+}
+impl TmcmGlobalParameter for SerialBaudRate {}
+impl ReadableGlobalParameter for SerialBaudRate {}
+impl ReadableTmcmGlobalParameter for SerialBaudRate {}
+impl WriteableGlobalParameter for SerialBaudRate {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(*self as u32)
+    }
+}
+impl WriteableTmcmGlobalParameter for SerialBaudRate {}
+
+/// Serial Secondary Address
+///
+/// A second address of the module on the RS232/RS485 line, 0 disables it. Commands sent to the
+/// secondary address are executed but not replied to, so several modules sharing a secondary
+/// address can be commanded at once. See `TmcmModule::with_no_reply`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SerialSecondaryAddress(u8);
+impl SerialSecondaryAddress {
+    pub fn new(address: u8) -> Self {
+        SerialSecondaryAddress(address)
+    }
+    pub fn disabled() -> Self {
+        SerialSecondaryAddress(0)
+    }
+}
+impl From<SerialSecondaryAddress> for u8 {
+    fn from(address: SerialSecondaryAddress) -> u8 {
+        address.0
+    }
+}
+impl GlobalParameter for SerialSecondaryAddress {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 87;
+}
+impl FromOperand for SerialSecondaryAddress {
+    fn from_operand(operand: Operand) -> Self {SerialSecondaryAddress(operand[0])}
+}
+impl TmcmGlobalParameter for SerialSecondaryAddress {}
+impl ReadableGlobalParameter for SerialSecondaryAddress {}
+impl ReadableTmcmGlobalParameter for SerialSecondaryAddress {}
+impl WriteableGlobalParameter for SerialSecondaryAddress {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for SerialSecondaryAddress {}
+
+/// Telegram Pause Time
+///
+/// The time in milliseconds the module waits after receiving a command before sending the reply
+/// on RS485, giving the host time to switch its transceiver from transmitting to receiving.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TelegramPauseTime(u8);
+impl TelegramPauseTime {
+    pub fn new(milliseconds: u8) -> Self {
+        TelegramPauseTime(milliseconds)
+    }
+}
+impl From<TelegramPauseTime> for u8 {
+    fn from(pause: TelegramPauseTime) -> u8 {
+        pause.0
+    }
+}
+impl GlobalParameter for TelegramPauseTime {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 75;
+}
+impl FromOperand for TelegramPauseTime {
+    fn from_operand(operand: Operand) -> Self {TelegramPauseTime(operand[0])}
+}
+impl TmcmGlobalParameter for TelegramPauseTime {}
+impl ReadableGlobalParameter for TelegramPauseTime {}
+impl ReadableTmcmGlobalParameter for TelegramPauseTime {}
+impl WriteableGlobalParameter for TelegramPauseTime {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for TelegramPauseTime {}
+
+/// Serial Host Address
+///
+/// The address the module puts in its replies on the RS232/RS485 line, see `TmcmModule::with_reply_address_check`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SerialHostAddress(u8);
+impl SerialHostAddress {
+    pub fn new(address: u8) -> Self {
+        SerialHostAddress(address)
+    }
+}
+impl From<SerialHostAddress> for u8 {
+    fn from(address: SerialHostAddress) -> u8 {
+        address.0
+    }
+}
+impl GlobalParameter for SerialHostAddress {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 76;
+}
+impl FromOperand for SerialHostAddress {
+    fn from_operand(operand: Operand) -> Self {SerialHostAddress(operand[0])}
+}
+impl TmcmGlobalParameter for SerialHostAddress {}
+impl ReadableGlobalParameter for SerialHostAddress {}
+impl ReadableTmcmGlobalParameter for SerialHostAddress {}
+impl WriteableGlobalParameter for SerialHostAddress {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for SerialHostAddress {}
+
+/// CAN Bit Rate
+///
+/// The bit rate of the CAN interface. The rate is stored in EEPROM and takes effect after the
+/// module has been reset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CanBitrate {
+    Kbps20 = 2,
+    Kbps50 = 3,
+    Kbps100 = 4,
+    Kbps125 = 5,
+    Kbps250 = 6,
+    Kbps500 = 7,
+    Kbps1000 = 8,
+}
+impl CanBitrate {
+    /// The bit rate in bits per second
+    pub fn bits_per_second(&self) -> u32 {
+        match *self {
+            CanBitrate::Kbps20 => 20_000,
+            CanBitrate::Kbps50 => 50_000,
+            CanBitrate::Kbps100 => 100_000,
+            CanBitrate::Kbps125 => 125_000,
+            CanBitrate::Kbps250 => 250_000,
+            CanBitrate::Kbps500 => 500_000,
+            CanBitrate::Kbps1000 => 1_000_000,
+        }
+    }
+}
+impl GlobalParameter for CanBitrate {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 69;
+}
+impl Return for CanBitrate {
+    fn try_from_operand(operand: Operand) -> Result<Self, InvalidOperand> {
+        match operand.to_u32() {
+            2 => Ok(CanBitrate::Kbps20),
+            3 => Ok(CanBitrate::Kbps50),
+            4 => Ok(CanBitrate::Kbps100),
+            5 => Ok(CanBitrate::Kbps125),
+            6 => Ok(CanBitrate::Kbps250),
+            7 => Ok(CanBitrate::Kbps500),
+            8 => Ok(CanBitrate::Kbps1000),
+            _ => Err(InvalidOperand),
+        }
+    }
+}
+impl TmcmGlobalParameter for CanBitrate {}
+impl ReadableGlobalParameter for CanBitrate {}
+impl ReadableTmcmGlobalParameter for CanBitrate {}
+impl WriteableGlobalParameter for CanBitrate {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(*self as u32)
+    }
+}
+impl WriteableTmcmGlobalParameter for CanBitrate {}
+
+/// CAN Reply ID
+///
+/// The CAN identifier used for replies from the module (1-0x7ff).
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CanReplyId(u16);
+param_define_range!(CanReplyId, u16, 1, 0x7ff);
+impl From<CanReplyId> for u16 {
+    fn from(id: CanReplyId) -> u16 {
+        id.0
+    }
+}
+impl GlobalParameter for CanReplyId {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 70;
+}
+impl FromOperand for CanReplyId {
+    fn from_operand(operand: Operand) -> Self {CanReplyId(u16::from(operand[0]) | (u16::from(operand[1]) << 8))}
+}
+impl TmcmGlobalParameter for CanReplyId {}
+impl ReadableGlobalParameter for CanReplyId {}
+impl ReadableTmcmGlobalParameter for CanReplyId {}
+impl WriteableGlobalParameter for CanReplyId {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for CanReplyId {}
+
+/// CAN ID
+///
+/// The CAN identifier the module receives commands on (1-0x7ff).
+/// The ID is stored in EEPROM and takes effect after the reply to `SGP` has been sent.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CanId(u16);
+param_define_range!(CanId, u16, 1, 0x7ff);
+impl From<CanId> for u16 {
+    fn from(id: CanId) -> u16 {
+        id.0
+    }
+}
+impl GlobalParameter for CanId {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 71;
+}
+impl FromOperand for CanId {
+    fn from_operand(operand: Operand) -> Self {CanId(u16::from(operand[0]) | (u16::from(operand[1]) << 8))}
+}
+impl TmcmGlobalParameter for CanId {}
+impl ReadableGlobalParameter for CanId {}
+impl ReadableTmcmGlobalParameter for CanId {}
+impl WriteableGlobalParameter for CanId {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for CanId {}
+
+/// CAN Secondary ID
+///
+/// A second CAN identifier the module receives commands on (1-0x7ff), 0 disables it. Commands sent
+/// to the secondary ID are executed but not replied to, so several modules sharing a secondary ID
+/// form a group that can be commanded at once. See `Bus::write_group_command`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CanSecondaryId(u16);
+param_define_range!(CanSecondaryId, u16, 0, 0x7ff);
+impl CanSecondaryId {
+    pub fn disabled() -> Self {
+        CanSecondaryId(0)
+    }
+}
+impl From<CanSecondaryId> for u16 {
+    fn from(id: CanSecondaryId) -> u16 {
+        id.0
+    }
+}
+impl GlobalParameter for CanSecondaryId {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 83;
+}
+impl FromOperand for CanSecondaryId {
+    fn from_operand(operand: Operand) -> Self {CanSecondaryId(u16::from(operand[0]) | (u16::from(operand[1]) << 8))}
+}
+impl TmcmGlobalParameter for CanSecondaryId {}
+impl ReadableGlobalParameter for CanSecondaryId {}
+impl ReadableTmcmGlobalParameter for CanSecondaryId {}
+impl WriteableGlobalParameter for CanSecondaryId {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(u32::from(self.0))
+    }
+}
+impl WriteableTmcmGlobalParameter for CanSecondaryId {}
+
+/// Tick Timer
+///
+/// A timer counting milliseconds since the module was powered on. It wraps around after
+/// `u32::MAX` milliseconds (about 49.7 days) and can be set to any value, see the `tick_timer` module.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TickTimer(u32);
+impl TickTimer {
+    pub fn new(ticks: u32) -> Self {
+        TickTimer(ticks)
+    }
+}
+impl From<TickTimer> for u32 {
+    fn from(ticks: TickTimer) -> u32 {
+        ticks.0
+    }
+}
+impl GlobalParameter for TickTimer {
+    const BANK: u8 = 0;
+    const NUMBER: u8 = 132;
+}
+impl FromOperand for TickTimer {
+    fn from_operand(operand: Operand) -> Self {TickTimer(operand.to_u32())}
+}
+impl TmcmGlobalParameter for TickTimer {}
+impl ReadableGlobalParameter for TickTimer {}
+impl ReadableTmcmGlobalParameter for TickTimer {}
+impl WriteableGlobalParameter for TickTimer {
+    fn operand(&self) -> Operand {
+        Operand::from_u32(self.0)
+    }
+}
+impl WriteableTmcmGlobalParameter for TickTimer {}
@@ -0,0 +1,281 @@
+//! Monitoring the error flags of a module.
+//!
+//! `TmcmModule::health` reads the `ExtendedErrorFlags` and `DriverErrorFlags` of an axis and
+//! combines them into `ErrorFlags`, suitable for periodic polling by a monitoring dashboard.
+//! Reading the extended error flags clears them on the module.
+//!
+//! The supply voltage and driver temperature are exposed through different inputs and parameters
+//! depending on the module family. `MonitoringChannels` maps them to their source, either
+//! declared by hand or looked up from the module type with `MonitoringChannels::for_module_type`.
+//! `TmcmModule::supply_voltage_mv` and `TmcmModule::driver_temperature_c` return
+//! `Error::Unsupported` if the quantity has no channel.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::health::MonitoringChannels;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1).with_monitoring(MonitoringChannels::for_module_type(1140));
+//!
+//!     println!("supply voltage: {} mV", module.supply_voltage_mv().unwrap());
+//!
+//!     let health = module.health(0).unwrap();
+//!     if health.overtemperature() || health.stallguard_error() {
+//!         println!("axis 0 needs attention: {:?}", health);
+//!     }
+//! }
+//! ```
+
+use units::{LinearScale, Scaled};
+use modules::tmcm::axis_parameters::{
+    ExtendedErrorFlags,
+    DriverErrorFlags,
+};
+
+/// The combined error flags of one axis.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorFlags {
+    extended: u8,
+    driver: u8,
+}
+
+impl ErrorFlags {
+    /// Combine the extended error flags and driver error flags of an axis
+    pub fn new(extended: ExtendedErrorFlags, driver: DriverErrorFlags) -> Self {
+        ErrorFlags{
+            extended: extended.into(),
+            driver: driver.into(),
+        }
+    }
+
+    /// The motor was stopped by stall detection
+    pub fn stallguard_error(&self) -> bool {
+        self.extended & 0x01 != 0
+    }
+
+    /// The encoder deviated too far from the actual position
+    pub fn deviation_error(&self) -> bool {
+        self.extended & 0x02 != 0
+    }
+
+    /// The driver has shut down because of overtemperature
+    pub fn overtemperature(&self) -> bool {
+        self.driver & 0x02 != 0
+    }
+
+    /// The driver is close to shutting down because of overtemperature
+    pub fn overtemperature_prewarning(&self) -> bool {
+        self.driver & 0x04 != 0
+    }
+
+    /// A short to ground was detected on one of the coils
+    pub fn short_to_ground(&self) -> bool {
+        self.driver & 0x18 != 0
+    }
+
+    /// An open load was detected on one of the coils
+    pub fn open_load(&self) -> bool {
+        self.driver & 0x60 != 0
+    }
+
+    /// Returns true if none of the error conditions above are present
+    ///
+    /// The stallGuard status and standstill bits of the driver are not errors and are ignored.
+    pub fn is_ok(&self) -> bool {
+        self.extended & 0x03 == 0 && self.driver & 0x7e == 0
+    }
+}
+
+/// Where a monitored value is read from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MonitoringSource {
+    /// An input read with `GIO`.
+    Input {
+        port_number: u8,
+        bank_number: u8,
+    },
+
+    /// A global parameter read with `GGP`.
+    GlobalParameter {
+        number: u8,
+        bank_number: u8,
+    },
+
+    /// An axis parameter read with `GAP`.
+    AxisParameter {
+        number: u8,
+        motor_number: u8,
+    },
+}
+
+/// A monitored value: its source and the conversion of the raw value.
+///
+/// The converted value is `raw * multiplier / divisor + offset`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MonitoringChannel {
+    source: MonitoringSource,
+    multiplier: i32,
+    divisor: i32,
+    offset: i32,
+}
+
+impl MonitoringChannel {
+    /// A channel using the raw value without conversion
+    pub fn new(source: MonitoringSource) -> Self {
+        MonitoringChannel{source, multiplier: 1, divisor: 1, offset: 0}
+    }
+
+    /// Scale the raw value by `multiplier / divisor`
+    pub fn scaled(mut self, multiplier: i32, divisor: i32) -> Self {
+        assert!(divisor != 0);
+        self.multiplier = multiplier;
+        self.divisor = divisor;
+        self
+    }
+
+    /// Add `offset` after scaling
+    pub fn offset(mut self, offset: i32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The source of the raw value
+    pub fn source(&self) -> MonitoringSource {
+        self.source
+    }
+
+    /// Convert a raw value read from the source
+    pub fn convert(&self, raw: i32) -> i32 {
+        (i64::from(raw) * i64::from(self.multiplier) / i64::from(self.divisor)) as i32 + self.offset
+    }
+
+    /// Convert a raw value read from the source without truncating the fractional part
+    pub fn convert_f32(&self, raw: i32) -> f32 {
+        self.scale().to_physical(raw)
+    }
+
+    /// The conversion of the raw value as a `LinearScale`
+    pub fn scale(&self) -> LinearScale {
+        LinearScale::new(self.multiplier as f32 / self.divisor as f32, self.offset as f32)
+    }
+
+    /// A raw value read from the source together with the conversion of this channel
+    pub fn value(&self, raw: i32) -> MonitoredValue {
+        MonitoredValue{channel: *self, raw}
+    }
+}
+
+/// A raw value read from a `MonitoringChannel`, keeping its fractional part through `Scaled`.
+///
+/// ```
+/// use tmcl::units::Scaled;
+/// use tmcl::modules::tmcm::health::{MonitoringChannel, MonitoringSource};
+///
+/// // A temperature sensor reporting 0.5 degrees celsius per unit, starting at -40 degrees.
+/// let channel = MonitoringChannel::new(MonitoringSource::Input{port_number: 9, bank_number: 1})
+///     .scaled(1, 2)
+///     .offset(-40);
+/// let value = channel.value(125);
+/// assert_eq!(value.converted(), 22);
+/// assert_eq!(value.physical(), 22.5);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MonitoredValue {
+    channel: MonitoringChannel,
+    raw: i32,
+}
+
+impl MonitoredValue {
+    /// The value converted by the channel, with the fractional part truncated
+    pub fn converted(&self) -> i32 {
+        self.channel.convert(self.raw)
+    }
+}
+
+impl Scaled for MonitoredValue {
+    fn scale(&self) -> LinearScale {
+        self.channel.scale()
+    }
+
+    fn raw(&self) -> i32 {
+        self.raw
+    }
+}
+
+/// The channels of the monitored values of a module.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MonitoringChannels {
+    supply_voltage: Option<MonitoringChannel>,
+    driver_temperature: Option<MonitoringChannel>,
+}
+
+impl MonitoringChannels {
+    /// No monitored values
+    pub fn new() -> Self {
+        MonitoringChannels{supply_voltage: None, driver_temperature: None}
+    }
+
+    /// The known channels of a module family, as reported by `GetFirmwareVersion::module_type`
+    ///
+    /// The TMCM-1140, TMCM-1160, TMCM-1161 and TMCM-6110 report the supply voltage in units of
+    /// 0.1 V on input 8 of bank 1. Unknown module types have no channels.
+    pub fn for_module_type(module_type: u16) -> Self {
+        match module_type {
+            1140 | 1160 | 1161 | 6110 => MonitoringChannels::new()
+                .with_supply_voltage(MonitoringChannel::new(MonitoringSource::Input{port_number: 8, bank_number: 1}).scaled(100, 1)),
+            _ => MonitoringChannels::new(),
+        }
+    }
+
+    /// The channel of the supply voltage, converting to millivolts
+    pub fn with_supply_voltage(mut self, channel: MonitoringChannel) -> Self {
+        self.supply_voltage = Some(channel);
+        self
+    }
+
+    /// The channel of the driver temperature, converting to degrees celsius
+    pub fn with_driver_temperature(mut self, channel: MonitoringChannel) -> Self {
+        self.driver_temperature = Some(channel);
+        self
+    }
+
+    /// The channel of the supply voltage
+    pub fn supply_voltage(&self) -> Option<MonitoringChannel> {
+        self.supply_voltage
+    }
+
+    /// The channel of the driver temperature
+    pub fn driver_temperature(&self) -> Option<MonitoringChannel> {
+        self.driver_temperature
+    }
+}
@@ -0,0 +1,149 @@
+//! Host supervision of a module with a heartbeat.
+//!
+//! A `Heartbeat` periodically writes a user variable or toggles a digital output. Running
+//! `program::watchdog_program` on the module (stand-alone mode) stops the motors when the user
+//! variable has not been written within the timeout, i.e. when the host or the bus died.
+//! Toggling an output is meant for external watchdog hardware, e.g. a safety relay.
+//!
+//! The heartbeat interval should be well below the timeout of the watchdog program.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::heartbeat::HeartbeatTarget;
+//! # #[cfg(feature = "std")]
+//! use tmcl::program::watchdog_program;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! # #[cfg(feature = "std")]
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!
+//!     // Stop motor 0 when user variable 55 was not written for 500 ms.
+//!     module.download_program(&watchdog_program(55, 50, &[0])).unwrap();
+//!
+//!     let mut heartbeat = module.heartbeat(HeartbeatTarget::UserVariable(55))
+//!         .with_interval(Duration::from_millis(100));
+//!     loop {
+//!         heartbeat.poll().unwrap();
+//!         std::thread::sleep(Duration::from_millis(10));
+//!     }
+//! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+
+use lib::ops::Deref;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+
+use modules::tmcm::TmcmModule;
+use modules::tmcm::io::OUTPUT_BANK;
+use modules::tmcm::instructions::SIO;
+
+/// The value written to the user variable on every beat.
+pub const HEARTBEAT_VALUE: i32 = 1;
+
+/// What a `Heartbeat` writes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HeartbeatTarget {
+    /// Write `HEARTBEAT_VALUE` to the user variable with the given index.
+    UserVariable(u8),
+
+    /// Toggle the digital output with the given port number.
+    Output(u8),
+}
+
+/// Periodically signals to a module that the host is alive, created with `TmcmModule::heartbeat`.
+#[derive(Debug)]
+pub struct Heartbeat<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a> {
+    module: &'a TmcmModule<'a, IF, Cell, T>,
+    target: HeartbeatTarget,
+    output_state: bool,
+    #[cfg(feature = "std")]
+    interval: Duration,
+    #[cfg(feature = "std")]
+    last_beat: Option<Instant>,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> Heartbeat<'a, IF, Cell, T> {
+    /// Create a heartbeat writing to `target` of `module`
+    pub fn new(module: &'a TmcmModule<'a, IF, Cell, T>, target: HeartbeatTarget) -> Self {
+        Heartbeat{
+            module,
+            target,
+            output_state: false,
+            #[cfg(feature = "std")]
+            interval: Duration::from_millis(100),
+            #[cfg(feature = "std")]
+            last_beat: None,
+        }
+    }
+
+    /// Set the interval between beats when using `poll`, the default is 100 ms
+    #[cfg(feature = "std")]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The target written on every beat
+    pub fn target(&self) -> HeartbeatTarget {
+        self.target
+    }
+
+    /// Write the heartbeat once
+    pub fn beat(&mut self) -> Result<(), Error<IF::Error>> {
+        match self.target {
+            HeartbeatTarget::UserVariable(index) => self.module.user_variables().set(index, HEARTBEAT_VALUE),
+            HeartbeatTarget::Output(port_number) => {
+                self.module.write_command(SIO::new(OUTPUT_BANK, port_number, !self.output_state))?;
+                self.output_state = !self.output_state;
+                Ok(())
+            },
+        }
+    }
+
+    /// Write the heartbeat if the interval has elapsed since the last beat
+    ///
+    /// Returns true if the heartbeat was written. Should be called more often than the interval.
+    #[cfg(feature = "std")]
+    pub fn poll(&mut self) -> Result<bool, Error<IF::Error>> {
+        if self.last_beat.is_some_and(|last_beat| last_beat.elapsed() < self.interval) {
+            return Ok(false);
+        }
+        self.beat()?;
+        self.last_beat = Some(Instant::now());
+        Ok(true)
+    }
+}
@@ -0,0 +1,248 @@
+//! A high-level homing routine built on top of the `RFS` instruction.
+//!
+//! Homing is a multi-step sequence: the reference search is configured through axis parameters
+//! 193-195, started with `RFS`, polled until it has finished and then (optionally) the position
+//! counter is zeroed at the found reference point.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::homing::Homing;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!
+//!     Homing::new(0)
+//!         .mode(ReferenceSearchMode::new(ReferenceSearchType::HomeSwitchNegative, false))
+//!         .search_speed(ReferenceSearchSpeed::new(500))
+//!         .switch_speed(ReferenceSwitchSpeed::new(50))
+//!         .run(&module, |progress| println!("{:?}", progress))
+//!         .unwrap();
+//! }
+//! ```
+
+use lib::cell::RefCell;
+use lib::ops::Deref;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use cancel::Cancel;
+use clock::{Clock, Delay, Poller};
+use Interface;
+
+use modules::tmcm::TmcmModule;
+use modules::tmcm::instructions::{
+    SAP,
+    RfsStart,
+    RfsStop,
+    RfsStatus,
+};
+use modules::tmcm::axis_parameters::{
+    ActualPosition,
+    ReferenceSearchMode,
+    ReferenceSearchSpeed,
+    ReferenceSwitchSpeed,
+};
+
+/// The progress of a running `Homing` routine.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HomingProgress {
+    /// The reference search parameters have been written to the module.
+    Configured,
+
+    /// The reference search has been started.
+    Started,
+
+    /// The reference search is still running, contains the number of times the status has been polled.
+    Searching(u32),
+
+    /// The reference search has finished.
+    Finished,
+
+    /// The position counter has been set to zero at the reference point.
+    Zeroed,
+}
+
+/// A builder for the homing (reference search) routine of a single axis.
+///
+/// Parameters that are not set are left as they are currently configured in the module.
+#[derive(Debug, PartialEq)]
+pub struct Homing {
+    motor_number: u8,
+    mode: Option<ReferenceSearchMode>,
+    search_speed: Option<ReferenceSearchSpeed>,
+    switch_speed: Option<ReferenceSwitchSpeed>,
+    zero_position: bool,
+}
+
+impl Homing {
+    /// Create a new homing routine for the given motor
+    ///
+    /// By default the position counter is zeroed when the reference search has finished.
+    pub fn new(motor_number: u8) -> Self {
+        Homing{
+            motor_number,
+            mode: None,
+            search_speed: None,
+            switch_speed: None,
+            zero_position: true,
+        }
+    }
+
+    /// Set the reference search mode, including the home switch polarity
+    pub fn mode(mut self, mode: ReferenceSearchMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Set the speed used when searching for the reference switch
+    pub fn search_speed(mut self, speed: ReferenceSearchSpeed) -> Self {
+        self.search_speed = Some(speed);
+        self
+    }
+
+    /// Set the speed used when positioning at the switching point
+    pub fn switch_speed(mut self, speed: ReferenceSwitchSpeed) -> Self {
+        self.switch_speed = Some(speed);
+        self
+    }
+
+    /// Choose if the position counter should be set to zero when the reference search has finished
+    pub fn zero_position(mut self, zero_position: bool) -> Self {
+        self.zero_position = zero_position;
+        self
+    }
+
+    /// Configure and run the reference search, blocking until it has finished.
+    ///
+    /// `progress` is called every time the routine advances and every time the status of the
+    /// reference search is polled. It is a good place to sleep between polls when running with `std`.
+    /// The interface is not borrowed while `progress` runs, so it may use the module itself.
+    pub fn run<'a, IF, Cell, T, F>(self, module: &'a TmcmModule<'a, IF, Cell, T>, progress: F) -> Result<(), Error<IF::Error>>
+    where
+        IF: Interface,
+        Cell: InteriorMut<'a, IF>,
+        T: Deref<Target=Cell>,
+        F: FnMut(HomingProgress),
+    {
+        self.run_cancellable(module, progress, || false)
+    }
+
+    /// Like `run`, but `cancel` is checked before every bus transaction.
+    ///
+    /// If the reference search has been started when cancelled, it is stopped with `RFS STOP`
+    /// before `Error::Cancelled` is returned.
+    pub fn run_cancellable<'a, IF, Cell, T, F, C>(self, module: &'a TmcmModule<'a, IF, Cell, T>, mut progress: F, mut cancel: C) -> Result<(), Error<IF::Error>>
+    where
+        IF: Interface,
+        Cell: InteriorMut<'a, IF>,
+        T: Deref<Target=Cell>,
+        F: FnMut(HomingProgress),
+        C: Cancel,
+    {
+        if let Some(mode) = self.mode {
+            check(&mut cancel)?;
+            module.write_command(SAP::new(self.motor_number, mode))?;
+        }
+        if let Some(speed) = self.search_speed {
+            check(&mut cancel)?;
+            module.write_command(SAP::new(self.motor_number, speed))?;
+        }
+        if let Some(speed) = self.switch_speed {
+            check(&mut cancel)?;
+            module.write_command(SAP::new(self.motor_number, speed))?;
+        }
+        progress(HomingProgress::Configured);
+
+        check(&mut cancel)?;
+        module.write_command(RfsStart::new(self.motor_number))?;
+        progress(HomingProgress::Started);
+
+        let mut polls = 0;
+        loop {
+            if cancel.is_cancelled() {
+                module.write_command(RfsStop::new(self.motor_number))?;
+                return Err(Error::Cancelled);
+            }
+            if !module.write_command(RfsStatus::new(self.motor_number))?.is_searching() {
+                break;
+            }
+            polls += 1;
+            progress(HomingProgress::Searching(polls));
+        }
+        progress(HomingProgress::Finished);
+
+        if self.zero_position {
+            check(&mut cancel)?;
+            module.write_command(SAP::new(self.motor_number, ActualPosition::new(0)))?;
+            progress(HomingProgress::Zeroed);
+        }
+
+        Ok(())
+    }
+
+    /// Like `run`, but the reference search is polled at the pace of a `Poller`.
+    ///
+    /// If the poller's timeout passes before homing has finished, a started reference search is
+    /// stopped with `RFS STOP` and `Error::Timeout` is returned.
+    pub fn run_with<'a, IF, Cell, T, F, C, D>(self, module: &'a TmcmModule<'a, IF, Cell, T>, mut progress: F, poller: &mut Poller<C, D>) -> Result<(), Error<IF::Error>>
+    where
+        IF: Interface,
+        Cell: InteriorMut<'a, IF>,
+        T: Deref<Target=Cell>,
+        F: FnMut(HomingProgress),
+        C: Clock,
+        D: Delay,
+    {
+        poller.start();
+        let poller = RefCell::new(poller);
+        let result = self.run_cancellable(
+            module,
+            |step| {
+                if let HomingProgress::Searching(_) = step {
+                    poller.borrow_mut().pause();
+                }
+                progress(step);
+            },
+            || poller.borrow_mut().expired(),
+        );
+        match result {
+            Err(Error::Cancelled) => Err(Error::Timeout),
+            result => result,
+        }
+    }
+}
+
+fn check<E, C: Cancel>(cancel: &mut C) -> Result<(), Error<E>> {
+    if cancel.is_cancelled() {
+        Err(Error::Cancelled)
+    } else {
+        Ok(())
+    }
+}
@@ -9,12 +9,36 @@ pub use instructions::{
     GAP,
     STAP,
     RSAP,
-    RFS,
+    SGP,
+    GGP,
+    STGP,
+    RSGP,
+    RfsStart,
+    RfsStop,
+    RfsStatus,
     SIO,
     GIO,
+    SCO,
+    GCO,
+    CCO,
+    JC,
+    JA,
+    CSUB,
+    RSUB,
+    WAIT,
+    STOP,
     CALC,
+    AAP,
+    AGP,
+    GetFirmwareVersion,
+    RequestTargetPositionReachedEvent,
+    RawInstruction,
     MoveOperation,
-    ReferenceSearchAction,
+    ReferenceSearchStatus,
+    FirmwareVersion,
+    WaitCondition,
+    Condition,
+    COORDINATE_EEPROM_MOTOR_NUMBER,
 };
 
 use modules::tmcm::TmcmInstruction;
@@ -22,6 +46,8 @@ use modules::tmcm::TmcmInstruction;
 use modules::tmcm::{
     WriteableTmcmAxisParameter,
     ReadableTmcmAxisParameter,
+    WriteableTmcmGlobalParameter,
+    ReadableTmcmGlobalParameter,
 };
 
 
@@ -33,7 +59,26 @@ impl<T: WriteableTmcmAxisParameter> TmcmInstruction for SAP<T> {}
 impl<T: ReadableTmcmAxisParameter> TmcmInstruction for GAP<T> {}
 impl<T: WriteableTmcmAxisParameter> TmcmInstruction for STAP<T> {}
 impl<T: WriteableTmcmAxisParameter> TmcmInstruction for RSAP<T> {}
-impl TmcmInstruction for RFS {}
+impl<T: WriteableTmcmGlobalParameter> TmcmInstruction for SGP<T> {}
+impl<T: ReadableTmcmGlobalParameter> TmcmInstruction for GGP<T> {}
+impl<T: WriteableTmcmGlobalParameter> TmcmInstruction for STGP<T> {}
+impl<T: WriteableTmcmGlobalParameter> TmcmInstruction for RSGP<T> {}
+impl TmcmInstruction for RfsStart {}
+impl TmcmInstruction for RfsStop {}
+impl TmcmInstruction for RfsStatus {}
 impl TmcmInstruction for SIO {}
 impl TmcmInstruction for GIO {}
+impl TmcmInstruction for SCO {}
+impl TmcmInstruction for GCO {}
+impl TmcmInstruction for CCO {}
+impl TmcmInstruction for JC {}
+impl TmcmInstruction for JA {}
+impl TmcmInstruction for CSUB {}
+impl TmcmInstruction for RSUB {}
+impl TmcmInstruction for WAIT {}
+impl TmcmInstruction for STOP {}
+impl TmcmInstruction for GetFirmwareVersion {}
+impl TmcmInstruction for RequestTargetPositionReachedEvent {}
 impl TmcmInstruction for CALC {}
+impl<T: WriteableTmcmAxisParameter> TmcmInstruction for AAP<T> {}
+impl<T: WriteableTmcmGlobalParameter> TmcmInstruction for AGP<T> {}
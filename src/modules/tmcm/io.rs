@@ -0,0 +1,64 @@
+//! Digital input/output helpers.
+
+/// The bank number of the digital inputs used with `GIO`.
+pub const INPUT_BANK: u8 = 0;
+
+/// The bank number of the digital outputs used with `SIO` and `GIO`.
+pub const OUTPUT_BANK: u8 = 2;
+
+/// A snapshot of the digital outputs of a module.
+///
+/// Only the ports that have been written or read are known, every port number is supported.
+///
+/// ```
+/// use tmcl::modules::tmcm::io::IoState;
+///
+/// let mut state = IoState::new();
+/// state.set_output(40, true);
+/// assert_eq!(state.output(40), Some(true));
+/// assert_eq!(state.output(8), None);
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct IoState {
+    states: [u32; 8],
+    known: [u32; 8],
+}
+
+impl IoState {
+    /// Create a snapshot where no ports are known
+    pub fn new() -> Self {
+        IoState{states: [0; 8], known: [0; 8]}
+    }
+
+    /// The state of an output port, `None` if the state is not known
+    pub fn output(&self, port_number: u8) -> Option<bool> {
+        let (word, mask) = bit(port_number);
+        if self.known[word] & mask != 0 {
+            Some(self.states[word] & mask != 0)
+        } else {
+            None
+        }
+    }
+
+    /// Record the state of an output port
+    pub fn set_output(&mut self, port_number: u8, state: bool) {
+        let (word, mask) = bit(port_number);
+        self.known[word] |= mask;
+        if state {
+            self.states[word] |= mask;
+        } else {
+            self.states[word] &= !mask;
+        }
+    }
+
+    /// Forget the state of an output port
+    pub fn invalidate(&mut self, port_number: u8) {
+        let (word, mask) = bit(port_number);
+        self.known[word] &= !mask;
+    }
+}
+
+/// The index of the word holding a port and the mask of its bit
+fn bit(port_number: u8) -> (usize, u32) {
+    (usize::from(port_number / 32), 1 << (port_number % 32))
+}
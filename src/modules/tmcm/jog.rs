@@ -0,0 +1,238 @@
+//! Jogging an axis from a stream of velocity setpoints.
+//!
+//! `Jog` turns setpoints from e.g. a gamepad stick or a GUI slider into `ROR`/`ROL` commands.
+//! Setpoints usually arrive much faster than they need to be sent to the module, so commands are
+//! rate limited to one per interval and only sent when the velocity changes. Setpoints are clamped
+//! to a maximum velocity.
+//!
+//! A jog is a deadman control: if no setpoint arrives within the deadman timeout, e.g. because
+//! the gamepad was disconnected or the GUI hung, the motor is stopped on the next `update`.
+//! Stopping, whether by a zero setpoint, `release` or the deadman, is never rate limited.
+//!
+//! For modules whose firmware does not ramp the velocity in velocity mode, `with_acceleration`
+//! ramps the commanded velocity on the host instead: every command changes the velocity by at
+//! most the acceleration times the time since the previous command, so `update` must be called
+//! at least once per interval until the setpoint is reached. A zero setpoint then decelerates
+//! the motor too, and a reversal decelerates to a stop before accelerating in the other
+//! direction. `release` and the deadman still stop the motor immediately.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::jog::Jog;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! # fn read_stick() -> Option<f32> {unimplemented!()}
+//! #
+//! # #[cfg(feature = "std")]
+//! fn main() {
+//!     use tmcl::clock::StdClock;
+//!
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!
+//!     let mut jog = Jog::new(module.axis(0), StdClock::new())
+//!         .with_max_velocity(1000)
+//!         .with_interval(Duration::from_millis(50))
+//!         .with_deadman_timeout(Duration::from_millis(200))
+//!         .with_acceleration(4000);
+//!
+//!     loop {
+//!         // `read_stick` returns the stick position from -1.0 to 1.0 when it has moved.
+//!         if let Some(position) = read_stick() {
+//!             jog.set((position * 1000.0) as i32).unwrap();
+//!         }
+//!         jog.update().unwrap();
+//!         std::thread::sleep(Duration::from_millis(10));
+//!     }
+//! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use clock::Clock;
+
+use modules::tmcm::axis::Axis;
+
+/// Rate limited, deadman guarded velocity control of an axis.
+#[derive(Debug)]
+pub struct Jog<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a, C: Clock> {
+    axis: Axis<'a, IF, Cell, T>,
+    clock: C,
+    max_velocity: u32,
+    interval: Duration,
+    deadman_timeout: Duration,
+    acceleration: Option<u32>,
+    setpoint: i32,
+    setpoint_time: Option<Duration>,
+    commanded: Option<i32>,
+    command_time: Option<Duration>,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>, C: Clock> Jog<'a, IF, Cell, T, C> {
+    /// Jog `axis`, with the time taken from `clock`
+    ///
+    /// By default the velocity is limited to 2047, commands are sent at most every 100 ms and the
+    /// deadman timeout is 500 ms.
+    pub fn new(axis: Axis<'a, IF, Cell, T>, clock: C) -> Self {
+        Jog{
+            axis,
+            clock,
+            max_velocity: 2047,
+            interval: Duration::from_millis(100),
+            deadman_timeout: Duration::from_millis(500),
+            acceleration: None,
+            setpoint: 0,
+            setpoint_time: None,
+            commanded: None,
+            command_time: None,
+        }
+    }
+
+    /// Clamp setpoints to at most `max_velocity` in either direction
+    pub fn with_max_velocity(mut self, max_velocity: u32) -> Self {
+        self.max_velocity = max_velocity;
+        self
+    }
+
+    /// Send a new velocity at most once per `interval`
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Stop the motor when no setpoint has arrived for `timeout`
+    pub fn with_deadman_timeout(mut self, timeout: Duration) -> Self {
+        self.deadman_timeout = timeout;
+        self
+    }
+
+    /// Ramp the commanded velocity on the host, changing it by at most `acceleration` per second
+    pub fn with_acceleration(mut self, acceleration: u32) -> Self {
+        self.acceleration = Some(acceleration);
+        self
+    }
+
+    /// The jogged axis
+    pub fn axis(&self) -> Axis<'a, IF, Cell, T> {
+        self.axis
+    }
+
+    /// The last setpoint, clamped to the maximum velocity
+    pub fn setpoint(&self) -> i32 {
+        self.setpoint
+    }
+
+    /// The velocity last sent to the module, `None` before the first command
+    pub fn commanded(&self) -> Option<i32> {
+        self.commanded
+    }
+
+    /// Set a new velocity setpoint, right if positive and left if negative, and `update`
+    pub fn set(&mut self, velocity: i32) -> Result<(), Error<IF::Error>> {
+        let max_velocity = self.max_velocity.min(i32::MAX as u32) as i32;
+        self.setpoint = velocity.clamp(-max_velocity, max_velocity);
+        self.setpoint_time = Some(self.clock.now());
+        self.update()
+    }
+
+    /// Stop the motor immediately, until the next setpoint arrives
+    pub fn release(&mut self) -> Result<(), Error<IF::Error>> {
+        self.setpoint = 0;
+        self.setpoint_time = None;
+        self.command(0)
+    }
+
+    /// Send the setpoint to the module if it changed and the interval has passed, or stop the
+    /// motor if the deadman timeout has passed
+    ///
+    /// Must be called regularly, also when no new setpoints arrive.
+    pub fn update(&mut self) -> Result<(), Error<IF::Error>> {
+        let now = self.clock.now();
+        let alive = match self.setpoint_time {
+            Some(time) => now.checked_sub(time).is_none_or(|elapsed| elapsed <= self.deadman_timeout),
+            None => false,
+        };
+        let velocity = if alive { self.setpoint } else { 0 };
+        if self.commanded == Some(velocity) || (self.commanded.is_none() && velocity == 0) {
+            return Ok(());
+        }
+        let since_command = self.command_time.map(|time| now.checked_sub(time).unwrap_or_default());
+        let ramped = alive && self.acceleration.is_some();
+        if (velocity != 0 || ramped) && since_command.is_some_and(|elapsed| elapsed < self.interval) {
+            return Ok(());
+        }
+        match self.acceleration {
+            Some(acceleration) if alive => {
+                let elapsed = since_command.unwrap_or(self.interval);
+                self.command(ramp(self.commanded.unwrap_or(0), velocity, acceleration, elapsed))
+            },
+            _ => self.command(velocity),
+        }
+    }
+
+    /// Stop jogging, returning the axis and the clock
+    ///
+    /// The motor is not stopped, call `release` first.
+    pub fn into_inner(self) -> (Axis<'a, IF, Cell, T>, C) {
+        (self.axis, self.clock)
+    }
+
+    fn command(&mut self, velocity: i32) -> Result<(), Error<IF::Error>> {
+        if velocity == 0 {
+            self.axis.stop()?;
+        } else {
+            self.axis.rotate(velocity)?;
+        }
+        self.commanded = Some(velocity);
+        self.command_time = Some(self.clock.now());
+        Ok(())
+    }
+}
+
+/// The velocity after moving from `current` towards `target` with `acceleration` for `elapsed`
+///
+/// The velocity changes by at least 1, and stops at 0 when the direction reverses.
+fn ramp(current: i32, target: i32, acceleration: u32, elapsed: Duration) -> i32 {
+    let step = (u128::from(acceleration) * elapsed.as_micros() / 1_000_000).clamp(1, i32::MAX as u128) as i32;
+    let next = if target > current {
+        current.saturating_add(step).min(target)
+    } else {
+        current.saturating_sub(step).max(target)
+    };
+    if (current > 0 && next < 0) || (current < 0 && next > 0) {
+        0
+    } else {
+        next
+    }
+}
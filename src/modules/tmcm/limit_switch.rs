@@ -0,0 +1,171 @@
+//! Events for limit switch transitions.
+//!
+//! A `LimitSwitchMonitor` reads `LeftLimitSwitchState` and `RightLimitSwitchState` of an axis
+//! every time it is polled and reports a `LimitSwitchEvent` when a switch is pressed or released,
+//! so applications can react to manual end-stop hits without edge detection of their own. A new
+//! state is only reported once it was read unchanged for the debounce time, which filters out
+//! contact bounce and noise on long switch cables. The state read on the first poll is taken as
+//! the initial state and not reported.
+//!
+//! ```
+//! # #[cfg(feature = "std")]
+//! fn main() {
+//!     use std::cell::RefCell;
+//!     use std::time::Duration;
+//!     use tmcl::clock::MockClock;
+//!     use tmcl::opcodes::OPCODE_GAP;
+//!     use tmcl::testing::{expect_command, Ordering, Script};
+//!     use tmcl::modules::tmcm::TmcmModule as Module;
+//!     use tmcl::modules::tmcm::limit_switch::{LimitSwitchEvent, LimitSwitchMonitor, Side};
+//!
+//!     let mut script = Script::new(Ordering::Strict);
+//!     // The right switch is released, bounces and is pressed.
+//!     for &right in &[0, 1, 0, 1, 1, 1] {
+//!         script.push(expect_command(OPCODE_GAP).type_number(11..=11).reply_with_value(0));
+//!         script.push(expect_command(OPCODE_GAP).type_number(10..=10).reply_with_value(right));
+//!     }
+//!     let interface = RefCell::new(script);
+//!     let module = Module::new(&interface, 1);
+//!
+//!     let clock = MockClock::new();
+//!     let mut monitor = LimitSwitchMonitor::new(module.axis(0), &clock).with_debounce(Duration::from_millis(20));
+//!     let mut events = Vec::new();
+//!     for _ in 0..6 {
+//!         monitor.poll(|event| events.push(event)).unwrap();
+//!         clock.advance(Duration::from_millis(10));
+//!     }
+//!     assert_eq!(events, [LimitSwitchEvent{motor_number: 0, side: Side::Right, pressed: true}]);
+//! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use clock::Clock;
+
+use modules::tmcm::axis::Axis;
+use modules::tmcm::axis_parameters::{
+    LeftLimitSwitchState,
+    RightLimitSwitchState,
+};
+
+/// The side of an axis a limit switch is on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Side {
+    /// The left, or reference, switch.
+    Left,
+
+    /// The right switch.
+    Right,
+}
+
+/// A limit switch was pressed or released.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LimitSwitchEvent {
+    pub motor_number: u8,
+    pub side: Side,
+
+    /// True if the switch was pressed, false if it was released
+    pub pressed: bool,
+}
+
+/// The debounced state of one switch.
+#[derive(Debug, Clone, Copy, Default)]
+struct Debouncer {
+    stable: Option<bool>,
+    changed_at: Option<Duration>,
+}
+
+impl Debouncer {
+    /// Update with a reading at `now`, returns the new state once it has been stable for `debounce`
+    fn update(&mut self, state: bool, now: Duration, debounce: Duration) -> Option<bool> {
+        match self.stable {
+            None => {
+                self.stable = Some(state);
+                None
+            },
+            Some(stable) if stable == state => {
+                self.changed_at = None;
+                None
+            },
+            Some(_) => {
+                let changed_at = *self.changed_at.get_or_insert(now);
+                if now.checked_sub(changed_at).unwrap_or_default() >= debounce {
+                    self.stable = Some(state);
+                    self.changed_at = None;
+                    Some(state)
+                } else {
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Reports debounced transitions of the limit switches of an axis.
+#[derive(Debug)]
+pub struct LimitSwitchMonitor<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a, C: Clock> {
+    axis: Axis<'a, IF, Cell, T>,
+    clock: C,
+    debounce: Duration,
+    left: Debouncer,
+    right: Debouncer,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>, C: Clock> LimitSwitchMonitor<'a, IF, Cell, T, C> {
+    /// Monitor the limit switches of `axis`, reporting every change without debouncing
+    pub fn new(axis: Axis<'a, IF, Cell, T>, clock: C) -> Self {
+        LimitSwitchMonitor{
+            axis,
+            clock,
+            debounce: Duration::from_secs(0),
+            left: Debouncer::default(),
+            right: Debouncer::default(),
+        }
+    }
+
+    /// Only report a new state once it was read unchanged for `debounce`
+    ///
+    /// The debounce time should span a few polls, a state that only lasted between two polls is
+    /// never seen.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// The monitored axis
+    pub fn axis(&self) -> Axis<'a, IF, Cell, T> {
+        self.axis
+    }
+
+    /// The debounced state of the switch on `side`, `None` before the first poll
+    pub fn pressed(&self, side: Side) -> Option<bool> {
+        match side {
+            Side::Left => self.left.stable,
+            Side::Right => self.right.stable,
+        }
+    }
+
+    /// Read both switches and call `callback` for every debounced transition, left before right
+    pub fn poll<F: FnMut(LimitSwitchEvent)>(&mut self, mut callback: F) -> Result<(), Error<IF::Error>> {
+        let left = bool::from(self.axis.get::<LeftLimitSwitchState>()?);
+        let right = bool::from(self.axis.get::<RightLimitSwitchState>()?);
+        let now = self.clock.now();
+        let motor_number = self.axis.motor_number();
+        if let Some(pressed) = self.left.update(left, now, self.debounce) {
+            callback(LimitSwitchEvent{motor_number, side: Side::Left, pressed});
+        }
+        if let Some(pressed) = self.right.update(right, now, self.debounce) {
+            callback(LimitSwitchEvent{motor_number, side: Side::Right, pressed});
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+//! A task per module, serving commands from a mailbox.
+//!
+//! `ModuleTask::spawn` starts a thread that owns a module and executes the commands sent to it
+//! through `ModuleHandle`s one at a time. Handles are cheap to clone and can be used from any
+//! thread without a mutex in sight; the task serializes access to the module, and a `SharedBus`
+//! can serialize access between the tasks of the modules on the same bus.
+//!
+//! Every request returns a `PendingReply`, a one-shot channel for the reply. It can be waited
+//! for (with or without a timeout) or awaited, as it implements `Future`. Dropping it, or a
+//! timeout expiring, cancels the request if the task has not started executing it yet.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! use tmcl::shared::SharedBus;
+//! use tmcl::modules::tmcm::mailbox::ModuleTask;
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let bus = SharedBus::new(MyInterface::new());
+//!
+//!     let tasks: Vec<_> = (1..4).map(|address| {
+//!         let bus = Arc::new(bus.clone());
+//!         ModuleTask::spawn(move |inbox| inbox.serve(&Module::new(bus, address)))
+//!     }).collect();
+//!
+//!     let pending: Vec<_> = tasks.iter()
+//!         .map(|task| task.handle().request(GAP::<ActualPosition>::new(0)))
+//!         .collect();
+//!     for reply in pending {
+//!         println!("{:?}", reply.wait_timeout(Duration::from_millis(100)));
+//!     }
+//! }
+//! ```
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Encode;
+use Interface;
+use Operand;
+use Return;
+use instructions::{DirectInstruction, RawInstruction};
+use modules::tmcm::{TmcmInstruction, TmcmModule};
+
+type RawReply<E> = Result<Operand, Error<E>>;
+
+#[derive(Debug)]
+struct SlotState<E> {
+    reply: Option<RawReply<E>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+}
+
+/// The one-shot channel shared by a request and its `PendingReply`.
+///
+/// No user code runs while the state is locked, so the lock is never poisoned.
+#[derive(Debug)]
+struct Slot<E> {
+    state: Mutex<SlotState<E>>,
+    ready: Condvar,
+}
+
+impl<E> Slot<E> {
+    fn new() -> Self {
+        Slot{
+            state: Mutex::new(SlotState{reply: None, waker: None, cancelled: false}),
+            ready: Condvar::new(),
+        }
+    }
+
+    /// Store the reply, unless the slot has already been completed
+    fn complete(&self, reply: RawReply<E>) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            if state.reply.is_some() {
+                return;
+            }
+            state.reply = Some(reply);
+            state.waker.take()
+        };
+        self.ready.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn cancel(&self) {
+        self.state.lock().unwrap().cancelled = true;
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+}
+
+#[derive(Debug)]
+struct Request<E> {
+    encoded: [u8; 7],
+    slot: Arc<Slot<E>>,
+}
+
+/// A request dropped without a reply, e.g. still queued when the inbox is dropped or while the
+/// task is unwinding, completes with `Error::Cancelled` so waiting for it does not block forever.
+impl<E> Drop for Request<E> {
+    fn drop(&mut self) {
+        self.slot.complete(Err(Error::Cancelled));
+    }
+}
+
+/// The receiving end of the mailbox, given to the closure running in a `ModuleTask`.
+#[derive(Debug)]
+pub struct Inbox<E> {
+    requests: Receiver<Request<E>>,
+}
+
+impl<E> Inbox<E> {
+    /// Execute the requests on `module` until every `ModuleHandle` has been dropped
+    ///
+    /// Requests that were cancelled before their turn are skipped without being transmitted.
+    pub fn serve<'a, IF, Cell, T>(self, module: &'a TmcmModule<'a, IF, Cell, T>)
+    where
+        IF: Interface<Error=E>,
+        Cell: InteriorMut<'a, IF>,
+        T: Deref<Target=Cell>,
+    {
+        for request in self.requests {
+            if request.slot.is_cancelled() {
+                continue;
+            }
+            let encoded = request.encoded;
+            let value = Operand::from_wire([encoded[3], encoded[4], encoded[5], encoded[6]]).to_i32();
+            request.slot.complete(module.write_encoded(RawInstruction::new(encoded[0], encoded[1], encoded[2], value)));
+        }
+    }
+}
+
+/// Sends requests to the mailbox of a `ModuleTask`.
+#[derive(Debug)]
+pub struct ModuleHandle<E> {
+    requests: Sender<Request<E>>,
+}
+
+impl<E> ModuleHandle<E> {
+    /// Queue `instruction` for execution by the task
+    ///
+    /// If the task has stopped, the returned reply resolves to `Error::Cancelled`.
+    pub fn request<Instruction: TmcmInstruction + DirectInstruction>(&self, instruction: Instruction) -> PendingReply<Instruction::Return, E> {
+        let slot = Arc::new(Slot::new());
+        // A request that can not be sent is dropped, completing it with `Error::Cancelled`.
+        let _ = self.requests.send(Request{encoded: instruction.encode(), slot: slot.clone()});
+        PendingReply{slot, pd: PhantomData}
+    }
+
+    /// Queue `instruction` and block until it has been executed
+    pub fn write_command<Instruction: TmcmInstruction + DirectInstruction>(&self, instruction: Instruction) -> Result<Instruction::Return, Error<E>> {
+        self.request(instruction).wait()
+    }
+}
+
+impl<E> Clone for ModuleHandle<E> {
+    fn clone(&self) -> Self {
+        ModuleHandle{requests: self.requests.clone()}
+    }
+}
+
+/// The reply to a request, resolved once the task has executed it.
+#[derive(Debug)]
+pub struct PendingReply<R: Return, E> {
+    slot: Arc<Slot<E>>,
+    pd: PhantomData<fn() -> R>,
+}
+
+impl<R: Return, E> PendingReply<R, E> {
+    /// Block until the reply has arrived
+    pub fn wait(self) -> Result<R, Error<E>> {
+        let mut state = self.slot.state.lock().unwrap();
+        loop {
+            if let Some(reply) = state.reply.take() {
+                return decode(reply);
+            }
+            state = self.slot.ready.wait(state).unwrap();
+        }
+    }
+
+    /// Block until the reply has arrived, or return `Error::Timeout` after `timeout`
+    ///
+    /// The request is cancelled on timeout. If the task has already started executing it, the
+    /// command still completes on the module.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<R, Error<E>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.slot.state.lock().unwrap();
+        loop {
+            if let Some(reply) = state.reply.take() {
+                return decode(reply);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                state.cancelled = true;
+                return Err(Error::Timeout);
+            }
+            state = self.slot.ready.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Cancel the request if the task has not started executing it yet
+    pub fn cancel(self) {}
+
+    /// Returns true if the reply has arrived
+    pub fn is_ready(&self) -> bool {
+        self.slot.state.lock().unwrap().reply.is_some()
+    }
+}
+
+impl<R: Return, E> Future for PendingReply<R, E> {
+    type Output = Result<R, Error<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.slot.state.lock().unwrap();
+        match state.reply.take() {
+            Some(reply) => Poll::Ready(decode(reply)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<R: Return, E> Drop for PendingReply<R, E> {
+    fn drop(&mut self) {
+        self.slot.cancel();
+    }
+}
+
+fn decode<R: Return, E>(reply: RawReply<E>) -> Result<R, Error<E>> {
+    reply.and_then(|operand| R::try_from_operand(operand).or(Err(Error::InvalidReply)))
+}
+
+/// A thread owning a module and serving the requests sent through its handles.
+#[derive(Debug)]
+pub struct ModuleTask<E> {
+    handle: ModuleHandle<E>,
+    thread: JoinHandle<()>,
+}
+
+impl<E: Send + 'static> ModuleTask<E> {
+    /// Spawn a thread running `task` with the inbox of a new mailbox
+    ///
+    /// The module is created inside `task`, typically as `inbox.serve(&TmcmModule::new(bus, address))`,
+    /// so it does not have to be `Send` itself, only the interface handle moved into it.
+    ///
+    /// Requests still queued when `task` returns or panics without serving them resolve to
+    /// `Error::Cancelled`.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use tmcl::Error;
+    /// use tmcl::modules::tmcm::mailbox::ModuleTask;
+    /// use tmcl::modules::tmcm::instructions::GAP;
+    /// use tmcl::modules::tmcm::axis_parameters::ActualPosition;
+    ///
+    /// let task = ModuleTask::<()>::spawn(|inbox| {
+    ///     thread::sleep(Duration::from_millis(50));
+    ///     drop(inbox);
+    /// });
+    /// let reply = task.handle().request(GAP::<ActualPosition>::new(0));
+    /// assert!(matches!(reply.wait(), Err(Error::Cancelled)));
+    /// ```
+    pub fn spawn<F: FnOnce(Inbox<E>) + Send + 'static>(task: F) -> Self {
+        let (requests, inbox) = mpsc::channel();
+        let thread = thread::spawn(move || task(Inbox{requests: inbox}));
+        ModuleTask{handle: ModuleHandle{requests}, thread}
+    }
+}
+
+impl<E> ModuleTask<E> {
+    /// A handle sending requests to this task
+    pub fn handle(&self) -> ModuleHandle<E> {
+        self.handle.clone()
+    }
+
+    /// Stop accepting requests through this task and wait for the thread to finish
+    ///
+    /// The thread finishes once the queued requests are served and all other handles are dropped.
+    pub fn join(self) -> thread::Result<()> {
+        drop(self.handle);
+        self.thread.join()
+    }
+}
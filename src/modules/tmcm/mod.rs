@@ -2,54 +2,1144 @@
 
 use lib::ops::Deref;
 use lib::marker::PhantomData;
+use lib::cell;
+use lib::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 pub mod instructions;
 pub mod axis_parameters;
+pub mod global_parameters;
+pub mod homing;
+pub mod jog;
+pub mod limit_switch;
+pub mod io;
+pub mod audit;
+pub mod axis;
+pub mod backlash;
+pub mod brake;
+pub mod cache;
+pub mod persistence;
+pub mod capabilities;
+pub mod settings;
+pub mod health;
+pub mod user_variables;
+pub mod ramp;
+pub mod heartbeat;
+pub mod stats;
+pub mod streaming;
+pub mod subscription;
+pub mod parameter_info;
+pub mod tick_timer;
+pub mod pattern;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "std")]
+pub mod diagnostics;
+#[cfg(feature = "std")]
+pub mod mailbox;
 
 use interior_mut::InteriorMut;
 
 use Error;
+use clock::Clock;
+#[cfg(any(feature = "std", feature = "heapless"))]
+use cancel::Cancel;
 use Instruction;
+use Encode;
 use instructions::DirectInstruction;
+use instructions::RawInstruction;
 use Interface;
+use remote::RemoteRequest;
 use Return;
-use Status;
+use FromOperand;
+use Operand;
+use IntoResult;
 use Command;
+use opcodes::{
+    OPCODE_SAP,
+    OPCODE_GAP,
+    OPCODE_GGP,
+    OPCODE_STGP,
+    OPCODE_RSGP,
+    OPCODE_SIO,
+    OPCODE_GIO,
+    OPCODE_ENTER_DOWNLOAD_MODE,
+    OPCODE_EXIT_DOWNLOAD_MODE,
+};
+use CommandContext;
+use Reply;
+use ErrStatus;
 use AxisParameter;
 use ReadableAxisParameter;
 use WriteableAxisParameter;
+use GlobalParameter;
+use ReadableGlobalParameter;
+use WriteableGlobalParameter;
 
+use self::instructions::{
+    SAP,
+    MST,
+    SIO,
+    GIO,
+    GAP,
+    SGP,
+    GGP,
+};
+use self::global_parameters::{
+    SerialSecondaryAddress,
+    CanSecondaryId,
+    TickTimer,
+};
+use self::axis_parameters::{
+    SoftStopFlag,
+    MaximumPositioningSpeed,
+    MaximumAcceleration,
+    PulseDivisor,
+    RampDivisor,
+    PositionCaptureMode,
+    CapturedPosition,
+    ExtendedErrorFlags,
+    DriverErrorFlags,
+};
+use self::audit::AuditTrail;
+use self::axis::Axis;
+use self::capabilities::Capabilities;
+use self::settings::ModuleSettings;
+use self::user_variables::UserVariables;
+use self::heartbeat::{
+    Heartbeat,
+    HeartbeatTarget,
+};
+use self::health::{
+    ErrorFlags,
+    MonitoringChannel,
+    MonitoringChannels,
+    MonitoringSource,
+    MonitoredValue,
+};
+use self::ramp::validate_acceleration;
+use self::stats::ModuleStats;
+use self::parameter_info::{
+    ValueOutOfSpec,
+    axis_parameter,
+};
+use self::tick_timer::TickSample;
+use self::persistence::{
+    PersistenceToken,
+    writes_eeprom,
+};
+use self::io::{
+    IoState,
+    OUTPUT_BANK,
+};
+#[cfg(any(feature = "std", feature = "heapless"))]
+use program::TmclProgram;
+#[cfg(feature = "std")]
+use self::diagnostics::{
+    AxisParameterDump,
+    dumped_axis_parameters,
+};
+
+/// The instruction number for entering download mode, where received instructions are stored instead of executed.
+pub const ENTER_DOWNLOAD_MODE: u8 = OPCODE_ENTER_DOWNLOAD_MODE;
+
+/// The instruction number for leaving download mode.
+pub const EXIT_DOWNLOAD_MODE: u8 = OPCODE_EXIT_DOWNLOAD_MODE;
+
+
+/// How the reply address of replies from a module is validated.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReplyAddressCheck {
+    /// The reply address is not checked.
+    Disabled,
+
+    /// Replies must have the given reply address (the host address, usually 2).
+    Expect(u8),
+}
+
+/// How often a command is retransmitted when it fails.
+///
+/// Commands are retried when no reply was received in time (`Error::Timeout`) or when the module
+/// reported a wrong checksum. Note that a command may have been executed even if its reply was
+/// lost, so retrying relative moves (e.g. `MVP` with `MoveOperation::Relative`) can move too far.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RetryPolicy {
+    retries: u8,
+}
+
+impl RetryPolicy {
+    /// Never retry a command
+    pub fn none() -> Self {
+        RetryPolicy{retries: 0}
+    }
+
+    /// Retry a command at most `retries` times
+    pub fn new(retries: u8) -> Self {
+        RetryPolicy{retries}
+    }
+
+    /// The maximum number of retries of a command
+    pub fn retries(&self) -> u8 {
+        self.retries
+    }
+
+    fn applies_to<E>(&self, error: &Error<E>) -> bool {
+        matches!(*error, Error::Timeout | Error::ProtocolError(ErrStatus::WrongChecksum, _))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
+
+/// An event reported to the observer of a module.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ModuleEvent<'r> {
+    /// A command was transmitted, contains the command in binary command format.
+    Transmitted([u8; 9]),
+
+    /// A reply was received.
+    Received(&'r Reply),
+
+    /// A reply to another command was received and discarded, see `TmcmModule::with_strict_reply_ordering`.
+    Discarded(&'r Reply),
+
+    /// The command failed and is retransmitted, contains the number of the retry (starting at 1).
+    Retrying(u8),
+
+    /// A `GAP` reply contained a value outside of the documented range, see `TmcmModule::with_reply_validation`.
+    ValueOutOfSpec(ValueOutOfSpec),
+}
 
 /// This type represennts a TMCM module other than TMCM-100 and Monopack 2.
+///
+/// A module only needs a shared reference to write commands, and the lifetime `'a` shrinks to the
+/// borrow at hand, so a module can be owned by the struct using it. With an owned interface handle
+/// like `Rc<RefCell<_>>` or `Arc<SharedBus<_>>` the module is also `Clone`, and several structs can
+/// each hold a module on the same bus.
+///
+/// ```no_run
+/// extern crate tmcl;
+///
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use tmcl::modules::tmcm::axis_parameters::*;
+/// use tmcl::modules::tmcm::TmcmModule as Module;
+///
+/// # use tmcl::Interface;
+/// # use tmcl::Encode;
+/// # use tmcl::Command;
+/// # use tmcl::Reply;
+/// #
+/// # struct MyInterface();
+/// # #[derive(Debug)]
+/// # struct MyInterfaceError();
+/// #
+/// # impl MyInterface { fn new() -> Self {unimplemented!()} }
+/// #
+/// # impl Interface for MyInterface {
+///    # type Error = MyInterfaceError;
+///    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+///        # unimplemented!()
+///    # }
+///    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+///        # unimplemented!()
+///    # }
+/// # }
+/// #
+/// type Bus = Rc<RefCell<MyInterface>>;
+///
+/// struct Gantry {
+///     module: Module<'static, MyInterface, RefCell<MyInterface>, Bus>,
+/// }
+///
+/// impl Gantry {
+///     fn stop(&self) -> Result<(), tmcl::Error<MyInterfaceError>> {
+///         for axis in self.module.axes() {
+///             axis.stop()?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+///     let bus = Rc::new(RefCell::new(MyInterface::new()));
+///     let module = Module::new(bus, 1).with_axis_count(2);
+///
+///     let gantry = Gantry{module: module.clone()};
+///     gantry.stop().unwrap();
+///     println!("{:?}", module.axis(0).get::<ActualPosition>());
+/// }
+/// ```
 #[derive(Debug)]
 pub struct TmcmModule<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell> + 'a> {
     /// The module address
     address: u8,
     interface: T,
+    timeout: Option<Duration>,
+    axis_count: u8,
+    persistence_guard: bool,
+    reply_address_check: ReplyAddressCheck,
+    capabilities: Capabilities,
+    retry_policy: RetryPolicy,
+    observer: Option<fn(ModuleEvent)>,
+    audit_trail: Option<AuditTrail>,
+    monitoring: MonitoringChannels,
+    strict_reply_ordering: bool,
+    no_reply: bool,
+    reply_validation: bool,
+    write_verification: bool,
+    stats: cell::Cell<ModuleStats>,
+    io_state: cell::Cell<IoState>,
+    moved_negative: cell::Cell<u32>,
     pd1: PhantomData<&'a IF>,
     pd2: PhantomData<&'a T>,
 }
 
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell> + Clone> Clone for TmcmModule<'a, IF, Cell, T> {
+    /// Create a module with the same configuration using a clone of the interface handle
+    ///
+    /// The statistics are copied, and counted separately from then on.
+    fn clone(&self) -> Self {
+        TmcmModule{
+            address: self.address,
+            interface: self.interface.clone(),
+            timeout: self.timeout,
+            axis_count: self.axis_count,
+            persistence_guard: self.persistence_guard,
+            reply_address_check: self.reply_address_check,
+            capabilities: self.capabilities,
+            retry_policy: self.retry_policy,
+            observer: self.observer,
+            audit_trail: self.audit_trail,
+            monitoring: self.monitoring,
+            strict_reply_ordering: self.strict_reply_ordering,
+            no_reply: self.no_reply,
+            reply_validation: self.reply_validation,
+            write_verification: self.write_verification,
+            stats: self.stats.clone(),
+            io_state: self.io_state.clone(),
+            moved_negative: self.moved_negative.clone(),
+            pd1: PhantomData,
+            pd2: PhantomData,
+        }
+    }
+}
+
+/// Where a command is written to and how long its reply is awaited.
+#[derive(Debug, Clone, Copy)]
+struct Destination {
+    address: u8,
+    timeout: Option<Duration>,
+}
+
+/// Information about a command that has been written to a module.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CommandReport {
+    elapsed: Duration,
+}
+
+#[cfg(feature = "std")]
+impl CommandReport {
+    /// The time between starting to transmit the command and receiving the reply
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
 impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> TmcmModule<'a, IF, Cell, T> {
     /// Create a new module
     pub fn new(interface: T, address: u8) -> Self {
         TmcmModule{
             address,
             interface,
+            timeout: None,
+            axis_count: 1,
+            persistence_guard: false,
+            reply_address_check: ReplyAddressCheck::Disabled,
+            capabilities: Capabilities::all(),
+            retry_policy: RetryPolicy::none(),
+            observer: None,
+            audit_trail: None,
+            monitoring: MonitoringChannels::new(),
+            strict_reply_ordering: false,
+            no_reply: false,
+            reply_validation: false,
+            write_verification: false,
+            stats: cell::Cell::new(ModuleStats::new()),
+            io_state: cell::Cell::new(IoState::new()),
+            moved_negative: cell::Cell::new(0),
             pd1: PhantomData{},
             pd2: PhantomData{},
         }
     }
 
+    /// Configure a module with the builder style `ModuleSettings`
+    ///
+    /// The module address defaults to 1.
+    pub fn builder(interface: T) -> ModuleSettings<'a, IF, Cell, T> {
+        ModuleSettings::new(interface)
+    }
+
+    /// Set a deadline for every command written to the module.
+    ///
+    /// If the reply is not received within `timeout`, `Error::Timeout` is returned.
+    /// The deadline can only be enforced if the interface implements `Interface::receive_reply_timeout`.
+    /// With the `std` feature the time spent transmitting the command is included in the deadline.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Reject instructions writing to EEPROM unless they are issued with `write_persistent`
+    ///
+    /// See the `persistence` module for which instructions are guarded.
+    pub fn with_persistence_guard(mut self) -> Self {
+        self.persistence_guard = true;
+        self
+    }
+
+    /// Choose how the reply address of replies is validated
+    ///
+    /// The reply address is not checked by default. When replies are routed through a gateway that
+    /// rewrites the reply address, expect the address used by the gateway.
+    /// Replies with another reply address result in `Error::UnexpectedReplyAddress`.
+    pub fn with_reply_address_check(mut self, check: ReplyAddressCheck) -> Self {
+        self.reply_address_check = check;
+        self
+    }
+
+    /// Retransmit failed commands according to `retry_policy`
+    ///
+    /// The deadline set with `with_timeout` applies to every attempt.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Call `observer` for every transmitted command, received reply and retry
+    ///
+    /// The interface is borrowed while the observer runs, it must not use the module itself.
+    pub fn with_observer(mut self, observer: fn(ModuleEvent)) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Record every parameter change in `audit_trail`, see the `audit` module
+    pub fn with_audit_trail(mut self, audit_trail: AuditTrail) -> Self {
+        self.audit_trail = Some(audit_trail);
+        self
+    }
+
+    /// Discard stale replies, e.g. replies to earlier commands that timed out
+    ///
+    /// Pending input is discarded with `Interface::discard_input` before every command is
+    /// transmitted, and received replies with another command number than the transmitted command
+    /// are discarded until the right reply arrives or the deadline is reached. Without `std`, the
+    /// deadline is applied to every received reply instead of the command as a whole.
+    pub fn with_strict_reply_ordering(mut self) -> Self {
+        self.strict_reply_ordering = true;
+        self
+    }
+
+    /// Declare that the module does not reply on its address, e.g. a `SerialSecondaryAddress`
+    ///
+    /// `send_command` then only transmits the command. Commands written with `write_command` still
+    /// wait for a reply and fail with `Error::Timeout` (or block, without a timeout).
+    pub fn with_no_reply(mut self) -> Self {
+        self.no_reply = true;
+        self
+    }
+
+    /// Check the values in `GAP` replies against the documented range of the axis parameter
+    ///
+    /// Values outside of the range are reported as `ModuleEvent::ValueOutOfSpec` and counted in
+    /// the statistics, the command itself does not fail. See the `parameter_info` module.
+    pub fn with_reply_validation(mut self) -> Self {
+        self.reply_validation = true;
+        self
+    }
+
+    /// Read back every axis parameter written with `SAP` with `GAP` and compare it to the written value
+    ///
+    /// A value the module silently clamped or ignored results in `Error::VerificationFailed` with
+    /// both values, after the write. Only the bits in `ParameterInfo::writable_mask` are compared,
+    /// status bits maintained by the module are ignored. Parameters documented as read-only in
+    /// `parameter_info` are not read back, writing them fails on the module anyway. This costs one
+    /// extra command per write.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use tmcl::Error;
+    /// use tmcl::opcodes::{OPCODE_GAP, OPCODE_SAP};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::TmcmModule;
+    /// use tmcl::modules::tmcm::axis_parameters::{MaximumPositioningSpeed, ReferenceSearchMode, ReferenceSearchType};
+    /// use tmcl::modules::tmcm::instructions::SAP;
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     .expect(expect_command(OPCODE_SAP).type_number(193..=193).value(1..=1))
+    ///     // Only the mode byte of the reference search mode is compared.
+    ///     .expect(expect_command(OPCODE_GAP).type_number(193..=193).reply_with_value(0x0101))
+    ///     .expect(expect_command(OPCODE_SAP).type_number(4..=4).value(2000..=2000))
+    ///     .expect(expect_command(OPCODE_GAP).type_number(4..=4).reply_with_value(1000));
+    /// let interface = RefCell::new(script);
+    /// let module = TmcmModule::new(&interface, 1).with_write_verification();
+    ///
+    /// module.write_command(SAP::new(0, ReferenceSearchMode::new(ReferenceSearchType::LeftSwitch, false))).unwrap();
+    /// assert!(matches!(
+    ///     module.write_command(SAP::new(0, MaximumPositioningSpeed::new(2000))),
+    ///     Err(Error::VerificationFailed{written: 2000, read: 1000})
+    /// ));
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_write_verification(mut self) -> Self {
+        self.write_verification = true;
+        self
+    }
+
+    /// Declare where the monitored values of the module are read from
+    pub fn with_monitoring(mut self, monitoring: MonitoringChannels) -> Self {
+        self.monitoring = monitoring;
+        self
+    }
+
+    /// Declare the instructions supported by the module
+    ///
+    /// Unsupported instructions are rejected with `Error::Unsupported` before being transmitted.
+    /// All instructions are assumed to be supported unless this is set.
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// The instructions supported by the module
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Set the number of axes of the module, used by `axes`
+    ///
+    /// Modules are assumed to have a single axis unless this is set.
+    pub fn with_axis_count(mut self, axis_count: u8) -> Self {
+        self.axis_count = axis_count;
+        self
+    }
+
+    /// The number of axes of the module
+    pub fn axis_count(&self) -> u8 {
+        self.axis_count
+    }
+
+    /// A handle to the axis with the given motor number
+    pub fn axis(&'a self, motor_number: u8) -> Axis<'a, IF, Cell, T> {
+        Axis::new(self, motor_number)
+    }
+
+    /// Handles to all the axes of the module, ordered by motor number
+    pub fn axes(&'a self) -> impl Iterator<Item=Axis<'a, IF, Cell, T>> + 'a {
+        (0..self.axis_count).map(move |motor_number| Axis::new(self, motor_number))
+    }
+
+    /// The user variables of the module
+    pub fn user_variables(&'a self) -> UserVariables<'a, IF, Cell, T> {
+        UserVariables::new(self)
+    }
+
+    /// The communication statistics of the module, see the `stats` module
+    pub fn stats(&self) -> ModuleStats {
+        self.stats.get()
+    }
+
+    /// Set all communication statistics back to zero
+    pub fn reset_stats(&self) {
+        self.stats.set(ModuleStats::new());
+    }
+
+    /// The state of the digital outputs as last written with `SIO` or read with `GIO`
+    ///
+    /// Every acknowledged `SIO` and `GIO` on the output bank updates the snapshot, whether it was
+    /// written with `set_outputs`, `read_outputs` or `write_command`.
+    pub fn io_state(&self) -> IoState {
+        self.io_state.get()
+    }
+
+    /// Returns true if the last backlash compensated move of the motor was in the negative direction
+    pub(crate) fn moved_negative(&self, motor_number: u8) -> bool {
+        motor_number < 32 && self.moved_negative.get() & (1 << motor_number) != 0
+    }
+
+    /// Remember the direction of a backlash compensated move, for motor numbers below 32
+    pub(crate) fn set_moved_negative(&self, motor_number: u8, negative: bool) {
+        if motor_number >= 32 {
+            return;
+        }
+        let moved_negative = self.moved_negative.get() & !(1 << motor_number);
+        self.moved_negative.set(moved_negative | (u32::from(negative) << motor_number));
+    }
+
+    /// A heartbeat writing to `target`, see the `heartbeat` module
+    pub fn heartbeat(&'a self, target: HeartbeatTarget) -> Heartbeat<'a, IF, Cell, T> {
+        Heartbeat::new(self, target)
+    }
+
     /// Synchronously write a command and wait for the Reply
     pub fn write_command<Instruction: TmcmInstruction + DirectInstruction>(&'a self, instruction: Instruction) -> Result<Instruction::Return, Error<IF::Error>> {
-        let mut interface = self.interface.borrow_int_mut().or(Err(Error::InterfaceUnavailable))?;
-        interface.transmit_command(&Command::new(self.address, instruction)).map_err(|e| Error::InterfaceError(e))?;
-        let reply = interface.receive_reply().map_err(|e| Error::InterfaceError(e))?;
-        match reply.status() {
-            Status::Ok(_) => Ok(<Instruction::Return as Return>::from_operand(reply.operand())),
-            Status::Err(e) => Err(e.into()),
+        self.write_encoded(instruction)
+    }
+
+    /// Synchronously write a command and wait for the Reply, reporting how long it took
+    #[cfg(feature = "std")]
+    pub fn write_command_with_report<Instruction: TmcmInstruction + DirectInstruction>(&'a self, instruction: Instruction) -> Result<(Instruction::Return, CommandReport), Error<IF::Error>> {
+        let start = Instant::now();
+        let ret = self.write_encoded(instruction)?;
+        Ok((ret, CommandReport{elapsed: start.elapsed()}))
+    }
+
+    /// Write a command without using its reply value
+    ///
+    /// If the module is declared with `with_no_reply` the command is transmitted without waiting for
+    /// a reply (fire and forget), whether it was executed is not known. Otherwise the reply is
+    /// awaited like with `write_command`.
+    pub fn send_command<Instruction: TmcmInstruction + DirectInstruction>(&'a self, instruction: Instruction) -> Result<(), Error<IF::Error>> {
+        if !self.no_reply {
+            return self.write_encoded::<_, Operand>(instruction).map(|_| ());
+        }
+        self.send_command_to(self.address, instruction)
+    }
+
+    /// Set the secondary address of the module, 0 disables it
+    ///
+    /// The module must be addressed by its primary address. The new value is read back and
+    /// `Error::VerificationFailed` is returned if it was not taken over.
+    ///
+    /// The address is stored in EEPROM, a module created `with_persistence_guard` returns
+    /// `Error::PersistenceNotAllowed`. Write it with `write_persistent` instead.
+    pub fn set_secondary_address(&'a self, address: u8) -> Result<(), Error<IF::Error>> {
+        self.write_command(SGP::new(SerialSecondaryAddress::new(address)))?;
+        let read = u8::from(self.write_command(GGP::<SerialSecondaryAddress>::new())?);
+        if read != address {
+            return Err(Error::VerificationFailed{written: i32::from(address), read: i32::from(read)});
+        }
+        Ok(())
+    }
+
+    /// Set the CAN secondary ID of the module, making it a member of the group with that ID, 0 disables it
+    ///
+    /// The new value is read back and `Error::VerificationFailed` is returned if it was not taken
+    /// over. See `Bus::write_group_command` for commanding the group.
+    ///
+    /// The ID is stored in EEPROM, a module created `with_persistence_guard` returns
+    /// `Error::PersistenceNotAllowed`. Write it with `write_persistent` instead.
+    pub fn set_can_group_id(&'a self, group_id: u16) -> Result<(), Error<IF::Error>> {
+        self.write_command(SGP::new(CanSecondaryId::new(group_id)))?;
+        let read = u16::from(self.write_command(GGP::<CanSecondaryId>::new())?);
+        if read != group_id {
+            return Err(Error::VerificationFailed{written: i32::from(group_id), read: i32::from(read)});
+        }
+        Ok(())
+    }
+
+    /// Synchronously write a command that is not known by this crate and wait for the Reply
+    ///
+    /// This is an escape hatch for vendor specific or undocumented instructions,
+    /// no checks are done on the instruction and the raw reply operand is returned.
+    pub fn write_raw_command(&'a self, instruction_number: u8, type_number: u8, motor_bank_number: u8, value: i32) -> Result<Operand, Error<IF::Error>> {
+        self.write_encoded(RawInstruction::new(instruction_number, type_number, motor_bank_number, value))
+    }
+
+    /// Synchronously write a command that writes to EEPROM and wait for the Reply
+    ///
+    /// The write is counted by `token`. If the limit of the token has been reached
+    /// `Error::PersistenceNotAllowed` is returned without writing the command.
+    pub fn write_persistent<Instruction: TmcmInstruction + DirectInstruction>(&'a self, instruction: Instruction, token: &mut PersistenceToken) -> Result<Instruction::Return, Error<IF::Error>> {
+        if !token.record() {
+            return Err(Error::PersistenceNotAllowed);
         }
+        self.transmit_encoded(instruction)
+    }
+
+    /// Store several global parameters, given as `(bank, number)`, to EEPROM with one `STGP` each
+    ///
+    /// Every write is counted by `token`. If the token does not allow all of them, nothing is
+    /// stored and `Error::PersistenceNotAllowed` is returned. `progress` is called with the number
+    /// of parameters stored so far and the total after every write, e.g. to report the progress of
+    /// a provisioning script.
+    pub fn store_global_parameters<F: FnMut(usize, usize)>(&'a self, parameters: &[(u8, u8)], token: &mut PersistenceToken, mut progress: F) -> Result<(), Error<IF::Error>> {
+        if token.remaining().is_some_and(|remaining| (remaining as usize) < parameters.len()) {
+            return Err(Error::PersistenceNotAllowed);
+        }
+        for (stored, &(bank, number)) in parameters.iter().enumerate() {
+            token.record();
+            self.transmit_encoded::<_, Operand>(RawInstruction::new(OPCODE_STGP, number, bank, 0))?;
+            progress(stored + 1, parameters.len());
+        }
+        Ok(())
+    }
+
+    /// Restore several global parameters, given as `(bank, number)`, from EEPROM with one `RSGP` each
+    ///
+    /// `progress` is called as in `store_global_parameters`.
+    pub fn restore_global_parameters<F: FnMut(usize, usize)>(&'a self, parameters: &[(u8, u8)], mut progress: F) -> Result<(), Error<IF::Error>> {
+        for (restored, &(bank, number)) in parameters.iter().enumerate() {
+            self.write_encoded::<_, Operand>(RawInstruction::new(OPCODE_RSGP, number, bank, 0))?;
+            progress(restored + 1, parameters.len());
+        }
+        Ok(())
+    }
+
+    /// Set several digital outputs, given as `(port number, state)`, with one `SIO` per port.
+    ///
+    /// If `verify` is set every output is read back with `GIO` and `Error::VerificationFailed` is
+    /// returned if it does not have the written state.
+    /// The returned snapshot is `io_state`, it contains the state of all the written outputs.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use tmcl::opcodes::{OPCODE_GIO, OPCODE_SIO};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::TmcmModule;
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     .expect(expect_command(OPCODE_SIO).type_number(1..=1).value(1..=1))
+    ///     .expect(expect_command(OPCODE_GIO).type_number(2..=2).reply_with_value(0));
+    /// let interface = RefCell::new(script);
+    /// let module = TmcmModule::new(&interface, 1);
+    ///
+    /// module.set_outputs(&[(1, true)], false).unwrap();
+    /// let state = module.read_outputs(&[2]).unwrap();
+    /// assert_eq!(state, module.io_state());
+    /// assert_eq!(state.output(1), Some(true));
+    /// assert_eq!(state.output(2), Some(false));
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn set_outputs(&'a self, outputs: &[(u8, bool)], verify: bool) -> Result<IoState, Error<IF::Error>> {
+        for &(port_number, value) in outputs {
+            self.write_command(SIO::new(OUTPUT_BANK, port_number, value))?;
+        }
+        if verify {
+            for &(port_number, value) in outputs {
+                let read = self.write_command(GIO::new(OUTPUT_BANK, port_number))? != 0;
+                if read != value {
+                    return Err(Error::VerificationFailed{written: i32::from(value), read: i32::from(read)});
+                }
+            }
+        }
+        Ok(self.io_state())
+    }
+
+    /// Read the state of the given digital outputs with one `GIO` per port
+    ///
+    /// The returned snapshot is `io_state`, it contains the state of all the read outputs.
+    pub fn read_outputs(&'a self, port_numbers: &[u8]) -> Result<IoState, Error<IF::Error>> {
+        for &port_number in port_numbers {
+            self.write_command(GIO::new(OUTPUT_BANK, port_number))?;
+        }
+        Ok(self.io_state())
+    }
+
+    /// Download a program to the module, starting at address 0
+    ///
+    /// The module is put in download mode, the assembled program is transmitted instruction by
+    /// instruction and download mode is left again. The program is not started.
+    #[cfg(any(feature = "std", feature = "heapless"))]
+    pub fn download_program<const N: usize>(&'a self, program: &TmclProgram<N>) -> Result<(), Error<IF::Error>> {
+        self.download_program_cancellable(program, || false)
+    }
+
+    /// Like `download_program`, but `cancel` is checked before every instruction is transmitted
+    ///
+    /// When cancelled, download mode is left before `Error::Cancelled` is returned.
+    /// The program memory of the module then contains a partially downloaded program.
+    #[cfg(any(feature = "std", feature = "heapless"))]
+    pub fn download_program_cancellable<C: Cancel, const N: usize>(&'a self, program: &TmclProgram<N>, mut cancel: C) -> Result<(), Error<IF::Error>> {
+        let assembled = program.assemble().or(Err(Error::InvalidProgram))?;
+        self.write_raw_command(ENTER_DOWNLOAD_MODE, 0, 0, 0)?;
+        for encoded in &assembled {
+            if cancel.is_cancelled() {
+                self.write_raw_command(EXIT_DOWNLOAD_MODE, 0, 0, 0)?;
+                return Err(Error::Cancelled);
+            }
+            let value = i32::from_be_bytes([encoded[3], encoded[4], encoded[5], encoded[6]]);
+            self.write_raw_command(encoded[0], encoded[1], encoded[2], value)?;
+        }
+        self.write_raw_command(EXIT_DOWNLOAD_MODE, 0, 0, 0)?;
+        Ok(())
+    }
+
+    /// Read the raw value of every axis parameter in `diagnostics::dumped_axis_parameters` with one `GAP` per parameter
+    #[cfg(feature = "std")]
+    pub fn dump_axis_parameters(&'a self, motor_number: u8) -> Result<AxisParameterDump, Error<IF::Error>> {
+        let mut dump = AxisParameterDump::new();
+        for info in dumped_axis_parameters() {
+            let operand = self.write_raw_command(OPCODE_GAP, info.number(), motor_number, 0)?;
+            dump.insert(info.number(), i32::from_operand(operand));
+        }
+        Ok(dump)
+    }
+
+    /// Read the error flags of an axis with one `GAP` for `ExtendedErrorFlags` and one for `DriverErrorFlags`
+    ///
+    /// Reading the extended error flags clears them on the module.
+    pub fn health(&'a self, motor_number: u8) -> Result<ErrorFlags, Error<IF::Error>> {
+        let extended = self.write_command(GAP::<ExtendedErrorFlags>::new(motor_number))?;
+        let driver = self.write_command(GAP::<DriverErrorFlags>::new(motor_number))?;
+        Ok(ErrorFlags::new(extended, driver))
+    }
+
+    /// Read the tick timer, with the host time taken from `clock`
+    pub fn sample_tick_timer<C: Clock>(&'a self, clock: &mut C) -> Result<TickSample, Error<IF::Error>> {
+        let transmitted = clock.now();
+        let ticks = u32::from(self.write_command(GGP::<TickTimer>::new())?);
+        let round_trip = clock.now().checked_sub(transmitted).unwrap_or_default();
+        Ok(TickSample::new(transmitted + round_trip / 2, round_trip, ticks))
+    }
+
+    /// Read the supply voltage in millivolts
+    ///
+    /// Returns `Error::Unsupported` if no supply voltage channel is declared with `with_monitoring`.
+    pub fn supply_voltage_mv(&'a self) -> Result<i32, Error<IF::Error>> {
+        self.supply_voltage().map(|value| value.converted())
+    }
+
+    /// Read the supply voltage in millivolts, keeping the fractional part
+    pub fn supply_voltage(&'a self) -> Result<MonitoredValue, Error<IF::Error>> {
+        self.read_monitored(self.monitoring.supply_voltage())
+    }
+
+    /// Read the driver temperature in degrees celsius
+    ///
+    /// Returns `Error::Unsupported` if no driver temperature channel is declared with `with_monitoring`.
+    pub fn driver_temperature_c(&'a self) -> Result<i32, Error<IF::Error>> {
+        self.driver_temperature().map(|value| value.converted())
+    }
+
+    /// Read the driver temperature in degrees celsius, keeping the fractional part
+    pub fn driver_temperature(&'a self) -> Result<MonitoredValue, Error<IF::Error>> {
+        self.read_monitored(self.monitoring.driver_temperature())
+    }
+
+    fn read_monitored(&'a self, channel: Option<MonitoringChannel>) -> Result<MonitoredValue, Error<IF::Error>> {
+        let channel = channel.ok_or(Error::Unsupported)?;
+        let operand = match channel.source() {
+            MonitoringSource::Input{port_number, bank_number} => self.write_raw_command(OPCODE_GIO, port_number, bank_number, 0)?,
+            MonitoringSource::GlobalParameter{number, bank_number} => self.write_raw_command(OPCODE_GGP, number, bank_number, 0)?,
+            MonitoringSource::AxisParameter{number, motor_number} => self.write_raw_command(OPCODE_GAP, number, motor_number, 0)?,
+        };
+        Ok(channel.value(i32::from_operand(operand)))
+    }
+
+    /// Write a command to the module at `address` instead of the address of this module
+    ///
+    /// Every setting of this module applies, except that `timeout` replaces its timeout. This is
+    /// how `Bus` addresses the modules on it.
+    pub(crate) fn write_command_to<Instruction: TmcmInstruction + DirectInstruction>(&'a self, address: u8, timeout: Option<Duration>, instruction: Instruction) -> Result<Instruction::Return, Error<IF::Error>> {
+        self.write_encoded_to(Destination{address, timeout}, instruction)
+    }
+
+    /// Transmit a command to the module at `address` without awaiting a reply
+    ///
+    /// The persistence guard, the capability check and the audit trail apply like to every other
+    /// write. The old value is not recorded since no reply is read.
+    pub(crate) fn send_command_to<Instruction: TmcmInstruction + DirectInstruction>(&'a self, address: u8, instruction: Instruction) -> Result<(), Error<IF::Error>> {
+        if self.persistence_guard && writes_eeprom(&instruction) {
+            return Err(Error::PersistenceNotAllowed);
+        }
+        let encoded = instruction.encode();
+        if !self.capabilities.supports_number(encoded[0]) {
+            return Err(Error::Unsupported);
+        }
+        let result = self.transmit_unacknowledged(Command::new(address, instruction));
+        if let Some(audit_trail) = self.audit_trail.filter(|audit_trail| audit_trail.records(encoded[0])) {
+            audit_trail.record(address, encoded, None, result.is_ok());
+        }
+        result
+    }
+
+    fn transmit_unacknowledged<E: Encode>(&'a self, command: Command<E>) -> Result<(), Error<IF::Error>> {
+        let mut interface = self.interface.borrow_int_mut()
+            .or(Err(Error::InterfaceUnavailable{address: command.module_address(), instruction_number: command.serialize_can()[0]}))?;
+        interface.transmit_command(&command).map_err(Error::InterfaceError)?;
+        self.record(ModuleStats::record_command);
+        self.observe(ModuleEvent::Transmitted(command.serialize()));
+        Ok(())
+    }
+
+    /// The interface handle of the module
+    pub(crate) fn interface(&self) -> &T {
+        &self.interface
+    }
+
+    fn destination(&self) -> Destination {
+        Destination{address: self.address, timeout: self.timeout}
+    }
+
+    fn write_encoded<E: Encode, R: Return>(&'a self, instruction: E) -> Result<R, Error<IF::Error>> {
+        self.write_encoded_to(self.destination(), instruction)
+    }
+
+    fn write_encoded_to<E: Encode, R: Return>(&'a self, destination: Destination, instruction: E) -> Result<R, Error<IF::Error>> {
+        if self.persistence_guard && writes_eeprom(&instruction) {
+            return Err(Error::PersistenceNotAllowed);
+        }
+        self.transmit_encoded_to(destination, instruction)
+    }
+
+    fn transmit_encoded<E: Encode, R: Return>(&'a self, instruction: E) -> Result<R, Error<IF::Error>> {
+        self.transmit_encoded_to(self.destination(), instruction)
+    }
+
+    fn transmit_encoded_to<E: Encode, R: Return>(&'a self, destination: Destination, instruction: E) -> Result<R, Error<IF::Error>> {
+        let encoded = instruction.encode();
+        if !self.capabilities.supports_number(encoded[0]) {
+            return Err(Error::Unsupported);
+        }
+        let value = i32::from_be_bytes([encoded[3], encoded[4], encoded[5], encoded[6]]);
+        let instruction = RawInstruction::new(encoded[0], encoded[1], encoded[2], value);
+        let result = self.transmit_audited(destination, encoded, instruction)?;
+        if self.write_verification && encoded[0] == OPCODE_SAP {
+            self.verify_axis_parameter(destination, encoded[1], encoded[2], value)?;
+        }
+        Ok(result)
+    }
+
+    fn verify_axis_parameter(&'a self, destination: Destination, parameter_number: u8, motor_number: u8, written: i32) -> Result<(), Error<IF::Error>> {
+        let info = axis_parameter(parameter_number);
+        if info.is_some_and(|info| !info.is_writable()) {
+            return Ok(());
+        }
+        let read = self.transmit_retried::<Operand>(destination, RawInstruction::new(OPCODE_GAP, parameter_number, motor_number, 0))?.to_i32();
+        if !info.map_or(read == written, |info| info.was_written(written, read)) {
+            return Err(Error::VerificationFailed{written, read});
+        }
+        Ok(())
+    }
+
+    fn transmit_audited<R: Return>(&'a self, destination: Destination, encoded: [u8; 7], instruction: RawInstruction) -> Result<R, Error<IF::Error>> {
+        match self.audit_trail {
+            Some(audit_trail) if audit_trail.records(encoded[0]) => {
+                let old_value = audit_trail.read_instruction(encoded[0])
+                    .and_then(|read| self.transmit_retried::<Operand>(destination, RawInstruction::new(read, encoded[1], encoded[2], 0)).ok())
+                    .map(|operand| operand.to_i32());
+                let result = self.transmit_retried(destination, instruction);
+                audit_trail.record(destination.address, encoded, old_value, result.is_ok());
+                result
+            },
+            _ => self.transmit_retried(destination, instruction),
+        }
+    }
+
+    fn transmit_retried<R: Return>(&'a self, destination: Destination, instruction: RawInstruction) -> Result<R, Error<IF::Error>> {
+        let mut retry = 0;
+        loop {
+            match self.transmit_once(destination, instruction) {
+                Err(ref e) if retry < self.retry_policy.retries() && self.retry_policy.applies_to(e) => {
+                    retry += 1;
+                    self.record(ModuleStats::record_retry);
+                    self.observe(ModuleEvent::Retrying(retry));
+                },
+                result => return result,
+            }
+        }
+    }
+
+    fn transmit_once<R: Return>(&'a self, destination: Destination, instruction: RawInstruction) -> Result<R, Error<IF::Error>> {
+        let result = self.transmit_once_unrecorded(destination, instruction);
+        match result {
+            Err(Error::Timeout) => self.record(ModuleStats::record_timeout),
+            Err(Error::ProtocolError(ErrStatus::WrongChecksum, _)) => self.record(ModuleStats::record_checksum_error),
+            _ => (),
+        }
+        result
+    }
+
+    fn transmit_once_unrecorded<R: Return>(&'a self, destination: Destination, instruction: RawInstruction) -> Result<R, Error<IF::Error>> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        let instruction_number = instruction.encode()[0];
+        let mut interface = self.interface.borrow_int_mut()
+            .or(Err(Error::InterfaceUnavailable{address: destination.address, instruction_number}))?;
+        if self.strict_reply_ordering {
+            interface.discard_input().map_err(Error::InterfaceError)?;
+        }
+        let command = Command::new(destination.address, instruction);
+        interface.transmit_command(&command).map_err(Error::InterfaceError)?;
+        self.record(ModuleStats::record_command);
+        self.observe(ModuleEvent::Transmitted(command.serialize()));
+        let reply = loop {
+            let reply = match destination.timeout {
+                None => interface.receive_reply().map_err(Error::InterfaceError)?,
+                Some(timeout) => {
+                    #[cfg(feature = "std")]
+                    let timeout = timeout.checked_sub(start.elapsed()).ok_or(Error::Timeout)?;
+                    interface.receive_reply_timeout(timeout).map_err(Error::InterfaceError)?.ok_or(Error::Timeout)?
+                },
+            };
+            if self.strict_reply_ordering && reply.command_number() != instruction_number {
+                self.observe(ModuleEvent::Discarded(&reply));
+                continue;
+            }
+            break reply;
+        };
+        #[cfg(feature = "std")]
+        self.record(|stats| stats.record_round_trip(start.elapsed()));
+        self.observe(ModuleEvent::Received(&reply));
+        if let ReplyAddressCheck::Expect(reply_address) = self.reply_address_check {
+            if reply.reply_address() != reply_address {
+                return Err(Error::UnexpectedReplyAddress(reply.reply_address()));
+            }
+        }
+        let reply = reply.into_result()
+            .map_err(|e| Error::ProtocolError(e, CommandContext::new(destination.address, instruction.encode())))?;
+        if self.reply_validation && instruction_number == OPCODE_GAP {
+            self.validate(instruction.encode()[1], reply.operand());
+        }
+        self.track_io(instruction.encode(), reply.operand());
+        R::try_from_operand(reply.operand()).or(Err(Error::InvalidReply))
+    }
+
+    /// Update `io_state` after an acknowledged `SIO` or `GIO` on the output bank
+    fn track_io(&self, encoded: [u8; 7], operand: Operand) {
+        if encoded[2] != OUTPUT_BANK {
+            return;
+        }
+        let value = match encoded[0] {
+            OPCODE_SIO => encoded[3..7].iter().any(|&byte| byte != 0),
+            OPCODE_GIO => operand.to_i32() != 0,
+            _ => return,
+        };
+        let mut state = self.io_state.get();
+        state.set_output(encoded[1], value);
+        self.io_state.set(state);
+    }
+
+    fn validate(&self, parameter_number: u8, operand: Operand) {
+        let out_of_spec = axis_parameter(parameter_number).and_then(|info| info.check(operand.to_i32()));
+        if let Some(out_of_spec) = out_of_spec {
+            self.record(ModuleStats::record_out_of_spec);
+            self.observe(ModuleEvent::ValueOutOfSpec(out_of_spec));
+        }
+    }
+
+    fn record<F: FnOnce(&mut ModuleStats)>(&self, f: F) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+
+    fn observe(&self, event: ModuleEvent) {
+        if let Some(observer) = self.observer {
+            observer(event);
+        }
+    }
+
+    /// Set the maximum positioning speed and acceleration of an axis
+    ///
+    /// The pulse and ramp divisors are read from the axis and the acceleration is validated against
+    /// them, see the `ramp` module. If it is outside the allowed range `Error::InvalidRamp` is
+    /// returned and nothing is written.
+    pub fn set_ramp(&'a self, motor_number: u8, speed: MaximumPositioningSpeed, acceleration: MaximumAcceleration) -> Result<(), Error<IF::Error>> {
+        let pulse_divisor = u8::from(self.write_command(GAP::<PulseDivisor>::new(motor_number))?);
+        let ramp_divisor = u8::from(self.write_command(GAP::<RampDivisor>::new(motor_number))?);
+        let acceleration_value = u16::from(acceleration);
+        validate_acceleration(acceleration_value, pulse_divisor, ramp_divisor).map_err(Error::InvalidRamp)?;
+        self.write_command(SAP::new(motor_number, speed))?;
+        self.write_command(SAP::new(motor_number, MaximumAcceleration::new(acceleration_value)))
+    }
+
+    /// Capture the position of an axis on the next trigger of its latch input
+    ///
+    /// `mode` selects the edge, `PositionCaptureMode::Disabled` disarms the capture.
+    pub fn arm_position_capture(&'a self, motor_number: u8, mode: PositionCaptureMode) -> Result<(), Error<IF::Error>> {
+        self.write_command(SAP::new(motor_number, mode))
+    }
+
+    /// Read the position captured since `arm_position_capture`, `None` if the input has not triggered yet
+    pub fn read_captured_position(&'a self, motor_number: u8) -> Result<Option<i32>, Error<IF::Error>> {
+        if self.write_command(GAP::<PositionCaptureMode>::new(motor_number))? != PositionCaptureMode::Disabled {
+            return Ok(None);
+        }
+        self.write_command(GAP::<CapturedPosition>::new(motor_number)).map(|position| Some(position.into()))
+    }
+
+    /// Stop the motor by decelerating along the ramp instead of stopping immediately.
+    ///
+    /// If the `SoftStopFlag` of the axis is not set it is set before issuing `MST` and cleared
+    /// again afterwards, so stops caused by the limit switches keep their configured behaviour.
+    /// The flag is restored even if `MST` failed.
+    ///
+    /// ```
+    /// # #[cfg(feature = "std")]
+    /// # fn main() {
+    /// use std::cell::RefCell;
+    /// use tmcl::opcodes::{OPCODE_GAP, OPCODE_MST, OPCODE_SAP};
+    /// use tmcl::testing::{expect_command, Ordering, Script};
+    /// use tmcl::modules::tmcm::TmcmModule as Module;
+    ///
+    /// let script = Script::new(Ordering::Strict)
+    ///     .expect(expect_command(OPCODE_GAP).type_number(149..=149).reply_with_value(0))
+    ///     .expect(expect_command(OPCODE_SAP).type_number(149..=149).value(1..=1))
+    ///     .expect(expect_command(OPCODE_MST))
+    ///     .expect(expect_command(OPCODE_SAP).type_number(149..=149).value(0..=0));
+    /// let interface = RefCell::new(script);
+    /// let module = Module::new(&interface, 1);
+    ///
+    /// module.soft_stop(0).unwrap();
+    /// assert_eq!(interface.borrow().verify(), Ok(()));
+    /// # }
+    /// # #[cfg(not(feature = "std"))]
+    /// # fn main() {}
+    /// ```
+    pub fn soft_stop(&'a self, motor_number: u8) -> Result<(), Error<IF::Error>> {
+        let previous = self.write_command(GAP::<SoftStopFlag>::new(motor_number))?;
+        if previous == SoftStopFlag::soft() {
+            return self.write_command(MST::new(motor_number));
+        }
+        self.write_command(SAP::new(motor_number, SoftStopFlag::soft()))?;
+        let stopped = self.write_command(MST::new(motor_number));
+        self.write_command(SAP::new(motor_number, previous))?;
+        stopped
+    }
+}
+
+impl<'a, IF: RemoteRequest, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> TmcmModule<'a, IF, Cell, T> {
+    /// Request the status of the module with a CAN remote frame, see `remote`
+    ///
+    /// The reply is returned as received, its content depends on the firmware. The timeout and the
+    /// observer of the module apply, retries and the reply address check do not.
+    pub fn request_status(&'a self) -> Result<Reply, Error<IF::Error>> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        let mut interface = self.interface.borrow_int_mut()
+            .or(Err(Error::InterfaceUnavailable{address: self.address, instruction_number: 0}))?;
+        interface.transmit_remote_request(u16::from(self.address)).map_err(Error::InterfaceError)?;
+        self.record(ModuleStats::record_command);
+        let reply = match self.timeout {
+            None => interface.receive_reply().map_err(Error::InterfaceError)?,
+            Some(timeout) => {
+                #[cfg(feature = "std")]
+                let timeout = timeout.checked_sub(start.elapsed()).ok_or(Error::Timeout)?;
+                interface.receive_reply_timeout(timeout).map_err(Error::InterfaceError)?.ok_or(Error::Timeout)?
+            },
+        };
+        self.observe(ModuleEvent::Received(&reply));
+        Ok(reply)
     }
 }
 
@@ -66,3 +1156,12 @@ pub trait ReadableTmcmAxisParameter: ReadableAxisParameter {}
 
 /// A `WriteableAxisParamtere` useable with all TMCM modules other than TMCM-100 and Monopack 2.
 pub trait WriteableTmcmAxisParameter: WriteableAxisParameter {}
+
+/// A `GlobalParameter` useable with all TMCM modules other than TMCM-100 and Monopack 2.
+pub trait TmcmGlobalParameter: GlobalParameter {}
+
+/// A `ReadableGlobalParameter` useable with all TMCM modules other than TMCM-100 and Monopack 2.
+pub trait ReadableTmcmGlobalParameter: ReadableGlobalParameter {}
+
+/// A `WriteableGlobalParameter` useable with all TMCM modules other than TMCM-100 and Monopack 2.
+pub trait WriteableTmcmGlobalParameter: WriteableGlobalParameter {}
@@ -0,0 +1,196 @@
+//! Metadata about the axis parameters, for validating values read from a module.
+//!
+//! `axis_parameter` looks up the name, mnemonic and documented value range of an axis parameter by
+//! its number. With `TmcmModule::with_reply_validation`, every `GAP` reply is checked against the
+//! range. A value outside of it does not fail the command, but is reported to the observer as
+//! `ModuleEvent::ValueOutOfSpec` and counted in the statistics. Such values are a hint at wiring
+//! problems or firmware returning garbage.
+//!
+//! Some ranges depend on the module, e.g. the maximum current, in which case the widest documented
+//! range is used.
+//!
+//! ```
+//! use tmcl::modules::tmcm::parameter_info::axis_parameter;
+//!
+//! let info = axis_parameter(4).unwrap();
+//! assert_eq!(info.name(), "MaximumPositioningSpeed");
+//! assert_eq!(info.mnemonic(), "MPS");
+//! assert!(info.contains(2047));
+//! assert_eq!(info.check(3000).unwrap().max(), 2047);
+//! assert!(info.was_written(2047, 2047));
+//! assert!(!info.was_written(3000, 2047));
+//! assert_eq!(axis_parameter(3).unwrap().writable_mask(), 0);
+//! ```
+
+use modules::tmcm::axis_parameters::{
+    MaximumAcceleration,
+    MaximumPositioningSpeed,
+    PulseDivisor,
+    RampDivisor,
+    ReferenceSearchSpeed,
+    ReferenceSwitchSpeed,
+};
+
+/// The documented name, mnemonic and value range of an axis parameter.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParameterInfo {
+    number: u8,
+    name: &'static str,
+    mnemonic: &'static str,
+    min: i32,
+    max: i32,
+    writable_mask: u32,
+    cleared_by_read: bool,
+}
+
+impl ParameterInfo {
+    const fn new(number: u8, name: &'static str, mnemonic: &'static str, min: i32, max: i32) -> Self {
+        ParameterInfo{number, name, mnemonic, min, max, writable_mask: u32::MAX, cleared_by_read: false}
+    }
+
+    const fn read_only(self) -> Self {
+        ParameterInfo{writable_mask: 0, ..self}
+    }
+
+    const fn writable_bits(self, writable_mask: u32) -> Self {
+        ParameterInfo{writable_mask, ..self}
+    }
+
+    const fn cleared_by_read(self) -> Self {
+        ParameterInfo{cleared_by_read: true, ..self}
+    }
+
+    /// The parameter number
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// The name of the type representing the parameter, e.g. `ActualPosition`
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The mnemonic of the parameter, e.g. `AP`
+    pub fn mnemonic(&self) -> &'static str {
+        self.mnemonic
+    }
+
+    /// The lowest documented value
+    pub fn min(&self) -> i32 {
+        self.min
+    }
+
+    /// The highest documented value
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+
+    /// Returns true if the parameter can be written with `SAP`
+    pub fn is_writable(&self) -> bool {
+        self.writable_mask != 0
+    }
+
+    /// The bits of the value that can be written with `SAP`, 0 for read-only parameters
+    ///
+    /// The other bits are status bits maintained by the module.
+    pub fn writable_mask(&self) -> u32 {
+        self.writable_mask
+    }
+
+    /// Returns true if `read` has the writable bits of `written`
+    pub fn was_written(&self, written: i32, read: i32) -> bool {
+        (written ^ read) as u32 & self.writable_mask == 0
+    }
+
+    /// Returns true if reading the parameter with `GAP` clears it on the module
+    pub fn is_cleared_by_read(&self) -> bool {
+        self.cleared_by_read
+    }
+
+    /// Returns true if `value` is within the documented range
+    pub fn contains(&self, value: i32) -> bool {
+        self.min <= value && value <= self.max
+    }
+
+    /// Check `value` against the documented range
+    pub fn check(&self, value: i32) -> Option<ValueOutOfSpec> {
+        if self.contains(value) {
+            None
+        } else {
+            Some(ValueOutOfSpec{number: self.number, value, min: self.min, max: self.max})
+        }
+    }
+}
+
+/// A value read from a module outside of the documented range of the parameter.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ValueOutOfSpec {
+    number: u8,
+    value: i32,
+    min: i32,
+    max: i32,
+}
+
+impl ValueOutOfSpec {
+    /// The parameter number
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// The value read from the module
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// The lowest documented value
+    pub fn min(&self) -> i32 {
+        self.min
+    }
+
+    /// The highest documented value
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+}
+
+/// The axis parameters known to this crate, ordered by number.
+///
+/// The ranges of the parameters with checked constructors are their `MIN` and `MAX`. Only the
+/// mode byte of `ReferenceSearchMode` is written, the upper bytes are left to the module.
+pub const AXIS_PARAMETERS: &[ParameterInfo] = &[
+    ParameterInfo::new(1, "ActualPosition", "AP", i32::MIN, i32::MAX),
+    ParameterInfo::new(3, "ActualSpeed", "AS", -2047, 2047).read_only(),
+    ParameterInfo::new(4, "MaximumPositioningSpeed", "MPS", MaximumPositioningSpeed::MIN as i32, MaximumPositioningSpeed::MAX as i32),
+    ParameterInfo::new(5, "MaximumAcceleration", "MA", MaximumAcceleration::MIN as i32, MaximumAcceleration::MAX as i32),
+    ParameterInfo::new(6, "AbsoluteMaxCurrent", "AMC", 0, 1500),
+    ParameterInfo::new(7, "StandbyCurrent", "SBC", 0, 1500),
+    ParameterInfo::new(8, "TargetPositionReached", "TPR", 0, 1).read_only(),
+    ParameterInfo::new(10, "RightLimitSwitchState", "RLSS", 0, 1).read_only(),
+    ParameterInfo::new(11, "LeftLimitSwitchState", "LLSS", 0, 1).read_only(),
+    ParameterInfo::new(12, "RightLimitSwitchDisable", "RLSD", 0, 1),
+    ParameterInfo::new(13, "LeftLimitSwitchDisable", "LLSD", 0, 1),
+    ParameterInfo::new(140, "MicrostepResolution", "MSR", 0, 6),
+    ParameterInfo::new(149, "SoftStopFlag", "SSF", 0, 1),
+    ParameterInfo::new(153, "RampDivisor", "RD", RampDivisor::MIN as i32, RampDivisor::MAX as i32),
+    ParameterInfo::new(154, "PulseDivisor", "PD", PulseDivisor::MIN as i32, PulseDivisor::MAX as i32),
+    ParameterInfo::new(193, "ReferenceSearchMode", "RSM", 1, 0x88).writable_bits(0xff),
+    ParameterInfo::new(194, "ReferenceSearchSpeed", "RSS", ReferenceSearchSpeed::MIN as i32, ReferenceSearchSpeed::MAX as i32),
+    ParameterInfo::new(195, "ReferenceSwitchSpeed", "RSWS", ReferenceSwitchSpeed::MIN as i32, ReferenceSwitchSpeed::MAX as i32),
+    ParameterInfo::new(196, "EndSwitchDistance", "ESD", i32::MIN, i32::MAX).read_only(),
+    ParameterInfo::new(207, "ExtendedErrorFlags", "EEF", 0, 255).read_only().cleared_by_read(),
+    ParameterInfo::new(208, "DriverErrorFlags", "DEF", 0, 255).read_only(),
+    ParameterInfo::new(220, "PositionCaptureMode", "PCM", 0, 2),
+    ParameterInfo::new(221, "CapturedPosition", "CP", i32::MIN, i32::MAX).read_only(),
+];
+
+/// The metadata of the axis parameter with the given number, `None` if it is unknown
+pub fn axis_parameter(number: u8) -> Option<&'static ParameterInfo> {
+    AXIS_PARAMETERS.iter().find(|info| info.number == number)
+}
+
+/// The metadata of the axis parameter with the given mnemonic, ignoring case
+pub fn axis_parameter_by_mnemonic(mnemonic: &str) -> Option<&'static ParameterInfo> {
+    AXIS_PARAMETERS.iter().find(|info| info.mnemonic.eq_ignore_ascii_case(mnemonic))
+}
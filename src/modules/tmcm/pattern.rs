@@ -0,0 +1,243 @@
+//! Timed patterns on spare digital outputs.
+//!
+//! A spare output of a module can control a brake, a relay or a lamp. `PatternGenerator` plays a
+//! `Pattern` of output states, e.g. a pulse train or an enable sequence, on such an output with
+//! one `SIO` per change. The timing is done on the host, so it is only as good as the host clock
+//! and the bus allow, typically within a few milliseconds.
+//!
+//! The steps are scheduled relative to the start of the pattern, so the time spent on the bus does
+//! not add up over the pattern. Each `SIO` is sent early by the estimated time it takes to reach
+//! the module, half of the measured round trip, so the output changes close to the scheduled time.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::clock::MockClock;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::pattern::{Pattern, PatternGenerator, Step};
+//! # use tmcl::{Command, Encode, Interface, OkStatus, Operand, Reply, Status};
+//! #
+//! # struct MyInterface(u8);
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = ();
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), ()> {
+//!        # self.0 = command.serialize()[1];
+//!        # Ok(())
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, ()> {
+//!        # Ok(Reply::new(2, 1, Status::Ok(OkStatus::Ok), self.0, Operand::ZERO))
+//!    # }
+//! # }
+//!
+//! let interface = RefCell::new(MyInterface(0));
+//! let module = Module::new(&interface, 1);
+//! let clock = MockClock::new();
+//!
+//! // Three 50 ms pulses on output 1, 100 ms apart.
+//! let steps = [Step::high(Duration::from_millis(50)), Step::low(Duration::from_millis(50))];
+//! let pattern = Pattern::new(&steps).with_repetitions(3);
+//!
+//! let mut generator = PatternGenerator::new(&module, 1, &clock, &clock);
+//! let report = generator.play(&pattern).unwrap();
+//! assert_eq!(report.changes(), 6);
+//! assert_eq!(clock.elapsed(), pattern.duration());
+//! ```
+
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use clock::{Clock, Delay};
+
+use modules::tmcm::TmcmModule;
+use modules::tmcm::io::OUTPUT_BANK;
+use modules::tmcm::instructions::SIO;
+
+/// An output state held for a duration.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Step {
+    state: bool,
+    duration: Duration,
+}
+
+impl Step {
+    /// Hold the output at `state` for `duration`
+    pub fn new(state: bool, duration: Duration) -> Self {
+        Step{state, duration}
+    }
+
+    /// Hold the output high for `duration`
+    pub fn high(duration: Duration) -> Self {
+        Step::new(true, duration)
+    }
+
+    /// Hold the output low for `duration`
+    pub fn low(duration: Duration) -> Self {
+        Step::new(false, duration)
+    }
+
+    /// The output state
+    pub fn state(&self) -> bool {
+        self.state
+    }
+
+    /// How long the state is held
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// A sequence of steps, played once or repeatedly.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Pattern<'p> {
+    steps: &'p [Step],
+    repetitions: u32,
+}
+
+impl<'p> Pattern<'p> {
+    /// Play `steps` once
+    pub fn new(steps: &'p [Step]) -> Self {
+        Pattern{steps, repetitions: 1}
+    }
+
+    /// Play the steps `repetitions` times in a row
+    pub fn with_repetitions(mut self, repetitions: u32) -> Self {
+        self.repetitions = repetitions;
+        self
+    }
+
+    /// The steps of one repetition
+    pub fn steps(&self) -> &'p [Step] {
+        self.steps
+    }
+
+    /// The number of times the steps are played
+    pub fn repetitions(&self) -> u32 {
+        self.repetitions
+    }
+
+    /// The time it takes to play the pattern
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(Step::duration).sum::<Duration>() * self.repetitions
+    }
+}
+
+/// How well a pattern was played.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PatternReport {
+    changes: u32,
+    max_lateness: Duration,
+    latency: Duration,
+}
+
+impl PatternReport {
+    /// The number of `SIO` sent, a step with the same state as the previous one sends none
+    pub fn changes(&self) -> u32 {
+        self.changes
+    }
+
+    /// The largest estimated delay of an output change after its scheduled time
+    pub fn max_lateness(&self) -> Duration {
+        self.max_lateness
+    }
+
+    /// The estimated time from sending a `SIO` until it reaches the module, half the round trip
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+}
+
+/// Plays patterns on a digital output of a module, timed by a host clock.
+#[derive(Debug)]
+pub struct PatternGenerator<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a, C: Clock, D: Delay> {
+    module: &'a TmcmModule<'a, IF, Cell, T>,
+    port_number: u8,
+    clock: C,
+    delay: D,
+    latency: Duration,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>, C: Clock, D: Delay> PatternGenerator<'a, IF, Cell, T, C, D> {
+    /// Play patterns on output `port_number` of `module`, timed with `clock` and waiting with `delay`
+    pub fn new(module: &'a TmcmModule<'a, IF, Cell, T>, port_number: u8, clock: C, delay: D) -> Self {
+        PatternGenerator{module, port_number, clock, delay, latency: Duration::from_secs(0)}
+    }
+
+    /// The output the patterns are played on
+    pub fn port_number(&self) -> u8 {
+        self.port_number
+    }
+
+    /// The current estimate of the time from sending a `SIO` until it reaches the module
+    ///
+    /// Zero until the first `SIO` has been sent, then refined with every round trip.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// Play `pattern`, returning when the last step has been held for its duration
+    ///
+    /// The first change is sent immediately and the rest of the pattern is scheduled from the
+    /// time it is estimated to reach the module.
+    pub fn play(&mut self, pattern: &Pattern) -> Result<PatternReport, Error<IF::Error>> {
+        let mut report = PatternReport::default();
+        let mut start = None;
+        let mut offset = Duration::from_secs(0);
+        let mut state = None;
+        for _ in 0..pattern.repetitions {
+            for step in pattern.steps {
+                if state != Some(step.state) {
+                    let scheduled = start.map(|start: Duration| start + offset);
+                    if let Some(scheduled) = scheduled {
+                        self.wait_until(scheduled.saturating_sub(self.latency));
+                    }
+                    let sent = self.clock.now();
+                    self.module.write_command(SIO::new(OUTPUT_BANK, self.port_number, step.state))?;
+                    let round_trip = self.clock.now().saturating_sub(sent);
+                    let arrival = sent + self.update_latency(round_trip);
+                    match scheduled {
+                        Some(scheduled) => report.max_lateness = report.max_lateness.max(arrival.saturating_sub(scheduled)),
+                        None => start = Some(arrival),
+                    }
+                    report.changes += 1;
+                    state = Some(step.state);
+                }
+                offset += step.duration;
+            }
+        }
+        if let Some(start) = start {
+            self.wait_until(start + offset);
+        }
+        report.latency = self.latency;
+        Ok(report)
+    }
+
+    /// Release the clock and delay
+    pub fn into_inner(self) -> (C, D) {
+        (self.clock, self.delay)
+    }
+
+    /// Smooth the latency estimate with a new round trip, returning the latency of this round trip
+    fn update_latency(&mut self, round_trip: Duration) -> Duration {
+        let latency = round_trip / 2;
+        self.latency = if self.latency == Duration::from_secs(0) {
+            latency
+        } else {
+            (self.latency * 3 + latency) / 4
+        };
+        latency
+    }
+
+    fn wait_until(&mut self, time: Duration) {
+        let now = self.clock.now();
+        if time > now {
+            self.delay.delay(time - now);
+        }
+    }
+}
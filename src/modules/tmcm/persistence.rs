@@ -0,0 +1,145 @@
+//! Guarding against unintended EEPROM writes.
+//!
+//! Instructions like `STAP`, and `SGP` to the configuration in bank 0, write to the EEPROM of the module, which only endures a limited number
+//! of write cycles. When a module is created `with_persistence_guard` these instructions are
+//! rejected by `write_command` and `write_raw_command`, and can only be issued through
+//! `TmcmModule::write_persistent` with a `PersistenceToken`. The token counts the writes, can
+//! limit them and calls a hook on every write.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::Error;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::persistence::PersistenceToken;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug, PartialEq)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1).with_persistence_guard();
+//!
+//!     assert_eq!(
+//!         module.write_command(STAP::<MaximumPositioningSpeed>::new(0)),
+//!         Err(Error::PersistenceNotAllowed),
+//!     );
+//!
+//!     let mut token = PersistenceToken::new()
+//!         .with_limit(10)
+//!         .with_hook(|writes| println!("EEPROM written {} times", writes));
+//!     module.write_persistent(STAP::<MaximumPositioningSpeed>::new(0), &mut token).unwrap();
+//! }
+//! ```
+
+use Encode;
+use instructions::COORDINATE_EEPROM_MOTOR_NUMBER;
+use opcodes::{
+    OPCODE_STAP,
+    OPCODE_STGP,
+    OPCODE_SCO,
+    OPCODE_SGP,
+};
+
+/// Permission to write to the EEPROM of a module, keeping count of the writes.
+#[derive(Debug, Clone)]
+pub struct PersistenceToken {
+    writes: u32,
+    limit: Option<u32>,
+    hook: Option<fn(u32)>,
+}
+
+impl PersistenceToken {
+    /// Create a token allowing an unlimited number of writes
+    pub fn new() -> Self {
+        PersistenceToken{
+            writes: 0,
+            limit: None,
+            hook: None,
+        }
+    }
+
+    /// Allow at most `limit` writes with this token
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Call `hook` with the total number of writes after every write
+    pub fn with_hook(mut self, hook: fn(u32)) -> Self {
+        self.hook = Some(hook);
+        self
+    }
+
+    /// The number of writes issued with this token
+    pub fn writes(&self) -> u32 {
+        self.writes
+    }
+
+    /// The number of writes left before the limit is reached, `None` if unlimited
+    pub fn remaining(&self) -> Option<u32> {
+        self.limit.map(|limit| limit.saturating_sub(self.writes))
+    }
+
+    /// Count a write, returns false if the limit has been reached
+    pub(crate) fn record(&mut self) -> bool {
+        if self.remaining() == Some(0) {
+            return false;
+        }
+        self.writes += 1;
+        if let Some(hook) = self.hook {
+            hook(self.writes);
+        }
+        true
+    }
+}
+
+impl Default for PersistenceToken {
+    fn default() -> Self {
+        PersistenceToken::new()
+    }
+}
+
+/// Returns true if the instruction writes to EEPROM (`STAP`, `STGP`, `SCO` to EEPROM and `SGP` to bank 0 below 128)
+///
+/// The global parameters 64 to 127 in bank 0, e.g. the addresses and bit rates, are stored in
+/// EEPROM as soon as they are set.
+///
+/// ```
+/// use tmcl::modules::tmcm::global_parameters::{SerialSecondaryAddress, TickTimer};
+/// use tmcl::modules::tmcm::instructions::SGP;
+/// use tmcl::modules::tmcm::persistence::writes_eeprom;
+///
+/// assert!(writes_eeprom(&SGP::new(SerialSecondaryAddress::new(3))));
+/// assert!(!writes_eeprom(&SGP::new(TickTimer::new(0))));
+/// ```
+pub fn writes_eeprom<E: Encode>(instruction: &E) -> bool {
+    let encoded = instruction.encode();
+    match encoded[0] {
+        OPCODE_STAP | OPCODE_STGP => true,
+        OPCODE_SCO => encoded[2] == COORDINATE_EEPROM_MOTOR_NUMBER,
+        OPCODE_SGP => encoded[2] == 0 && encoded[1] < 128,
+        _ => false,
+    }
+}
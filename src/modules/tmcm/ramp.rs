@@ -0,0 +1,87 @@
+//! Validating the ramp of the TMC428 motion controller.
+//!
+//! The maximum acceleration (axis parameter 5) is only executed correctly when it is within a
+//! range given by the pulse divisor (154) and the ramp divisor (153), see the TMC 428 datasheet (p.24).
+//! With `d = ramp_divisor - pulse_divisor` the range is
+//!
+//! - lower limit: `2^(d - 1)` if `d > 0`, otherwise 1
+//! - upper limit: `2^(d + 12) - 1` if `d < -1`, otherwise 2047
+//!
+//! `TmcmModule::set_ramp` reads the divisors of the axis and validates the acceleration against
+//! them before writing anything.
+//!
+//! ```
+//! use tmcl::modules::tmcm::ramp::*;
+//!
+//! assert_eq!(acceleration_limits(3, 7), Some((8, 2047)));
+//! assert_eq!(acceleration_limits(7, 3), Some((1, 255)));
+//!
+//! let error = validate_acceleration(4, 3, 7).unwrap_err();
+//! assert_eq!(error.min(), Some(8));
+//! ```
+
+/// The acceleration of a ramp is outside the range allowed by the divisors.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RampError {
+    acceleration: u16,
+    pulse_divisor: u8,
+    ramp_divisor: u8,
+}
+
+impl RampError {
+    /// The rejected acceleration
+    pub fn acceleration(&self) -> u16 {
+        self.acceleration
+    }
+
+    /// The pulse divisor of the axis
+    pub fn pulse_divisor(&self) -> u8 {
+        self.pulse_divisor
+    }
+
+    /// The ramp divisor of the axis
+    pub fn ramp_divisor(&self) -> u8 {
+        self.ramp_divisor
+    }
+
+    /// The lowest allowed acceleration, `None` if no acceleration is allowed with these divisors
+    pub fn min(&self) -> Option<u16> {
+        acceleration_limits(self.pulse_divisor, self.ramp_divisor).map(|(min, _)| min)
+    }
+
+    /// The highest allowed acceleration, `None` if no acceleration is allowed with these divisors
+    pub fn max(&self) -> Option<u16> {
+        acceleration_limits(self.pulse_divisor, self.ramp_divisor).map(|(_, max)| max)
+    }
+}
+
+impl ::lib::fmt::Display for RampError {
+    fn fmt(&self, f: &mut ::lib::fmt::Formatter) -> ::lib::fmt::Result {
+        write!(f, "acceleration {} not allowed with pulse divisor {} and ramp divisor {}", self.acceleration, self.pulse_divisor, self.ramp_divisor)
+    }
+}
+
+/// The lowest and highest allowed acceleration, `None` if the divisors allow no acceleration at all
+pub fn acceleration_limits(pulse_divisor: u8, ramp_divisor: u8) -> Option<(u16, u16)> {
+    let d = i32::from(ramp_divisor) - i32::from(pulse_divisor);
+    let min = if d > 0 { 1u32 << (d - 1) } else { 1 };
+    let max = match d {
+        _ if d + 12 <= 0 => 0,
+        _ if d < -1 => (1u32 << (d + 12)) - 1,
+        _ => 2047,
+    };
+    if min <= max {
+        Some((min as u16, max as u16))
+    } else {
+        None
+    }
+}
+
+/// Check that `acceleration` is allowed with the given divisors
+pub fn validate_acceleration(acceleration: u16, pulse_divisor: u8, ramp_divisor: u8) -> Result<(), RampError> {
+    match acceleration_limits(pulse_divisor, ramp_divisor) {
+        Some((min, max)) if acceleration >= min && acceleration <= max => Ok(()),
+        _ => Err(RampError{acceleration, pulse_divisor, ramp_divisor}),
+    }
+}
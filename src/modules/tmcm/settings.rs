@@ -0,0 +1,148 @@
+//! Configuring a module in one place.
+//!
+//! `TmcmModule::new` is enough for most uses. `TmcmModule::builder` returns `ModuleSettings`,
+//! gathering the address, deadline, retries, expected reply address and observer of a module.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::{ModuleEvent, RetryPolicy, TmcmModule as Module};
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn log(event: ModuleEvent) {
+//!     println!("{:?}", event);
+//! }
+//!
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::builder(&interface)
+//!         .address(3)
+//!         .timeout(Duration::from_millis(100))
+//!         .retry_policy(RetryPolicy::new(2))
+//!         .reply_address(2)
+//!         .observer(log)
+//!         .build();
+//!
+//!     module.write_command(ROR::new(0, 250)).unwrap();
+//! }
+//! ```
+
+use lib::ops::Deref;
+use lib::marker::PhantomData;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Interface;
+
+use modules::tmcm::{
+    TmcmModule,
+    ReplyAddressCheck,
+    RetryPolicy,
+    ModuleEvent,
+};
+
+/// The settings of a module, created with `TmcmModule::builder`.
+#[derive(Debug)]
+pub struct ModuleSettings<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell> + 'a> {
+    interface: T,
+    address: u8,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    reply_address_check: ReplyAddressCheck,
+    observer: Option<fn(ModuleEvent)>,
+    strict_reply_ordering: bool,
+    pd1: PhantomData<&'a IF>,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> ModuleSettings<'a, IF, Cell, T> {
+    /// Create settings for the module at address 1 on `interface`
+    pub fn new(interface: T) -> Self {
+        ModuleSettings{
+            interface,
+            address: 1,
+            timeout: None,
+            retry_policy: RetryPolicy::none(),
+            reply_address_check: ReplyAddressCheck::Disabled,
+            observer: None,
+            strict_reply_ordering: false,
+            pd1: PhantomData{},
+        }
+    }
+
+    /// The module address
+    pub fn address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// The deadline for every command, see `TmcmModule::with_timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How failed commands are retransmitted, see `TmcmModule::with_retry_policy`
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// The expected reply address of replies, see `TmcmModule::with_reply_address_check`
+    pub fn reply_address(mut self, reply_address: u8) -> Self {
+        self.reply_address_check = ReplyAddressCheck::Expect(reply_address);
+        self
+    }
+
+    /// The observer of the module, see `TmcmModule::with_observer`
+    pub fn observer(mut self, observer: fn(ModuleEvent)) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Discard stale replies, see `TmcmModule::with_strict_reply_ordering`
+    pub fn strict_reply_ordering(mut self) -> Self {
+        self.strict_reply_ordering = true;
+        self
+    }
+
+    /// Create the module
+    pub fn build(self) -> TmcmModule<'a, IF, Cell, T> {
+        let mut module = TmcmModule::new(self.interface, self.address)
+            .with_retry_policy(self.retry_policy)
+            .with_reply_address_check(self.reply_address_check);
+        if let Some(timeout) = self.timeout {
+            module = module.with_timeout(timeout);
+        }
+        if let Some(observer) = self.observer {
+            module = module.with_observer(observer);
+        }
+        if self.strict_reply_ordering {
+            module = module.with_strict_reply_ordering();
+        }
+        module
+    }
+}
@@ -0,0 +1,213 @@
+//! Snapshots of the complete configuration of a module.
+//!
+//! `ModuleSnapshot::capture` reads the axis parameters of every axis, the coordinate table of every
+//! axis and the global parameters, including the user variables. `restore` writes the writable
+//! part of a snapshot back, to the same module or to a replacement, e.g. when provisioning a fleet
+//! of machines or swapping a module returned for repair. With the `serde` feature, snapshots can
+//! be serialized with any serde format.
+//!
+//! Parameters the module does not support are left out of the snapshot. `ExtendedErrorFlags` is
+//! never read, since reading clears it. The addresses of the module (global parameters 66, 70, 71
+//! and 87) are captured but not restored, since the module would stop answering on its current
+//! address in the middle of the restore.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::snapshot::ModuleSnapshot;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let old = Module::new(&interface, 1).with_axis_count(3);
+//!     let snapshot = ModuleSnapshot::capture(&old).unwrap();
+//!
+//!     // The replacement module was given address 2.
+//!     let new = Module::new(&interface, 2).with_axis_count(3);
+//!     snapshot.restore(&new).unwrap();
+//! }
+//! ```
+
+use lib::ops::Deref;
+use std::vec::Vec;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use ErrStatus;
+use Interface;
+use opcodes::{
+    OPCODE_SAP,
+    OPCODE_GAP,
+    OPCODE_SGP,
+    OPCODE_GGP,
+    OPCODE_SCO,
+    OPCODE_GCO,
+};
+
+use modules::tmcm::TmcmModule;
+use modules::tmcm::parameter_info::AXIS_PARAMETERS;
+use modules::tmcm::user_variables::{
+    DEFAULT_USER_VARIABLE_COUNT,
+    USER_VARIABLE_BANK,
+};
+
+/// The number of coordinates per axis in the coordinate table.
+pub const COORDINATE_COUNT: u8 = 20;
+
+/// The global parameters captured from bank 0, as `(number, restored)`.
+const GLOBAL_PARAMETERS: &[(u8, bool)] = &[
+    (66, false),
+    (70, false),
+    (71, false),
+    (84, true),
+    (87, false),
+];
+
+/// The axis parameter that is cleared when read, `ExtendedErrorFlags`.
+const CLEARED_ON_READ: u8 = 207;
+
+/// A parameter value in a snapshot.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParameterValue {
+    pub bank: u8,
+    pub number: u8,
+    pub value: i32,
+}
+
+/// The axis parameters and coordinates of one axis.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AxisSnapshot {
+    pub motor_number: u8,
+
+    /// The axis parameters, the bank is always 0
+    pub parameters: Vec<ParameterValue>,
+
+    /// The positions of the coordinates 1 to `COORDINATE_COUNT`
+    pub coordinates: Vec<i32>,
+}
+
+impl AxisSnapshot {
+    /// The value of the axis parameter with the given number, if it was captured
+    pub fn parameter(&self, number: u8) -> Option<i32> {
+        self.parameters.iter().find(|parameter| parameter.number == number).map(|parameter| parameter.value)
+    }
+}
+
+/// The configuration of a module.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleSnapshot {
+    pub axes: Vec<AxisSnapshot>,
+
+    /// The global parameters of bank 0 and the user variables
+    pub globals: Vec<ParameterValue>,
+}
+
+impl ModuleSnapshot {
+    /// Read the configuration of every axis of `module` and its global parameters
+    pub fn capture<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>>(module: &'a TmcmModule<'a, IF, Cell, T>) -> Result<Self, Error<IF::Error>> {
+        let mut axes = Vec::new();
+        for axis in module.axes() {
+            let motor_number = axis.motor_number();
+            let mut parameters = Vec::new();
+            for info in AXIS_PARAMETERS.iter().filter(|info| info.number() != CLEARED_ON_READ) {
+                if let Some(value) = read(module, OPCODE_GAP, info.number(), motor_number)? {
+                    parameters.push(ParameterValue{bank: 0, number: info.number(), value});
+                }
+            }
+            let mut coordinates = Vec::new();
+            for coordinate_number in 1..=COORDINATE_COUNT {
+                match read(module, OPCODE_GCO, coordinate_number, motor_number)? {
+                    Some(position) => coordinates.push(position),
+                    None => break,
+                }
+            }
+            axes.push(AxisSnapshot{motor_number, parameters, coordinates});
+        }
+        let mut globals = Vec::new();
+        for &(number, _) in GLOBAL_PARAMETERS {
+            if let Some(value) = read(module, OPCODE_GGP, number, 0)? {
+                globals.push(ParameterValue{bank: 0, number, value});
+            }
+        }
+        for number in 0..DEFAULT_USER_VARIABLE_COUNT {
+            match read(module, OPCODE_GGP, number, USER_VARIABLE_BANK)? {
+                Some(value) => globals.push(ParameterValue{bank: USER_VARIABLE_BANK, number, value}),
+                None => break,
+            }
+        }
+        Ok(ModuleSnapshot{axes, globals})
+    }
+
+    /// The axis with the given motor number, if it was captured
+    pub fn axis(&self, motor_number: u8) -> Option<&AxisSnapshot> {
+        self.axes.iter().find(|axis| axis.motor_number == motor_number)
+    }
+
+    /// The value of a global parameter, if it was captured
+    pub fn global(&self, bank: u8, number: u8) -> Option<i32> {
+        self.globals.iter().find(|global| global.bank == bank && global.number == number).map(|global| global.value)
+    }
+
+    /// Write the writable axis parameters, the coordinates and the global parameters except the
+    /// addresses to `module`
+    ///
+    /// The values are written to RAM, use `TmcmModule::write_persistent` with `STAP` and `STGP` to
+    /// store them.
+    pub fn restore<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>>(&self, module: &'a TmcmModule<'a, IF, Cell, T>) -> Result<(), Error<IF::Error>> {
+        for axis in &self.axes {
+            for parameter in &axis.parameters {
+                if AXIS_PARAMETERS.iter().any(|info| info.number() == parameter.number && info.is_writable()) {
+                    module.write_raw_command(OPCODE_SAP, parameter.number, axis.motor_number, parameter.value)?;
+                }
+            }
+            for (coordinate_number, &position) in (1..=COORDINATE_COUNT).zip(axis.coordinates.iter()) {
+                module.write_raw_command(OPCODE_SCO, coordinate_number, axis.motor_number, position)?;
+            }
+        }
+        for global in &self.globals {
+            let restored = global.bank == USER_VARIABLE_BANK
+                || GLOBAL_PARAMETERS.iter().any(|&(number, restored)| global.bank == 0 && number == global.number && restored);
+            if restored {
+                module.write_raw_command(OPCODE_SGP, global.number, global.bank, global.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read a value, `None` if the module does not support it
+fn read<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>>(module: &'a TmcmModule<'a, IF, Cell, T>, instruction_number: u8, type_number: u8, motor_bank_number: u8) -> Result<Option<i32>, Error<IF::Error>> {
+    match module.write_raw_command(instruction_number, type_number, motor_bank_number, 0) {
+        Ok(operand) => Ok(Some(operand.to_i32())),
+        Err(Error::ProtocolError(ErrStatus::WrongType, _)) |
+        Err(Error::ProtocolError(ErrStatus::InvalidCommand, _)) |
+        Err(Error::ProtocolError(ErrStatus::InvalidValue, _)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
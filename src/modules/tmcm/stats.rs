@@ -0,0 +1,148 @@
+//! Communication statistics of a module.
+//!
+//! Every `TmcmModule` counts the commands it transmits and the ways they fail. A growing number
+//! of retries, checksum errors or timeouts, or an increasing round-trip time, is an early sign of
+//! degrading cabling or termination. The counters can be exported to a metrics system by
+//! implementing `Metrics`.
+//!
+//! ```
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::stats::{Metrics, ModuleStats};
+//!
+//! struct Printer;
+//!
+//! impl Metrics for Printer {
+//!     fn counter(&mut self, address: u8, name: &'static str, value: u64) {
+//!         println!("module {} {} = {}", address, name, value);
+//!     }
+//!
+//!     fn duration(&mut self, address: u8, name: &'static str, value: Duration) {
+//!         println!("module {} {} = {:?}", address, name, value);
+//!     }
+//! }
+//!
+//! let stats = ModuleStats::new();
+//! assert_eq!(stats.commands_sent(), 0);
+//! assert_eq!(stats.average_round_trip(), None);
+//! stats.export(1, &mut Printer);
+//! ```
+
+use lib::time::Duration;
+
+/// A destination for the statistics of modules, e.g. a metrics registry.
+pub trait Metrics {
+    /// Record a monotonically increasing counter of the module with the given address
+    fn counter(&mut self, address: u8, name: &'static str, value: u64);
+
+    /// Record a duration of the module with the given address
+    fn duration(&mut self, address: u8, name: &'static str, value: Duration);
+}
+
+/// Counters of the communication with one module, returned by `TmcmModule::stats`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ModuleStats {
+    commands_sent: u64,
+    retries: u64,
+    checksum_errors: u64,
+    timeouts: u64,
+    out_of_spec: u64,
+    round_trips: u64,
+    round_trip_total: Duration,
+}
+
+impl ModuleStats {
+    /// Statistics where every counter is zero
+    pub fn new() -> Self {
+        ModuleStats{
+            commands_sent: 0,
+            retries: 0,
+            checksum_errors: 0,
+            timeouts: 0,
+            out_of_spec: 0,
+            round_trips: 0,
+            round_trip_total: Duration::from_secs(0),
+        }
+    }
+
+    /// The number of commands transmitted, including retransmissions
+    pub fn commands_sent(&self) -> u64 {
+        self.commands_sent
+    }
+
+    /// The number of retransmissions caused by the `RetryPolicy`
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    /// The number of replies where the module reported a wrong checksum
+    pub fn checksum_errors(&self) -> u64 {
+        self.checksum_errors
+    }
+
+    /// The number of commands where no reply was received in time
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts
+    }
+
+    /// The number of values read from the module outside of their documented range
+    ///
+    /// Only counted with `TmcmModule::with_reply_validation`.
+    pub fn out_of_spec_values(&self) -> u64 {
+        self.out_of_spec
+    }
+
+    /// The average time from starting to transmit a command until its reply was received
+    ///
+    /// Round trips are only measured with `std`, without it this is always `None`.
+    pub fn average_round_trip(&self) -> Option<Duration> {
+        if self.round_trips == 0 {
+            None
+        } else {
+            Some(self.round_trip_total / self.round_trips as u32)
+        }
+    }
+
+    /// Write all statistics of the module with the given address to `metrics`
+    pub fn export<M: Metrics>(&self, address: u8, metrics: &mut M) {
+        metrics.counter(address, "commands_sent", self.commands_sent);
+        metrics.counter(address, "retries", self.retries);
+        metrics.counter(address, "checksum_errors", self.checksum_errors);
+        metrics.counter(address, "timeouts", self.timeouts);
+        metrics.counter(address, "out_of_spec_values", self.out_of_spec);
+        if let Some(average) = self.average_round_trip() {
+            metrics.duration(address, "average_round_trip", average);
+        }
+    }
+
+    /// Count a transmitted command
+    pub(crate) fn record_command(&mut self) {
+        self.commands_sent += 1;
+    }
+
+    /// Count a retransmission
+    pub(crate) fn record_retry(&mut self) {
+        self.retries += 1;
+    }
+
+    /// Count a reply with `ErrStatus::WrongChecksum`
+    pub(crate) fn record_checksum_error(&mut self) {
+        self.checksum_errors += 1;
+    }
+
+    /// Count a command without a reply in time
+    pub(crate) fn record_timeout(&mut self) {
+        self.timeouts += 1;
+    }
+
+    /// Count a value outside of its documented range
+    pub(crate) fn record_out_of_spec(&mut self) {
+        self.out_of_spec += 1;
+    }
+
+    /// Add the round trip time of a command to the average
+    #[cfg(feature = "std")]
+    pub(crate) fn record_round_trip(&mut self, round_trip: Duration) {
+        self.round_trips += 1;
+        self.round_trip_total += round_trip;
+    }
+}
@@ -0,0 +1,203 @@
+//! Streaming a trajectory of absolute targets to an axis.
+//!
+//! A `Streamer` holds a bounded queue of targets and feeds them to the axis with `MVP ABS` while
+//! the axis is moving. The next target is sent when the axis is within the lookahead distance of
+//! the current one, so the axis continues towards it without stopping in between. The queue is
+//! bounded by `N`, so a producer computing targets faster than the axis can move them has to wait
+//! for `push` to succeed again, and `poll` tells it when the queue has run dry while the axis is
+//! still moving.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::streaming::{StreamState, Streamer};
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!     let mut streamer: Streamer<_, _, _, 8> = Streamer::new(module.axis(0), 200);
+//!
+//!     let mut targets = (1..100).map(|i| i * 1000);
+//!     let mut next = targets.next();
+//!     loop {
+//!         while let Some(target) = next {
+//!             if streamer.push(target).is_err() {
+//!                 break;
+//!             }
+//!             next = targets.next();
+//!         }
+//!         match streamer.poll().unwrap() {
+//!             StreamState::Finished if next.is_none() => break,
+//!             StreamState::Starved => println!("the trajectory is computed too slowly"),
+//!             _ => std::thread::sleep(std::time::Duration::from_millis(10)),
+//!         }
+//!     }
+//! }
+//! ```
+
+use lib::ops::Deref;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use clock::{Clock, Delay, Poller};
+
+use modules::tmcm::axis::Axis;
+use modules::tmcm::axis_parameters::{
+    ActualPosition,
+    TargetPositionReached,
+};
+use modules::tmcm::instructions::MoveOperation;
+
+/// The state of a `Streamer` after a poll.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StreamState {
+    /// The axis is moving and targets are queued.
+    Streaming,
+
+    /// The axis is moving towards the last target sent, but the queue is empty.
+    Starved,
+
+    /// The queue is empty and the axis has reached the last target sent.
+    Finished,
+}
+
+/// The target could not be queued because the queue is full, contains the rejected target.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct QueueFull(pub i32);
+
+/// Feeds a bounded queue of absolute targets to an axis.
+#[derive(Debug)]
+pub struct Streamer<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a, const N: usize> {
+    axis: Axis<'a, IF, Cell, T>,
+    lookahead: u32,
+    queue: [i32; N],
+    head: usize,
+    len: usize,
+    current: Option<i32>,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>, const N: usize> Streamer<'a, IF, Cell, T, N> {
+    /// Stream to `axis`, sending the next target when the axis is within `lookahead` of the current one
+    pub fn new(axis: Axis<'a, IF, Cell, T>, lookahead: u32) -> Self {
+        Streamer{
+            axis,
+            lookahead,
+            queue: [0; N],
+            head: 0,
+            len: 0,
+            current: None,
+        }
+    }
+
+    /// The axis the targets are streamed to
+    pub fn axis(&self) -> Axis<'a, IF, Cell, T> {
+        self.axis
+    }
+
+    /// The target the axis is moving towards, `None` before the first target was sent
+    pub fn current(&self) -> Option<i32> {
+        self.current
+    }
+
+    /// The number of queued targets that have not been sent yet
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no targets are queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if no more targets can be queued
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Queue an absolute target
+    ///
+    /// The target is sent by a later `poll`.
+    pub fn push(&mut self, target: i32) -> Result<(), QueueFull> {
+        if self.is_full() {
+            return Err(QueueFull(target));
+        }
+        self.queue[(self.head + self.len) % N] = target;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Forget the queued targets and stop the motor
+    pub fn abort(&mut self) -> Result<(), Error<IF::Error>> {
+        self.len = 0;
+        self.current = None;
+        self.axis.stop()
+    }
+
+    /// Send the next target if the axis is close enough to the current one
+    ///
+    /// Reads `TargetPositionReached`, and `ActualPosition` while the axis is moving and targets are queued.
+    pub fn poll(&mut self) -> Result<StreamState, Error<IF::Error>> {
+        let reached = match self.current {
+            None => true,
+            Some(_) => bool::from(self.axis.get::<TargetPositionReached>()?),
+        };
+        if self.is_empty() {
+            return Ok(if reached { StreamState::Finished } else { StreamState::Starved });
+        }
+        let due = match self.current {
+            Some(target) if !reached => i32::from(self.axis.get::<ActualPosition>()?).abs_diff(target) <= self.lookahead,
+            _ => true,
+        };
+        if due {
+            let target = self.queue[self.head];
+            self.axis.move_to(MoveOperation::Absolute(target))?;
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.current = Some(target);
+        }
+        Ok(StreamState::Streaming)
+    }
+
+    /// Poll until the queued targets have been sent and the last one is reached, paced by a `Poller`
+    ///
+    /// Returns `Error::Timeout` if the poller's timeout passes first. The axis keeps moving.
+    pub fn finish_with<C: Clock, D: Delay>(&mut self, poller: &mut Poller<C, D>) -> Result<(), Error<IF::Error>> {
+        poller.start();
+        loop {
+            if self.poll()? == StreamState::Finished {
+                return Ok(());
+            }
+            if poller.expired() {
+                return Err(Error::Timeout);
+            }
+            poller.pause();
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Reading a set of parameters at a fixed rate.
+//!
+//! A `Subscription` reads a fixed list of axis parameters with `GAP` every period and delivers the
+//! values as a `Sample`, which is the building block for dashboards and data logging. The periods
+//! are scheduled from the first sample, so the rate does not drift with the time the reads take.
+//! When the reads take longer than a period, or the application polls too late, the periods that
+//! were missed are skipped and counted as overruns, both in the sample following them and in
+//! `Subscription::overruns`.
+//!
+//! Samples can be taken without blocking with `poll`, one at a time with `next_sample`, as an
+//! iterator with `samples`, or delivered to a callback with `run_until`. A callback sending the
+//! samples to a `std::sync::mpsc` channel hands them to another thread.
+//!
+//! ```
+//! # #[cfg(feature = "std")]
+//! fn main() {
+//!     use std::cell::RefCell;
+//!     use std::time::Duration;
+//!     use tmcl::clock::MockClock;
+//!     use tmcl::opcodes::OPCODE_GAP;
+//!     use tmcl::testing::{expect_command, Ordering, Script};
+//!     use tmcl::modules::tmcm::TmcmModule as Module;
+//!     use tmcl::modules::tmcm::subscription::Subscription;
+//!
+//!     let mut script = Script::new(Ordering::Strict);
+//!     for position in 0..3 {
+//!         script.push(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(position * 100));
+//!         script.push(expect_command(OPCODE_GAP).type_number(3..=3).reply_with_value(100));
+//!     }
+//!     let interface = RefCell::new(script);
+//!     let module = Module::new(&interface, 1);
+//!
+//!     // The actual position and the actual speed of motor 0, ten times a second.
+//!     let clock = MockClock::new();
+//!     let mut subscription = Subscription::new(&module, [(0, 1), (0, 3)], &clock, Duration::from_millis(100));
+//!
+//!     let samples: Vec<_> = subscription.samples(&clock).take(3).map(Result::unwrap).collect();
+//!     assert_eq!(samples[2].values(), &[200, 100]);
+//!     assert_eq!(samples[2].timestamp(), Duration::from_millis(200));
+//!     assert_eq!(subscription.overruns(), 0);
+//! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use cancel::Cancel;
+use clock::{Clock, Delay};
+use opcodes::OPCODE_GAP;
+
+use modules::tmcm::TmcmModule;
+
+/// The values of the parameters of a `Subscription` read in one period.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sample<const N: usize> {
+    timestamp: Duration,
+    values: [i32; N],
+    missed: u32,
+}
+
+impl<const N: usize> Sample<N> {
+    /// The time of the clock of the subscription when the reads started
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// The values, in the order of the entries of the subscription
+    pub fn values(&self) -> &[i32; N] {
+        &self.values
+    }
+
+    /// The number of periods missed since the previous sample
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+}
+
+/// Reads a list of axis parameters every period.
+#[derive(Debug)]
+pub struct Subscription<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a, C: Clock, const N: usize> {
+    module: &'a TmcmModule<'a, IF, Cell, T>,
+    entries: [(u8, u8); N],
+    clock: C,
+    period: Duration,
+    next: Option<Duration>,
+    overruns: u32,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>, C: Clock, const N: usize> Subscription<'a, IF, Cell, T, C, N> {
+    /// Read the axis parameters given as `(motor number, parameter number)` every `period`
+    ///
+    /// The first sample is taken on the first poll.
+    pub fn new(module: &'a TmcmModule<'a, IF, Cell, T>, entries: [(u8, u8); N], clock: C, period: Duration) -> Self {
+        assert!(period > Duration::from_secs(0), "the period of a subscription must not be zero");
+        Subscription{
+            module,
+            entries,
+            clock,
+            period,
+            next: None,
+            overruns: 0,
+        }
+    }
+
+    /// The entries read every period, as `(motor number, parameter number)`
+    pub fn entries(&self) -> &[(u8, u8); N] {
+        &self.entries
+    }
+
+    /// The time between samples
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// The number of periods missed since the subscription was created
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+
+    /// The time until the next sample is due, zero if it is due now
+    pub fn until_due(&mut self) -> Duration {
+        match self.next {
+            Some(next) => next.checked_sub(self.clock.now()).unwrap_or_default(),
+            None => Duration::from_secs(0),
+        }
+    }
+
+    /// Read the parameters if the next sample is due, without waiting
+    ///
+    /// If a read fails the error is returned and the period counts as taken, so the next poll
+    /// waits for the following period.
+    pub fn poll(&mut self) -> Result<Option<Sample<N>>, Error<IF::Error>> {
+        let now = self.clock.now();
+        let due = self.next.unwrap_or(now);
+        if now < due {
+            return Ok(None);
+        }
+        let missed = ((now - due).as_nanos() / self.period.as_nanos()) as u32;
+        self.next = Some(due + self.period * (missed + 1));
+        self.overruns = self.overruns.saturating_add(missed);
+
+        let mut values = [0; N];
+        for (value, &(motor_number, parameter_number)) in values.iter_mut().zip(self.entries.iter()) {
+            *value = self.module.write_raw_command(OPCODE_GAP, parameter_number, motor_number, 0)?.to_i32();
+        }
+        Ok(Some(Sample{timestamp: now, values, missed}))
+    }
+
+    /// Wait with `delay` until the next sample is due and take it
+    pub fn next_sample<D: Delay>(&mut self, delay: &mut D) -> Result<Sample<N>, Error<IF::Error>> {
+        loop {
+            if let Some(sample) = self.poll()? {
+                return Ok(sample);
+            }
+            let remaining = self.until_due();
+            delay.delay(remaining);
+        }
+    }
+
+    /// An endless iterator over the samples, waiting with `delay` between them
+    pub fn samples<D: Delay>(&mut self, delay: D) -> Samples<'_, 'a, IF, Cell, T, C, D, N> {
+        Samples{subscription: self, delay}
+    }
+
+    /// Call `callback` with every sample until `cancel` is cancelled, waiting with `delay` between them
+    ///
+    /// Cancellation is checked before every sample. Returns the first error reading a sample.
+    pub fn run_until<D: Delay, X: Cancel, F: FnMut(&Sample<N>)>(&mut self, mut delay: D, mut cancel: X, mut callback: F) -> Result<(), Error<IF::Error>> {
+        while !cancel.is_cancelled() {
+            callback(&self.next_sample(&mut delay)?);
+        }
+        Ok(())
+    }
+}
+
+/// The samples of a `Subscription`, see `Subscription::samples`.
+#[derive(Debug)]
+pub struct Samples<'s, 'a: 's, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a, C: Clock + 's, D: Delay, const N: usize> {
+    subscription: &'s mut Subscription<'a, IF, Cell, T, C, N>,
+    delay: D,
+}
+
+impl<'s, 'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>, C: Clock, D: Delay, const N: usize> Iterator for Samples<'s, 'a, IF, Cell, T, C, D, N> {
+    type Item = Result<Sample<N>, Error<IF::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.subscription.next_sample(&mut self.delay))
+    }
+}
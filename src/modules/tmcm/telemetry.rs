@@ -0,0 +1,138 @@
+//! Writing the samples of a subscription to CSV files.
+//!
+//! A `CsvSink` writes one row per `Sample` of a `Subscription`, with the wall clock time of the
+//! sample as an ISO 8601 timestamp in UTC, the number of periods missed before it and a column
+//! per entry. The columns are named after the parameter and the motor, e.g. `ActualPosition_0`,
+//! using the names in `parameter_info`, so the files can be loaded into any analysis tool as they
+//! are.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//! use tmcl::clock::MockClock;
+//! use tmcl::opcodes::OPCODE_GAP;
+//! use tmcl::testing::{expect_command, Ordering, Script};
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::subscription::Subscription;
+//! use tmcl::modules::tmcm::telemetry::CsvSink;
+//!
+//! let script = Script::new(Ordering::Strict)
+//!     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(1000))
+//!     .expect(expect_command(OPCODE_GAP).type_number(3..=3).reply_with_value(-20));
+//! let interface = RefCell::new(script);
+//! let module = Module::new(&interface, 1);
+//!
+//! let clock = MockClock::new();
+//! let entries = [(0, 1), (0, 3)];
+//! let mut subscription = Subscription::new(&module, entries, &clock, Duration::from_millis(100));
+//!
+//! // The wall clock time when the mock clock read zero.
+//! let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+//! let mut sink = CsvSink::new(Vec::new(), &entries, start).unwrap();
+//! for sample in subscription.samples(&clock).take(1) {
+//!     sink.write(&sample.unwrap()).unwrap();
+//! }
+//! assert_eq!(
+//!     String::from_utf8(sink.into_inner()).unwrap(),
+//!     "timestamp,missed,ActualPosition_0,ActualSpeed_0\n2023-11-14T22:13:20.000000Z,0,1000,-20\n",
+//! );
+//! ```
+
+use std::io;
+use std::io::Write;
+use std::string::String;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use modules::tmcm::parameter_info::axis_parameter;
+use modules::tmcm::subscription::Sample;
+
+/// The name of the column of an entry, the parameter name followed by the motor number
+///
+/// ```
+/// use tmcl::modules::tmcm::telemetry::column_name;
+///
+/// assert_eq!(column_name(1, 4), "MaximumPositioningSpeed_1");
+/// assert_eq!(column_name(0, 250), "Parameter250_0");
+/// ```
+pub fn column_name(motor_number: u8, parameter_number: u8) -> String {
+    match axis_parameter(parameter_number) {
+        Some(info) => format!("{}_{}", info.name(), motor_number),
+        None => format!("Parameter{}_{}", parameter_number, motor_number),
+    }
+}
+
+/// Writes samples as rows of a CSV file.
+#[derive(Debug)]
+pub struct CsvSink<W: Write> {
+    writer: W,
+    start: SystemTime,
+}
+
+impl<W: Write> CsvSink<W> {
+    /// Write the header for `entries`, given as `(motor number, parameter number)`
+    ///
+    /// `start` is the wall clock time at which the clock of the subscription read zero, e.g.
+    /// `SystemTime::now()` when creating a `StdClock`.
+    pub fn new(mut writer: W, entries: &[(u8, u8)], start: SystemTime) -> io::Result<Self> {
+        write!(writer, "timestamp,missed")?;
+        for &(motor_number, parameter_number) in entries {
+            write!(writer, ",{}", column_name(motor_number, parameter_number))?;
+        }
+        writeln!(writer)?;
+        Ok(CsvSink{writer, start})
+    }
+
+    /// Write `sample` as a row
+    pub fn write<const N: usize>(&mut self, sample: &Sample<N>) -> io::Result<()> {
+        write_timestamp(&mut self.writer, self.start + sample.timestamp())?;
+        write!(self.writer, ",{}", sample.missed())?;
+        for value in sample.values() {
+            write!(self.writer, ",{}", value)?;
+        }
+        writeln!(self.writer)
+    }
+
+    /// Flush the writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Release the writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Write `time` in ISO 8601 format in UTC with microseconds, times before 1970 as 1970
+fn write_timestamp<W: Write>(writer: &mut W, time: SystemTime) -> io::Result<()> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days(seconds / 86400);
+    let seconds_of_day = seconds % 86400;
+    write!(
+        writer,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_micros(),
+    )
+}
+
+/// The date of the given number of days since 1970-01-01 in the proleptic Gregorian calendar
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // Count from 0000-03-01, so the leap day is the last day of a year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,218 @@
+//! Correlating the tick timer of a module with the host clock.
+//!
+//! The `TickTimer` global parameter counts milliseconds since the module was powered on. Module
+//! side timestamps, e.g. ticks stored by a TMCL program, can be related to host logs by sampling
+//! the timer with `TmcmModule::sample_tick_timer` and feeding the samples to a `TickSync`. Each
+//! sample assumes the module read its timer halfway through the round trip, so samples with a short
+//! round trip are the most accurate. With more than one sample, `TickSync` fits a line through them
+//! and also estimates how much faster or slower the module clock runs than the host clock.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::tick_timer::TickSync;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! # #[cfg(feature = "std")]
+//! fn main() {
+//!     use tmcl::clock::StdClock;
+//!
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!     let mut clock = StdClock::new();
+//!
+//!     let mut sync = TickSync::new();
+//!     for _ in 0..10 {
+//!         sync.add(module.sample_tick_timer(&mut clock).unwrap());
+//!         std::thread::sleep(Duration::from_millis(500));
+//!     }
+//!     println!("module clock drift: {:?} ppm", sync.drift_ppm());
+//!     println!("tick 123456 happened at {:?} on the host clock", sync.host_time(123456));
+//! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+
+use lib::time::Duration;
+
+/// The tick timer of a module read at a known host time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TickSample {
+    host_time: Duration,
+    round_trip: Duration,
+    ticks: u32,
+}
+
+impl TickSample {
+    /// Create a sample of `ticks`, read halfway through a round trip taking `round_trip`
+    pub fn new(host_time: Duration, round_trip: Duration, ticks: u32) -> Self {
+        TickSample{host_time, round_trip, ticks}
+    }
+
+    /// The host time halfway through the round trip, as returned by the `Clock` used for sampling
+    pub fn host_time(&self) -> Duration {
+        self.host_time
+    }
+
+    /// The duration of the round trip, the uncertainty of the sample
+    pub fn round_trip(&self) -> Duration {
+        self.round_trip
+    }
+
+    /// The tick timer in milliseconds
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+}
+
+/// Estimates the offset and drift between the host clock and the tick timer of a module.
+///
+/// The tick timer is unwrapped relative to the first sample, so samples and conversions must be
+/// within about 24 days of it.
+///
+/// ```
+/// use std::time::Duration;
+/// use tmcl::modules::tmcm::tick_timer::{TickSample, TickSync};
+///
+/// let mut sync = TickSync::new();
+/// sync.add(TickSample::new(Duration::from_secs(10), Duration::from_millis(2), 5000));
+/// sync.add(TickSample::new(Duration::from_secs(20), Duration::from_millis(2), 15001));
+///
+/// assert_eq!(sync.drift_ppm().map(f64::round), Some(100.0));
+/// assert_eq!(sync.ticks(Duration::from_secs(30)), Some(25002));
+/// assert_eq!(sync.host_time(5000), Some(Duration::from_secs(10)));
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TickSync {
+    first: Option<TickSample>,
+    count: u32,
+    sum_host: f64,
+    sum_module: f64,
+    sum_host_host: f64,
+    sum_host_module: f64,
+}
+
+impl TickSync {
+    /// Create an estimate without samples
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sample to the estimate
+    pub fn add(&mut self, sample: TickSample) {
+        let first = *self.first.get_or_insert(sample);
+        let host = secs_between(first.host_time, sample.host_time) * 1000.0;
+        let module = f64::from(sample.ticks.wrapping_sub(first.ticks) as i32);
+        self.count += 1;
+        self.sum_host += host;
+        self.sum_module += module;
+        self.sum_host_host += host * host;
+        self.sum_host_module += host * module;
+    }
+
+    /// The number of samples added
+    pub fn len(&self) -> u32 {
+        self.count
+    }
+
+    /// Returns true if no samples have been added
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// How many milliseconds the module clock gains per million host milliseconds
+    ///
+    /// Negative if the module clock is slower. `None` until two samples at different host times
+    /// have been added.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        self.fit().map(|(slope, _)| (slope - 1.0) * 1_000_000.0)
+    }
+
+    /// The tick timer of the module at host time zero, in milliseconds
+    ///
+    /// This is negative if the module was powered on after the host clock started. `None` without
+    /// samples.
+    pub fn offset_ms(&self) -> Option<f64> {
+        let first = self.first?;
+        let (slope, intercept) = self.line()?;
+        let first_host = first.host_time.as_secs_f64() * 1000.0;
+        Some(f64::from(first.ticks) + intercept - slope * first_host)
+    }
+
+    /// The host time at which the tick timer had the value `ticks`
+    ///
+    /// `None` without samples or if `ticks` lies before host time zero.
+    pub fn host_time(&self, ticks: u32) -> Option<Duration> {
+        let first = self.first?;
+        let (slope, intercept) = self.line()?;
+        let module = f64::from(ticks.wrapping_sub(first.ticks) as i32);
+        let host = first.host_time.as_secs_f64() + (module - intercept) / slope / 1000.0;
+        if host < 0.0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(host))
+    }
+
+    /// The value of the tick timer at `host_time`, as returned by the `Clock` used for sampling
+    ///
+    /// `None` without samples.
+    pub fn ticks(&self, host_time: Duration) -> Option<u32> {
+        let first = self.first?;
+        let (slope, intercept) = self.line()?;
+        let host = secs_between(first.host_time, host_time) * 1000.0;
+        let module = intercept + slope * host;
+        let rounded = (if module < 0.0 { module - 0.5 } else { module + 0.5 }) as i64;
+        Some(first.ticks.wrapping_add(rounded as u32))
+    }
+
+    /// Slope and intercept of the least squares fit, in milliseconds relative to the first sample
+    fn fit(&self) -> Option<(f64, f64)> {
+        let n = f64::from(self.count);
+        let denominator = n * self.sum_host_host - self.sum_host * self.sum_host;
+        if self.count < 2 || denominator <= 0.0 {
+            return None;
+        }
+        let slope = (n * self.sum_host_module - self.sum_host * self.sum_module) / denominator;
+        Some((slope, (self.sum_module - slope * self.sum_host) / n))
+    }
+
+    /// The fitted line, assuming no drift while there are too few samples to fit one
+    fn line(&self) -> Option<(f64, f64)> {
+        if self.count == 0 {
+            return None;
+        }
+        self.fit().or_else(|| {
+            let n = f64::from(self.count);
+            Some((1.0, (self.sum_module - self.sum_host) / n))
+        })
+    }
+}
+
+/// `to - from` in seconds, negative if `to` is before `from`
+fn secs_between(from: Duration, to: Duration) -> f64 {
+    to.as_secs_f64() - from.as_secs_f64()
+}
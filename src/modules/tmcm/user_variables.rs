@@ -0,0 +1,202 @@
+//! The user variables of a module, optionally persisted in EEPROM.
+//!
+//! TMCM modules have general purpose user variables in global parameter bank 2 (56 on most
+//! modules). They are read with `GGP` and written with `SGP`. Variables stored to EEPROM with
+//! `STGP` are restored automatically at power up, which makes them useful for keeping e.g.
+//! calibration offsets on the module itself. Structs spanning several variables implement `Persist`.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::persistence::PersistenceToken;
+//! use tmcl::modules::tmcm::user_variables::Persist;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! #[derive(Debug, PartialEq)]
+//! struct Calibration {
+//!     offset: i32,
+//!     gain: i32,
+//! }
+//!
+//! impl Persist for Calibration {
+//!     const WORDS: usize = 2;
+//!
+//!     fn save(&self, words: &mut [i32]) {
+//!         words[0] = self.offset;
+//!         words[1] = self.gain;
+//!     }
+//!
+//!     fn load(words: &[i32]) -> Self {
+//!         Calibration{offset: words[0], gain: words[1]}
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1);
+//!     let variables = module.user_variables();
+//!
+//!     let mut token = PersistenceToken::new();
+//!     variables.save(10, &Calibration{offset: -12, gain: 1000}, &mut token).unwrap();
+//!     assert_eq!(variables.load::<Calibration>(10).unwrap(), Calibration{offset: -12, gain: 1000});
+//! }
+//! ```
+
+use lib::ops::Deref;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+use FromOperand;
+use Operand;
+use instructions::RawInstruction;
+use opcodes::{
+    OPCODE_SGP,
+    OPCODE_GGP,
+    OPCODE_STGP,
+    OPCODE_RSGP,
+};
+
+use modules::tmcm::TmcmModule;
+use modules::tmcm::persistence::PersistenceToken;
+
+/// The global parameter bank of the user variables.
+pub const USER_VARIABLE_BANK: u8 = 2;
+
+/// The number of user variables of most TMCM modules.
+pub const DEFAULT_USER_VARIABLE_COUNT: u8 = 56;
+
+/// A value stored in consecutive user variables.
+pub trait Persist: Sized {
+    /// The number of user variables used, at most 256
+    const WORDS: usize;
+
+    /// Write the value to `words`, which has length `WORDS`
+    fn save(&self, words: &mut [i32]);
+
+    /// Read the value from `words`, which has length `WORDS`
+    fn load(words: &[i32]) -> Self;
+}
+
+/// The user variables of a module, created with `TmcmModule::user_variables`.
+#[derive(Debug)]
+pub struct UserVariables<'a, IF: Interface + 'a, Cell: InteriorMut<'a, IF> + 'a, T: Deref<Target=Cell> + 'a> {
+    module: &'a TmcmModule<'a, IF, Cell, T>,
+    count: u8,
+}
+
+impl<'a, IF: Interface, Cell: InteriorMut<'a, IF>, T: Deref<Target=Cell>> UserVariables<'a, IF, Cell, T> {
+    /// Access the `DEFAULT_USER_VARIABLE_COUNT` user variables of `module`
+    pub fn new(module: &'a TmcmModule<'a, IF, Cell, T>) -> Self {
+        UserVariables{module, count: DEFAULT_USER_VARIABLE_COUNT}
+    }
+
+    /// Set the number of user variables of the module, used for bounds checking
+    pub fn with_count(mut self, count: u8) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// The number of user variables of the module
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+
+    /// Read a user variable with `GGP`
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn get(&self, index: u8) -> Result<i32, Error<IF::Error>> {
+        self.check_bounds(index, 1);
+        self.module.write_encoded(RawInstruction::new(OPCODE_GGP, index, USER_VARIABLE_BANK, 0)).map(i32::from_operand)
+    }
+
+    /// Write a user variable with `SGP`, the value is lost at power down unless it is stored
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn set(&self, index: u8, value: i32) -> Result<(), Error<IF::Error>> {
+        self.check_bounds(index, 1);
+        self.module.write_encoded::<_, Operand>(RawInstruction::new(OPCODE_SGP, index, USER_VARIABLE_BANK, value)).map(|_| ())
+    }
+
+    /// Store the current value of a user variable to EEPROM with `STGP`
+    ///
+    /// The write is counted by `token`, see `TmcmModule::write_persistent`.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn store(&self, index: u8, token: &mut PersistenceToken) -> Result<(), Error<IF::Error>> {
+        self.check_bounds(index, 1);
+        if !token.record() {
+            return Err(Error::PersistenceNotAllowed);
+        }
+        self.module.transmit_encoded::<_, Operand>(RawInstruction::new(OPCODE_STGP, index, USER_VARIABLE_BANK, 0)).map(|_| ())
+    }
+
+    /// Restore a user variable from EEPROM with `RSGP`
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn restore(&self, index: u8) -> Result<(), Error<IF::Error>> {
+        self.check_bounds(index, 1);
+        self.module.write_encoded::<_, Operand>(RawInstruction::new(OPCODE_RSGP, index, USER_VARIABLE_BANK, 0)).map(|_| ())
+    }
+
+    /// Write `value` to the user variables starting at `start` and store them to EEPROM
+    ///
+    /// # Panics
+    /// If the value does not fit in the user variables.
+    pub fn save<P: Persist>(&self, start: u8, value: &P, token: &mut PersistenceToken) -> Result<(), Error<IF::Error>> {
+        self.check_bounds(start, P::WORDS);
+        let mut words = [0i32; 256];
+        value.save(&mut words[..P::WORDS]);
+        for (offset, word) in words[..P::WORDS].iter().enumerate() {
+            self.set(start + offset as u8, *word)?;
+        }
+        for offset in 0..P::WORDS {
+            self.store(start + offset as u8, token)?;
+        }
+        Ok(())
+    }
+
+    /// Read a value from the user variables starting at `start`
+    ///
+    /// # Panics
+    /// If the value does not fit in the user variables.
+    pub fn load<P: Persist>(&self, start: u8) -> Result<P, Error<IF::Error>> {
+        self.check_bounds(start, P::WORDS);
+        let mut words = [0i32; 256];
+        for (offset, word) in words[..P::WORDS].iter_mut().enumerate() {
+            *word = self.get(start + offset as u8)?;
+        }
+        Ok(P::load(&words[..P::WORDS]))
+    }
+
+    fn check_bounds(&self, start: u8, words: usize) {
+        assert!(usize::from(start) + words <= usize::from(self.count), "user variable out of bounds");
+    }
+}
@@ -0,0 +1,325 @@
+//! Named instruction numbers and parameter numbers.
+//!
+//! Tooling working with raw instructions (parsers, fuzzers, bus sniffers) can use these instead of
+//! magic numbers. The typed instructions and parameters of the crate use the same numbers.
+//!
+//! ```
+//! use tmcl::opcodes::*;
+//!
+//! assert_eq!(OPCODE_MVP, 4);
+//! assert_eq!(ParameterNumber::axis(140), Some(ParameterNumber::MicrostepResolution));
+//! assert_eq!(ParameterNumber::MicrostepResolution.name(), "MicrostepResolution");
+//! ```
+
+use AxisParameter;
+use GlobalParameter;
+use modules::tmcm::axis_parameters;
+use modules::tmcm::global_parameters;
+use modules::tmcm::parameter_info::{self, ParameterInfo};
+
+/// The instruction number of `ROR`.
+pub const OPCODE_ROR: u8 = 1;
+/// The instruction number of `ROL`.
+pub const OPCODE_ROL: u8 = 2;
+/// The instruction number of `MST`.
+pub const OPCODE_MST: u8 = 3;
+/// The instruction number of `MVP`.
+pub const OPCODE_MVP: u8 = 4;
+/// The instruction number of `SAP`.
+pub const OPCODE_SAP: u8 = 5;
+/// The instruction number of `GAP`.
+pub const OPCODE_GAP: u8 = 6;
+/// The instruction number of `STAP`.
+pub const OPCODE_STAP: u8 = 7;
+/// The instruction number of `RSAP`.
+pub const OPCODE_RSAP: u8 = 8;
+/// The instruction number of `SGP`.
+pub const OPCODE_SGP: u8 = 9;
+/// The instruction number of `GGP`.
+pub const OPCODE_GGP: u8 = 10;
+/// The instruction number of `STGP`.
+pub const OPCODE_STGP: u8 = 11;
+/// The instruction number of `RSGP`.
+pub const OPCODE_RSGP: u8 = 12;
+/// The instruction number of `RFS`.
+pub const OPCODE_RFS: u8 = 13;
+/// The instruction number of `SIO`.
+pub const OPCODE_SIO: u8 = 14;
+/// The instruction number of `GIO`.
+pub const OPCODE_GIO: u8 = 15;
+/// The instruction number of `CALC`.
+pub const OPCODE_CALC: u8 = 19;
+/// The instruction number of `COMP`.
+pub const OPCODE_COMP: u8 = 20;
+/// The instruction number of `JC`.
+pub const OPCODE_JC: u8 = 21;
+/// The instruction number of `JA`.
+pub const OPCODE_JA: u8 = 22;
+/// The instruction number of `CSUB`.
+pub const OPCODE_CSUB: u8 = 23;
+/// The instruction number of `RSUB`.
+pub const OPCODE_RSUB: u8 = 24;
+/// The instruction number of `EI`.
+pub const OPCODE_EI: u8 = 25;
+/// The instruction number of `DI`.
+pub const OPCODE_DI: u8 = 26;
+/// The instruction number of `WAIT`.
+pub const OPCODE_WAIT: u8 = 27;
+/// The instruction number of `STOP`.
+pub const OPCODE_STOP: u8 = 28;
+/// The instruction number of `SCO`.
+pub const OPCODE_SCO: u8 = 30;
+/// The instruction number of `GCO`.
+pub const OPCODE_GCO: u8 = 31;
+/// The instruction number of `CCO`.
+pub const OPCODE_CCO: u8 = 32;
+/// The instruction number of `CALCX`.
+pub const OPCODE_CALCX: u8 = 33;
+/// The instruction number of `AAP`.
+pub const OPCODE_AAP: u8 = 34;
+/// The instruction number of `AGP`.
+pub const OPCODE_AGP: u8 = 35;
+/// The instruction number of `CLE`.
+pub const OPCODE_CLE: u8 = 36;
+/// The instruction number of `VECT`.
+pub const OPCODE_VECT: u8 = 37;
+/// The instruction number of `RETI`.
+pub const OPCODE_RETI: u8 = 38;
+/// The instruction number of `ACO`.
+pub const OPCODE_ACO: u8 = 39;
+
+/// Stop the running TMCL program.
+pub const OPCODE_STOP_APPLICATION: u8 = 128;
+/// Start the TMCL program.
+pub const OPCODE_RUN_APPLICATION: u8 = 129;
+/// Execute one instruction of the TMCL program.
+pub const OPCODE_STEP_APPLICATION: u8 = 130;
+/// Reset the program counter of the TMCL program.
+pub const OPCODE_RESET_APPLICATION: u8 = 131;
+/// Store received instructions in program memory instead of executing them.
+pub const OPCODE_ENTER_DOWNLOAD_MODE: u8 = 132;
+/// Leave download mode.
+pub const OPCODE_EXIT_DOWNLOAD_MODE: u8 = 133;
+/// Read an instruction from program memory.
+pub const OPCODE_READ_TMCL_MEMORY: u8 = 134;
+/// Get the status of the TMCL program.
+pub const OPCODE_GET_APPLICATION_STATUS: u8 = 135;
+/// Get the module type and firmware version.
+pub const OPCODE_GET_FIRMWARE_VERSION: u8 = 136;
+/// Restore the factory default settings.
+pub const OPCODE_RESTORE_FACTORY_DEFAULTS: u8 = 137;
+/// Request an event when the target position is reached.
+pub const OPCODE_REQUEST_TARGET_POSITION_REACHED_EVENT: u8 = 138;
+
+/// The axis and global parameters known by this crate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParameterNumber {
+    /// Axis parameter `ActualPosition`.
+    ActualPosition,
+    /// Axis parameter `ActualSpeed`.
+    ActualSpeed,
+    /// Axis parameter `MaximumPositioningSpeed`.
+    MaximumPositioningSpeed,
+    /// Axis parameter `MaximumAcceleration`.
+    MaximumAcceleration,
+    /// Axis parameter `AbsoluteMaxCurrent`.
+    AbsoluteMaxCurrent,
+    /// Axis parameter `StandbyCurrent`.
+    StandbyCurrent,
+    /// Axis parameter `TargetPositionReached`.
+    TargetPositionReached,
+    /// Axis parameter `RightLimitSwitchState`.
+    RightLimitSwitchState,
+    /// Axis parameter `LeftLimitSwitchState`.
+    LeftLimitSwitchState,
+    /// Axis parameter `RightLimitSwitchDisable`.
+    RightLimitSwitchDisable,
+    /// Axis parameter `LeftLimitSwitchDisable`.
+    LeftLimitSwitchDisable,
+    /// Axis parameter `MicrostepResolution`.
+    MicrostepResolution,
+    /// Axis parameter `SoftStopFlag`.
+    SoftStopFlag,
+    /// Axis parameter `RampDivisor`.
+    RampDivisor,
+    /// Axis parameter `PulseDivisor`.
+    PulseDivisor,
+    /// Axis parameter `ReferenceSearchMode`.
+    ReferenceSearchMode,
+    /// Axis parameter `ReferenceSearchSpeed`.
+    ReferenceSearchSpeed,
+    /// Axis parameter `ReferenceSwitchSpeed`.
+    ReferenceSwitchSpeed,
+    /// Axis parameter `EndSwitchDistance`.
+    EndSwitchDistance,
+    /// Axis parameter `ExtendedErrorFlags`.
+    ExtendedErrorFlags,
+    /// Axis parameter `DriverErrorFlags`.
+    DriverErrorFlags,
+    /// Axis parameter `PositionCaptureMode`.
+    PositionCaptureMode,
+    /// Axis parameter `CapturedPosition`.
+    CapturedPosition,
+    /// Global parameter `CoordinateStorage`.
+    CoordinateStorage,
+    /// Global parameter `SerialAddress`.
+    SerialAddress,
+    /// Global parameter `SerialSecondaryAddress`.
+    SerialSecondaryAddress,
+    /// Global parameter `SerialBaudRate`.
+    SerialBaudRate,
+    /// Global parameter `CanBitrate`.
+    CanBitrate,
+    /// Global parameter `TelegramPauseTime`.
+    TelegramPauseTime,
+    /// Global parameter `SerialHostAddress`.
+    SerialHostAddress,
+    /// Global parameter `CanReplyId`.
+    CanReplyId,
+    /// Global parameter `CanId`.
+    CanId,
+}
+
+impl ParameterNumber {
+    /// All known parameters, axis parameters first
+    pub const ALL: [ParameterNumber; 32] = [
+        ParameterNumber::ActualPosition,
+        ParameterNumber::ActualSpeed,
+        ParameterNumber::MaximumPositioningSpeed,
+        ParameterNumber::MaximumAcceleration,
+        ParameterNumber::AbsoluteMaxCurrent,
+        ParameterNumber::StandbyCurrent,
+        ParameterNumber::TargetPositionReached,
+        ParameterNumber::RightLimitSwitchState,
+        ParameterNumber::LeftLimitSwitchState,
+        ParameterNumber::RightLimitSwitchDisable,
+        ParameterNumber::LeftLimitSwitchDisable,
+        ParameterNumber::MicrostepResolution,
+        ParameterNumber::SoftStopFlag,
+        ParameterNumber::RampDivisor,
+        ParameterNumber::PulseDivisor,
+        ParameterNumber::ReferenceSearchMode,
+        ParameterNumber::ReferenceSearchSpeed,
+        ParameterNumber::ReferenceSwitchSpeed,
+        ParameterNumber::EndSwitchDistance,
+        ParameterNumber::ExtendedErrorFlags,
+        ParameterNumber::DriverErrorFlags,
+        ParameterNumber::PositionCaptureMode,
+        ParameterNumber::CapturedPosition,
+        ParameterNumber::CoordinateStorage,
+        ParameterNumber::SerialAddress,
+        ParameterNumber::SerialSecondaryAddress,
+        ParameterNumber::SerialBaudRate,
+        ParameterNumber::CanBitrate,
+        ParameterNumber::TelegramPauseTime,
+        ParameterNumber::SerialHostAddress,
+        ParameterNumber::CanReplyId,
+        ParameterNumber::CanId,
+    ];
+
+    /// The parameter number
+    pub fn number(&self) -> u8 {
+        match *self {
+            ParameterNumber::ActualPosition => axis_parameters::ActualPosition::NUMBER,
+            ParameterNumber::ActualSpeed => axis_parameters::ActualSpeed::NUMBER,
+            ParameterNumber::MaximumPositioningSpeed => axis_parameters::MaximumPositioningSpeed::NUMBER,
+            ParameterNumber::MaximumAcceleration => axis_parameters::MaximumAcceleration::NUMBER,
+            ParameterNumber::AbsoluteMaxCurrent => axis_parameters::AbsoluteMaxCurrent::NUMBER,
+            ParameterNumber::StandbyCurrent => axis_parameters::StandbyCurrent::NUMBER,
+            ParameterNumber::TargetPositionReached => axis_parameters::TargetPositionReached::NUMBER,
+            ParameterNumber::RightLimitSwitchState => axis_parameters::RightLimitSwitchState::NUMBER,
+            ParameterNumber::LeftLimitSwitchState => axis_parameters::LeftLimitSwitchState::NUMBER,
+            ParameterNumber::RightLimitSwitchDisable => axis_parameters::RightLimitSwitchDisable::NUMBER,
+            ParameterNumber::LeftLimitSwitchDisable => axis_parameters::LeftLimitSwitchDisable::NUMBER,
+            ParameterNumber::MicrostepResolution => axis_parameters::MicrostepResolution::NUMBER,
+            ParameterNumber::SoftStopFlag => axis_parameters::SoftStopFlag::NUMBER,
+            ParameterNumber::RampDivisor => axis_parameters::RampDivisor::NUMBER,
+            ParameterNumber::PulseDivisor => axis_parameters::PulseDivisor::NUMBER,
+            ParameterNumber::ReferenceSearchMode => axis_parameters::ReferenceSearchMode::NUMBER,
+            ParameterNumber::ReferenceSearchSpeed => axis_parameters::ReferenceSearchSpeed::NUMBER,
+            ParameterNumber::ReferenceSwitchSpeed => axis_parameters::ReferenceSwitchSpeed::NUMBER,
+            ParameterNumber::EndSwitchDistance => axis_parameters::EndSwitchDistance::NUMBER,
+            ParameterNumber::ExtendedErrorFlags => axis_parameters::ExtendedErrorFlags::NUMBER,
+            ParameterNumber::DriverErrorFlags => axis_parameters::DriverErrorFlags::NUMBER,
+            ParameterNumber::PositionCaptureMode => axis_parameters::PositionCaptureMode::NUMBER,
+            ParameterNumber::CapturedPosition => axis_parameters::CapturedPosition::NUMBER,
+            ParameterNumber::CoordinateStorage => global_parameters::CoordinateStorage::NUMBER,
+            ParameterNumber::SerialAddress => global_parameters::SerialAddress::NUMBER,
+            ParameterNumber::SerialSecondaryAddress => global_parameters::SerialSecondaryAddress::NUMBER,
+            ParameterNumber::SerialBaudRate => global_parameters::SerialBaudRate::NUMBER,
+            ParameterNumber::CanBitrate => global_parameters::CanBitrate::NUMBER,
+            ParameterNumber::TelegramPauseTime => global_parameters::TelegramPauseTime::NUMBER,
+            ParameterNumber::SerialHostAddress => global_parameters::SerialHostAddress::NUMBER,
+            ParameterNumber::CanReplyId => global_parameters::CanReplyId::NUMBER,
+            ParameterNumber::CanId => global_parameters::CanId::NUMBER,
+        }
+    }
+
+    /// The bank of a global parameter, `None` for axis parameters
+    pub fn global_bank(&self) -> Option<u8> {
+        match *self {
+            ParameterNumber::CoordinateStorage => Some(global_parameters::CoordinateStorage::BANK),
+            ParameterNumber::SerialAddress => Some(global_parameters::SerialAddress::BANK),
+            ParameterNumber::SerialSecondaryAddress => Some(global_parameters::SerialSecondaryAddress::BANK),
+            ParameterNumber::SerialBaudRate => Some(global_parameters::SerialBaudRate::BANK),
+            ParameterNumber::CanBitrate => Some(global_parameters::CanBitrate::BANK),
+            ParameterNumber::TelegramPauseTime => Some(global_parameters::TelegramPauseTime::BANK),
+            ParameterNumber::SerialHostAddress => Some(global_parameters::SerialHostAddress::BANK),
+            ParameterNumber::CanReplyId => Some(global_parameters::CanReplyId::BANK),
+            ParameterNumber::CanId => Some(global_parameters::CanId::BANK),
+            _ => None,
+        }
+    }
+
+    /// The name of the parameter, the same as the name of its type
+    ///
+    /// The names of the axis parameters are taken from `parameter_info::AXIS_PARAMETERS`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ParameterNumber::CoordinateStorage => "CoordinateStorage",
+            ParameterNumber::SerialAddress => "SerialAddress",
+            ParameterNumber::SerialSecondaryAddress => "SerialSecondaryAddress",
+            ParameterNumber::SerialBaudRate => "SerialBaudRate",
+            ParameterNumber::CanBitrate => "CanBitrate",
+            ParameterNumber::TelegramPauseTime => "TelegramPauseTime",
+            ParameterNumber::SerialHostAddress => "SerialHostAddress",
+            ParameterNumber::CanReplyId => "CanReplyId",
+            ParameterNumber::CanId => "CanId",
+            _ => self.info().map_or("", |info| info.name()),
+        }
+    }
+
+    /// The metadata of an axis parameter, `None` for global parameters
+    ///
+    /// ```
+    /// use tmcl::opcodes::ParameterNumber;
+    /// use tmcl::modules::tmcm::parameter_info::AXIS_PARAMETERS;
+    ///
+    /// let axis: Vec<_> = ParameterNumber::ALL.iter().filter_map(|p| p.info()).collect();
+    /// assert_eq!(axis.len(), AXIS_PARAMETERS.len());
+    /// assert_eq!(ParameterNumber::PulseDivisor.info().unwrap().mnemonic(), "PD");
+    /// assert_eq!(ParameterNumber::CanId.info(), None);
+    /// ```
+    pub fn info(&self) -> Option<&'static ParameterInfo> {
+        match self.global_bank() {
+            Some(_) => None,
+            None => parameter_info::axis_parameter(self.number()),
+        }
+    }
+
+    /// The axis parameter with the given number
+    pub fn axis(number: u8) -> Option<ParameterNumber> {
+        ParameterNumber::ALL.iter().cloned().find(|p| p.global_bank().is_none() && p.number() == number)
+    }
+
+    /// The global parameter with the given bank and number
+    pub fn global(bank: u8, number: u8) -> Option<ParameterNumber> {
+        ParameterNumber::ALL.iter().cloned().find(|p| p.global_bank() == Some(bank) && p.number() == number)
+    }
+
+    /// The parameter with the given name, ignoring case
+    pub fn from_name(name: &str) -> Option<ParameterNumber> {
+        ParameterNumber::ALL.iter().cloned().find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+}
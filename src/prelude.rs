@@ -0,0 +1,77 @@
+//! Re-exports of the types most applications need.
+//!
+//! The generic module and the TMCM modules have their own `SAP`, `GAP` and parameter types, so
+//! the instructions and parameters are split into `prelude::tmcm` and `prelude::generic`. Each of
+//! them also re-exports everything in `prelude`, so one glob import is enough.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use tmcl::prelude::tmcm::*;
+//!
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() -> Result<(), Error<MyInterfaceError>> {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = TmcmModule::new(&interface, 1);
+//!
+//!     module.write_command(SAP::new(0, MaximumPositioningSpeed::new(1000)))?;
+//!     module.write_command(MVP::new(0, MoveOperation::Absolute(5000)))?;
+//!     println!("{:?}", module.axis(0).get::<ActualPosition>()?);
+//!     Ok(())
+//! }
+//! ```
+
+pub use Interface;
+pub use Command;
+pub use Reply;
+pub use Error;
+pub use ErrorKind;
+pub use Status;
+pub use OkStatus;
+pub use ErrStatus;
+pub use Operand;
+pub use Instruction;
+pub use Encode;
+pub use Return;
+pub use FromOperand;
+pub use bus::Bus;
+
+#[cfg(feature = "std")]
+pub use serial::Serial;
+#[cfg(feature = "std")]
+pub use shared::SharedBus;
+
+/// The TMCM module with its instructions and parameters.
+pub mod tmcm {
+    pub use prelude::*;
+
+    pub use modules::tmcm::TmcmModule;
+    pub use modules::tmcm::axis::Axis;
+    pub use modules::tmcm::instructions::*;
+    pub use modules::tmcm::axis_parameters::*;
+    pub use modules::tmcm::global_parameters::*;
+}
+
+/// The generic module with its instructions.
+pub mod generic {
+    pub use prelude::*;
+
+    pub use modules::generic::GenericModule;
+    pub use modules::generic::instructions::*;
+}
@@ -0,0 +1,240 @@
+//! Host side motion profiles.
+//!
+//! Most modules have a built-in ramp generator that is used by `MVP`, `ROR` and `ROL`.
+//! When that is not sufficient (e.g. for synchronized multi-axis motion) the velocity ramp can be
+//! computed on the host instead and streamed to the module as a series of `ROR`/`ROL` instructions.
+//!
+//! All velocities and accelerations are in device units, the same units as used for the
+//! `MaximumPositioningSpeed` (4) and `MaximumAcceleration` (5) axis parameters.
+//!
+//! ```
+//! use std::time::Duration;
+//! use tmcl::profiles::{Divisors, VelocityProfile};
+//!
+//! let divisors = Divisors::new(3, 7);
+//! let profile = VelocityProfile::trapezoidal(0, 1000, 100, divisors, Duration::from_millis(10));
+//!
+//! let setpoints: Vec<i32> = profile.collect();
+//! assert_eq!(setpoints.last(), Some(&1000));
+//! ```
+
+use lib::ops::Deref;
+use lib::time::Duration;
+
+use interior_mut::InteriorMut;
+
+use Error;
+use Interface;
+
+use modules::tmcm::TmcmModule;
+use modules::tmcm::instructions::{
+    ROR,
+    ROL,
+    MST,
+};
+
+/// The clock frequency of the TMC428 motion controller used on most TMCM modules.
+pub const DEFAULT_CLOCK_FREQUENCY: u32 = 16_000_000;
+
+/// The pulse and ramp divisors (axis parameters 154 and 153) used to convert between device
+/// units and physical units.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Divisors {
+    pulse_divisor: u8,
+    ramp_divisor: u8,
+    clock_frequency: u32,
+}
+
+impl Divisors {
+    /// Create divisors for a module running at `DEFAULT_CLOCK_FREQUENCY`
+    pub fn new(pulse_divisor: u8, ramp_divisor: u8) -> Self {
+        assert!(pulse_divisor <= 13);
+        assert!(ramp_divisor <= 13);
+        Divisors{pulse_divisor, ramp_divisor, clock_frequency: DEFAULT_CLOCK_FREQUENCY}
+    }
+
+    /// Use a different clock frequency than `DEFAULT_CLOCK_FREQUENCY`
+    pub fn with_clock_frequency(mut self, clock_frequency: u32) -> Self {
+        self.clock_frequency = clock_frequency;
+        self
+    }
+
+    /// The pulse divisor
+    pub fn pulse_divisor(&self) -> u8 {
+        self.pulse_divisor
+    }
+
+    /// The ramp divisor
+    pub fn ramp_divisor(&self) -> u8 {
+        self.ramp_divisor
+    }
+
+    /// Convert a velocity in device units to microsteps per second
+    pub fn velocity_to_usteps_per_second(&self, velocity: i32) -> f32 {
+        self.clock_frequency as f32 * velocity as f32 / (1u32 << (self.pulse_divisor + 16)) as f32
+    }
+
+    /// Convert a velocity in microsteps per second to device units, rounded to the nearest integer
+    pub fn usteps_per_second_to_velocity(&self, usteps_per_second: f32) -> i32 {
+        round(usteps_per_second * (1u32 << (self.pulse_divisor + 16)) as f32 / self.clock_frequency as f32) as i32
+    }
+
+    /// Convert an acceleration in device units to microsteps per second squared
+    pub fn acceleration_to_usteps_per_second_squared(&self, acceleration: u32) -> f32 {
+        let f = self.clock_frequency as f32;
+        f * f * acceleration as f32 / (1u64 << (self.pulse_divisor + self.ramp_divisor + 29)) as f32
+    }
+
+    /// Convert an acceleration in microsteps per second squared to device units, rounded to the nearest integer
+    pub fn usteps_per_second_squared_to_acceleration(&self, usteps_per_second_squared: f32) -> u32 {
+        let f = self.clock_frequency as f32;
+        round(usteps_per_second_squared * (1u64 << (self.pulse_divisor + self.ramp_divisor + 29)) as f32 / (f * f)) as u32
+    }
+
+    /// The change of velocity (in device units) per second caused by an acceleration in device units.
+    ///
+    /// The pulse divisor affects velocity and acceleration equally and therefore cancels out.
+    pub fn velocity_change_per_second(&self, acceleration: u32) -> f32 {
+        self.clock_frequency as f32 * acceleration as f32 / (1u32 << (self.ramp_divisor + 13)) as f32
+    }
+}
+
+/// The shape of a velocity ramp
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProfileShape {
+    /// Constant acceleration until the target velocity is reached.
+    Trapezoidal,
+
+    /// The acceleration is ramped up and down with the given jerk (device acceleration units per second).
+    SCurve(u32),
+}
+
+/// An iterator over velocity setpoints sampled at a fixed period.
+///
+/// The iterator yields one setpoint per period and ends with the target velocity.
+/// Negative velocities mean rotation to the left.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VelocityProfile {
+    shape: ProfileShape,
+    target: i32,
+    velocity: f32,
+    acceleration: f32,
+    max_acceleration: f32,
+    jerk: f32,
+    period: f32,
+    finished: bool,
+}
+
+impl VelocityProfile {
+    /// Create a velocity profile with the given shape
+    ///
+    /// `acceleration` is the maximum acceleration in device units, `period` is the time between two setpoints.
+    pub fn new(shape: ProfileShape, from: i32, to: i32, acceleration: u32, divisors: Divisors, period: Duration) -> Self {
+        assert!(acceleration > 0);
+        let jerk = match shape {
+            ProfileShape::Trapezoidal => 0.0,
+            ProfileShape::SCurve(jerk) => {
+                assert!(jerk > 0);
+                divisors.velocity_change_per_second(jerk)
+            },
+        };
+        VelocityProfile{
+            shape,
+            target: to,
+            velocity: from as f32,
+            acceleration: 0.0,
+            max_acceleration: divisors.velocity_change_per_second(acceleration),
+            jerk,
+            period: period.as_secs() as f32 + period.subsec_nanos() as f32 * 1e-9,
+            finished: false,
+        }
+    }
+
+    /// Create a trapezoidal velocity profile
+    pub fn trapezoidal(from: i32, to: i32, acceleration: u32, divisors: Divisors, period: Duration) -> Self {
+        VelocityProfile::new(ProfileShape::Trapezoidal, from, to, acceleration, divisors, period)
+    }
+
+    /// Create a jerk limited (S-curve) velocity profile
+    pub fn s_curve(from: i32, to: i32, acceleration: u32, jerk: u32, divisors: Divisors, period: Duration) -> Self {
+        VelocityProfile::new(ProfileShape::SCurve(jerk), from, to, acceleration, divisors, period)
+    }
+
+    /// The shape of the profile
+    pub fn shape(&self) -> ProfileShape {
+        self.shape
+    }
+
+    fn next_acceleration(&self, remaining: f32) -> f32 {
+        match self.shape {
+            ProfileShape::Trapezoidal => self.max_acceleration,
+            ProfileShape::SCurve(_) => {
+                // Velocity change needed to bring the acceleration back down to zero.
+                let ramp_down = self.acceleration * self.acceleration / (2.0 * self.jerk);
+                if remaining <= ramp_down {
+                    self.acceleration - self.jerk * self.period
+                } else {
+                    self.acceleration + self.jerk * self.period
+                }.max(self.jerk * self.period).min(self.max_acceleration)
+            },
+        }
+    }
+}
+
+impl Iterator for VelocityProfile {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        if self.finished {
+            return None;
+        }
+
+        let remaining = self.target as f32 - self.velocity;
+        self.acceleration = self.next_acceleration(abs(remaining));
+        let step = self.acceleration * self.period;
+
+        if abs(remaining) <= step {
+            self.velocity = self.target as f32;
+            self.finished = true;
+            Some(self.target)
+        } else {
+            self.velocity += if remaining < 0.0 {-step} else {step};
+            Some(self.velocity as i32)
+        }
+    }
+}
+
+fn abs(v: f32) -> f32 {
+    if v < 0.0 {-v} else {v}
+}
+
+/// Round to the nearest integer, `f32::round` is not available without `std`.
+pub(crate) fn round(v: f32) -> f32 {
+    if v < 0.0 {-((0.5 - v) as u64 as f32)} else {(v + 0.5) as u64 as f32}
+}
+
+/// Stream a velocity profile to a motor using `ROR`/`ROL`.
+///
+/// `wait` is called after every setpoint and is responsible for keeping the fixed rate the
+/// profile was computed for (e.g. by sleeping for the profile period).
+pub fn stream<'a, IF, Cell, T, P, F>(module: &'a TmcmModule<'a, IF, Cell, T>, motor_number: u8, profile: P, mut wait: F) -> Result<(), Error<IF::Error>>
+where
+    IF: Interface,
+    Cell: InteriorMut<'a, IF>,
+    T: Deref<Target=Cell>,
+    P: IntoIterator<Item=i32>,
+    F: FnMut(),
+{
+    for velocity in profile {
+        if velocity > 0 {
+            module.write_command(ROR::new(motor_number, velocity as u32))?;
+        } else if velocity < 0 {
+            module.write_command(ROL::new(motor_number, velocity.wrapping_neg() as u32))?;
+        } else {
+            module.write_command(MST::new(motor_number))?;
+        }
+        wait();
+    }
+    Ok(())
+}
+
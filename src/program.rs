@@ -0,0 +1,497 @@
+//! Stand-alone TMCL programs assembled on the host.
+//!
+//! Jumps and subroutine calls can target labels instead of addresses, the addresses are resolved
+//! when the program is assembled. Labels can be anonymous or named, named labels end up in the
+//! symbol table of the program.
+//!
+//! ```
+//! use tmcl::program::TmclProgram;
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! let mut program = TmclProgram::new();
+//! let start = program.named_label("start");
+//! let back_and_forth = program.named_label("back_and_forth");
+//!
+//! program.bind(start);
+//! program
+//!     .push_csub(back_and_forth)
+//!     .push_ja(start);
+//!
+//! program.bind(back_and_forth);
+//! program
+//!     .push(MVP::new(0, MoveOperation::Absolute(10000)))
+//!     .push(WAIT::new(WaitCondition::Position{motor_number: 0, timeout: 0}))
+//!     .push(MVP::new(0, MoveOperation::Absolute(0)))
+//!     .push(WAIT::new(WaitCondition::Position{motor_number: 0, timeout: 0}))
+//!     .push(RSUB);
+//!
+//! let assembled = program.assemble().unwrap();
+//! assert_eq!(assembled[0], [23, 0, 0, 0, 0, 0, 2]);
+//! assert_eq!(program.symbol("back_and_forth"), Some(2));
+//! ```
+//!
+//! Assembled programs (e.g. uploaded from a module) can be turned back into readable instructions.
+//!
+//! ```
+//! use tmcl::program::disassemble;
+//!
+//! let listing: Vec<String> = disassemble(&[[4, 0, 0, 0, 0, 0x27, 0x10], [22, 0, 0, 0, 0, 0, 0]])
+//!     .iter()
+//!     .map(|instruction| instruction.to_string())
+//!     .collect();
+//!
+//! assert_eq!(listing, vec!["0: MVP 0, 0, 10000", "1: JA 0, 0, 0"]);
+//! ```
+//!
+//! Values can be computed on the module with `CALC` and moved into parameters with `AAP`/`AGP`.
+//!
+//! ```
+//! use tmcl::program::TmclProgram;
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//!
+//! let mut program = TmclProgram::new();
+//! program
+//!     .push(GAP::<MaximumPositioningSpeed>::new(0))
+//!     .push(CALC::Div(2))
+//!     .push(AAP::<MaximumPositioningSpeed>::new(1));
+//!
+//! assert_eq!(program.assemble().unwrap()[2], [34, 4, 1, 0, 0, 0, 0]);
+//! ```
+//!
+//! `input_trigger_program` generates a program executing an action, like stopping a motor, when a
+//! digital input becomes active.
+//!
+//! ```
+//! use tmcl::program::{input_trigger_program, InputTrigger, TriggerAction};
+//!
+//! let program = input_trigger_program(&[
+//!     InputTrigger::new(0, TriggerAction::Stop(0)),
+//!     InputTrigger::new(1, TriggerAction::MoveToCoordinate{motor_number: 0, coordinate: 1}).active_low(),
+//! ]);
+//! assert!(program.assemble().is_ok());
+//! ```
+//!
+//! `watchdog_program` generates a program that stops the motors when the host stops sending a
+//! heartbeat, see `modules::tmcm::heartbeat`.
+//!
+//! Without `std` the program is available with the `heapless` feature. Instructions, labels and
+//! jumps are then stored in `heapless::Vec`s with capacity `N` each, so small programs can be
+//! assembled without an allocator. Named labels and `disassemble` require `std`.
+//!
+//! ```ignore
+//! let mut program: TmclProgram<16> = TmclProgram::default();
+//! let start = program.new_label();
+//! program.bind(start);
+//! program.push(MVP::new(0, MoveOperation::Relative(100))).push_ja(start);
+//! let assembled: heapless::Vec<[u8; 7], 16> = program.assemble().unwrap();
+//! ```
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use lib::fmt;
+
+use Encode;
+use instructions::mnemonic;
+use instructions::{
+    JC,
+    JA,
+    CSUB,
+    MST,
+    MVP,
+    GIO,
+    RfsStart,
+    WAIT,
+    STOP,
+    RawInstruction,
+    Condition,
+    WaitCondition,
+    MoveOperation,
+};
+use opcodes::{
+    OPCODE_SGP,
+    OPCODE_GGP,
+    OPCODE_COMP,
+};
+use modules::tmcm::user_variables::USER_VARIABLE_BANK;
+use modules::tmcm::io::INPUT_BANK;
+
+/// A position in a `TmclProgram` that can be jumped to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Label(usize);
+
+/// The capacity of a `TmclProgram` without `std` when none is given.
+pub const DEFAULT_PROGRAM_CAPACITY: usize = 64;
+
+/// The reason a program could not be assembled.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AssembleError {
+    /// A jump targets a label that was never bound.
+    UnboundLabel(Label),
+
+    /// More instructions, labels or jumps were pushed than the program has capacity for.
+    ///
+    /// Only possible without `std`.
+    CapacityExceeded,
+}
+
+#[cfg(feature = "std")]
+type Storage<T, const N: usize> = Vec<T>;
+#[cfg(not(feature = "std"))]
+type Storage<T, const N: usize> = heapless::Vec<T, N>;
+
+/// A TMCL program, a sequence of instructions to be executed by the module in stand-alone mode.
+///
+/// Every instruction is kept in its encoded form: `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0]`.
+/// The address of an instruction is its index in the program.
+///
+/// `N` is the capacity without `std`, with `std` the program grows as needed and `N` is ignored.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TmclProgram<const N: usize = DEFAULT_PROGRAM_CAPACITY> {
+    instructions: Storage<[u8; 7], N>,
+    labels: Storage<Option<u32>, N>,
+    #[cfg(feature = "std")]
+    symbols: BTreeMap<String, Label>,
+    fixups: Storage<(usize, Label), N>,
+    overflowed: bool,
+}
+
+impl TmclProgram {
+    /// Create an empty program
+    ///
+    /// Programs with a capacity other than `DEFAULT_PROGRAM_CAPACITY` are created with `Default`.
+    pub fn new() -> Self {
+        TmclProgram::default()
+    }
+}
+
+impl<const N: usize> TmclProgram<N> {
+
+    /// Append an instruction to the program
+    ///
+    /// Any `Instruction` can be appended, as well as a `RawInstruction`.
+    /// Without `std`, instructions beyond the capacity are dropped and `assemble` fails.
+    pub fn push<I: Encode>(&mut self, instruction: I) -> &mut Self {
+        let encoded = instruction.encode();
+        self.overflowed |= push(&mut self.instructions, encoded);
+        self
+    }
+
+    /// Append a conditional jump to `label`
+    ///
+    /// The address of the label is filled in when the program is assembled.
+    ///
+    /// ```
+    /// use tmcl::program::TmclProgram;
+    /// use tmcl::modules::tmcm::instructions::*;
+    ///
+    /// let mut program = TmclProgram::new();
+    /// let shutdown = program.new_label();
+    /// program
+    ///     .push_jc(Condition::ESD, shutdown)
+    ///     .push(MVP::new(0, MoveOperation::Absolute(10000)));
+    /// program.bind(shutdown);
+    /// program.push(STOP);
+    ///
+    /// assert_eq!(program.assemble().unwrap()[0], [21, 12, 0, 0, 0, 0, 2]);
+    /// ```
+    pub fn push_jc(&mut self, condition: Condition, label: Label) -> &mut Self {
+        self.overflowed |= push(&mut self.fixups, (self.instructions.len(), label));
+        self.push(JC::new(condition, 0))
+    }
+
+    /// Append an unconditional jump to `label`
+    ///
+    /// The address of the label is filled in when the program is assembled.
+    pub fn push_ja(&mut self, label: Label) -> &mut Self {
+        self.overflowed |= push(&mut self.fixups, (self.instructions.len(), label));
+        self.push(JA::new(0))
+    }
+
+    /// Append a call to the subroutine at `label`
+    ///
+    /// The address of the label is filled in when the program is assembled.
+    pub fn push_csub(&mut self, label: Label) -> &mut Self {
+        self.overflowed |= push(&mut self.fixups, (self.instructions.len(), label));
+        self.push(CSUB::new(0))
+    }
+
+    /// Create a new label, it must be bound before the program is assembled
+    pub fn new_label(&mut self) -> Label {
+        let label = Label(self.labels.len());
+        self.overflowed |= push(&mut self.labels, None);
+        label
+    }
+
+    /// Get the label with the given name, creating it if it doesn't exist
+    #[cfg(feature = "std")]
+    pub fn named_label(&mut self, name: &str) -> Label {
+        if let Some(label) = self.symbols.get(name) {
+            return *label;
+        }
+        let label = self.new_label();
+        self.symbols.insert(String::from(name), label);
+        label
+    }
+
+    /// Bind `label` to the address of the next instruction pushed to the program
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        let address = self.instructions.len() as u32;
+        if let Some(bound) = self.labels.get_mut(label.0) {
+            *bound = Some(address);
+        }
+        self
+    }
+
+    /// The address `label` is bound to
+    pub fn address(&self, label: Label) -> Option<u32> {
+        self.labels.get(label.0).cloned().and_then(|address| address)
+    }
+
+    /// The address of the named label
+    #[cfg(feature = "std")]
+    pub fn symbol(&self, name: &str) -> Option<u32> {
+        self.symbols.get(name).and_then(|label| self.address(*label))
+    }
+
+    /// All named labels and the addresses they are bound to, ordered by name
+    #[cfg(feature = "std")]
+    pub fn symbols(&self) -> impl Iterator<Item=(&str, Option<u32>)> {
+        self.symbols.iter().map(move |(name, label)| (name.as_str(), self.address(*label)))
+    }
+
+    /// The number of instructions in the program
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Returns true if the program contains no instructions
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /// Resolve all jump targets and return the encoded instructions
+    pub fn assemble(&self) -> Result<Storage<[u8; 7], N>, AssembleError> {
+        if self.overflowed {
+            return Err(AssembleError::CapacityExceeded);
+        }
+        let mut instructions = self.instructions.clone();
+        for &(index, label) in &self.fixups {
+            let address = self.address(label).ok_or(AssembleError::UnboundLabel(label))?;
+            instructions[index][3] = (address >> 24) as u8;
+            instructions[index][4] = (address >> 16) as u8;
+            instructions[index][5] = (address >> 8) as u8;
+            instructions[index][6] = address as u8;
+        }
+        Ok(instructions)
+    }
+}
+
+/// Push `item` to `storage`, returns true if it didn't fit
+#[cfg(feature = "std")]
+fn push<T>(storage: &mut Vec<T>, item: T) -> bool {
+    storage.push(item);
+    false
+}
+
+/// Push `item` to `storage`, returns true if it didn't fit
+#[cfg(not(feature = "std"))]
+fn push<T, const N: usize>(storage: &mut Storage<T, N>, item: T) -> bool {
+    storage.push(item).is_err()
+}
+
+/// A program stopping the motors when the host stops writing to a user variable
+///
+/// The program clears user variable `variable`, waits `timeout_ticks` (10 ms each) and checks
+/// whether the variable was written in the meantime, e.g. by a `Heartbeat`. If not, every motor in
+/// `motor_numbers` is stopped with `MST` and the program ends.
+pub fn watchdog_program(variable: u8, timeout_ticks: u32, motor_numbers: &[u8]) -> TmclProgram {
+    let mut program = TmclProgram::new();
+    let start = program.new_label();
+    program.bind(start);
+    program
+        .push(RawInstruction::new(OPCODE_SGP, variable, USER_VARIABLE_BANK, 0))
+        .push(WAIT::new(WaitCondition::Ticks(timeout_ticks)))
+        .push(RawInstruction::new(OPCODE_GGP, variable, USER_VARIABLE_BANK, 0))
+        .push(RawInstruction::new(OPCODE_COMP, 0, 0, 0))
+        .push_jc(Condition::NE, start);
+    for &motor_number in motor_numbers {
+        program.push(MST::new(motor_number));
+    }
+    program.push(STOP);
+    program
+}
+
+/// An action executed by `input_trigger_program` when an input becomes active.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TriggerAction {
+    /// Stop the motor with `MST`.
+    Stop(u8),
+
+    /// Start a reference search of the motor with `RFS START`.
+    ReferenceSearch(u8),
+
+    /// Move the motor to a stored coordinate with `MVP COORD`.
+    MoveToCoordinate{motor_number: u8, coordinate: u32},
+}
+
+/// A digital input bound to a `TriggerAction`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InputTrigger {
+    port_number: u8,
+    active_level: bool,
+    action: TriggerAction,
+}
+
+impl InputTrigger {
+    /// Execute `action` when the input with the given port number becomes high
+    pub fn new(port_number: u8, action: TriggerAction) -> Self {
+        InputTrigger{port_number, active_level: true, action}
+    }
+
+    /// Execute the action when the input becomes low instead
+    pub fn active_low(mut self) -> Self {
+        self.active_level = false;
+        self
+    }
+
+    /// The port number of the input
+    pub fn port_number(&self) -> u8 {
+        self.port_number
+    }
+
+    /// The action executed when the input becomes active
+    pub fn action(&self) -> TriggerAction {
+        self.action
+    }
+}
+
+/// A program executing the action of a trigger when its input becomes active
+///
+/// The program polls the inputs (bank 0) with `GIO` forever. When an input is active its action
+/// is executed once, and the program waits for the input to become inactive again before
+/// continuing to poll, so every activation triggers the action once.
+///
+/// ```
+/// use tmcl::program::{input_trigger_program, InputTrigger, TriggerAction};
+///
+/// let program = input_trigger_program(&[
+///     InputTrigger::new(2, TriggerAction::MoveToCoordinate{motor_number: 0, coordinate: 5}),
+/// ]);
+/// // GIO, COMP and JC come first, then MVP COORD 5 (type 2).
+/// assert_eq!(program.assemble().unwrap()[3], [4, 2, 0, 0, 0, 0, 5]);
+/// ```
+pub fn input_trigger_program(triggers: &[InputTrigger]) -> TmclProgram {
+    let mut program = TmclProgram::new();
+    let poll = program.new_label();
+    program.bind(poll);
+    for trigger in triggers {
+        let next = program.new_label();
+        push_input_check(&mut program, trigger).push_jc(Condition::NE, next);
+        match trigger.action {
+            TriggerAction::Stop(motor_number) => program.push(MST::new(motor_number)),
+            TriggerAction::ReferenceSearch(motor_number) => program.push(RfsStart::new(motor_number)),
+            TriggerAction::MoveToCoordinate{motor_number, coordinate} => {
+                program.push(MVP::new(motor_number, MoveOperation::Coordinate(coordinate)))
+            },
+        };
+        let release = program.new_label();
+        program.bind(release);
+        push_input_check(&mut program, trigger).push_jc(Condition::EQ, release);
+        program.bind(next);
+    }
+    program.push_ja(poll);
+    program
+}
+
+/// Compare the state of the input of `trigger` with its active level
+fn push_input_check<'p>(program: &'p mut TmclProgram, trigger: &InputTrigger) -> &'p mut TmclProgram {
+    program
+        .push(GIO::new(INPUT_BANK, trigger.port_number))
+        .push(RawInstruction::new(OPCODE_COMP, 0, 0, trigger.active_level as i32))
+}
+
+/// An instruction decoded from program memory.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DisassembledInstruction {
+    address: u32,
+    instruction_number: u8,
+    type_number: u8,
+    motor_bank_number: u8,
+    value: i32,
+}
+
+impl DisassembledInstruction {
+    /// Decode an instruction in the form `[CMD_N, TYPE_N, MOTOR_N, VALUE3, VALUE2, VALUE1, VALUE0]`
+    pub fn new(address: u32, encoded: [u8; 7]) -> Self {
+        DisassembledInstruction{
+            address,
+            instruction_number: encoded[0],
+            type_number: encoded[1],
+            motor_bank_number: encoded[2],
+            value: i32::from_be_bytes([encoded[3], encoded[4], encoded[5], encoded[6]]),
+        }
+    }
+
+    /// The address of the instruction in program memory
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+
+    /// The instruction number
+    pub fn instruction_number(&self) -> u8 {
+        self.instruction_number
+    }
+
+    /// The type number
+    pub fn type_number(&self) -> u8 {
+        self.type_number
+    }
+
+    /// The motor/bank number
+    pub fn motor_bank_number(&self) -> u8 {
+        self.motor_bank_number
+    }
+
+    /// The value of the operand
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// The mnemonic of the instruction, `None` if the instruction number is unknown
+    pub fn mnemonic(&self) -> Option<&'static str> {
+        mnemonic(self.instruction_number)
+    }
+}
+
+impl fmt::Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.address)?;
+        match self.mnemonic() {
+            Some(mnemonic) => write!(f, "{}", mnemonic)?,
+            None => write!(f, "#{}", self.instruction_number)?,
+        }
+        write!(f, " {}, {}, {}", self.type_number, self.motor_bank_number, self.value)
+    }
+}
+
+/// Convert assembled program memory back into instructions
+#[cfg(feature = "std")]
+pub fn disassemble(program: &[[u8; 7]]) -> Vec<DisassembledInstruction> {
+    program.iter()
+        .enumerate()
+        .map(|(address, encoded)| DisassembledInstruction::new(address as u32, *encoded))
+        .collect()
+}
+
+/// The instruction number of a mnemonic, e.g. 4 for `MVP`
+pub fn instruction_number(mnemonic: &str) -> Option<u8> {
+    (0..=255u8).find(|number| self::mnemonic(*number).is_some_and(|m| m.eq_ignore_ascii_case(mnemonic)))
+}
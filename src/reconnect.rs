@@ -0,0 +1,193 @@
+//! Reconnecting interfaces after the connection has been lost.
+//!
+//! USB-serial adapters get unplugged and CAN adapters get reset. `Reconnecting` wraps an interface
+//! created by a connect function. When the interface fails with an error indicating that the
+//! device is gone, it is dropped and the command fails. The next command reconnects first,
+//! retrying with exponential backoff. Connection state changes are reported to a hook.
+//!
+//! ```no_run
+//! use std::cell::RefCell;
+//! use std::fs::OpenOptions;
+//! use tmcl::demux::Demux;
+//! use tmcl::reconnect::{ConnectionEvent, Reconnecting};
+//! use tmcl::serial::Serial;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! let interface = RefCell::new(
+//!     Reconnecting::new(|| {
+//!         let tty = OpenOptions::new().read(true).write(true).open("/dev/ttyUSB0")?;
+//!         Ok(Demux::new(Serial::new(tty)))
+//!     })
+//!     .with_hook(|event: ConnectionEvent| println!("{:?}", event))
+//! );
+//! let module = Module::new(&interface, 1);
+//!
+//! module.write_command(ROR::new(0, 250)).unwrap();
+//! ```
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use Interface;
+use Encode;
+use Command;
+use Reply;
+
+/// A change of the connection state of a `Reconnecting` interface.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConnectionEvent {
+    /// The connection was established, contains the number of the successful attempt.
+    Connected(u32),
+
+    /// The connection was lost.
+    Disconnected,
+
+    /// Connecting failed, contains the number of the failed attempt and the time until the next.
+    ConnectFailed(u32, Duration),
+
+    /// Connecting failed too many times, the command fails with `io::ErrorKind::NotConnected`.
+    GaveUp,
+}
+
+/// Returns true if the error means that the device is gone and the interface must be reopened.
+///
+/// This is the case for broken pipes, end of file and the OS errors `EIO`, `ENXIO`, `EBADF` and `ENODEV`.
+pub fn is_disconnect(error: &io::Error) -> bool {
+    match error.kind() {
+        io::ErrorKind::BrokenPipe
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::UnexpectedEof => true,
+        _ => matches!(error.raw_os_error(), Some(5) | Some(6) | Some(9) | Some(19)),
+    }
+}
+
+/// An interface that is reopened after the connection has been lost.
+///
+/// Errors of the underlying interface are converted into `io::Error`.
+pub struct Reconnecting<IF: Interface, C: FnMut() -> io::Result<IF>> {
+    interface: Option<IF>,
+    connect: C,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_attempts: u32,
+    hook: Option<fn(ConnectionEvent)>,
+}
+
+impl<IF: Interface, C: FnMut() -> io::Result<IF>> Reconnecting<IF, C> {
+    /// Create an interface using `connect` to (re)open the underlying interface
+    ///
+    /// The interface is opened when the first command is transmitted. By default connecting is
+    /// attempted 5 times, waiting 100 ms after the first failure and doubling the wait up to 5 s.
+    pub fn new(connect: C) -> Self {
+        Reconnecting{
+            interface: None,
+            connect,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            max_attempts: 5,
+            hook: None,
+        }
+    }
+
+    /// Wait `initial` after the first failed attempt, doubling the wait after every attempt up to `max`
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Give up after `max_attempts` failed attempts to connect
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0);
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Call `hook` on every change of the connection state
+    pub fn with_hook(mut self, hook: fn(ConnectionEvent)) -> Self {
+        self.hook = Some(hook);
+        self
+    }
+
+    /// Returns true if the underlying interface is currently open
+    pub fn is_connected(&self) -> bool {
+        self.interface.is_some()
+    }
+
+    /// Close the underlying interface, it is reopened by the next command
+    pub fn disconnect(&mut self) {
+        if self.interface.take().is_some() {
+            self.notify(ConnectionEvent::Disconnected);
+        }
+    }
+
+    fn notify(&self, event: ConnectionEvent) {
+        if let Some(hook) = self.hook {
+            hook(event);
+        }
+    }
+
+    fn connected(&mut self) -> io::Result<&mut IF> {
+        if self.interface.is_none() {
+            let mut backoff = self.initial_backoff;
+            let mut attempt = 1;
+            loop {
+                match (self.connect)() {
+                    Ok(interface) => {
+                        self.interface = Some(interface);
+                        self.notify(ConnectionEvent::Connected(attempt));
+                        break;
+                    },
+                    Err(_) if attempt >= self.max_attempts => {
+                        self.notify(ConnectionEvent::GaveUp);
+                        return Err(io::Error::new(io::ErrorKind::NotConnected, "reconnecting failed"));
+                    },
+                    Err(_) => {
+                        self.notify(ConnectionEvent::ConnectFailed(attempt, backoff));
+                        thread::sleep(backoff);
+                        backoff = (backoff * 2).min(self.max_backoff);
+                        attempt += 1;
+                    },
+                }
+            }
+        }
+        Ok(self.interface.as_mut().unwrap())
+    }
+
+    fn check<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        if let Err(ref e) = result {
+            if is_disconnect(e) {
+                self.disconnect();
+            }
+        }
+        result
+    }
+}
+
+impl<IF: Interface, C: FnMut() -> io::Result<IF>> Interface for Reconnecting<IF, C> where IF::Error: Into<io::Error> {
+    type Error = io::Error;
+
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        let result = self.connected()?.transmit_command(command).map_err(Into::into);
+        self.check(result)
+    }
+
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        let result = self.connected()?.receive_reply().map_err(Into::into);
+        self.check(result)
+    }
+
+    fn receive_reply_timeout(&mut self, timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        let result = self.connected()?.receive_reply_timeout(timeout).map_err(Into::into);
+        self.check(result)
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        let result = self.connected()?.discard_input().map_err(Into::into);
+        self.check(result)
+    }
+}
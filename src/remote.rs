@@ -0,0 +1,63 @@
+//! Querying the status of a module with CAN remote frames.
+//!
+//! The TMCL protocol itself has no status query cheaper than a command, but some CAN firmwares
+//! answer a remote frame with the module address as identifier with a reply frame, e.g. containing
+//! the position or the status flags. In dense networks polling such status with remote frames
+//! halves the bus load compared to a `GAP` round trip, since the request carries no data.
+//!
+//! Interfaces able to transmit remote frames implement `RemoteRequest`, with the `socketcan`
+//! feature `CANSocket` does. `TmcmModule::request_status` is only available with such interfaces,
+//! and should only be used with firmwares documented to answer remote frames, other modules ignore
+//! them and the request times out.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::cell::RefCell;
+//! use std::time::Duration;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::remote::RemoteRequest;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! # impl RemoteRequest for MyInterface {
+//!    # fn transmit_remote_request(&mut self, id: u16) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let interface = RefCell::new(MyInterface::new());
+//!     let module = Module::new(&interface, 1).with_timeout(Duration::from_millis(10));
+//!
+//!     let status = module.request_status().unwrap();
+//!     println!("status of module 1: {:?}", status.operand());
+//! }
+//! ```
+
+use Interface;
+
+/// An interface able to transmit CAN remote frames.
+pub trait RemoteRequest: Interface {
+    /// Transmit a remote frame with the standard identifier `id`
+    fn transmit_remote_request(&mut self, id: u16) -> Result<(), Self::Error>;
+}
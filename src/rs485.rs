@@ -0,0 +1,215 @@
+//! Half-duplex RS-485 lines with explicit driver direction control.
+//!
+//! Many RS-485 transceivers have a driver enable (DE) and receiver enable (RE) pin that must be
+//! toggled by the host. `HalfDuplex` wraps a `FrameInterface`, enables the driver while a frame is
+//! transmitted and switches back to receiving before the module starts replying.
+//!
+//! Depending on the UART, the last byte may still be shifting out when the transmission returns.
+//! The turnaround time is waited for before the driver is disabled. It must be shorter than the
+//! telegram pause time of the module (global parameter 75), otherwise the start of the reply is
+//! clobbered by the still enabled driver.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! # #[cfg(feature = "std")]
+//! use std::cell::RefCell;
+//! # #[cfg(feature = "std")]
+//! use std::fs::OpenOptions;
+//! # #[cfg(feature = "std")]
+//! use std::time::Duration;
+//! # #[cfg(feature = "std")]
+//! use tmcl::demux::Demux;
+//! # #[cfg(feature = "std")]
+//! use tmcl::rs485::{Direction, HalfDuplex};
+//! # #[cfg(feature = "std")]
+//! use tmcl::serial::Serial;
+//! # #[cfg(feature = "std")]
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! # #[cfg(feature = "std")]
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! # fn set_de_pin(_high: bool) {}
+//! # #[cfg(feature = "std")]
+//! fn main() {
+//!     let tty = OpenOptions::new().read(true).write(true).open("/dev/ttyS0").unwrap();
+//!     let line = HalfDuplex::new(Serial::new(tty), |direction| set_de_pin(direction == Direction::Transmit))
+//!         .with_turnaround(Duration::from_micros(200), std::thread::sleep);
+//!
+//!     let interface = RefCell::new(Demux::new(line));
+//!     let module = Module::new(&interface, 1);
+//!     module.write_command(ROR::new(0, 250)).unwrap();
+//! }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! ```
+
+use lib::convert::Infallible;
+use lib::time::Duration;
+
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::digital::OutputPin;
+
+use Timestamp;
+use demux::{FrameInterface, ReceivedFrame};
+
+/// The direction of a half-duplex line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    /// The driver is enabled, the host is transmitting.
+    Transmit,
+
+    /// The driver is disabled, the host is receiving.
+    Receive,
+}
+
+/// Controls the direction of the transceiver, typically through the DE/RE pins.
+///
+/// Implemented for closures taking a `Direction`, and for embedded-hal output pins through
+/// `DirectionPin` when the `embedded-hal` feature is enabled.
+pub trait DirectionControl {
+    type Error;
+
+    /// Switch the transceiver to the given direction
+    fn set_direction(&mut self, direction: Direction) -> Result<(), Self::Error>;
+}
+
+impl<F: FnMut(Direction)> DirectionControl for F {
+    type Error = Infallible;
+
+    fn set_direction(&mut self, direction: Direction) -> Result<(), Self::Error> {
+        self(direction);
+        Ok(())
+    }
+}
+
+/// An output pin connected to the (combined) DE/RE pins of the transceiver.
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug)]
+pub struct DirectionPin<P: OutputPin> {
+    pin: P,
+    active_high: bool,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<P: OutputPin> DirectionPin<P> {
+    /// The pin is driven high while transmitting
+    pub fn active_high(pin: P) -> Self {
+        DirectionPin{pin, active_high: true}
+    }
+
+    /// The pin is driven low while transmitting
+    pub fn active_low(pin: P) -> Self {
+        DirectionPin{pin, active_high: false}
+    }
+
+    /// Release the pin
+    pub fn into_inner(self) -> P {
+        self.pin
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<P: OutputPin> DirectionControl for DirectionPin<P> {
+    type Error = P::Error;
+
+    fn set_direction(&mut self, direction: Direction) -> Result<(), Self::Error> {
+        if (direction == Direction::Transmit) == self.active_high {
+            self.pin.set_high()
+        } else {
+            self.pin.set_low()
+        }
+    }
+}
+
+/// An error on a half-duplex line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HalfDuplexError<E, D> {
+    /// The underlying line failed.
+    Interface(E),
+
+    /// Switching the direction failed.
+    Direction(D),
+}
+
+/// A `FrameInterface` on a half-duplex line where the direction is switched by the host.
+#[derive(Debug)]
+pub struct HalfDuplex<FI: FrameInterface, D: DirectionControl, W: FnMut(Duration)> {
+    interface: FI,
+    direction: D,
+    wait: W,
+    turnaround: Duration,
+}
+
+impl<FI: FrameInterface, D: DirectionControl> HalfDuplex<FI, D, fn(Duration)> {
+    /// Create a half-duplex line without turnaround time
+    ///
+    /// The transceiver is switched to `Direction::Receive` when the first frame is transmitted.
+    pub fn new(interface: FI, direction: D) -> Self {
+        HalfDuplex{
+            interface,
+            direction,
+            wait: no_wait,
+            turnaround: Duration::from_secs(0),
+        }
+    }
+}
+
+impl<FI: FrameInterface, D: DirectionControl, W: FnMut(Duration)> HalfDuplex<FI, D, W> {
+    /// Wait `turnaround` between transmitting the last byte and disabling the driver
+    ///
+    /// `wait` is responsible for waiting the given time, e.g. `std::thread::sleep`.
+    pub fn with_turnaround<W2: FnMut(Duration)>(self, turnaround: Duration, wait: W2) -> HalfDuplex<FI, D, W2> {
+        HalfDuplex{
+            interface: self.interface,
+            direction: self.direction,
+            wait,
+            turnaround,
+        }
+    }
+
+    /// The time waited before the driver is disabled
+    pub fn turnaround(&self) -> Duration {
+        self.turnaround
+    }
+
+    /// Release the underlying line and direction control
+    pub fn into_inner(self) -> (FI, D) {
+        (self.interface, self.direction)
+    }
+}
+
+impl<FI: FrameInterface, D: DirectionControl, W: FnMut(Duration)> FrameInterface for HalfDuplex<FI, D, W> {
+    type Error = HalfDuplexError<FI::Error, D::Error>;
+
+    /// Transmit a frame with the driver enabled
+    ///
+    /// The driver is disabled again even if the transmission failed, to not block the bus.
+    fn transmit_frame(&mut self, frame: &[u8; 9]) -> Result<(), Self::Error> {
+        self.direction.set_direction(Direction::Transmit).map_err(HalfDuplexError::Direction)?;
+        let transmitted = self.interface.transmit_frame(frame);
+        if transmitted.is_ok() && self.turnaround > Duration::from_secs(0) {
+            (self.wait)(self.turnaround);
+        }
+        self.direction.set_direction(Direction::Receive).map_err(HalfDuplexError::Direction)?;
+        transmitted.map_err(HalfDuplexError::Interface)
+    }
+
+    fn receive_frame(&mut self) -> Result<[u8; 9], Self::Error> {
+        self.interface.receive_frame().map_err(HalfDuplexError::Interface)
+    }
+
+    fn receive_frame_timestamped(&mut self) -> Result<([u8; 9], Option<Timestamp>), Self::Error> {
+        self.interface.receive_frame_timestamped().map_err(HalfDuplexError::Interface)
+    }
+
+    fn receive_frame_timeout(&mut self, timeout: Duration) -> Result<Option<ReceivedFrame>, Self::Error> {
+        self.interface.receive_frame_timeout(timeout).map_err(HalfDuplexError::Interface)
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        self.interface.discard_input().map_err(HalfDuplexError::Interface)
+    }
+}
+
+fn no_wait(_: Duration) {}
@@ -0,0 +1,364 @@
+//! Serial lines (RS232, RS485, USB virtual COM ports) using the binary command format.
+//!
+//! `Serial` turns anything implementing `Read` and `Write` (e.g. an opened and configured tty) into a
+//! `FrameInterface`. Wrap it in a `Demux` to use it as an `Interface`.
+//!
+//! ```no_run
+//! use std::cell::RefCell;
+//! use std::fs::OpenOptions;
+//! use tmcl::demux::Demux;
+//! use tmcl::serial::Serial;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::instructions::*;
+//!
+//! let tty = OpenOptions::new().read(true).write(true).open("/dev/ttyACM0").unwrap();
+//! let interface = RefCell::new(Demux::new(Serial::new(tty)));
+//! let module = Module::new(&interface, 1);
+//!
+//! module.write_command(ROR::new(0, 250)).unwrap();
+//! ```
+//!
+//! Some gateways recalculate, strip or replace the checksum of the frames they forward. A
+//! `ChecksumMode` makes the serial line interoperate with them.
+//!
+//! ```
+//! use tmcl::serial::{ChecksumMode, Serial};
+//!
+//! // A gateway expecting the XOR of all bytes instead of the sum.
+//! fn xor(bytes: &[u8]) -> u8 {
+//!     bytes.iter().fold(0, |checksum, byte| checksum ^ byte)
+//! }
+//!
+//! let line = Serial::new(std::io::Cursor::new(Vec::new()))
+//!     .with_checksum_mode(ChecksumMode::Custom(xor));
+//! ```
+//!
+//! Ports may accept only part of a frame, e.g. when a write times out. The rest of the frame is
+//! kept and written before anything else, so the module never sees a truncated frame followed by
+//! the next one. Retransmitting the same frame only writes the rest. Every frame is flushed before
+//! `transmit_frame` returns, so it has left the host before the reply is waited for. Likewise,
+//! the bytes of a partially received frame are kept until the next receive completes it.
+//!
+//! Errors are reported as `SerialError`, telling transmit failures apart from receive failures.
+//!
+//! ```
+//! use std::io::{self, Read, Write};
+//! use tmcl::demux::FrameInterface;
+//! use tmcl::serial::{Serial, SerialError};
+//!
+//! // A port accepting `budget` bytes before writes time out.
+//! struct Congested {
+//!     written: Vec<u8>,
+//!     budget: usize,
+//! }
+//!
+//! impl Read for Congested {
+//!     fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> { Ok(0) }
+//! }
+//!
+//! impl Write for Congested {
+//!     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+//!         if self.budget == 0 {
+//!             return Err(io::Error::new(io::ErrorKind::TimedOut, "congested"));
+//!         }
+//!         let n = buf.len().min(self.budget);
+//!         self.budget -= n;
+//!         self.written.extend_from_slice(&buf[..n]);
+//!         Ok(n)
+//!     }
+//!     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+//! }
+//!
+//! let frame = [1, 4, 0, 0, 0, 0, 3, 0xe8, 0xf0];
+//! let mut line = Serial::new(Congested{written: Vec::new(), budget: 4});
+//! match line.transmit_frame(&frame) {
+//!     Err(SerialError::Write(e)) => assert_eq!(e.kind(), io::ErrorKind::TimedOut),
+//!     _ => panic!("expected a write error"),
+//! }
+//! assert_eq!(line.pending_output(), 5);
+//!
+//! // Retrying writes the rest of the frame only.
+//! line.get_mut().budget = 100;
+//! line.transmit_frame(&frame).unwrap();
+//! assert_eq!(line.into_inner().written, frame);
+//! ```
+
+use std::io;
+use std::io::{Read, Write};
+use std::fmt;
+use std::time::Duration;
+
+use checksum;
+use Timestamp;
+use demux::{FrameInterface, ReceivedFrame};
+
+/// How the checksum (the last byte) of frames on the serial line is computed and checked.
+#[derive(Debug, Clone, Copy)]
+pub enum ChecksumMode {
+    /// The checksum of the binary command format, the sum of the first 8 bytes.
+    Standard,
+
+    /// Transmitted frames have the standard checksum, the checksum of received frames is not checked.
+    Ignore,
+
+    /// The checksum is computed from the first 8 bytes by the given function, both when
+    /// transmitting and when checking received frames.
+    Custom(fn(&[u8]) -> u8),
+}
+
+/// An error on a serial line.
+#[derive(Debug)]
+pub enum SerialError {
+    /// Writing or flushing a frame failed.
+    ///
+    /// The part of the frame that was not written is written before the next frame.
+    Write(io::Error),
+
+    /// Reading a frame or discarding input failed.
+    ///
+    /// The part of the frame that was received is completed by the next receive.
+    Read(io::Error),
+}
+
+impl SerialError {
+    /// The underlying I/O error
+    pub fn io_error(&self) -> &io::Error {
+        match *self {
+            SerialError::Write(ref e) => e,
+            SerialError::Read(ref e) => e,
+        }
+    }
+
+    /// The kind of the underlying I/O error
+    pub fn kind(&self) -> io::ErrorKind {
+        self.io_error().kind()
+    }
+}
+
+impl fmt::Display for SerialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerialError::Write(ref e) => write!(f, "transmitting failed: {}", e),
+            SerialError::Read(ref e) => write!(f, "receiving failed: {}", e),
+        }
+    }
+}
+
+impl From<SerialError> for io::Error {
+    fn from(e: SerialError) -> io::Error {
+        match e {
+            SerialError::Write(e) => e,
+            SerialError::Read(e) => e,
+        }
+    }
+}
+
+const FRAME_LENGTH: usize = 9;
+
+type SetTimeout<T> = fn(&mut T, Option<Duration>) -> io::Result<()>;
+
+/// A serial line transmitting and receiving 9 byte frames.
+#[derive(Debug)]
+pub struct Serial<T: Read + Write> {
+    port: T,
+    discard: Option<fn(&mut T) -> io::Result<()>>,
+    set_timeout: Option<SetTimeout<T>>,
+    checksum_mode: ChecksumMode,
+    output: [u8; FRAME_LENGTH],
+    written: usize,
+    input: [u8; FRAME_LENGTH],
+    received: usize,
+}
+
+impl<T: Read + Write> Serial<T> {
+    /// Create a new serial line on top of an opened port
+    pub fn new(port: T) -> Self {
+        Serial{
+            port,
+            discard: None,
+            set_timeout: None,
+            checksum_mode: ChecksumMode::Standard,
+            output: [0; FRAME_LENGTH],
+            written: FRAME_LENGTH,
+            input: [0; FRAME_LENGTH],
+            received: 0,
+        }
+    }
+
+    /// Use another checksum than the standard one, e.g. for gateways modifying the checksum
+    ///
+    /// Received frames are handed on with the standard checksum if their checksum is accepted,
+    /// and with a wrong one otherwise, so they are classified as garbage by `Demux`.
+    pub fn with_checksum_mode(mut self, checksum_mode: ChecksumMode) -> Self {
+        self.checksum_mode = checksum_mode;
+        self
+    }
+
+    /// The checksum mode of the serial line
+    pub fn checksum_mode(&self) -> ChecksumMode {
+        self.checksum_mode
+    }
+
+    /// Set the procedure discarding pending input, used by `FrameInterface::discard_input`
+    ///
+    /// A blocking port can not be drained in a portable way, typical procedures flush the input
+    /// queue of the tty (`tcflush`) or read with a short timeout until nothing more is received.
+    /// Without a procedure, pending input is not discarded.
+    pub fn with_discard(mut self, discard: fn(&mut T) -> io::Result<()>) -> Self {
+        self.discard = Some(discard);
+        self
+    }
+
+    /// Set the procedure changing the read timeout of the port, used by `FrameInterface::receive_frame_timeout`
+    ///
+    /// `None` means blocking without a timeout. A read that times out must fail with
+    /// `io::ErrorKind::WouldBlock` or `io::ErrorKind::TimedOut`, like reads from a `TcpStream` with
+    /// a read timeout. Without a procedure, receiving blocks regardless of the timeout.
+    ///
+    /// ```
+    /// use std::io::{self, Read, Write};
+    /// use std::time::Duration;
+    /// use tmcl::Interface;
+    /// use tmcl::demux::Demux;
+    /// use tmcl::serial::Serial;
+    ///
+    /// // A line where no module replies, every read times out.
+    /// struct Silent;
+    ///
+    /// impl Read for Silent {
+    ///     fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+    ///         Err(io::Error::new(io::ErrorKind::TimedOut, "no data"))
+    ///     }
+    /// }
+    /// impl Write for Silent {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { Ok(buf.len()) }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    ///
+    /// let mut interface = Demux::new(Serial::new(Silent).with_timeout(|_, _| Ok(())));
+    /// assert!(interface.receive_reply_timeout(Duration::from_millis(10)).unwrap().is_none());
+    /// ```
+    pub fn with_timeout(mut self, set_timeout: fn(&mut T, Option<Duration>) -> io::Result<()>) -> Self {
+        self.set_timeout = Some(set_timeout);
+        self
+    }
+
+    /// The number of bytes of the last frame that are not written yet
+    pub fn pending_output(&self) -> usize {
+        FRAME_LENGTH - self.written
+    }
+
+    /// The number of bytes of a partially received frame
+    pub fn buffered_input(&self) -> usize {
+        self.received
+    }
+
+    /// Mutably borrow the underlying port, e.g. to change its timeouts
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.port
+    }
+
+    /// Release the underlying port
+    ///
+    /// Pending output and buffered input are lost.
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        while self.written < FRAME_LENGTH {
+            match self.port.write(&self.output[self.written..]) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write whole frame")),
+                Ok(n) => self.written += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_pending(&mut self) -> io::Result<()> {
+        while self.received < FRAME_LENGTH {
+            match self.port.read(&mut self.input[self.received..]) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to read whole frame")),
+                Ok(n) => self.received += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Read + Write> FrameInterface for Serial<T> {
+    type Error = SerialError;
+
+    /// Write a frame and flush the port
+    ///
+    /// If a previous frame was only partially written, its rest is written first. If that frame is
+    /// the one being transmitted, e.g. when a command is retried, only the rest is written.
+    fn transmit_frame(&mut self, frame: &[u8; 9]) -> Result<(), Self::Error> {
+        let mut frame = *frame;
+        if let ChecksumMode::Custom(custom) = self.checksum_mode {
+            frame[8] = custom(&frame[..8]);
+        }
+        if self.written == FRAME_LENGTH || self.output != frame {
+            self.write_pending().map_err(SerialError::Write)?;
+            self.output = frame;
+            self.written = 0;
+        }
+        self.write_pending().map_err(SerialError::Write)?;
+        self.port.flush().map_err(SerialError::Write)
+    }
+
+    fn receive_frame(&mut self) -> Result<[u8; 9], Self::Error> {
+        self.read_pending().map_err(SerialError::Read)?;
+        self.received = 0;
+        let mut frame = self.input;
+        let standard = checksum(&frame[..8]);
+        frame[8] = match self.checksum_mode {
+            ChecksumMode::Standard => frame[8],
+            ChecksumMode::Ignore => standard,
+            ChecksumMode::Custom(custom) if custom(&frame[..8]) == frame[8] => standard,
+            ChecksumMode::Custom(_) => standard.wrapping_add(1),
+        };
+        Ok(frame)
+    }
+
+    /// Receive a frame, timestamped with `Timestamp::now` when its last byte has been read
+    fn receive_frame_timestamped(&mut self) -> Result<([u8; 9], Option<Timestamp>), Self::Error> {
+        let frame = self.receive_frame()?;
+        Ok((frame, Some(Timestamp::now())))
+    }
+
+    /// Receive a frame with the read timeout of the port set to `timeout`
+    ///
+    /// A partially received frame is kept when the timeout elapses and completed by the next
+    /// receive. Blocks without a timeout unless the serial line was created `with_timeout`.
+    fn receive_frame_timeout(&mut self, timeout: Duration) -> Result<Option<ReceivedFrame>, Self::Error> {
+        let set_timeout = match self.set_timeout {
+            Some(set_timeout) => set_timeout,
+            None => return self.receive_frame_timestamped().map(Some),
+        };
+        if timeout == Duration::from_secs(0) {
+            return Ok(None);
+        }
+        set_timeout(&mut self.port, Some(timeout)).map_err(SerialError::Read)?;
+        let frame = match self.receive_frame_timestamped() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(SerialError::Read(ref e)) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => Ok(None),
+            Err(e) => Err(e),
+        };
+        set_timeout(&mut self.port, None).map_err(SerialError::Read)?;
+        frame
+    }
+
+    /// Discard pending input, including a partially received frame
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        self.received = 0;
+        match self.discard {
+            Some(discard) => discard(&mut self.port).map_err(SerialError::Read),
+            None => Ok(()),
+        }
+    }
+}
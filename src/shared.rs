@@ -0,0 +1,210 @@
+//! Sharing an interface between threads with fair scheduling.
+//!
+//! A `Mutex` gives no guarantee about which waiting thread gets the interface next, a thread
+//! polling in a tight loop can starve the others. `SharedBus` hands out the interface in the
+//! order it was requested. Clones of a `SharedBus` share the interface and the queue, and can be
+//! given a `Priority`: requests with `Priority::High` (e.g. emergency stops) are served before all
+//! waiting requests with `Priority::Normal`.
+//!
+//! ```no_run
+//! extern crate tmcl;
+//!
+//! use std::sync::Arc;
+//!
+//! use tmcl::shared::{Priority, SharedBus};
+//! use tmcl::modules::tmcm::instructions::*;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//!
+//! # use tmcl::Interface;
+//! # use tmcl::Encode;
+//! # use tmcl::Command;
+//! # use tmcl::Reply;
+//! #
+//! # struct MyInterface();
+//! # #[derive(Debug)]
+//! # struct MyInterfaceError();
+//! #
+//! # impl MyInterface { fn new() -> Self {unimplemented!()} }
+//! #
+//! # impl Interface for MyInterface {
+//!    # type Error = MyInterfaceError;
+//!    # fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//!    # fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+//!        # unimplemented!()
+//!    # }
+//! # }
+//! #
+//! fn main() {
+//!     let bus = SharedBus::new(MyInterface::new());
+//!     let emergency = Module::new(Arc::new(bus.with_priority(Priority::High)), 1);
+//!     let poller = Module::new(Arc::new(bus), 1);
+//!
+//!     std::thread::spawn(move || {
+//!         loop {
+//!             poller.write_command(GAP::<ActualPosition>::new(0)).unwrap();
+//!         }
+//!     });
+//!
+//!     emergency.write_command(MST::new(0)).unwrap();
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use interior_mut::InteriorMut;
+
+/// The priority of the requests for the interface made through a `SharedBus`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Priority {
+    /// Served in the order requested.
+    Normal,
+
+    /// Served before all `Normal` requests, in the order requested.
+    High,
+}
+
+/// The interface of a `SharedBus` was poisoned by a thread panicking while using it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Poisoned;
+
+#[derive(Debug)]
+struct Queue {
+    next_ticket: u64,
+    normal: VecDeque<u64>,
+    high: VecDeque<u64>,
+    busy: bool,
+}
+
+impl Queue {
+    fn is_next(&self, ticket: u64) -> bool {
+        !self.busy && self.high.front().or_else(|| self.normal.front()) == Some(&ticket)
+    }
+}
+
+#[derive(Debug)]
+struct Inner<IF> {
+    interface: Mutex<IF>,
+    queue: Mutex<Queue>,
+    turn: Condvar,
+}
+
+/// An interface shared between threads, handed out in FIFO order.
+#[derive(Debug)]
+pub struct SharedBus<IF> {
+    inner: Arc<Inner<IF>>,
+    priority: Priority,
+}
+
+impl<IF> SharedBus<IF> {
+    /// Share `interface`, requests are made with `Priority::Normal`
+    pub fn new(interface: IF) -> Self {
+        SharedBus{
+            inner: Arc::new(Inner{
+                interface: Mutex::new(interface),
+                queue: Mutex::new(Queue{
+                    next_ticket: 0,
+                    normal: VecDeque::new(),
+                    high: VecDeque::new(),
+                    busy: false,
+                }),
+                turn: Condvar::new(),
+            }),
+            priority: Priority::Normal,
+        }
+    }
+
+    /// A handle to the same interface making requests with `priority`
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        SharedBus{inner: self.inner.clone(), priority}
+    }
+
+    /// The priority of the requests made through this handle
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Block until it is this caller's turn and get exclusive access to the interface
+    pub fn lock(&self) -> Result<SharedBusGuard<'_, IF>, Poisoned> {
+        let mut queue = self.inner.queue.lock().or(Err(Poisoned))?;
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        match self.priority {
+            Priority::Normal => queue.normal.push_back(ticket),
+            Priority::High => queue.high.push_back(ticket),
+        }
+        while !queue.is_next(ticket) {
+            queue = self.inner.turn.wait(queue).or(Err(Poisoned))?;
+        }
+        match self.priority {
+            Priority::Normal => queue.normal.pop_front(),
+            Priority::High => queue.high.pop_front(),
+        };
+        queue.busy = true;
+        drop(queue);
+
+        match self.inner.interface.lock() {
+            Ok(interface) => Ok(SharedBusGuard{inner: &self.inner, interface: Some(interface)}),
+            Err(_) => {
+                release(&self.inner);
+                Err(Poisoned)
+            },
+        }
+    }
+}
+
+impl<IF> Clone for SharedBus<IF> {
+    fn clone(&self) -> Self {
+        self.with_priority(self.priority)
+    }
+}
+
+impl<'a, IF: 'a> InteriorMut<'a, IF> for SharedBus<IF> {
+    type RefMut = SharedBusGuard<'a, IF>;
+    type Error = Poisoned;
+
+    fn borrow_int_mut(&'a self) -> Result<Self::RefMut, Self::Error> {
+        self.lock()
+    }
+}
+
+/// Exclusive access to the interface of a `SharedBus`, the next request is served when dropped.
+#[derive(Debug)]
+pub struct SharedBusGuard<'a, IF: 'a> {
+    inner: &'a Inner<IF>,
+    interface: Option<MutexGuard<'a, IF>>,
+}
+
+impl<'a, IF> Deref for SharedBusGuard<'a, IF> {
+    type Target = IF;
+
+    fn deref(&self) -> &IF {
+        self.interface.as_ref().unwrap()
+    }
+}
+
+impl<'a, IF> DerefMut for SharedBusGuard<'a, IF> {
+    fn deref_mut(&mut self) -> &mut IF {
+        self.interface.as_mut().unwrap()
+    }
+}
+
+impl<'a, IF> Drop for SharedBusGuard<'a, IF> {
+    fn drop(&mut self) {
+        self.interface.take();
+        release(self.inner);
+    }
+}
+
+fn release<IF>(inner: &Inner<IF>) {
+    let mut queue = match inner.queue.lock() {
+        Ok(queue) => queue,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    queue.busy = false;
+    inner.turn.notify_all();
+}
@@ -1,4 +1,5 @@
 use std::io;
+use std::time::Duration;
 
 use socketcan::{
     CANSocket,
@@ -6,28 +7,144 @@ use socketcan::{
 };
 
 use Interface;
-use Instruction;
+use Encode;
 use Reply;
 use Command;
 use Status;
+use Operand;
+use Timestamp;
+use demux::{FrameInterface, ReceivedFrame};
+use remote::RemoteRequest;
+#[cfg(feature = "canopen")]
+use canopen::CanTransport;
 
 impl Interface for CANSocket {
     type Error = io::Error;
 
-    fn transmit_command<T: Instruction>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
-        let frame = CANFrame::new(u32::from(command.module_address), &command.serialize_can(), false, false).unwrap();
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        let frame = CANFrame::new(u32::from(command.module_address), &command.serialize_can(), false, false)
+            .or(Err(io::Error::new(io::ErrorKind::InvalidInput, "not a valid CAN frame")))?;
         self.write_frame_insist(&frame)
     }
 
     fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
-        // TODO: make robust
         let frame = self.read_frame()?;
+        let data = frame.data();
+        if data.len() < 7 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "reply frame is too short"));
+        }
         Ok(Reply::new(
             frame.id() as u8,
-            frame.data()[0],
-            Status::try_from_u8(frame.data()[1]).unwrap(),
-            frame.data()[2],
-            [frame.data()[6], frame.data()[5], frame.data()[4], frame.data()[3]],
-        ))
+            data[0],
+            Status::from_u8(data[1]),
+            data[2],
+            Operand::from_wire([data[3], data[4], data[5], data[6]]),
+        ).with_timestamp(Timestamp::now()))
     }
-}
\ No newline at end of file
+
+    fn receive_reply_timeout(&mut self, timeout: Duration) -> Result<Option<Reply>, Self::Error> {
+        // A read timeout of zero means blocking forever.
+        if timeout == Duration::from_secs(0) {
+            return Ok(None);
+        }
+        self.set_read_timeout(timeout)?;
+        let reply = match self.receive_reply() {
+            Ok(reply) => Ok(Some(reply)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => Ok(None),
+            Err(e) => Err(e),
+        };
+        self.set_read_timeout(Duration::from_secs(0))?;
+        reply
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        discard_frames(self)
+    }
+}
+impl RemoteRequest for CANSocket {
+    fn transmit_remote_request(&mut self, id: u16) -> Result<(), Self::Error> {
+        let frame = CANFrame::new(u32::from(id), &[], true, false)
+            .or(Err(io::Error::new(io::ErrorKind::InvalidInput, "not a valid CAN frame")))?;
+        self.write_frame_insist(&frame)
+    }
+}
+
+/// Frames are converted between the CAN format and the binary format used on serial lines,
+/// making it possible to receive events on CAN with `Demux`.
+impl FrameInterface for CANSocket {
+    type Error = io::Error;
+
+    fn transmit_frame(&mut self, frame: &[u8; 9]) -> Result<(), Self::Error> {
+        let frame = CANFrame::new(u32::from(frame[0]), &frame[1..8], false, false)
+            .or(Err(io::Error::new(io::ErrorKind::InvalidInput, "not a valid CAN frame")))?;
+        self.write_frame_insist(&frame)
+    }
+
+    fn receive_frame(&mut self) -> Result<[u8; 9], Self::Error> {
+        let frame = self.read_frame()?;
+        let data = frame.data();
+        if data.len() < 7 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "reply frame is too short"));
+        }
+        let mut serial_frame = [frame.id() as u8, data[0], data[1], data[2], data[3], data[4], data[5], data[6], 0];
+        serial_frame[8] = serial_frame[..8].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        Ok(serial_frame)
+    }
+
+    /// Receive a frame, timestamped with `Timestamp::now` when it has been read from the socket
+    fn receive_frame_timestamped(&mut self) -> Result<([u8; 9], Option<Timestamp>), Self::Error> {
+        let frame = self.receive_frame()?;
+        Ok((frame, Some(Timestamp::now())))
+    }
+
+    fn receive_frame_timeout(&mut self, timeout: Duration) -> Result<Option<ReceivedFrame>, Self::Error> {
+        // A read timeout of zero means blocking forever.
+        if timeout == Duration::from_secs(0) {
+            return Ok(None);
+        }
+        self.set_read_timeout(timeout)?;
+        let frame = match self.receive_frame_timestamped() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => Ok(None),
+            Err(e) => Err(e),
+        };
+        self.set_read_timeout(Duration::from_secs(0))?;
+        frame
+    }
+
+    fn discard_input(&mut self) -> Result<(), Self::Error> {
+        discard_frames(self)
+    }
+}
+
+/// Read frames without blocking until no more frames are queued
+fn discard_frames(socket: &CANSocket) -> io::Result<()> {
+    socket.set_nonblocking(true)?;
+    let discarded = loop {
+        match socket.read_frame() {
+            Ok(_) => (),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+    socket.set_nonblocking(false)?;
+    discarded
+}
+
+#[cfg(feature = "canopen")]
+impl CanTransport for CANSocket {
+    type Error = io::Error;
+
+    fn transmit(&mut self, id: u16, data: &[u8; 8]) -> Result<(), Self::Error> {
+        let frame = CANFrame::new(u32::from(id), data, false, false)
+            .or(Err(io::Error::new(io::ErrorKind::InvalidInput, "not a valid CAN frame")))?;
+        self.write_frame_insist(&frame)
+    }
+
+    fn receive(&mut self) -> Result<(u16, [u8; 8]), Self::Error> {
+        let frame = self.read_frame()?;
+        let mut data = [0; 8];
+        data[..frame.data().len()].copy_from_slice(frame.data());
+        Ok((frame.id() as u16, data))
+    }
+}
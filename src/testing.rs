@@ -0,0 +1,250 @@
+//! Scripted mock interfaces for testing code driving modules.
+//!
+//! A `Script` is an interface answering commands according to a list of `Expectation`s, which
+//! makes it possible to verify complex sequences, e.g. a homing procedure or a batch of
+//! configuration writes, in CI without hardware. Each expectation matches one command by its
+//! instruction number and optionally its type number, motor or bank number and value, and tells
+//! what to reply with.
+//!
+//! With `Ordering::Strict` the commands must arrive in the order of the expectations. With
+//! `Ordering::Lenient` any unmet expectation may match the next command, which suits sequences
+//! where only the set of commands matters. A command that matches no expectation fails with
+//! `ScriptError::UnexpectedCommand`, and `Script::verify` reports expectations that were not met.
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use tmcl::opcodes::{OPCODE_GAP, OPCODE_SAP};
+//! use tmcl::testing::{expect_command, Ordering, Script};
+//! use tmcl::modules::tmcm::TmcmModule as Module;
+//! use tmcl::modules::tmcm::axis_parameters::*;
+//!
+//! let script = Script::new(Ordering::Strict)
+//!     .expect(expect_command(OPCODE_SAP).type_number(4..=5).value(0..=2047))
+//!     .expect(expect_command(OPCODE_SAP).type_number(4..=5).value(0..=2047))
+//!     .expect(expect_command(OPCODE_GAP).type_number(1..=1).reply_with_value(1000));
+//!
+//! let interface = RefCell::new(script);
+//! let module = Module::new(&interface, 1);
+//! let axis = module.axis(0);
+//! axis.set(MaximumPositioningSpeed::new(1000)).unwrap();
+//! axis.set(MaximumAcceleration::new(500)).unwrap();
+//! assert_eq!(axis.get::<ActualPosition>().unwrap(), ActualPosition::new(1000));
+//!
+//! assert_eq!(interface.borrow().verify(), Ok(()));
+//! ```
+
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+use std::vec::Vec;
+
+use Interface;
+use Encode;
+use Command;
+use Reply;
+use Status;
+use OkStatus;
+use ErrStatus;
+use Operand;
+
+/// The reply address of the replies of a `Script`, the default host address of TMCL.
+pub const REPLY_ADDRESS: u8 = 2;
+
+/// The order in which a `Script` expects the commands.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Ordering {
+    /// Every command must match the first unmet expectation.
+    Strict,
+
+    /// Every command may match any unmet expectation, the first one matching is used.
+    Lenient,
+}
+
+/// An expected command and the reply to it, created with `expect_command`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Expectation {
+    instruction_number: u8,
+    type_number: RangeInclusive<u8>,
+    motor_bank_number: RangeInclusive<u8>,
+    value: RangeInclusive<i32>,
+    status: Status,
+    reply_value: i32,
+}
+
+/// Expect a command with the given instruction number, e.g. `OPCODE_SAP`
+///
+/// By default any type number, motor or bank number and value matches, and the reply is
+/// `OkStatus::Ok` with the value 0.
+pub fn expect_command(instruction_number: u8) -> Expectation {
+    Expectation{
+        instruction_number,
+        type_number: 0..=u8::MAX,
+        motor_bank_number: 0..=u8::MAX,
+        value: i32::MIN..=i32::MAX,
+        status: Status::Ok(OkStatus::Ok),
+        reply_value: 0,
+    }
+}
+
+impl Expectation {
+    /// Only match type numbers, e.g. axis parameter numbers, within `range`
+    pub fn type_number<R: RangeBounds<u8>>(mut self, range: R) -> Self {
+        self.type_number = inclusive(range, 0, u8::MAX, |bound| bound.checked_add(1), |bound| bound.checked_sub(1));
+        self
+    }
+
+    /// Only match motor or bank numbers within `range`
+    pub fn motor_bank_number<R: RangeBounds<u8>>(mut self, range: R) -> Self {
+        self.motor_bank_number = inclusive(range, 0, u8::MAX, |bound| bound.checked_add(1), |bound| bound.checked_sub(1));
+        self
+    }
+
+    /// Only match values within `range`
+    pub fn value<R: RangeBounds<i32>>(mut self, range: R) -> Self {
+        self.value = inclusive(range, i32::MIN, i32::MAX, |bound| bound.checked_add(1), |bound| bound.checked_sub(1));
+        self
+    }
+
+    /// Reply with `status` and `value`
+    pub fn reply_with(mut self, status: Status, value: i32) -> Self {
+        self.status = status;
+        self.reply_value = value;
+        self
+    }
+
+    /// Reply with `OkStatus::Ok` and `value`
+    pub fn reply_with_value(self, value: i32) -> Self {
+        self.reply_with(Status::Ok(OkStatus::Ok), value)
+    }
+
+    /// Reply with the error `status`
+    pub fn reply_with_error(self, status: ErrStatus) -> Self {
+        self.reply_with(Status::Err(status), 0)
+    }
+
+    /// Returns true if the command, in binary command format, matches the expectation
+    pub fn matches(&self, command: &[u8; 9]) -> bool {
+        let value = Operand::from_wire([command[4], command[5], command[6], command[7]]).to_i32();
+        command[1] == self.instruction_number
+            && self.type_number.contains(&command[2])
+            && self.motor_bank_number.contains(&command[3])
+            && self.value.contains(&value)
+    }
+
+    fn reply(&self, command: &[u8; 9]) -> Reply {
+        Reply::new(REPLY_ADDRESS, command[0], self.status, command[1], Operand::from_i32(self.reply_value))
+    }
+}
+
+/// Turn a range into an inclusive range, empty if an excluded bound leaves no values
+fn inclusive<T: Copy, R: RangeBounds<T>>(range: R, min: T, max: T, next: fn(T) -> Option<T>, previous: fn(T) -> Option<T>) -> RangeInclusive<T> {
+    let empty = max..=min;
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => match next(start) {
+            Some(start) => start,
+            None => return empty,
+        },
+        Bound::Unbounded => min,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end,
+        Bound::Excluded(&end) => match previous(end) {
+            Some(end) => end,
+            None => return empty,
+        },
+        Bound::Unbounded => max,
+    };
+    start..=end
+}
+
+/// The errors of a `Script`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ScriptError {
+    /// The command, in binary command format, matched no unmet expectation.
+    ///
+    /// With strict ordering, `expected` is the index of the expectation it should have matched.
+    UnexpectedCommand {
+        command: [u8; 9],
+        expected: Option<usize>,
+    },
+
+    /// A reply was received without transmitting a command first.
+    NoCommand,
+
+    /// The expectation with the given index was not met.
+    Unmet(usize),
+}
+
+/// A mock interface answering commands according to a list of expectations.
+#[derive(Debug, Clone)]
+pub struct Script {
+    ordering: Ordering,
+    expectations: Vec<Expectation>,
+    met: Vec<bool>,
+    reply: Option<Reply>,
+}
+
+impl Script {
+    /// A script without expectations
+    pub fn new(ordering: Ordering) -> Self {
+        Script{ordering, expectations: Vec::new(), met: Vec::new(), reply: None}
+    }
+
+    /// Add an expectation
+    pub fn expect(mut self, expectation: Expectation) -> Self {
+        self.push(expectation);
+        self
+    }
+
+    /// Add an expectation to a script already in use
+    pub fn push(&mut self, expectation: Expectation) {
+        self.expectations.push(expectation);
+        self.met.push(false);
+    }
+
+    /// The order in which the commands are expected
+    pub fn ordering(&self) -> Ordering {
+        self.ordering
+    }
+
+    /// The number of expectations not met yet
+    pub fn unmet(&self) -> usize {
+        self.met.iter().filter(|met| !**met).count()
+    }
+
+    /// Check that all expectations have been met, returning the first one that was not
+    pub fn verify(&self) -> Result<(), ScriptError> {
+        match self.met.iter().position(|met| !met) {
+            Some(index) => Err(ScriptError::Unmet(index)),
+            None => Ok(()),
+        }
+    }
+
+    /// The index of the expectation the command matches
+    fn find(&self, command: &[u8; 9]) -> Result<usize, ScriptError> {
+        let mut unmet = (0..self.expectations.len()).filter(|&index| !self.met[index]);
+        match self.ordering {
+            Ordering::Strict => match unmet.next() {
+                Some(index) if self.expectations[index].matches(command) => Ok(index),
+                expected => Err(ScriptError::UnexpectedCommand{command: *command, expected}),
+            },
+            Ordering::Lenient => unmet.find(|&index| self.expectations[index].matches(command))
+                .ok_or(ScriptError::UnexpectedCommand{command: *command, expected: None}),
+        }
+    }
+}
+
+impl Interface for Script {
+    type Error = ScriptError;
+
+    fn transmit_command<T: Encode>(&mut self, command: &Command<T>) -> Result<(), Self::Error> {
+        let command = command.serialize();
+        let index = self.find(&command)?;
+        self.met[index] = true;
+        self.reply = Some(self.expectations[index].reply(&command));
+        Ok(())
+    }
+
+    fn receive_reply(&mut self) -> Result<Reply, Self::Error> {
+        self.reply.take().ok_or(ScriptError::NoCommand)
+    }
+}
@@ -0,0 +1,474 @@
+//! Conversion between device units and physical units.
+//!
+//! Velocities and accelerations are given to the modules in internal units that depend on the
+//! motion controller, its clock frequency and (for the TMC428) the pulse and ramp divisors.
+//! `Scaling` combines these with the mechanical properties of the motor (full steps per revolution
+//! and microstep resolution) to convert to and from steps/s, rad/s and RPM.
+//!
+//! ```
+//! use tmcl::profiles::Divisors;
+//! use tmcl::units::{MotionController, Scaling};
+//! use tmcl::modules::tmcm::axis_parameters::MicrostepResolution;
+//!
+//! let scaling = Scaling::new(
+//!     MotionController::Tmc428(Divisors::new(3, 7)),
+//!     200,
+//!     MicrostepResolution::Micro64,
+//! );
+//!
+//! let velocity = scaling.rpm_to_velocity(60.0);
+//! assert_eq!(velocity, 419);
+//! assert!((scaling.velocity_to_rpm(velocity) - 60.0).abs() < 0.1);
+//! ```
+//!
+//! Currents (`AbsoluteMaxCurrent` and `StandbyCurrent`) are given in milliamps on some module
+//! families and as a fraction (0 to 255) of the maximum current of the module on others.
+//! `CurrentScale` converts milliamps to the value expected by the module.
+//!
+//! ```
+//! use tmcl::units::CurrentScale;
+//!
+//! // A module with a maximum current of 2800 mA.
+//! let scale = CurrentScale::Fraction{full_scale_ma: 2800};
+//! assert_eq!(scale.milliamps_to_value(1400), Ok(128));
+//! assert!(scale.milliamps_to_value(3000).is_err());
+//!
+//! assert_eq!(CurrentScale::MILLIAMPS_1500.milliamps_to_value(1000), Ok(1000));
+//! ```
+//!
+//! Fractional quantities, like scaled analog inputs or temperatures, are converted with a
+//! `LinearScale`. Types implementing `Scaled` carry their scale with them, e.g. `Fixed` and the
+//! `MonitoredValue` read from a monitoring channel of a module.
+//!
+//! ```
+//! use tmcl::Fixed;
+//! use tmcl::units::{LinearScale, Scaled};
+//!
+//! // A 12 bit analog input measuring 0 to 10 V.
+//! let input = LinearScale::new(10.0 / 4095.0, 0.0);
+//! assert!((input.to_physical(2048) - 5.0).abs() < 0.01);
+//! assert_eq!(input.to_raw(2.5), 1024);
+//!
+//! assert_eq!(Fixed::<16>::from_bits(0x18000).to_f32(), 1.5);
+//! assert_eq!(Fixed::<16>::from_bits(0x18000).physical(), 1.5);
+//! assert_eq!(Fixed::<16>::SCALE.factor(), 1.0 / 65536.0);
+//! ```
+
+use lib::f32::consts::PI;
+
+use Fixed;
+use profiles::Divisors;
+use profiles::round;
+use modules::tmcm::user_variables::Persist;
+use modules::tmcm::axis_parameters::{
+    MicrostepResolution,
+    AbsoluteMaxCurrent,
+    StandbyCurrent,
+};
+
+/// The clock frequency of the TMC5130 motion controller when using the internal clock.
+pub const TMC5130_DEFAULT_CLOCK_FREQUENCY: u32 = 12_000_000;
+
+/// The motion controller generating the step pulses, which decides how the internal units are defined.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MotionController {
+    /// TMC428 (and compatible) with the given divisors and clock frequency.
+    Tmc428(Divisors),
+
+    /// TMC5130 (and compatible) with the given clock frequency.
+    Tmc5130(u32),
+}
+
+impl MotionController {
+    /// Convert a velocity in device units to microsteps per second
+    pub fn velocity_to_usteps_per_second(&self, velocity: i32) -> f32 {
+        match *self {
+            MotionController::Tmc428(divisors) => divisors.velocity_to_usteps_per_second(velocity),
+            MotionController::Tmc5130(clock_frequency) => velocity as f32 * clock_frequency as f32 / (1u32 << 24) as f32,
+        }
+    }
+
+    /// Convert a velocity in microsteps per second to device units, rounded to the nearest integer
+    pub fn usteps_per_second_to_velocity(&self, usteps_per_second: f32) -> i32 {
+        match *self {
+            MotionController::Tmc428(divisors) => divisors.usteps_per_second_to_velocity(usteps_per_second),
+            MotionController::Tmc5130(clock_frequency) => round(usteps_per_second * (1u32 << 24) as f32 / clock_frequency as f32) as i32,
+        }
+    }
+
+    /// Convert an acceleration in device units to microsteps per second squared
+    pub fn acceleration_to_usteps_per_second_squared(&self, acceleration: u32) -> f32 {
+        match *self {
+            MotionController::Tmc428(divisors) => divisors.acceleration_to_usteps_per_second_squared(acceleration),
+            MotionController::Tmc5130(clock_frequency) => {
+                let f = clock_frequency as f32;
+                acceleration as f32 * f * f / (1u64 << 41) as f32
+            },
+        }
+    }
+
+    /// Convert an acceleration in microsteps per second squared to device units, rounded to the nearest integer
+    pub fn usteps_per_second_squared_to_acceleration(&self, usteps_per_second_squared: f32) -> u32 {
+        match *self {
+            MotionController::Tmc428(divisors) => divisors.usteps_per_second_squared_to_acceleration(usteps_per_second_squared),
+            MotionController::Tmc5130(clock_frequency) => {
+                let f = clock_frequency as f32;
+                round(usteps_per_second_squared * (1u64 << 41) as f32 / (f * f)) as u32
+            },
+        }
+    }
+}
+
+/// Conversion between device units and physical units for one axis.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Scaling {
+    controller: MotionController,
+    fullsteps_per_revolution: u16,
+    microstep_resolution: MicrostepResolution,
+}
+
+impl Scaling {
+    /// Create a scaling for a motor with the given number of full steps per revolution (typically 200)
+    pub fn new(controller: MotionController, fullsteps_per_revolution: u16, microstep_resolution: MicrostepResolution) -> Self {
+        assert!(fullsteps_per_revolution > 0);
+        Scaling{controller, fullsteps_per_revolution, microstep_resolution}
+    }
+
+    /// The motion controller
+    pub fn controller(&self) -> MotionController {
+        self.controller
+    }
+
+    /// The number of full steps per revolution of the motor
+    pub fn fullsteps_per_revolution(&self) -> u16 {
+        self.fullsteps_per_revolution
+    }
+
+    /// The microstep resolution
+    pub fn microstep_resolution(&self) -> MicrostepResolution {
+        self.microstep_resolution
+    }
+
+    fn usteps_per_revolution(&self) -> f32 {
+        self.fullsteps_per_revolution as f32 * self.microstep_resolution.microsteps() as f32
+    }
+
+    /// Convert a velocity in device units to full steps per second
+    pub fn velocity_to_steps_per_second(&self, velocity: i32) -> f32 {
+        self.controller.velocity_to_usteps_per_second(velocity) / self.microstep_resolution.microsteps() as f32
+    }
+
+    /// Convert a velocity in full steps per second to device units
+    pub fn steps_per_second_to_velocity(&self, steps_per_second: f32) -> i32 {
+        self.controller.usteps_per_second_to_velocity(steps_per_second * self.microstep_resolution.microsteps() as f32)
+    }
+
+    /// Convert a velocity in device units to revolutions per minute
+    pub fn velocity_to_rpm(&self, velocity: i32) -> f32 {
+        self.controller.velocity_to_usteps_per_second(velocity) * 60.0 / self.usteps_per_revolution()
+    }
+
+    /// Convert a velocity in revolutions per minute to device units
+    pub fn rpm_to_velocity(&self, rpm: f32) -> i32 {
+        self.controller.usteps_per_second_to_velocity(rpm * self.usteps_per_revolution() / 60.0)
+    }
+
+    /// Convert a velocity in device units to radians per second
+    pub fn velocity_to_radians_per_second(&self, velocity: i32) -> f32 {
+        self.controller.velocity_to_usteps_per_second(velocity) * 2.0 * PI / self.usteps_per_revolution()
+    }
+
+    /// Convert a velocity in radians per second to device units
+    pub fn radians_per_second_to_velocity(&self, radians_per_second: f32) -> i32 {
+        self.controller.usteps_per_second_to_velocity(radians_per_second * self.usteps_per_revolution() / (2.0 * PI))
+    }
+
+    /// Convert an acceleration in device units to full steps per second squared
+    pub fn acceleration_to_steps_per_second_squared(&self, acceleration: u32) -> f32 {
+        self.controller.acceleration_to_usteps_per_second_squared(acceleration) / self.microstep_resolution.microsteps() as f32
+    }
+
+    /// Convert an acceleration in full steps per second squared to device units
+    pub fn steps_per_second_squared_to_acceleration(&self, steps_per_second_squared: f32) -> u32 {
+        self.controller.usteps_per_second_squared_to_acceleration(steps_per_second_squared * self.microstep_resolution.microsteps() as f32)
+    }
+
+    /// Convert an acceleration in device units to radians per second squared
+    pub fn acceleration_to_radians_per_second_squared(&self, acceleration: u32) -> f32 {
+        self.controller.acceleration_to_usteps_per_second_squared(acceleration) * 2.0 * PI / self.usteps_per_revolution()
+    }
+
+    /// Convert an acceleration in radians per second squared to device units
+    pub fn radians_per_second_squared_to_acceleration(&self, radians_per_second_squared: f32) -> u32 {
+        self.controller.usteps_per_second_squared_to_acceleration(radians_per_second_squared * self.usteps_per_revolution() / (2.0 * PI))
+    }
+}
+
+/// A current exceeding what the module is able to deliver.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CurrentOutOfRange {
+    requested_ma: u16,
+    max_ma: u16,
+}
+
+impl CurrentOutOfRange {
+    /// The requested current in milliamps
+    pub fn requested_ma(&self) -> u16 {
+        self.requested_ma
+    }
+
+    /// The maximum current of the module in milliamps
+    pub fn max_ma(&self) -> u16 {
+        self.max_ma
+    }
+}
+
+/// How a module family interprets the value of the current axis parameters.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CurrentScale {
+    /// The value is the current in milliamps, at most `max_ma`.
+    Milliamps{max_ma: u16},
+
+    /// The value is a fraction of the maximum current of the module, 255 means `full_scale_ma`.
+    Fraction{full_scale_ma: u16},
+}
+
+impl CurrentScale {
+    /// The TMCM-300, 303, 310, 110, 610, 611 and 612 take the current in milliamps, up to 1.5 A.
+    pub const MILLIAMPS_1500: CurrentScale = CurrentScale::Milliamps{max_ma: 1500};
+
+    /// The maximum current of the module in milliamps
+    pub fn max_ma(&self) -> u16 {
+        match *self {
+            CurrentScale::Milliamps{max_ma} => max_ma,
+            CurrentScale::Fraction{full_scale_ma} => full_scale_ma,
+        }
+    }
+
+    /// Convert a current in milliamps to the value of the current parameters, rounded to the nearest value
+    pub fn milliamps_to_value(&self, milliamps: u16) -> Result<u16, CurrentOutOfRange> {
+        if milliamps > self.max_ma() {
+            return Err(CurrentOutOfRange{requested_ma: milliamps, max_ma: self.max_ma()});
+        }
+        match *self {
+            CurrentScale::Milliamps{..} => Ok(milliamps),
+            CurrentScale::Fraction{full_scale_ma} => {
+                let full_scale_ma = u32::from(full_scale_ma);
+                Ok(((u32::from(milliamps) * 255 + full_scale_ma / 2) / full_scale_ma) as u16)
+            },
+        }
+    }
+
+    /// Convert a value of the current parameters to milliamps, rounded to the nearest milliamp
+    pub fn value_to_milliamps(&self, value: u16) -> u16 {
+        match *self {
+            CurrentScale::Milliamps{..} => value,
+            CurrentScale::Fraction{full_scale_ma} => ((u32::from(value) * u32::from(full_scale_ma) + 127) / 255) as u16,
+        }
+    }
+
+    /// The `AbsoluteMaxCurrent` (run current) for a current in milliamps
+    pub fn absolute_max_current(&self, milliamps: u16) -> Result<AbsoluteMaxCurrent, CurrentOutOfRange> {
+        self.milliamps_to_value(milliamps).map(AbsoluteMaxCurrent::new)
+    }
+
+    /// The `StandbyCurrent` for a current in milliamps
+    pub fn standby_current(&self, milliamps: u16) -> Result<StandbyCurrent, CurrentOutOfRange> {
+        self.milliamps_to_value(milliamps).map(StandbyCurrent::new)
+    }
+}
+
+/// A linear conversion between a raw value and a physical quantity, `physical = raw * factor + offset`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinearScale {
+    factor: f32,
+    offset: f32,
+}
+
+impl LinearScale {
+    /// A scale multiplying the raw value by `factor` and adding `offset`
+    pub const fn new(factor: f32, offset: f32) -> Self {
+        LinearScale{factor, offset}
+    }
+
+    /// The scale of the raw bits of a fixed-point number with `FRAC` fractional bits, panics if `FRAC` is 32 or above
+    pub const fn fixed_point<const FRAC: u32>() -> Self {
+        assert!(FRAC < 32);
+        LinearScale{factor: 1.0 / (1u64 << FRAC) as f32, offset: 0.0}
+    }
+
+    /// The physical value of a raw value of 1, without the offset
+    pub fn factor(&self) -> f32 {
+        self.factor
+    }
+
+    /// The physical value of a raw value of 0
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Convert a raw value to the physical quantity
+    pub fn to_physical(&self, raw: i32) -> f32 {
+        raw as f32 * self.factor + self.offset
+    }
+
+    /// Convert a physical quantity to the raw value, rounded to the nearest integer and saturated
+    pub fn to_raw(&self, physical: f32) -> i32 {
+        round((physical - self.offset) / self.factor) as i32
+    }
+}
+
+/// A value with a fractional physical meaning, defined by a `LinearScale` on its raw value.
+pub trait Scaled {
+    /// The conversion of the raw value
+    fn scale(&self) -> LinearScale;
+
+    /// The raw value
+    fn raw(&self) -> i32;
+
+    /// The physical value
+    fn physical(&self) -> f32 {
+        self.scale().to_physical(self.raw())
+    }
+}
+
+impl<const FRAC: u32> Scaled for Fixed<FRAC> {
+    fn scale(&self) -> LinearScale {
+        Self::SCALE
+    }
+
+    fn raw(&self) -> i32 {
+        self.to_bits()
+    }
+}
+
+/// The direction of the physical axis relative to the motor.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Orientation {
+    /// Positive steps move in the positive physical direction.
+    Normal,
+
+    /// Positive steps move in the negative physical direction.
+    Inverted,
+}
+
+/// The mapping between the microstep position of an axis and a physical position, e.g. in mm.
+///
+/// `units = orientation * (steps - offset) / steps_per_unit`
+///
+/// A calibration implements `Persist` using 3 user variables, so it can be stored on the module
+/// itself with `UserVariables::save` and read back by any host with `UserVariables::load`. User
+/// variables that were never written load as an invalid calibration, see `is_valid`.
+///
+/// ```
+/// use tmcl::units::{Calibration, Orientation};
+/// use tmcl::modules::tmcm::user_variables::Persist;
+///
+/// // A lead screw with 2 mm pitch, 200 full steps and 16 microsteps per revolution.
+/// let calibration = Calibration::new(1600.0)
+///     .with_offset(-800)
+///     .with_orientation(Orientation::Inverted);
+/// assert_eq!(calibration.to_units(0), -0.5);
+/// assert_eq!(calibration.to_steps(-0.5), 0);
+///
+/// let mut words = [0; 3];
+/// calibration.save(&mut words);
+/// assert_eq!(Calibration::load(&words), calibration);
+/// assert!(!Calibration::load(&[0; 3]).is_valid());
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    steps_per_unit: f32,
+    offset: i32,
+    orientation: Orientation,
+}
+
+impl Calibration {
+    /// A calibration with `steps_per_unit` microsteps per physical unit, no offset and normal orientation
+    pub fn new(steps_per_unit: f32) -> Self {
+        Calibration{steps_per_unit, offset: 0, orientation: Orientation::Normal}
+    }
+
+    /// Set the position in microsteps of the physical zero
+    pub fn with_offset(mut self, offset: i32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Set the direction of the physical axis
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// The number of microsteps per physical unit
+    pub fn steps_per_unit(&self) -> f32 {
+        self.steps_per_unit
+    }
+
+    /// The position in microsteps of the physical zero
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// The direction of the physical axis
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Returns false if the steps per unit are zero or not finite, e.g. when loaded from unwritten
+    /// user variables
+    pub fn is_valid(&self) -> bool {
+        self.steps_per_unit.is_finite() && self.steps_per_unit != 0.0
+    }
+
+    /// The calibration as a `LinearScale` from microsteps to physical units
+    pub fn scale(&self) -> LinearScale {
+        let factor = self.sign() / self.steps_per_unit;
+        LinearScale::new(factor, -(self.offset as f32) * factor)
+    }
+
+    /// Convert a position in microsteps to physical units
+    pub fn to_units(&self, steps: i32) -> f32 {
+        self.sign() * (f64::from(steps) - f64::from(self.offset)) as f32 / self.steps_per_unit
+    }
+
+    /// Convert a position in physical units to microsteps, rounded to the nearest step and saturated
+    pub fn to_steps(&self, units: f32) -> i32 {
+        (round(self.sign() * units * self.steps_per_unit) as i64 + i64::from(self.offset))
+            .clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+    }
+
+    fn sign(&self) -> f32 {
+        match self.orientation {
+            Orientation::Normal => 1.0,
+            Orientation::Inverted => -1.0,
+        }
+    }
+}
+
+impl Persist for Calibration {
+    const WORDS: usize = 3;
+
+    fn save(&self, words: &mut [i32]) {
+        words[0] = self.steps_per_unit.to_bits() as i32;
+        words[1] = self.offset;
+        words[2] = match self.orientation {
+            Orientation::Normal => 1,
+            Orientation::Inverted => -1,
+        };
+    }
+
+    fn load(words: &[i32]) -> Self {
+        Calibration{
+            steps_per_unit: f32::from_bits(words[0] as u32),
+            offset: words[1],
+            orientation: if words[2] < 0 { Orientation::Inverted } else { Orientation::Normal },
+        }
+    }
+}